@@ -1,14 +1,17 @@
-use std::net::SocketAddr;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 use axum::extract::connect_info::ConnectInfo;
-use axum::extract::State;
-use axum::http::Request;
+use axum::extract::{FromRef, FromRequestParts, State};
+use axum::http::request::Parts;
+use axum::http::{Extensions, HeaderMap, Request};
 use axum::middleware::Next;
 use axum::response::Response;
 
 use crate::app::AppState;
+use crate::services::ip_in_cidrs;
 
 #[derive(Clone, Debug)]
 pub struct AccessLogUserId(pub Arc<Mutex<Option<String>>>);
@@ -22,12 +25,14 @@ pub async fn request_access_log(
     let method = req.method().to_string();
     let path = req.uri().path().to_string();
 
-    let client_ip = extract_client_ip(&req).unwrap_or_else(|| {
-        req.extensions()
-            .get::<ConnectInfo<SocketAddr>>()
-            .map(|ConnectInfo(addr)| addr.ip().to_string())
-            .unwrap_or_else(|| "-".to_string())
-    });
+    let ClientIp(mut client_ip) = client_ip(
+        req.headers(),
+        req.extensions(),
+        &state.config.trusted_proxies,
+    );
+    if client_ip.is_empty() {
+        client_ip = "-".to_string();
+    }
 
     let user_slot = Arc::new(Mutex::new(None));
     req.extensions_mut()
@@ -70,13 +75,89 @@ pub async fn request_access_log(
     resp
 }
 
-pub fn extract_client_ip(req: &Request<axum::body::Body>) -> Option<String> {
-    for key in ["x-forwarded-for", "x-real-ip", "forwarded"] {
-        if let Some(val) = req.headers().get(key).and_then(|v| v.to_str().ok()) {
-            if !val.trim().is_empty() {
-                return Some(val.trim().to_string());
-            }
+// the peer address, or the client a trusted proxy forwarded the request for;
+// empty when the server runs without connect info
+#[derive(Clone, Debug, Default)]
+pub struct ClientIp(pub String);
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+    AppState: FromRef<S>,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let state = AppState::from_ref(state);
+        Ok(client_ip(
+            &parts.headers,
+            &parts.extensions,
+            &state.config.trusted_proxies,
+        ))
+    }
+}
+
+pub fn client_ip(
+    headers: &HeaderMap,
+    extensions: &Extensions,
+    trusted_proxies: &[String],
+) -> ClientIp {
+    let peer = extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    ClientIp(
+        resolve_client_ip(headers, peer, trusted_proxies)
+            .map(|ip| ip.to_string())
+            .unwrap_or_default(),
+    )
+}
+
+// forwarding headers only count when the peer is a trusted proxy; x-forwarded-for is
+// walked from the nearest hop and the first address that is not a proxy is the client
+pub fn resolve_client_ip(
+    headers: &HeaderMap,
+    peer: Option<IpAddr>,
+    trusted_proxies: &[String],
+) -> Option<IpAddr> {
+    let peer = peer?.to_canonical();
+    if !ip_in_cidrs(trusted_proxies, peer) {
+        return Some(peer);
+    }
+    let hops = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<_>>();
+    if hops.is_empty() {
+        let real_ip = headers
+            .get("x-real-ip")
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_hop);
+        return Some(real_ip.unwrap_or(peer));
+    }
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        let Some(ip) = parse_hop(hop) else {
+            break;
+        };
+        client = ip;
+        if !ip_in_cidrs(trusted_proxies, ip) {
+            break;
         }
     }
-    None
+    Some(client)
+}
+
+// proxies may append the port, `1.2.3.4:5678` or `[::1]:5678`
+fn parse_hop(value: &str) -> Option<IpAddr> {
+    let value = value.trim();
+    value
+        .parse::<IpAddr>()
+        .or_else(|_| value.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .ok()
+        .map(|ip| ip.to_canonical())
 }
@@ -25,6 +25,17 @@ pub struct AdminPerfStats {
     pub cluster: AdminClusterStats,
     pub pools: AdminPoolStats,
     pub metrics: crate::infra::metrics::RuntimeMetricsSnapshot,
+    pub webhook_subscriptions: Vec<AdminWebhookSubscriptionStats>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminWebhookSubscriptionStats {
+    pub id: String,
+    pub url: String,
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub stats: crate::infra::metrics::WebhookSubscriptionStats,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -72,15 +83,45 @@ pub struct AdminConfigView {
     pub webhook_timeout_secs: u64,
     pub webhook_retries: usize,
     pub webhook_targets: Vec<String>,
+    pub has_webhook_secret: bool,
+    // urls with their own secret, the secrets themselves are never shown
+    pub webhook_signed_targets: Vec<String>,
+    pub webhook_outbox: bool,
+    pub webhook_max_attempts: u32,
+    pub webhook_backoff_secs: u64,
+    pub webhook_backoff_max_secs: u64,
+    pub webhook_circuit_threshold: u32,
+    pub webhook_circuit_cooldown_secs: u64,
+    pub webhook_event_retention_secs: u64,
+    pub has_presend_hook: bool,
+    pub presend_hook_timeout_ms: u64,
+    pub presend_hook_fail_open: bool,
     pub presence_backend: String,
     pub presence_node_id: String,
     pub presence_ttl_secs: u64,
     pub presence_heartbeat_secs: u64,
+    pub presence_debounce_ms: u64,
     pub ws_per_user_limit: usize,
     pub ws_client_queue_size: usize,
     pub ws_typing_interval_ms: u64,
     pub ws_drop_on_backpressure: bool,
+    pub ws_ticket_ttl_secs: u64,
+    pub ws_require_ticket: bool,
+    pub knock_expire_secs: u64,
+    pub dm_contacts_only: bool,
+    pub user_search_rate_limit: u32,
+    pub token_ttl_secs: u64,
+    pub token_idle_secs: u64,
+    pub refresh_token_ttl_secs: u64,
+    // empty when access tokens are opaque db tokens
+    pub signed_token_alg: String,
+    pub password_min_length: usize,
+    pub login_max_failures: u32,
+    pub login_ip_max_failures: u32,
+    pub trusted_proxies: Vec<String>,
+    pub oidc_providers: Vec<String>,
     pub has_openapi_token: bool,
+    pub openapi_allow_anonymous: bool,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -203,15 +244,57 @@ pub async fn config_view(
         webhook_timeout_secs: state.config.webhook_timeout_secs,
         webhook_retries: state.config.webhook_retries,
         webhook_targets: state.webhook_targets.as_ref().clone(),
+        has_webhook_secret: state.config.webhook_secret.is_some(),
+        webhook_signed_targets: state
+            .config
+            .webhook_target_secrets
+            .iter()
+            .map(|(url, _)| url.clone())
+            .collect(),
+        webhook_outbox: state.config.webhook_outbox,
+        webhook_max_attempts: state.config.webhook_max_attempts,
+        webhook_backoff_secs: state.config.webhook_backoff_secs,
+        webhook_backoff_max_secs: state.config.webhook_backoff_max_secs,
+        webhook_circuit_threshold: state.config.webhook_circuit_threshold,
+        webhook_circuit_cooldown_secs: state.config.webhook_circuit_cooldown_secs,
+        webhook_event_retention_secs: state.config.webhook_event_retention_secs,
+        has_presend_hook: state.config.presend_hook_url.is_some(),
+        presend_hook_timeout_ms: state.config.presend_hook_timeout_ms,
+        presend_hook_fail_open: state.config.presend_hook_fail_open,
         presence_backend: state.config.presence_backend.clone(),
         presence_node_id: state.config.presence_node_id.clone(),
         presence_ttl_secs: state.config.presence_ttl_secs,
         presence_heartbeat_secs: state.config.presence_heartbeat_secs,
+        presence_debounce_ms: state.config.presence_debounce_ms,
         ws_per_user_limit: state.config.ws_per_user_limit,
         ws_client_queue_size: state.config.ws_client_queue_size,
         ws_typing_interval_ms: state.config.ws_typing_interval_ms,
         ws_drop_on_backpressure: state.config.ws_drop_on_backpressure,
+        ws_ticket_ttl_secs: state.config.ws_ticket_ttl_secs,
+        ws_require_ticket: state.config.ws_require_ticket,
+        knock_expire_secs: state.config.knock_expire_secs,
+        dm_contacts_only: state.config.dm_contacts_only,
+        user_search_rate_limit: state.config.user_search_rate_limit,
+        token_ttl_secs: state.config.token_ttl_secs,
+        token_idle_secs: state.config.token_idle_secs,
+        refresh_token_ttl_secs: state.config.refresh_token_ttl_secs,
+        signed_token_alg: if state.config.signed_token_keys.is_empty() {
+            String::new()
+        } else {
+            state.config.signed_token_alg.clone()
+        },
+        password_min_length: state.config.password_min_length,
+        login_max_failures: state.config.login_max_failures,
+        login_ip_max_failures: state.config.login_ip_max_failures,
+        trusted_proxies: state.config.trusted_proxies.clone(),
+        oidc_providers: state
+            .config
+            .oidc_providers
+            .iter()
+            .map(|p| p.name.clone())
+            .collect(),
         has_openapi_token: state.config.openapi_token.is_some(),
+        openapi_allow_anonymous: state.config.openapi_allow_anonymous,
     }))
 }
 
@@ -253,6 +336,7 @@ pub async fn bootstrap_init(
     if form.password.is_empty() {
         return Err(ApiError::bad_request("password is required"));
     }
+    crate::api::auth::check_password_policy(&state, &form.user_id, &form.password)?;
 
     let user = state
         .user_service
@@ -281,7 +365,7 @@ pub async fn bootstrap_init(
         .map_err(|e| ApiError::internal(e.to_string()))?
         .ok_or_else(|| ApiError::internal("bootstrap user missing"))?;
     let mut active = existing.into_active_model();
-    active.password = Set(crate::api::auth::hash_password(&form.password));
+    active.password = Set(crate::api::auth::spawn_hash_password(&form.password).await);
     active.enabled = Set(true);
     active.is_staff = Set(true);
     active
@@ -355,6 +439,19 @@ pub async fn perf_stats(
             sessions,
         })
         .collect::<Vec<_>>();
+    let webhook_subscriptions = state
+        .webhook_subscriptions
+        .list()
+        .await
+        .map_err(map_domain_error)?
+        .into_iter()
+        .map(|sub| AdminWebhookSubscriptionStats {
+            stats: state.metrics.subscription_stats(&sub.id),
+            id: sub.id,
+            url: sub.url,
+            enabled: sub.enabled,
+        })
+        .collect();
     Ok(Json(AdminPerfStats {
         active_connections,
         active_users,
@@ -378,6 +475,7 @@ pub async fn perf_stats(
             webhook: state.webhook_pool.snapshot(),
         },
         metrics: state.metrics.snapshot(),
+        webhook_subscriptions,
     }))
 }
 
@@ -418,5 +516,6 @@ fn map_domain_error(err: crate::services::DomainError) -> ApiError {
         crate::services::DomainError::Forbidden => ApiError::Unauthorized,
         crate::services::DomainError::Validation(msg) => ApiError::bad_request(msg),
         crate::services::DomainError::Storage(err) => ApiError::internal(err),
+        crate::services::DomainError::Rejected { code, reason } => ApiError::Rejected(code, reason),
     }
 }
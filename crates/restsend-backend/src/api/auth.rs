@@ -1,14 +1,17 @@
 use axum::extract::State;
-use axum::http::{header, HeaderValue};
+use axum::http::{header, HeaderMap, HeaderValue};
 use axum::response::IntoResponse;
 use axum::Json;
 use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, Set};
 use std::time::Instant;
 
+use crate::api::access_log::ClientIp;
 use crate::api::error::{ApiError, ApiResult};
 use crate::app::AppState;
 use crate::entity::user;
-use crate::infra::event::{BackendEvent, UserGuestCreateEvent};
+use crate::infra::event::{AuthLockoutEvent, BackendEvent, UserGuestCreateEvent};
+use crate::infra::lockout::LoginGuard;
+use crate::services::{DomainError, IssuedToken, TokenStatus};
 
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,6 +20,8 @@ pub struct AuthRegisterForm {
     pub password: String,
     #[serde(default)]
     pub remember: bool,
+    #[serde(flatten)]
+    pub device: crate::DeviceInfoForm,
 }
 
 #[derive(serde::Deserialize)]
@@ -30,6 +35,8 @@ pub struct AuthLoginForm {
     pub auth_token: String,
     #[serde(default)]
     pub remember: bool,
+    #[serde(flatten)]
+    pub device: crate::DeviceInfoForm,
 }
 
 #[derive(serde::Deserialize)]
@@ -39,6 +46,15 @@ pub struct GuestLoginForm {
     pub guest_id: String,
     #[serde(default)]
     pub remember: bool,
+    #[serde(flatten)]
+    pub device: crate::DeviceInfoForm,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshTokenForm {
+    #[serde(default)]
+    pub refresh_token: String,
 }
 
 #[derive(serde::Serialize)]
@@ -47,6 +63,11 @@ pub struct AuthLoginResponse {
     pub email: String,
     pub display_name: String,
     pub token: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub refresh_token: String,
+    // rfc3339, empty when the token does not expire
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub expires_at: String,
     pub profile: AuthUserProfile,
     pub is_staff: bool,
 }
@@ -64,6 +85,8 @@ pub struct AuthUserProfile {
 
 pub async fn register(
     State(state): State<AppState>,
+    client_ip: ClientIp,
+    headers: HeaderMap,
     Json(form): Json<AuthRegisterForm>,
 ) -> ApiResult<axum::response::Response> {
     let st = Instant::now();
@@ -73,6 +96,7 @@ pub async fn register(
     if form.password.is_empty() {
         return Err(ApiError::bad_request("empty password"));
     }
+    check_password_policy(&state, &form.email, &form.password)?;
 
     let created = state
         .user_service
@@ -92,15 +116,16 @@ pub async fn register(
         .map_err(|e| ApiError::internal(e.to_string()))?
         .ok_or_else(|| ApiError::internal("registered user missing"))?;
     let mut active = existing.into_active_model();
-    active.password = Set(hash_password(&form.password));
+    active.password = Set(spawn_hash_password(&form.password).await);
     let saved = active
         .update(&state.db)
         .await
         .map_err(|e| ApiError::internal(e.to_string()))?;
 
-    let token = state
+    let (device, ip) = request_device(&headers, &client_ip, &form.device);
+    let issued = state
         .auth_service
-        .issue_token(&created.user_id)
+        .issue_token_with_device(&created.user_id, &device, &ip)
         .await
         .map_err(|e| ApiError::internal(e.to_string()))?;
     tracing::info!(
@@ -109,29 +134,39 @@ pub async fn register(
         elapsed_ms = st.elapsed().as_millis() as u64,
         "auth register succeeded"
     );
-    let resp = to_auth_login_response(saved.into(), token.clone());
-    Ok(cookie_response(resp, &token))
+    let resp = to_auth_login_response(saved.into(), &issued);
+    Ok(cookie_response(resp, &issued))
 }
 
 pub async fn login(
     State(state): State<AppState>,
+    client_ip: ClientIp,
+    headers: HeaderMap,
     Json(form): Json<AuthLoginForm>,
 ) -> ApiResult<axum::response::Response> {
     let st = Instant::now();
+    let ip = client_ip.0.clone();
     if !form.auth_token.is_empty() {
-        let user_id = state
+        let keys = login_keys("", &ip);
+        ensure_login_allowed(&state, &keys).await?;
+        let status = state
             .auth_service
-            .validate(&form.auth_token)
+            .validate_status(&form.auth_token)
             .await
-            .map_err(|e| ApiError::internal(e.to_string()))?
-            .ok_or_else(|| {
-                tracing::warn!(
-                    login_type = "token",
-                    elapsed_ms = st.elapsed().as_millis() as u64,
-                    "auth login rejected: invalid token"
-                );
-                ApiError::InvalidToken
-            })?;
+            .map_err(|e| ApiError::internal(e.to_string()))?;
+        let TokenStatus::Valid(user_id) = status else {
+            // expired tokens are routine, only guessed ones count as failures
+            if status == TokenStatus::Invalid {
+                record_login_failure(&state, &keys).await;
+            }
+            tracing::warn!(
+                login_type = "token",
+                reason = status.reason(),
+                elapsed_ms = st.elapsed().as_millis() as u64,
+                "auth login rejected: invalid token"
+            );
+            return Err(token_status_error(&status));
+        };
         let user = state
             .user_service
             .get_by_user_id(&user_id)
@@ -144,8 +179,13 @@ pub async fn login(
             elapsed_ms = st.elapsed().as_millis() as u64,
             "auth login succeeded"
         );
-        let resp = to_auth_login_response(user, form.auth_token.clone());
-        return Ok(cookie_response(resp, &form.auth_token));
+        let issued = IssuedToken {
+            token: form.auth_token.clone(),
+            refresh_token: String::new(),
+            expires_at: 0,
+        };
+        let resp = to_auth_login_response(user, &issued);
+        return Ok(cookie_response(resp, &issued));
     }
 
     if form.email.trim().is_empty() {
@@ -154,34 +194,55 @@ pub async fn login(
     if form.password.is_empty() {
         return Err(ApiError::bad_request("empty password"));
     }
+    let keys = login_keys(&form.email, &ip);
+    ensure_login_allowed(&state, &keys).await?;
 
-    let model = user::Entity::find_by_id(form.email.clone())
+    let Some(model) = user::Entity::find_by_id(form.email.clone())
         .one(&state.db)
         .await
         .map_err(|e| ApiError::internal(e.to_string()))?
-        .ok_or_else(|| {
-            tracing::warn!(
-                email = %form.email,
-                login_type = "password",
-                elapsed_ms = st.elapsed().as_millis() as u64,
-                "auth login rejected: user not found"
-            );
-            ApiError::InvalidToken
-        })?;
-    if !verify_password(&model.password, &form.password) {
+    else {
+        tracing::warn!(
+            email = %form.email,
+            login_type = "password",
+            elapsed_ms = st.elapsed().as_millis() as u64,
+            "auth login rejected: user not found"
+        );
+        record_login_failure(&state, &keys).await;
+        return Err(ApiError::InvalidToken);
+    };
+    if !spawn_verify_password(&model.password, &form.password).await {
         tracing::warn!(
             email = %form.email,
             login_type = "password",
             elapsed_ms = st.elapsed().as_millis() as u64,
             "auth login rejected: invalid password"
         );
+        record_login_failure(&state, &keys).await;
         return Err(ApiError::bad_request("invalid password"));
     }
+    // the ip key is kept: one good password must not reset the budget an address has
+    // for guessing other accounts
+    if let Err(err) = state
+        .login_guard
+        .clear(&LoginGuard::account_key(&form.email))
+        .await
+    {
+        tracing::warn!(email = %form.email, error = %err, "login failures reset failed");
+    }
+    if password_needs_rehash(&model.password) {
+        let mut active = model.clone().into_active_model();
+        active.password = Set(spawn_hash_password(&form.password).await);
+        if let Err(err) = active.update(&state.db).await {
+            tracing::warn!(email = %form.email, error = %err, "password rehash failed");
+        }
+    }
 
     let user: crate::User = model.into();
-    let token = state
+    let (device, ip) = request_device(&headers, &client_ip, &form.device);
+    let issued = state
         .auth_service
-        .issue_token(&user.user_id)
+        .issue_token_with_device(&user.user_id, &device, &ip)
         .await
         .map_err(|e| ApiError::internal(e.to_string()))?;
     tracing::info!(
@@ -191,8 +252,43 @@ pub async fn login(
         elapsed_ms = st.elapsed().as_millis() as u64,
         "auth login succeeded"
     );
-    let resp = to_auth_login_response(user, token.clone());
-    Ok(cookie_response(resp, &token))
+    let resp = to_auth_login_response(user, &issued);
+    Ok(cookie_response(resp, &issued))
+}
+
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(form): Json<RefreshTokenForm>,
+) -> ApiResult<axum::response::Response> {
+    let st = Instant::now();
+    let refreshed = state
+        .auth_service
+        .refresh(&form.refresh_token)
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+    let (user_id, issued) = match refreshed {
+        Ok(v) => v,
+        Err(status) => {
+            tracing::warn!(
+                reason = status.reason(),
+                elapsed_ms = st.elapsed().as_millis() as u64,
+                "auth refresh rejected"
+            );
+            return Err(token_status_error(&status));
+        }
+    };
+    let user = state
+        .user_service
+        .get_by_user_id(&user_id)
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+    tracing::info!(
+        user_id = %user_id,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "auth token refreshed"
+    );
+    let resp = to_auth_login_response(user, &issued);
+    Ok(cookie_response(resp, &issued))
 }
 
 pub async fn logout(
@@ -219,17 +315,41 @@ pub async fn logout(
 
 pub async fn guest_login(
     State(state): State<AppState>,
+    client_ip: ClientIp,
+    headers: HeaderMap,
     Json(form): Json<GuestLoginForm>,
 ) -> ApiResult<Json<AuthLoginResponse>> {
     let st = Instant::now();
     if form.guest_id.trim().is_empty() {
         return Err(ApiError::bad_request("guestId is required"));
     }
+    let ip = client_ip.0.clone();
+    let keys = login_keys(&form.guest_id, &ip);
+    ensure_login_allowed(&state, &keys).await?;
 
     let mut created_guest = false;
     let user = match state.user_service.get_by_user_id(&form.guest_id).await {
+        // guest ids only reach guest accounts, a hit on any other account is a guess
+        Ok(user) if user.source != "guest" => {
+            tracing::warn!(
+                user_id = %form.guest_id,
+                elapsed_ms = st.elapsed().as_millis() as u64,
+                "auth guest login rejected: not a guest account"
+            );
+            record_login_failure(&state, &keys).await;
+            return Err(ApiError::Unauthorized);
+        }
         Ok(user) => user,
-        Err(_) => {
+        Err(DomainError::Forbidden) => {
+            tracing::warn!(
+                user_id = %form.guest_id,
+                elapsed_ms = st.elapsed().as_millis() as u64,
+                "auth guest login rejected: account disabled"
+            );
+            record_login_failure(&state, &keys).await;
+            return Err(ApiError::Unauthorized);
+        }
+        Err(DomainError::NotFound) => {
             created_guest = true;
             state
                 .user_service
@@ -244,6 +364,7 @@ pub async fn guest_login(
                 .await
                 .map_err(|e| ApiError::internal(e.to_string()))?
         }
+        Err(err) => return Err(ApiError::internal(err.to_string())),
     };
 
     if created_guest {
@@ -254,9 +375,10 @@ pub async fn guest_login(
             }));
     }
 
-    let token = state
+    let (device, ip) = request_device(&headers, &client_ip, &form.device);
+    let issued = state
         .auth_service
-        .issue_token(&user.user_id)
+        .issue_token_with_device(&user.user_id, &device, &ip)
         .await
         .map_err(|e| ApiError::internal(e.to_string()))?;
     tracing::info!(
@@ -265,24 +387,116 @@ pub async fn guest_login(
         elapsed_ms = st.elapsed().as_millis() as u64,
         "auth guest login succeeded"
     );
-    Ok(Json(to_auth_login_response(user, token)))
+    Ok(Json(to_auth_login_response(user, &issued)))
 }
 
-fn cookie_response(data: AuthLoginResponse, token: &str) -> axum::response::Response {
-    let cookie = format!(
-        "token={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}",
-        token,
-        30 * 24 * 3600
-    );
+fn login_keys(user_id: &str, ip: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    if !user_id.trim().is_empty() {
+        keys.push(LoginGuard::account_key(user_id));
+    }
+    if !ip.trim().is_empty() {
+        keys.push(LoginGuard::ip_key(ip));
+    }
+    keys
+}
+
+async fn ensure_login_allowed(state: &AppState, keys: &[String]) -> ApiResult<()> {
+    let retry_after = state
+        .login_guard
+        .retry_after(keys)
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+    if retry_after > 0 {
+        tracing::warn!(keys = ?keys, retry_after, "auth login rejected: locked out");
+        return Err(ApiError::LockedOut(retry_after));
+    }
+    Ok(())
+}
+
+async fn record_login_failure(state: &AppState, keys: &[String]) {
+    for key in keys {
+        let entry = match state.login_guard.record_failure(key).await {
+            Ok(Some(entry)) => entry,
+            Ok(None) => continue,
+            Err(err) => {
+                tracing::warn!(key = %key, error = %err, "login failure record failed");
+                continue;
+            }
+        };
+        let lockout = crate::LoginLockout::from(entry);
+        tracing::warn!(
+            kind = %lockout.kind,
+            subject = %lockout.subject,
+            lockouts = lockout.lockouts,
+            locked_until = %lockout.locked_until,
+            "login locked out"
+        );
+        state
+            .event_bus
+            .publish(BackendEvent::AuthLockout(AuthLockoutEvent {
+                kind: lockout.kind,
+                subject: lockout.subject,
+                lockouts: lockout.lockouts,
+                locked_until: lockout.locked_until,
+            }));
+    }
+}
+
+// falls back to the user agent when the client does not name itself
+pub(crate) fn request_device(
+    headers: &HeaderMap,
+    client_ip: &ClientIp,
+    info: &crate::DeviceInfoForm,
+) -> (crate::DeviceInfoForm, String) {
+    let mut device = info.clone();
+    if device.device_name.trim().is_empty() {
+        device.device_name = headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+    }
+    (device, client_ip.0.clone())
+}
+
+pub(crate) fn cookie_response(
+    data: AuthLoginResponse,
+    issued: &IssuedToken,
+) -> axum::response::Response {
     let mut resp = axum::response::Json(data).into_response();
-    resp.headers_mut()
-        .insert(header::SET_COOKIE, HeaderValue::from_str(&cookie).unwrap());
+    resp.headers_mut().insert(
+        header::SET_COOKIE,
+        HeaderValue::from_str(&token_cookie(issued)).unwrap(),
+    );
     resp.headers_mut()
         .insert(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
     resp
 }
 
-fn to_auth_login_response(user: crate::User, token: String) -> AuthLoginResponse {
+// the cookie lives as long as the access token, tokens without a ttl keep the 30 day cookie
+pub(crate) fn token_cookie(issued: &IssuedToken) -> String {
+    let max_age = if issued.expires_at > 0 {
+        (issued.expires_at - chrono::Utc::now().timestamp()).max(0)
+    } else {
+        30 * 24 * 3600
+    };
+    format!(
+        "token={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}",
+        issued.token, max_age
+    )
+}
+
+pub(crate) fn token_status_error(status: &TokenStatus) -> ApiError {
+    match status {
+        TokenStatus::Expired | TokenStatus::IdleTimeout => {
+            ApiError::TokenExpired(status.reason().to_string())
+        }
+        _ => ApiError::InvalidToken,
+    }
+}
+
+pub(crate) fn to_auth_login_response(user: crate::User, issued: &IssuedToken) -> AuthLoginResponse {
     let crate::User {
         user_id,
         name,
@@ -303,10 +517,16 @@ fn to_auth_login_response(user: crate::User, token: String) -> AuthLoginResponse
     } else {
         avatar
     };
+    let expires_at = chrono::DateTime::from_timestamp(issued.expires_at, 0)
+        .filter(|_| issued.expires_at > 0)
+        .map(|at| at.to_rfc3339())
+        .unwrap_or_default();
     AuthLoginResponse {
         email: user_id,
         display_name,
-        token,
+        token: issued.token.clone(),
+        refresh_token: issued.refresh_token.clone(),
+        expires_at,
         profile: AuthUserProfile {
             avatar: profile_avatar,
             gender,
@@ -319,20 +539,139 @@ fn to_auth_login_response(user: crate::User, token: String) -> AuthLoginResponse
     }
 }
 
+const LEGACY_HASH_PREFIX: &str = "sha256$";
+const PASSWORD_MAX_LENGTH: usize = 128;
+
+// argon2id cost comes from the environment, like PASSWORD_SALT did for sha256
+fn password_params() -> &'static argon2::Params {
+    static PARAMS: std::sync::OnceLock<argon2::Params> = std::sync::OnceLock::new();
+    PARAMS.get_or_init(|| {
+        let env_u32 = |name: &str, default: u32| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+                .unwrap_or(default)
+        };
+        let memory_kib = env_u32("PASSWORD_ARGON2_MEMORY_KIB", argon2::Params::DEFAULT_M_COST);
+        let iterations = env_u32("PASSWORD_ARGON2_ITERATIONS", argon2::Params::DEFAULT_T_COST);
+        let parallelism = env_u32(
+            "PASSWORD_ARGON2_PARALLELISM",
+            argon2::Params::DEFAULT_P_COST,
+        );
+        argon2::Params::new(memory_kib, iterations, parallelism, None).unwrap_or_else(|err| {
+            tracing::warn!(error = %err, "invalid argon2 params, using defaults");
+            argon2::Params::default()
+        })
+    })
+}
+
+fn password_hasher() -> argon2::Argon2<'static> {
+    argon2::Argon2::new(
+        argon2::Algorithm::Argon2id,
+        argon2::Version::V0x13,
+        password_params().clone(),
+    )
+}
+
 pub(crate) fn hash_password(password: &str) -> String {
+    use argon2::password_hash::{PasswordHasher, SaltString};
+    use ring::rand::SecureRandom;
+
     if password.is_empty() {
         return String::new();
     }
-    let salt = std::env::var("PASSWORD_SALT").unwrap_or_default();
-    hash_password_with_salt(password, &salt)
+    let mut salt = [0u8; 16];
+    ring::rand::SystemRandom::new()
+        .fill(&mut salt)
+        .expect("system random unavailable");
+    let salt = SaltString::encode_b64(&salt).expect("argon2 salt");
+    password_hasher()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("argon2 params are validated")
+        .to_string()
+}
+
+// argon2 is deliberately slow, keep it off the async workers
+pub(crate) async fn spawn_hash_password(password: &str) -> String {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || hash_password(&password))
+        .await
+        .expect("password hashing task panicked")
+}
+
+pub(crate) async fn spawn_verify_password(password_hash: &str, password: &str) -> bool {
+    let (password_hash, password) = (password_hash.to_string(), password.to_string());
+    tokio::task::spawn_blocking(move || verify_password(&password_hash, &password))
+        .await
+        .expect("password verify task panicked")
 }
 
 pub(crate) fn verify_password(password_hash: &str, password: &str) -> bool {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+
     if password_hash.is_empty() {
         return password.is_empty();
     }
-    let expected = hash_password(password);
-    subtle_constant_time_eq(password_hash.as_bytes(), expected.as_bytes())
+    if password_hash.starts_with(LEGACY_HASH_PREFIX) {
+        let salt = std::env::var("PASSWORD_SALT").unwrap_or_default();
+        let expected = hash_password_with_salt(password, &salt);
+        return subtle_constant_time_eq(password_hash.as_bytes(), expected.as_bytes());
+    }
+    let Ok(parsed) = PasswordHash::new(password_hash) else {
+        return false;
+    };
+    // the cost stored in the hash wins over the current params
+    argon2::Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok()
+}
+
+// legacy sha256 hashes and argon2 hashes with outdated params get rehashed on login
+pub(crate) fn password_needs_rehash(password_hash: &str) -> bool {
+    if password_hash.is_empty() {
+        return false;
+    }
+    if password_hash.starts_with(LEGACY_HASH_PREFIX) {
+        return true;
+    }
+    let Ok(parsed) = argon2::password_hash::PasswordHash::new(password_hash) else {
+        return true;
+    };
+    let current = password_params();
+    parsed.algorithm != argon2::Algorithm::Argon2id.ident()
+        || argon2::Params::try_from(&parsed).map_or(true, |params| {
+            params.m_cost() != current.m_cost()
+                || params.t_cost() != current.t_cost()
+                || params.p_cost() != current.p_cost()
+        })
+}
+
+pub(crate) fn check_password_policy(
+    state: &AppState,
+    user_id: &str,
+    password: &str,
+) -> ApiResult<()> {
+    let length = password.chars().count();
+    if length < state.config.password_min_length {
+        return Err(ApiError::bad_request(format!(
+            "password must be at least {} characters",
+            state.config.password_min_length
+        )));
+    }
+    if length > PASSWORD_MAX_LENGTH {
+        return Err(ApiError::bad_request(format!(
+            "password must be at most {PASSWORD_MAX_LENGTH} characters"
+        )));
+    }
+    if password.trim() != password {
+        return Err(ApiError::bad_request(
+            "password must not start or end with whitespace",
+        ));
+    }
+    if password.eq_ignore_ascii_case(user_id.trim()) {
+        return Err(ApiError::bad_request("password must not match the user id"));
+    }
+    Ok(())
 }
 
 pub(crate) fn hash_password_with_salt(password: &str, salt: &str) -> String {
@@ -340,7 +679,7 @@ pub(crate) fn hash_password_with_salt(password: &str, salt: &str) -> String {
         return String::new();
     }
     let digest = sha256_hex(&(salt.to_string() + password));
-    format!("sha256${digest}")
+    format!("{LEGACY_HASH_PREFIX}{digest}")
 }
 
 fn sha256_hex(input: &str) -> String {
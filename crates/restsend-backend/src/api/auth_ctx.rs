@@ -6,6 +6,9 @@ use sea_orm::EntityTrait;
 use crate::api::error::ApiError;
 use crate::app::AppState;
 use crate::entity::user;
+use crate::services::{ApiKeyGrant, ApiScope};
+
+pub const DEVICE_HEADER: &str = "x-restsend-device";
 
 #[derive(Clone, Debug)]
 pub struct AuthUserId(pub String);
@@ -19,6 +22,10 @@ pub struct AuthCtx {
     pub token: String,
     pub is_staff: bool,
     pub is_super_openapi: bool,
+    // the api key the request came with, its route scope is already checked
+    pub api_key: Option<ApiKeyGrant>,
+    // ws session id of the calling client, from the X-Restsend-Device header
+    pub device: String,
 }
 
 impl AuthCtx {
@@ -26,15 +33,43 @@ impl AuthCtx {
         &self.user_id
     }
 
+    pub fn device(&self) -> &str {
+        &self.device
+    }
+
     pub fn ensure_user_or_staff(&self, target_user_id: &str) -> Result<(), ApiError> {
-        if self.user_id == target_user_id || self.is_staff || self.is_super_openapi {
+        if self.user_id == target_user_id || self.is_server() {
             return Ok(());
         }
         Err(ApiError::Unauthorized)
     }
 
     pub fn ensure_staff(&self) -> Result<(), ApiError> {
-        if self.is_staff || self.is_super_openapi {
+        if self.is_server() {
+            return Ok(());
+        }
+        Err(ApiError::Unauthorized)
+    }
+
+    // api keys only reach the /open routes they hold the scope for
+    fn is_server(&self) -> bool {
+        self.is_staff || self.is_super_openapi || self.api_key.is_some()
+    }
+
+    // for checks beyond the route scope: api keys need the scope granted, the super
+    // token and staff accounts hold every scope
+    pub fn ensure_scope(&self, scope: ApiScope) -> Result<(), ApiError> {
+        match &self.api_key {
+            Some(grant) if grant.allows(scope) => Ok(()),
+            Some(_) => Err(ApiError::InsufficientScope(scope.as_str())),
+            None if self.is_staff || self.is_super_openapi => Ok(()),
+            None => Err(ApiError::InsufficientScope(scope.as_str())),
+        }
+    }
+
+    // ownership can only be handed over by the owner, never through a role
+    pub fn ensure_topic_owner(&self, topic: &crate::Topic) -> Result<(), ApiError> {
+        if self.is_staff || self.is_super_openapi || topic.owner_id == self.user_id {
             return Ok(());
         }
         Err(ApiError::Unauthorized)
@@ -50,6 +85,34 @@ impl AuthCtx {
         }
         Err(ApiError::Unauthorized)
     }
+
+    // the owner and admins can only be kicked or banned by someone above them
+    pub fn ensure_outranks(&self, topic: &crate::Topic, target_id: &str) -> Result<(), ApiError> {
+        if topic.owner_id == target_id || topic.admins.iter().any(|v| v == target_id) {
+            return self.ensure_topic_owner(topic);
+        }
+        Ok(())
+    }
+
+    pub async fn ensure_topic_permission(
+        &self,
+        state: &AppState,
+        topic: &crate::Topic,
+        permission: crate::TopicPermission,
+    ) -> Result<(), ApiError> {
+        if self.is_staff || self.is_super_openapi {
+            return Ok(());
+        }
+        match state
+            .topic_service
+            .has_permission(topic, &self.user_id, permission)
+            .await
+        {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(ApiError::Unauthorized),
+            Err(err) => Err(ApiError::internal(err.to_string())),
+        }
+    }
 }
 
 #[axum::async_trait]
@@ -85,20 +148,35 @@ where
         }
 
         let super_tokens = super_token_set();
-        let is_super_openapi = parts.extensions.get::<AuthToken>().is_some_and(|token| {
-            super_tokens.contains(&token.0)
-                || state
-                    .config
-                    .openapi_token
-                    .as_ref()
-                    .is_some_and(|v| v == &token.0)
-        });
+        let api_key = parts.extensions.get::<ApiKeyGrant>().cloned();
+        // only admin keys stand in for the super token, other keys are limited to their
+        // route scopes
+        let is_super_openapi = api_key
+            .as_ref()
+            .is_some_and(|grant| grant.scopes.contains(&ApiScope::Admin))
+            || parts.extensions.get::<AuthToken>().is_some_and(|token| {
+                super_tokens.contains(&token.0)
+                    || state
+                        .config
+                        .openapi_token
+                        .as_ref()
+                        .is_some_and(|v| v == &token.0)
+            });
+
+        let device = parts
+            .headers
+            .get(DEVICE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_string())
+            .unwrap_or_default();
 
         Ok(AuthCtx {
             user_id,
             token,
             is_staff,
             is_super_openapi,
+            api_key,
+            device,
         })
     }
 }
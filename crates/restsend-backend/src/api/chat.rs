@@ -12,7 +12,7 @@ use crate::infra::event::{
 use crate::services::DomainError;
 use crate::{
     ChatLogSyncForm, Content, ListConversationForm, ListConversationResult, OpenApiChatMessageForm,
-    OpenApiSendMessageResponse, OpenApiUpdateConversationForm, RemoveMessagesForm,
+    OpenApiSendMessageResponse, OpenApiUpdateConversationForm, RemoveMessagesForm, TopicPermission,
 };
 
 pub(crate) fn conversation_update_fields(
@@ -28,9 +28,22 @@ pub(crate) fn conversation_update_fields(
     if let Some(remark) = form.remark.clone() {
         fields.insert("remark".to_string(), json!(remark));
     }
+    if let Some(tags) = form.tags.clone() {
+        fields.insert("tags".to_string(), json!(tags));
+    }
+    if let Some(extra) = form.extra.clone() {
+        fields.insert("extra".to_string(), json!(extra));
+    }
     serde_json::Value::Object(fields)
 }
 
+pub(crate) fn conversation_read_fields(conv: &crate::Conversation) -> serde_json::Value {
+    json!({
+        "lastReadSeq": conv.last_read_seq,
+        "unread": conv.unread,
+    })
+}
+
 pub(crate) fn build_conversation_update_payload(
     owner_id: &str,
     topic_id: &str,
@@ -188,7 +201,13 @@ pub async fn chat_update(
                 fields: fields.clone(),
             }));
         let payload = build_conversation_update_payload(auth.user_id(), &topic_id, &fields);
-        crate::api::push::broadcast_to_user(&state, auth.user_id(), &payload).await;
+        crate::api::push::broadcast_to_other_devices(
+            &state,
+            auth.user_id(),
+            auth.device(),
+            &payload,
+        )
+        .await;
     }
     Ok(Json(conv))
 }
@@ -206,10 +225,17 @@ pub async fn chat_read(
     state
         .event_bus
         .publish(BackendEvent::ConversationUpdate(ConversationUpdateEvent {
-            topic_id,
+            topic_id: topic_id.clone(),
             owner_id: auth.user_id().to_string(),
             fields: serde_json::to_value(&conv).unwrap_or_else(|_| serde_json::json!({})),
         }));
+    let payload = build_conversation_update_payload(
+        auth.user_id(),
+        &topic_id,
+        &conversation_read_fields(&conv),
+    );
+    crate::api::push::broadcast_to_other_devices(&state, auth.user_id(), auth.device(), &payload)
+        .await;
     state.event_bus.publish(BackendEvent::Read(ReadEvent {
         topic_id: conv.topic_id,
         user_id: auth.user_id().to_string(),
@@ -240,7 +266,8 @@ pub async fn chat_unread(
         &topic_id,
         &serde_json::json!({"markUnread": true}),
     );
-    crate::api::push::broadcast_to_user(&state, auth.user_id(), &payload).await;
+    crate::api::push::broadcast_to_other_devices(&state, auth.user_id(), auth.device(), &payload)
+        .await;
     Ok(Json(true))
 }
 
@@ -608,6 +635,7 @@ pub(crate) async fn send_chat_message(
     if effective_form.r#type.is_empty() {
         effective_form.r#type = "chat".to_string();
     }
+    ensure_send_permission(state, user_id, &topic_id, &effective_form).await?;
 
     let resp = match effective_form
         .content
@@ -628,6 +656,10 @@ pub(crate) async fn send_chat_message(
         }
     }
     .map_err(map_domain_error)?;
+    // the presend hook may have rewritten the content, fan out what was stored
+    if let Some(content) = resp.content.clone() {
+        effective_form.content = Some(content);
+    }
     update_topic_conversations(state, &topic_id, &resp, &effective_form).await;
     state.event_bus.publish(BackendEvent::Chat(ChatEvent {
         topic_id: topic_id.clone(),
@@ -653,6 +685,69 @@ pub(crate) async fn send_chat_message(
     Ok((effective_form, topic_id, resp))
 }
 
+async fn ensure_send_permission(
+    state: &AppState,
+    user_id: &str,
+    topic_id: &str,
+    form: &OpenApiChatMessageForm,
+) -> Result<(), ApiError> {
+    // unknown topics are left to the send path, lookup failures must not skip the checks
+    let topic = match state.topic_service.get_by_id(topic_id).await {
+        Ok(topic) => topic,
+        Err(DomainError::NotFound) => return Ok(()),
+        Err(err) => return Err(map_domain_error(err)),
+    };
+    let content = form.content.as_ref();
+    if !topic.multiple {
+        if matches!(
+            content.map(|c| c.content_type.as_str()),
+            Some("recall") | Some("update.extra")
+        ) {
+            return Ok(());
+        }
+        let attendee = if topic.owner_id == user_id {
+            &topic.attendee_id
+        } else {
+            &topic.owner_id
+        };
+        if attendee.is_empty() {
+            return Ok(());
+        }
+        return state
+            .chat_service
+            .ensure_dm_allowed(user_id, attendee)
+            .await
+            .map_err(map_domain_error);
+    }
+    let mut required = match content.map(|c| c.content_type.as_str()) {
+        Some("recall") | Some("update.extra") => return Ok(()),
+        _ => vec![TopicPermission::Send],
+    };
+    if content.is_some_and(|c| {
+        c.attachment.is_some()
+            || matches!(
+                c.content_type.as_str(),
+                "image" | "file" | "video" | "voice"
+            )
+    }) {
+        required.push(TopicPermission::SendAttachment);
+    }
+    if content.is_some_and(|c| c.mention_all) {
+        required.push(TopicPermission::MentionAll);
+    }
+    for permission in required {
+        if !state
+            .topic_service
+            .has_permission(&topic, user_id, permission)
+            .await
+            .map_err(map_domain_error)?
+        {
+            return Err(ApiError::Unauthorized);
+        }
+    }
+    Ok(())
+}
+
 fn map_domain_error(err: DomainError) -> ApiError {
     match err {
         DomainError::NotFound => ApiError::NotFound,
@@ -660,5 +755,6 @@ fn map_domain_error(err: DomainError) -> ApiError {
         DomainError::Conflict => ApiError::bad_request("resource already exists"),
         DomainError::Forbidden => ApiError::Unauthorized,
         DomainError::Storage(msg) => ApiError::internal(msg),
+        DomainError::Rejected { code, reason } => ApiError::Rejected(code, reason),
     }
 }
@@ -0,0 +1,176 @@
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::Utc;
+use serde_json::json;
+use std::time::Instant;
+
+use crate::api::auth_ctx::AuthCtx;
+use crate::api::error::{ApiError, ApiResult};
+use crate::app::AppState;
+use crate::infra::event::{BackendEvent, ContactRequestEvent};
+use crate::services::DomainError;
+use crate::{ContactRequest, ContactRequestForm, ContactRequestListForm};
+
+pub async fn contact_request_send(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(userid): Path<String>,
+    payload: Option<Json<ContactRequestForm>>,
+) -> ApiResult<Json<ContactRequest>> {
+    let st = Instant::now();
+    let req = state
+        .relation_service
+        .send_contact_request(
+            auth.user_id(),
+            &userid,
+            payload.map(|v| v.0).unwrap_or_default(),
+        )
+        .await
+        .map_err(map_domain_error)?;
+    // a crossed request is accepted right away, so report it as such
+    if req.status == "accepted" {
+        publish_contact_event(&state, "contact.accept", auth.user_id(), &req).await;
+    } else {
+        publish_contact_event(&state, "contact.request", auth.user_id(), &req).await;
+    }
+    tracing::info!(
+        user_id = %auth.user_id(),
+        target_user_id = %userid,
+        status = %req.status,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "contact request sent"
+    );
+    Ok(Json(req))
+}
+
+pub async fn contact_request_list(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    payload: Option<Json<ContactRequestListForm>>,
+) -> ApiResult<Json<Vec<ContactRequest>>> {
+    let st = Instant::now();
+    let items = state
+        .relation_service
+        .list_contact_requests(auth.user_id(), payload.map(|v| v.0).unwrap_or_default())
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        count = items.len(),
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "contact requests listed"
+    );
+    Ok(Json(items))
+}
+
+pub async fn contact_request_accept(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(userid): Path<String>,
+) -> ApiResult<Json<ContactRequest>> {
+    let st = Instant::now();
+    let req = state
+        .relation_service
+        .accept_contact_request(auth.user_id(), &userid)
+        .await
+        .map_err(map_domain_error)?;
+    publish_contact_event(&state, "contact.accept", auth.user_id(), &req).await;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        target_user_id = %userid,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "contact request accepted"
+    );
+    Ok(Json(req))
+}
+
+pub async fn contact_request_decline(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(userid): Path<String>,
+) -> ApiResult<Json<ContactRequest>> {
+    let st = Instant::now();
+    let req = state
+        .relation_service
+        .decline_contact_request(auth.user_id(), &userid)
+        .await
+        .map_err(map_domain_error)?;
+    publish_contact_event(&state, "contact.decline", auth.user_id(), &req).await;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        target_user_id = %userid,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "contact request declined"
+    );
+    Ok(Json(req))
+}
+
+pub async fn contact_request_cancel(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(userid): Path<String>,
+) -> ApiResult<Json<ContactRequest>> {
+    let st = Instant::now();
+    let req = state
+        .relation_service
+        .cancel_contact_request(auth.user_id(), &userid)
+        .await
+        .map_err(map_domain_error)?;
+    publish_contact_event(&state, "contact.cancel", auth.user_id(), &req).await;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        target_user_id = %userid,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "contact request canceled"
+    );
+    Ok(Json(req))
+}
+
+// pushed as a system request to both sides so every device of each user sees it
+async fn publish_contact_event(state: &AppState, name: &str, actor_id: &str, req: &ContactRequest) {
+    let event = ContactRequestEvent {
+        sender_id: req.sender_id.clone(),
+        receiver_id: req.receiver_id.clone(),
+        message: req.message.clone(),
+        source: req.source.clone(),
+    };
+    state.event_bus.publish(match name {
+        "contact.accept" => BackendEvent::ContactAccept(event),
+        "contact.decline" => BackendEvent::ContactDecline(event),
+        "contact.cancel" => BackendEvent::ContactCancel(event),
+        _ => BackendEvent::ContactRequest(event),
+    });
+
+    let payload = serde_json::to_string(&json!({
+        "type": "system",
+        "chatId": format!("contact-{}", uuid::Uuid::new_v4().simple()),
+        "attendee": actor_id,
+        "createdAt": Utc::now().to_rfc3339(),
+        "source": req.source,
+        "content": {
+            "type": name,
+            "text": req.message,
+            "unreadable": true,
+            "extra": {
+                "senderId": req.sender_id,
+                "receiverId": req.receiver_id,
+                "status": req.status,
+            },
+        },
+    }))
+    .unwrap_or_default();
+    for user_id in [&req.sender_id, &req.receiver_id] {
+        crate::api::push::broadcast_to_user(state, user_id, &payload).await;
+    }
+}
+
+fn map_domain_error(err: DomainError) -> ApiError {
+    match err {
+        DomainError::NotFound => ApiError::NotFound,
+        DomainError::Validation(msg) => ApiError::bad_request(msg),
+        DomainError::Conflict => ApiError::bad_request("resource already exists"),
+        DomainError::Forbidden => ApiError::Unauthorized,
+        DomainError::Storage(msg) => ApiError::internal(msg),
+        DomainError::Rejected { code, reason } => ApiError::Rejected(code, reason),
+    }
+}
@@ -10,6 +10,8 @@ pub enum ApiError {
     Unauthorized,
     #[error("invalid token")]
     InvalidToken,
+    #[error("token expired: {0}")]
+    TokenExpired(String),
     #[error("not found")]
     NotFound,
     #[error("bad request: {0}")]
@@ -18,6 +20,14 @@ pub enum ApiError {
     Internal(String),
     #[error("not implemented: {0}")]
     NotImplemented(String),
+    #[error("too many requests")]
+    TooManyRequests,
+    #[error("too many failed logins, retry in {0}s")]
+    LockedOut(u64),
+    #[error("insufficient scope, {0} required")]
+    InsufficientScope(&'static str),
+    #[error("{1}")]
+    Rejected(u16, String),
 }
 
 impl ApiError {
@@ -37,18 +47,32 @@ impl ApiError {
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let status = match self {
-            Self::Unauthorized | Self::InvalidToken => StatusCode::UNAUTHORIZED,
+            Self::Unauthorized | Self::InvalidToken | Self::TokenExpired(_) => {
+                StatusCode::UNAUTHORIZED
+            }
             Self::NotFound => StatusCode::NOT_FOUND,
             Self::BadRequest(_) => StatusCode::BAD_REQUEST,
             Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::NotImplemented(_) => StatusCode::NOT_IMPLEMENTED,
+            Self::TooManyRequests | Self::LockedOut(_) => StatusCode::TOO_MANY_REQUESTS,
+            Self::InsufficientScope(_) => StatusCode::FORBIDDEN,
+            Self::Rejected(code, _) => StatusCode::from_u16(code).unwrap_or(StatusCode::FORBIDDEN),
+        };
+        let retry_after = match self {
+            Self::LockedOut(secs) => Some(secs),
+            _ => None,
         };
 
         let body = ApiErrorBody {
             error: self.to_string(),
         };
 
-        (status, Json(body)).into_response()
+        let mut resp = (status, Json(body)).into_response();
+        if let Some(secs) = retry_after {
+            resp.headers_mut()
+                .insert(axum::http::header::RETRY_AFTER, secs.into());
+        }
+        resp
     }
 }
 
@@ -1,13 +1,17 @@
 use axum::extract::State;
-use axum::http::{header, Request};
+use axum::http::{header, HeaderMap, Request, Uri};
 use axum::middleware::Next;
 use axum::response::Response;
+use axum::routing::MethodRouter;
 
-use crate::api::access_log::AccessLogUserId;
+use crate::api::access_log::{client_ip, AccessLogUserId, ClientIp};
+use crate::api::auth::token_status_error;
 use crate::api::auth_ctx::{AuthToken, AuthUserId};
 use crate::api::error::ApiError;
 use crate::app::AppState;
-use crate::services::parse_bearer_token;
+use crate::services::{
+    parse_bearer_token, ApiKeyGrant, ApiKeyService, ApiScope, DomainError, TokenStatus,
+};
 
 pub async fn openapi_auth(
     State(state): State<AppState>,
@@ -20,11 +24,11 @@ pub async fn openapi_auth(
         .get(header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
     else {
-        if expected.is_some() {
-            tracing::warn!("openapi auth rejected: missing authorization header");
-            return Err(ApiError::Unauthorized);
+        if expected.is_none() && state.config.openapi_allow_anonymous {
+            return Ok(next.run(req).await);
         }
-        return Ok(next.run(req).await);
+        tracing::warn!("openapi auth rejected: missing authorization header");
+        return Err(ApiError::Unauthorized);
     };
 
     let token = parse_bearer_token(header_val).ok_or_else(|| {
@@ -41,19 +45,53 @@ pub async fn openapi_auth(
         }
         return Ok(next.run(req).await);
     }
+    if ApiKeyService::is_api_key(&token) {
+        let ClientIp(ip) = client_ip(
+            req.headers(),
+            req.extensions(),
+            &state.config.trusted_proxies,
+        );
+        let grant = state
+            .api_key_service
+            .authenticate(&token, &ip)
+            .await
+            .map_err(|err| match err {
+                DomainError::Storage(err) => ApiError::internal(err),
+                err => {
+                    tracing::warn!(ip = %ip, reason = %err, "openapi auth rejected: invalid api key");
+                    ApiError::Unauthorized
+                }
+            })?;
+        if let Some(slot) = req.extensions().get::<AccessLogUserId>() {
+            if let Ok(mut guard) = slot.0.lock() {
+                *guard = Some(format!("apikey:{}", grant.name));
+            }
+        }
+        req.extensions_mut().insert(grant);
+        return Ok(next.run(req).await);
+    }
 
-    let valid = state
+    let status = state
         .auth_service
-        .validate(&token)
+        .validate_status(&token)
         .await
         .map_err(|err| ApiError::internal(err.to_string()))?;
-    let Some(user_id) = valid else {
-        tracing::warn!("openapi auth rejected: invalid token");
-        return Err(ApiError::InvalidToken);
+    let TokenStatus::Valid(user_id) = status else {
+        tracing::warn!(
+            reason = status.reason(),
+            "openapi auth rejected: invalid token"
+        );
+        return Err(token_status_error(&status));
     };
+    let is_staff = state
+        .user_service
+        .is_staff(&user_id)
+        .await
+        .map_err(|err| ApiError::internal(err.to_string()))?;
     let user_for_log = user_id.clone();
     req.extensions_mut().insert(AuthToken(token));
     req.extensions_mut().insert(AuthUserId(user_id));
+    req.extensions_mut().insert(UserTokenScopes { is_staff });
     if let Some(slot) = req.extensions().get::<AccessLogUserId>() {
         if let Ok(mut guard) = slot.0.lock() {
             *guard = Some(user_for_log);
@@ -63,19 +101,88 @@ pub async fn openapi_auth(
     Ok(next.run(req).await)
 }
 
+// what a user token may reach on the openapi routes: every scope but admin, which
+// needs a staff account; the handlers still check the user owns what it touches
+#[derive(Clone, Copy, Debug)]
+pub struct UserTokenScopes {
+    pub is_staff: bool,
+}
+
+impl UserTokenScopes {
+    pub fn allows(&self, scope: ApiScope) -> bool {
+        self.is_staff || scope != ApiScope::Admin
+    }
+}
+
+// api keys and user tokens are limited by scope, the super token is checked by the handlers
+pub async fn require_scope(
+    State(scope): State<ApiScope>,
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if let Some(grant) = req.extensions().get::<ApiKeyGrant>() {
+        if !grant.allows(scope) {
+            tracing::warn!(
+                key_id = %grant.key_id,
+                scope = scope.as_str(),
+                path = %req.uri().path(),
+                "openapi auth rejected: insufficient scope"
+            );
+            return Err(ApiError::InsufficientScope(scope.as_str()));
+        }
+    } else if let Some(AuthUserId(user_id)) = req.extensions().get::<AuthUserId>() {
+        let allowed = req
+            .extensions()
+            .get::<UserTokenScopes>()
+            .is_some_and(|scopes| scopes.allows(scope));
+        if !allowed {
+            tracing::warn!(
+                user_id = %user_id,
+                scope = scope.as_str(),
+                path = %req.uri().path(),
+                "openapi auth rejected: insufficient scope for user token"
+            );
+            return Err(ApiError::InsufficientScope(scope.as_str()));
+        }
+    }
+    Ok(next.run(req).await)
+}
+
+pub fn scoped(scope: ApiScope, route: MethodRouter<AppState>) -> MethodRouter<AppState> {
+    route.route_layer(axum::middleware::from_fn_with_state(scope, require_scope))
+}
+
 pub async fn user_auth(
     State(state): State<AppState>,
     mut req: Request<axum::body::Body>,
     next: Next,
 ) -> Result<Response, ApiError> {
-    let token = req
-        .headers()
+    let token = request_token(req.headers(), req.uri()).ok_or_else(|| {
+        tracing::warn!("user auth rejected: missing authorization header or token cookie");
+        ApiError::Unauthorized
+    })?;
+    let user_id = authenticate_user_token(&state, &token).await?;
+    let user_for_log = user_id.clone();
+
+    req.extensions_mut().insert(AuthToken(token));
+    req.extensions_mut().insert(AuthUserId(user_id));
+    if let Some(slot) = req.extensions().get::<AccessLogUserId>() {
+        if let Ok(mut guard) = slot.0.lock() {
+            *guard = Some(user_for_log);
+        }
+    }
+    Ok(next.run(req).await)
+}
+
+// bearer header, then the token cookie, then `?token=` for browser websockets
+pub(crate) fn request_token(headers: &HeaderMap, uri: &Uri) -> Option<String> {
+    headers
         .get(header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
         .and_then(parse_bearer_token)
         .map(str::to_string)
         .or_else(|| {
-            req.headers()
+            headers
                 .get(header::COOKIE)
                 .and_then(|v| v.to_str().ok())
                 .and_then(|cookies| {
@@ -86,32 +193,28 @@ pub async fn user_auth(
                 })
         })
         .or_else(|| {
-            // WebSocket connections may pass token as a query parameter
-            req.uri()
-                .query()
-                .and_then(|q| q.split('&').find_map(|pair| pair.strip_prefix("token=").map(str::to_string)))
+            uri.query().and_then(|q| {
+                q.split('&')
+                    .find_map(|pair| pair.strip_prefix("token=").map(str::to_string))
+            })
         })
-        .ok_or_else(|| {
-            tracing::warn!("user auth rejected: missing authorization header or token cookie");
-            ApiError::Unauthorized
-        })?;
-    let valid = state
+}
+
+pub(crate) async fn authenticate_user_token(
+    state: &AppState,
+    token: &str,
+) -> Result<String, ApiError> {
+    let status = state
         .auth_service
-        .validate(&token)
+        .validate_status(token)
         .await
         .map_err(|err| ApiError::internal(err.to_string()))?;
-    let Some(user_id) = valid else {
-        tracing::warn!("user auth rejected: invalid token");
-        return Err(ApiError::InvalidToken);
+    let TokenStatus::Valid(user_id) = status else {
+        tracing::warn!(
+            reason = status.reason(),
+            "user auth rejected: invalid token"
+        );
+        return Err(token_status_error(&status));
     };
-    let user_for_log = user_id.clone();
-
-    req.extensions_mut().insert(AuthToken(token));
-    req.extensions_mut().insert(AuthUserId(user_id));
-    if let Some(slot) = req.extensions().get::<AccessLogUserId>() {
-        if let Ok(mut guard) = slot.0.lock() {
-            *guard = Some(user_for_log);
-        }
-    }
-    Ok(next.run(req).await)
+    Ok(user_id)
 }
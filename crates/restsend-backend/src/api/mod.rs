@@ -4,11 +4,14 @@ pub mod attachment;
 pub mod auth;
 pub mod auth_ctx;
 pub mod chat;
+pub mod contact;
 pub mod error;
 pub mod health;
 pub mod helpdesk;
 pub mod middleware_auth;
+pub mod oidc;
 pub mod openapi;
+pub mod presence;
 pub mod push;
 pub mod routes_ws;
 pub mod topic;
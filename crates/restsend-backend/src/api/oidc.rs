@@ -0,0 +1,223 @@
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::IntoResponse;
+use std::time::Instant;
+
+use crate::api::access_log::ClientIp;
+use crate::api::auth::{cookie_response, request_device, to_auth_login_response, token_cookie};
+use crate::api::error::{ApiError, ApiResult};
+use crate::app::AppState;
+use crate::infra::oidc::OidcPending;
+use crate::services::DomainError;
+
+const PENDING_COOKIE: &str = "oidc_pending";
+const PENDING_TTL_SECS: i64 = 600;
+
+#[derive(serde::Deserialize, Default)]
+pub struct OidcStartQuery {
+    // app path to land on after the callback, the json login response is returned without it
+    #[serde(default)]
+    pub redirect: String,
+}
+
+#[derive(serde::Deserialize, Default)]
+pub struct OidcCallbackQuery {
+    #[serde(default)]
+    pub code: String,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub error: String,
+}
+
+pub async fn start(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Query(query): Query<OidcStartQuery>,
+) -> ApiResult<axum::response::Response> {
+    let provider = state
+        .oidc
+        .provider(&provider)
+        .cloned()
+        .ok_or(ApiError::NotFound)?;
+    let redirect = query.redirect.trim();
+    if !is_local_redirect(redirect) {
+        return Err(ApiError::bad_request("redirect must be a relative path"));
+    }
+    let (url, pending) = state
+        .oidc
+        .start(&provider, redirect, PENDING_TTL_SECS)
+        .await
+        .map_err(|err| {
+            tracing::warn!(provider = %provider.name, error = %err, "oidc start failed");
+            ApiError::internal(err)
+        })?;
+    let cookie = format!(
+        "{PENDING_COOKIE}={}; Path=/auth/oidc; HttpOnly; SameSite=Lax; Max-Age={PENDING_TTL_SECS}",
+        pending.encode()
+    );
+    Ok(redirect_response(&url, &cookie))
+}
+
+pub async fn callback(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    client_ip: ClientIp,
+    headers: HeaderMap,
+    Query(query): Query<OidcCallbackQuery>,
+) -> ApiResult<axum::response::Response> {
+    let st = Instant::now();
+    let provider = state
+        .oidc
+        .provider(&provider)
+        .cloned()
+        .ok_or(ApiError::NotFound)?;
+    if !query.error.is_empty() {
+        tracing::warn!(provider = %provider.name, error = %query.error, "oidc login rejected by idp");
+        return Err(ApiError::bad_request(format!(
+            "oidc error: {}",
+            query.error
+        )));
+    }
+    let pending = pending_from_cookie(&headers).ok_or_else(|| {
+        tracing::warn!(provider = %provider.name, "oidc callback rejected: missing state cookie");
+        ApiError::Unauthorized
+    })?;
+    if pending.provider != provider.name
+        || pending.state != query.state
+        || pending.expires_at < chrono::Utc::now().timestamp()
+    {
+        tracing::warn!(provider = %provider.name, "oidc callback rejected: state mismatch");
+        return Err(ApiError::Unauthorized);
+    }
+    // the pending cookie is client supplied, check the redirect again before following it
+    if !is_local_redirect(&pending.redirect) {
+        tracing::warn!(provider = %provider.name, "oidc callback rejected: invalid redirect");
+        return Err(ApiError::Unauthorized);
+    }
+
+    let claims = state
+        .oidc
+        .exchange(&provider, &pending, &query.code)
+        .await
+        .map_err(|err| {
+            tracing::warn!(provider = %provider.name, error = %err, "oidc code exchange failed");
+            ApiError::Unauthorized
+        })?;
+    let subject = claims
+        .get(&provider.user_claim)
+        .map(claim_to_string)
+        .unwrap_or_default();
+    if subject.trim().is_empty() {
+        tracing::warn!(
+            provider = %provider.name,
+            claim = %provider.user_claim,
+            "oidc callback rejected: user claim missing"
+        );
+        return Err(ApiError::Unauthorized);
+    }
+
+    let mut user = state
+        .user_service
+        .get_or_create_for_oidc(&provider.name, &subject, provider.create_users)
+        .await
+        .map_err(|err| match err {
+            DomainError::NotFound | DomainError::Forbidden => ApiError::Unauthorized,
+            DomainError::Conflict => {
+                tracing::warn!(
+                    provider = %provider.name,
+                    subject = %subject,
+                    "oidc callback rejected: account exists but is not linked to the provider"
+                );
+                ApiError::Unauthorized
+            }
+            err => ApiError::internal(err.to_string()),
+        })?;
+    let user_id = user.user_id.clone();
+    let mut extra = user.extra.clone().unwrap_or_default();
+    let mut changed = false;
+    for name in &provider.extra_claims {
+        if let Some(value) = claims.get(name).map(claim_to_string) {
+            changed |= extra.insert(name.clone(), value.clone()) != Some(value);
+        }
+    }
+    if changed {
+        user = state
+            .user_service
+            .update(
+                &user_id,
+                crate::OpenApiUserForm {
+                    extra: Some(extra),
+                    ..crate::OpenApiUserForm::default()
+                },
+            )
+            .await
+            .map_err(|e| ApiError::internal(e.to_string()))?;
+    }
+
+    let (device, ip) = request_device(&headers, &client_ip, &crate::DeviceInfoForm::default());
+    let issued = state
+        .auth_service
+        .issue_token_with_device(&user_id, &device, &ip)
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+    tracing::info!(
+        user_id = %user_id,
+        provider = %provider.name,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "auth oidc login succeeded"
+    );
+
+    let clear_pending = format!("{PENDING_COOKIE}=; Path=/auth/oidc; HttpOnly; Max-Age=0");
+    let mut resp = if pending.redirect.is_empty() {
+        cookie_response(to_auth_login_response(user, &issued), &issued)
+    } else {
+        redirect_response(&pending.redirect, &token_cookie(&issued))
+    };
+    if let Ok(value) = HeaderValue::from_str(&clear_pending) {
+        resp.headers_mut().append(header::SET_COOKIE, value);
+    }
+    Ok(resp)
+}
+
+// only same-site paths, otherwise this is an open redirect. Browsers treat `\` like
+// `/`, so `/\evil.com` would leave the site as well
+fn is_local_redirect(redirect: &str) -> bool {
+    redirect.is_empty()
+        || (redirect.starts_with('/')
+            && !redirect.starts_with("//")
+            && !redirect.chars().any(|c| c == '\\' || c.is_control()))
+}
+
+fn redirect_response(location: &str, cookie: &str) -> axum::response::Response {
+    let mut resp = StatusCode::FOUND.into_response();
+    if let Ok(value) = HeaderValue::from_str(location) {
+        resp.headers_mut().insert(header::LOCATION, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(cookie) {
+        resp.headers_mut().append(header::SET_COOKIE, value);
+    }
+    resp
+}
+
+fn pending_from_cookie(headers: &HeaderMap) -> Option<OidcPending> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .find_map(|pair| {
+            pair.trim()
+                .strip_prefix(&format!("{PENDING_COOKIE}="))
+                .map(str::to_string)
+        })
+        .and_then(|value| OidcPending::decode(&value))
+}
+
+fn claim_to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(v) => v.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
@@ -7,10 +7,11 @@ use crate::api::auth_ctx::AuthCtx;
 use crate::api::error::{ApiError, ApiResult};
 use crate::app::AppState;
 use crate::infra::event::{
-    BackendEvent, ChatEvent, ConversationRemovedEvent, ConversationUpdateEvent,
+    BackendEvent, ChatEvent, ConversationRemovedEvent, ConversationUpdateEvent, TopicBanEvent,
     TopicChangeOwnerEvent, TopicSilentEvent, TopicSimpleEvent, TopicUserEvent,
 };
-use crate::services::DomainError;
+use crate::infra::lockout::LoginGuard;
+use crate::services::{ApiScope, DomainError};
 use crate::{
     ChatLogSyncForm, ListUserResult, OpenApiAuthForm, OpenApiChatMessageForm,
     OpenApiCreateTopicForm, OpenApiDocItem, OpenApiDocSchema, OpenApiImportTopicMessageForm,
@@ -18,8 +19,9 @@ use crate::{
     OpenApiSendChatMessageWithFormatForm, OpenApiSendMessageResponse, OpenApiSendTopicMessageForm,
     OpenApiSendTopicMessageWithFormatForm, OpenApiSilentTopicForm, OpenApiSilentTopicMembersForm,
     OpenApiUpdateConversationForm, OpenApiUpdateTopicExtraForm, OpenApiUpdateTopicForm,
-    OpenApiUpdateTopicMemberForm, OpenApiUserForm, OpenApiUserListForm, Relation, UserOnlineResult,
-    UserPublicProfile,
+    OpenApiUpdateTopicMemberForm, OpenApiUserForm, OpenApiUserListForm, Relation, TopicBanForm,
+    TopicInviteForm, TopicKnockRuleForm, TopicMemberListForm, TopicRoleAssignForm, TopicRoleForm,
+    UserOnlineResult, UserPublicProfile,
 };
 
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -134,7 +136,11 @@ pub async fn user_register(
         .await
         .map_err(map_domain_error)?;
 
-    Ok(Json(UserPublicProfile { user, auth_token }))
+    Ok(Json(UserPublicProfile {
+        user,
+        auth_token,
+        refresh_token: String::new(),
+    }))
 }
 
 pub async fn user_list(
@@ -167,6 +173,28 @@ pub async fn user_list(
     }))
 }
 
+pub async fn user_search(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    payload: Option<Json<crate::OpenApiUserSearchForm>>,
+) -> ApiResult<Json<crate::UserDirectoryResult>> {
+    auth.ensure_staff()?;
+    let form = payload.map(|v| v.0).unwrap_or_default();
+    let keyword = form.keyword.clone();
+    let result = state
+        .user_service
+        .search_users(form)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        keyword = %keyword,
+        total = result.total,
+        "openapi user search"
+    );
+    Ok(Json(result))
+}
+
 pub async fn user_set_enabled(
     State(state): State<AppState>,
     auth: AuthCtx,
@@ -226,6 +254,15 @@ pub async fn user_auth(
     payload: Option<Json<OpenApiAuthForm>>,
 ) -> ApiResult<Json<UserPublicProfile>> {
     auth.ensure_user_or_staff(&user_id)?;
+    // a token for a staff account is as good as the admin scope
+    if state
+        .user_service
+        .is_staff(&user_id)
+        .await
+        .map_err(map_domain_error)?
+    {
+        auth.ensure_scope(ApiScope::Admin)?;
+    }
     let form = payload.map(|v| v.0).unwrap_or_default();
 
     let user = state
@@ -234,13 +271,75 @@ pub async fn user_auth(
         .await
         .map_err(map_domain_error)?;
 
-    let auth_token = state
+    let issued = state
         .auth_service
-        .issue_token(&user_id)
+        .issue_token_with_device(&user_id, &form.device, &form.client_ip)
+        .await
+        .map_err(map_domain_error)?;
+
+    Ok(Json(UserPublicProfile {
+        user,
+        auth_token: issued.token,
+        refresh_token: issued.refresh_token,
+    }))
+}
+
+pub async fn user_sessions(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(user_id): Path<String>,
+) -> ApiResult<Json<Vec<crate::DeviceSession>>> {
+    auth.ensure_staff()?;
+    let items = crate::api::user::list_device_sessions(&state, &user_id, "")
+        .await
+        .map_err(map_domain_error)?;
+    Ok(Json(items))
+}
+
+pub async fn user_revoke_session(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((user_id, session_id)): Path<(String, String)>,
+) -> ApiResult<Json<bool>> {
+    auth.ensure_staff()?;
+    let device = state
+        .auth_service
+        .revoke_session(&user_id, &session_id)
         .await
         .map_err(map_domain_error)?;
+    if !device.is_empty() {
+        crate::api::user::kick_device(&state, &user_id, &device).await;
+    }
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        target_user_id = %user_id,
+        session_id = %session_id,
+        "openapi user session revoked"
+    );
+    Ok(Json(true))
+}
 
-    Ok(Json(UserPublicProfile { user, auth_token }))
+pub async fn user_revoke_all_sessions(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(user_id): Path<String>,
+) -> ApiResult<Json<bool>> {
+    auth.ensure_staff()?;
+    let devices = state
+        .auth_service
+        .revoke_other_sessions(&user_id, "")
+        .await
+        .map_err(map_domain_error)?;
+    for device in &devices {
+        crate::api::user::kick_device(&state, &user_id, device).await;
+    }
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        target_user_id = %user_id,
+        kicked = devices.len(),
+        "openapi user sessions revoked"
+    );
+    Ok(Json(true))
 }
 
 pub async fn user_update(
@@ -251,6 +350,9 @@ pub async fn user_update(
 ) -> ApiResult<Json<bool>> {
     auth.ensure_user_or_staff(&user_id)?;
     let has_password = !form.password.is_empty();
+    if has_password {
+        crate::api::auth::check_password_policy(&state, &user_id, &form.password)?;
+    }
     state
         .user_service
         .update(&user_id, form)
@@ -265,6 +367,284 @@ pub async fn user_update(
     Ok(Json(true))
 }
 
+pub async fn auth_lockouts(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+) -> ApiResult<Json<Vec<crate::LoginLockout>>> {
+    auth.ensure_staff()?;
+    let items = state
+        .login_guard
+        .list_locked()
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+    Ok(Json(items.into_iter().map(crate::LoginLockout::from).collect()))
+}
+
+pub async fn auth_lockouts_clear(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Json(form): Json<crate::OpenApiLockoutClearForm>,
+) -> ApiResult<Json<bool>> {
+    auth.ensure_staff()?;
+    let mut keys = Vec::new();
+    if !form.user_id.trim().is_empty() {
+        keys.push(LoginGuard::account_key(&form.user_id));
+    }
+    if !form.ip.trim().is_empty() {
+        keys.push(LoginGuard::ip_key(&form.ip));
+    }
+    if keys.is_empty() {
+        return Err(ApiError::bad_request("userId or ip is required"));
+    }
+    let mut cleared = false;
+    for key in &keys {
+        cleared |= state
+            .login_guard
+            .clear(key)
+            .await
+            .map_err(|e| ApiError::internal(e.to_string()))?;
+    }
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        keys = ?keys,
+        cleared,
+        "openapi login lockouts cleared"
+    );
+    Ok(Json(cleared))
+}
+
+pub async fn apikey_list(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+) -> ApiResult<Json<Vec<crate::OpenApiKey>>> {
+    auth.ensure_staff()?;
+    let items = state
+        .api_key_service
+        .list()
+        .await
+        .map_err(map_domain_error)?;
+    Ok(Json(items))
+}
+
+pub async fn apikey_create(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Json(form): Json<crate::OpenApiKeyForm>,
+) -> ApiResult<Json<crate::OpenApiKeySecret>> {
+    auth.ensure_staff()?;
+    let created = state
+        .api_key_service
+        .create(form, auth.user_id())
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        key_id = %created.key.id,
+        name = %created.key.name,
+        scopes = ?created.key.scopes,
+        "openapi api key created"
+    );
+    Ok(Json(created))
+}
+
+pub async fn apikey_rotate(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(key_id): Path<String>,
+) -> ApiResult<Json<crate::OpenApiKeySecret>> {
+    auth.ensure_staff()?;
+    let rotated = state
+        .api_key_service
+        .rotate(&key_id)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        key_id = %key_id,
+        "openapi api key rotated"
+    );
+    Ok(Json(rotated))
+}
+
+pub async fn apikey_revoke(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(key_id): Path<String>,
+) -> ApiResult<Json<bool>> {
+    auth.ensure_staff()?;
+    state
+        .api_key_service
+        .revoke(&key_id)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        key_id = %key_id,
+        "openapi api key revoked"
+    );
+    Ok(Json(true))
+}
+
+pub async fn webhook_events(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Json(form): Json<crate::WebhookSelectForm>,
+) -> ApiResult<Json<Vec<crate::WebhookEventView>>> {
+    auth.ensure_staff()?;
+    let items = state
+        .webhook_outbox
+        .list_events(&form)
+        .await
+        .map_err(map_domain_error)?;
+    Ok(Json(items))
+}
+
+pub async fn webhook_outbox(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Json(form): Json<crate::WebhookSelectForm>,
+) -> ApiResult<Json<Vec<crate::WebhookDelivery>>> {
+    auth.ensure_staff()?;
+    let items = state
+        .webhook_outbox
+        .list_outbox(&form)
+        .await
+        .map_err(map_domain_error)?;
+    Ok(Json(items))
+}
+
+pub async fn webhook_dead_letters(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Json(form): Json<crate::WebhookSelectForm>,
+) -> ApiResult<Json<Vec<crate::WebhookDelivery>>> {
+    auth.ensure_staff()?;
+    let items = state
+        .webhook_outbox
+        .list_dead_letters(&form)
+        .await
+        .map_err(map_domain_error)?;
+    Ok(Json(items))
+}
+
+pub async fn webhook_retry_dead_letters(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Json(form): Json<crate::WebhookSelectForm>,
+) -> ApiResult<Json<crate::WebhookRequeueResult>> {
+    auth.ensure_staff()?;
+    let count = state
+        .webhook_outbox
+        .retry_dead_letters(&form, chrono::Utc::now().timestamp())
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        ids = ?form.ids,
+        since = %form.since,
+        until = %form.until,
+        count,
+        "webhook dead letters requeued"
+    );
+    Ok(Json(crate::WebhookRequeueResult { count }))
+}
+
+pub async fn webhook_replay(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Json(form): Json<crate::WebhookSelectForm>,
+) -> ApiResult<Json<crate::WebhookRequeueResult>> {
+    auth.ensure_staff()?;
+    let count = state
+        .webhook_outbox
+        .replay(&form)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        ids = ?form.ids,
+        since = %form.since,
+        until = %form.until,
+        count,
+        "webhook events replayed"
+    );
+    Ok(Json(crate::WebhookRequeueResult { count }))
+}
+
+pub async fn webhook_subscription_list(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+) -> ApiResult<Json<Vec<crate::WebhookSubscription>>> {
+    auth.ensure_staff()?;
+    let items = state
+        .webhook_subscriptions
+        .list()
+        .await
+        .map_err(map_domain_error)?;
+    Ok(Json(items))
+}
+
+pub async fn webhook_subscription_create(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Json(form): Json<crate::WebhookSubscriptionForm>,
+) -> ApiResult<Json<crate::WebhookSubscription>> {
+    auth.ensure_staff()?;
+    let created = state
+        .webhook_subscriptions
+        .create(form, auth.user_id())
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        subscription_id = %created.id,
+        url = %created.url,
+        events = ?created.events,
+        "webhook subscription created"
+    );
+    Ok(Json(created))
+}
+
+pub async fn webhook_subscription_update(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(subscription_id): Path<String>,
+    Json(form): Json<crate::WebhookSubscriptionForm>,
+) -> ApiResult<Json<crate::WebhookSubscription>> {
+    auth.ensure_staff()?;
+    let updated = state
+        .webhook_subscriptions
+        .update(&subscription_id, form)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        subscription_id = %subscription_id,
+        enabled = updated.enabled,
+        "webhook subscription updated"
+    );
+    Ok(Json(updated))
+}
+
+pub async fn webhook_subscription_delete(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(subscription_id): Path<String>,
+) -> ApiResult<Json<bool>> {
+    auth.ensure_staff()?;
+    state
+        .webhook_subscriptions
+        .delete(&subscription_id)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        subscription_id = %subscription_id,
+        "webhook subscription deleted"
+    );
+    Ok(Json(true))
+}
+
 pub async fn user_relation_update(
     State(state): State<AppState>,
     auth: AuthCtx,
@@ -561,7 +941,7 @@ pub async fn topic_send_message(
     State(state): State<AppState>,
     _auth: AuthCtx,
     Path(topic_id): Path<String>,
-    Json(form): Json<OpenApiSendTopicMessageForm>,
+    Json(mut form): Json<OpenApiSendTopicMessageForm>,
 ) -> ApiResult<Json<OpenApiSendMessageResponse>> {
     if form.ensure {
         ensure_topic_exists_for_send(&state, &topic_id, &form).await?;
@@ -578,6 +958,9 @@ pub async fn topic_send_message(
         .send_to_topic(&topic_id, &sender_id, &form.message)
         .await
         .map_err(map_domain_error)?;
+    if let Some(content) = resp.content.clone() {
+        form.message.content = Some(content);
+    }
 
     fanout_topic_message(&state, &topic_id, &resp, &form.message).await;
     state.event_bus.publish(BackendEvent::Chat(ChatEvent {
@@ -601,7 +984,7 @@ pub async fn topic_send_message_with_format(
         ensure_topic_exists_for_converter_send(&state, &topic_id, &form).await?;
     }
 
-    let Some(message) = convert_message(&format, form.message)? else {
+    let Some(mut message) = convert_message(&format, form.message)? else {
         return Ok(Json(json!({})));
     };
 
@@ -610,6 +993,9 @@ pub async fn topic_send_message_with_format(
         .send_to_topic(&topic_id, &form.sender_id, &message)
         .await
         .map_err(map_domain_error)?;
+    if let Some(content) = resp.content.clone() {
+        message.content = Some(content);
+    }
 
     fanout_topic_message(&state, &topic_id, &resp, &message).await;
     state.event_bus.publish(BackendEvent::Chat(ChatEvent {
@@ -652,7 +1038,7 @@ pub async fn chat_send_message(
                 sender_id: sender_id.clone(),
                 attendee_id,
                 chat_id: form.message.chat_id.clone(),
-                code: 500,
+                code: send_error_code(&err),
                 message: err.to_string(),
                 ..OpenApiSendMessageResponse::default()
             }),
@@ -689,7 +1075,7 @@ pub async fn chat_send_message_with_format(
                 sender_id: sender_id.clone(),
                 attendee_id,
                 chat_id: message.chat_id.clone(),
-                code: 500,
+                code: send_error_code(&err),
                 message: err.to_string(),
                 ..OpenApiSendMessageResponse::default()
             }),
@@ -701,14 +1087,23 @@ pub async fn chat_send_message_with_format(
     ))
 }
 
+fn send_error_code(err: &DomainError) -> i32 {
+    match err {
+        DomainError::Rejected { code, .. } => *code as i32,
+        _ => 500,
+    }
+}
+
 pub async fn topic_members(
     State(state): State<AppState>,
     _auth: AuthCtx,
     Path(topic_id): Path<String>,
+    payload: Option<Json<TopicMemberListForm>>,
 ) -> ApiResult<Json<ListUserResult>> {
+    let form = payload.map(|v| v.0).unwrap_or_default();
     let result = state
         .topic_service
-        .list_members_detailed(&topic_id, None, Some(100))
+        .list_members_detailed(&topic_id, "", form)
         .await
         .map_err(map_domain_error)?;
     Ok(Json(result))
@@ -1031,56 +1426,313 @@ pub async fn topic_silent(
     Ok(Json(true))
 }
 
-pub async fn conversation_info(
+pub async fn topic_invite_create(
     State(state): State<AppState>,
     auth: AuthCtx,
-    Path((user_id, topic_id)): Path<(String, String)>,
-) -> ApiResult<Json<crate::Conversation>> {
-    auth.ensure_user_or_staff(&user_id)?;
-    let conversation = state
-        .conversation_service
-        .get_conversation(&user_id, &topic_id)
+    Path(topic_id): Path<String>,
+    payload: Option<Json<TopicInviteForm>>,
+) -> ApiResult<Json<crate::TopicInvite>> {
+    auth.ensure_staff()?;
+    let mut form = payload.map(|v| v.0).unwrap_or_default();
+    if form.created_by.is_empty() {
+        form.created_by = auth.user_id().to_string();
+    }
+    let invite = state
+        .topic_service
+        .create_invite(&topic_id, form)
         .await
         .map_err(map_domain_error)?;
-    Ok(Json(conversation))
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        "openapi topic invite link created"
+    );
+    Ok(Json(invite))
 }
 
-pub async fn conversation_update(
+pub async fn topic_invite_list(
     State(state): State<AppState>,
     auth: AuthCtx,
-    Path((user_id, topic_id)): Path<(String, String)>,
-    Json(form): Json<OpenApiUpdateConversationForm>,
-) -> ApiResult<Json<crate::Conversation>> {
-    auth.ensure_user_or_staff(&user_id)?;
-    let fields = crate::api::chat::conversation_update_fields(&form);
-    let conversation = state
-        .conversation_service
-        .update_conversation(&user_id, &topic_id, form)
+    Path(topic_id): Path<String>,
+) -> ApiResult<Json<Vec<crate::TopicInvite>>> {
+    auth.ensure_staff()?;
+    let invites = state
+        .topic_service
+        .list_invites(&topic_id)
         .await
         .map_err(map_domain_error)?;
-    if !fields.as_object().is_some_and(|v| v.is_empty()) {
-        state
-            .event_bus
-            .publish(BackendEvent::ConversationUpdate(ConversationUpdateEvent {
-                topic_id: topic_id.clone(),
-                owner_id: user_id.clone(),
-                fields: fields.clone(),
-            }));
-        let payload =
-            crate::api::chat::build_conversation_update_payload(&user_id, &topic_id, &fields);
-        crate::api::push::broadcast_to_user(&state, &user_id, &payload).await;
-    }
-    Ok(Json(conversation))
+    Ok(Json(invites))
 }
 
-pub async fn conversation_remove(
+pub async fn topic_invite_revoke(
     State(state): State<AppState>,
     auth: AuthCtx,
-    Path((user_id, topic_id)): Path<(String, String)>,
+    Path((topic_id, token)): Path<(String, String)>,
 ) -> ApiResult<Json<bool>> {
-    auth.ensure_user_or_staff(&user_id)?;
+    auth.ensure_staff()?;
     state
-        .conversation_service
+        .topic_service
+        .revoke_invite(&topic_id, &token)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        "openapi topic invite link revoked"
+    );
+    Ok(Json(true))
+}
+
+pub async fn topic_knock_rule_get(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+) -> ApiResult<Json<crate::TopicKnockRule>> {
+    auth.ensure_staff()?;
+    let rule = state
+        .topic_service
+        .get_knock_rule(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    Ok(Json(rule))
+}
+
+pub async fn topic_knock_rule_update(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+    payload: Option<Json<TopicKnockRuleForm>>,
+) -> ApiResult<Json<crate::TopicKnockRule>> {
+    auth.ensure_staff()?;
+    let rule = state
+        .topic_service
+        .update_knock_rule(&topic_id, payload.map(|v| v.0).unwrap_or_default())
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        "openapi topic knock rule updated"
+    );
+    Ok(Json(rule))
+}
+
+pub async fn topic_role_list(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+) -> ApiResult<Json<Vec<crate::TopicRole>>> {
+    auth.ensure_staff()?;
+    let roles = state
+        .topic_service
+        .list_roles(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    Ok(Json(roles))
+}
+
+pub async fn topic_role_update(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((topic_id, role)): Path<(String, String)>,
+    Json(form): Json<TopicRoleForm>,
+) -> ApiResult<Json<crate::TopicRole>> {
+    auth.ensure_staff()?;
+    let role = state
+        .topic_service
+        .update_role(&topic_id, &role, form)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        role = %role.name,
+        "openapi topic role updated"
+    );
+    Ok(Json(role))
+}
+
+pub async fn topic_role_remove(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((topic_id, role)): Path<(String, String)>,
+) -> ApiResult<Json<bool>> {
+    auth.ensure_staff()?;
+    state
+        .topic_service
+        .remove_role(&topic_id, &role)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        role = %role,
+        "openapi topic role removed"
+    );
+    Ok(Json(true))
+}
+
+pub async fn topic_role_assign(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((topic_id, user_id)): Path<(String, String)>,
+    Json(form): Json<TopicRoleAssignForm>,
+) -> ApiResult<Json<crate::TopicMember>> {
+    auth.ensure_staff()?;
+    let member = state
+        .topic_service
+        .assign_role(&topic_id, &user_id, &form.role)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        target_user_id = %user_id,
+        role = %member.role,
+        "openapi topic member role assigned"
+    );
+    Ok(Json(member))
+}
+
+pub async fn topic_ban_member(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((topic_id, user_id)): Path<(String, String)>,
+    payload: Option<Json<TopicBanForm>>,
+) -> ApiResult<Json<crate::TopicBan>> {
+    auth.ensure_staff()?;
+    let mut form = payload.map(|v| v.0).unwrap_or_default();
+    if form.admin_id.is_empty() {
+        form.admin_id = auth.user_id().to_string();
+    }
+    let (ban, removed) = state
+        .topic_service
+        .ban_member(&topic_id, &user_id, form)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        target_user_id = %user_id,
+        "openapi topic member banned"
+    );
+    if removed {
+        state
+            .event_bus
+            .publish(BackendEvent::TopicKickout(TopicUserEvent {
+                topic_id: topic_id.clone(),
+                admin_id: ban.admin_id.clone(),
+                user_id: user_id.clone(),
+                source: "openapi".to_string(),
+            }));
+    }
+    state
+        .event_bus
+        .publish(BackendEvent::TopicBan(TopicBanEvent {
+            topic_id,
+            admin_id: ban.admin_id.clone(),
+            user_id,
+            reason: ban.reason.clone(),
+            expires_at: ban.expires_at.clone(),
+            source: "openapi".to_string(),
+        }));
+    Ok(Json(ban))
+}
+
+pub async fn topic_unban_member(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((topic_id, user_id)): Path<(String, String)>,
+) -> ApiResult<Json<bool>> {
+    auth.ensure_staff()?;
+    state
+        .topic_service
+        .unban_member(&topic_id, &user_id)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        admin_user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        target_user_id = %user_id,
+        "openapi topic member unbanned"
+    );
+    state
+        .event_bus
+        .publish(BackendEvent::TopicUnban(TopicBanEvent {
+            topic_id,
+            admin_id: auth.user_id().to_string(),
+            user_id,
+            reason: String::new(),
+            expires_at: String::new(),
+            source: "openapi".to_string(),
+        }));
+    Ok(Json(true))
+}
+
+pub async fn topic_ban_list(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+) -> ApiResult<Json<Vec<crate::TopicBan>>> {
+    auth.ensure_staff()?;
+    let bans = state
+        .topic_service
+        .list_bans(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    Ok(Json(bans))
+}
+
+pub async fn conversation_info(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((user_id, topic_id)): Path<(String, String)>,
+) -> ApiResult<Json<crate::Conversation>> {
+    auth.ensure_user_or_staff(&user_id)?;
+    let conversation = state
+        .conversation_service
+        .get_conversation(&user_id, &topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    Ok(Json(conversation))
+}
+
+pub async fn conversation_update(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((user_id, topic_id)): Path<(String, String)>,
+    Json(form): Json<OpenApiUpdateConversationForm>,
+) -> ApiResult<Json<crate::Conversation>> {
+    auth.ensure_user_or_staff(&user_id)?;
+    let fields = crate::api::chat::conversation_update_fields(&form);
+    let conversation = state
+        .conversation_service
+        .update_conversation(&user_id, &topic_id, form)
+        .await
+        .map_err(map_domain_error)?;
+    if !fields.as_object().is_some_and(|v| v.is_empty()) {
+        state
+            .event_bus
+            .publish(BackendEvent::ConversationUpdate(ConversationUpdateEvent {
+                topic_id: topic_id.clone(),
+                owner_id: user_id.clone(),
+                fields: fields.clone(),
+            }));
+        let payload =
+            crate::api::chat::build_conversation_update_payload(&user_id, &topic_id, &fields);
+        crate::api::push::broadcast_to_user(&state, &user_id, &payload).await;
+    }
+    Ok(Json(conversation))
+}
+
+pub async fn conversation_remove(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((user_id, topic_id)): Path<(String, String)>,
+) -> ApiResult<Json<bool>> {
+    auth.ensure_user_or_staff(&user_id)?;
+    state
+        .conversation_service
         .remove_conversation(&user_id, &topic_id)
         .await
         .map_err(map_domain_error)?;
@@ -1170,6 +1822,15 @@ pub async fn docs() -> Json<Vec<OpenApiDocItem>> {
             Some(OpenApiDocSchema::String),
             OpenApiDocSchema::String,
         ),
+        doc(
+            "OpenAPI - User",
+            "POST",
+            "/open/user/search",
+            "Search users by keyword, source, staff and enabled flags",
+            false,
+            Some(OpenApiDocSchema::String),
+            OpenApiDocSchema::User,
+        ),
         doc(
             "OpenAPI - User",
             "POST",
@@ -1179,6 +1840,141 @@ pub async fn docs() -> Json<Vec<OpenApiDocItem>> {
             Some(OpenApiDocSchema::User),
             OpenApiDocSchema::Bool,
         ),
+        doc(
+            "OpenAPI - User",
+            "POST",
+            "/open/auth/lockouts",
+            "List active login lockouts by account and ip",
+            false,
+            None,
+            OpenApiDocSchema::LoginLockout,
+        ),
+        doc(
+            "OpenAPI - User",
+            "POST",
+            "/open/auth/lockouts/clear",
+            "Clear login failures and lockouts of a user id and/or ip",
+            false,
+            Some(OpenApiDocSchema::String),
+            OpenApiDocSchema::Bool,
+        ),
+        doc(
+            "OpenAPI - ApiKey",
+            "POST",
+            "/open/apikey/list",
+            "List api keys, secrets are never returned",
+            false,
+            None,
+            OpenApiDocSchema::OpenApiKey,
+        ),
+        doc(
+            "OpenAPI - ApiKey",
+            "POST",
+            "/open/apikey/create",
+            "Create an api key with scopes, ip allowlist and expiry, returns the secret once",
+            false,
+            Some(OpenApiDocSchema::OpenApiKey),
+            OpenApiDocSchema::OpenApiKey,
+        ),
+        doc(
+            "OpenAPI - ApiKey",
+            "POST",
+            "/open/apikey/rotate/:keyid",
+            "Replace the secret of an api key, returns the new secret once",
+            false,
+            None,
+            OpenApiDocSchema::OpenApiKey,
+        ),
+        doc(
+            "OpenAPI - ApiKey",
+            "POST",
+            "/open/apikey/revoke/:keyid",
+            "Delete an api key",
+            false,
+            None,
+            OpenApiDocSchema::Bool,
+        ),
+        doc(
+            "OpenAPI - Webhook",
+            "POST",
+            "/open/webhook/events",
+            "List recorded webhook events by ids or time range, newest first",
+            false,
+            Some(OpenApiDocSchema::WebhookSelect),
+            OpenApiDocSchema::WebhookEvent,
+        ),
+        doc(
+            "OpenAPI - Webhook",
+            "POST",
+            "/open/webhook/outbox",
+            "List pending webhook deliveries with attempts and last error",
+            false,
+            Some(OpenApiDocSchema::WebhookSelect),
+            OpenApiDocSchema::WebhookDelivery,
+        ),
+        doc(
+            "OpenAPI - Webhook",
+            "POST",
+            "/open/webhook/dead",
+            "List webhook deliveries that exhausted their attempts",
+            false,
+            Some(OpenApiDocSchema::WebhookSelect),
+            OpenApiDocSchema::WebhookDelivery,
+        ),
+        doc(
+            "OpenAPI - Webhook",
+            "POST",
+            "/open/webhook/dead/retry",
+            "Move dead letters selected by ids or time range back into the outbox",
+            false,
+            Some(OpenApiDocSchema::WebhookSelect),
+            OpenApiDocSchema::WebhookRequeueResult,
+        ),
+        doc(
+            "OpenAPI - Webhook",
+            "POST",
+            "/open/webhook/replay",
+            "Deliver events selected by ids or time range again to their current targets",
+            false,
+            Some(OpenApiDocSchema::WebhookSelect),
+            OpenApiDocSchema::WebhookRequeueResult,
+        ),
+        doc(
+            "OpenAPI - Webhook",
+            "POST",
+            "/open/webhook/subscription/list",
+            "List webhook subscriptions, secrets are never returned",
+            false,
+            None,
+            OpenApiDocSchema::WebhookSubscription,
+        ),
+        doc(
+            "OpenAPI - Webhook",
+            "POST",
+            "/open/webhook/subscription/create",
+            "Subscribe a url to events matching name patterns, optionally limited to topics or topic kinds",
+            false,
+            Some(OpenApiDocSchema::WebhookSubscription),
+            OpenApiDocSchema::WebhookSubscription,
+        ),
+        doc(
+            "OpenAPI - Webhook",
+            "POST",
+            "/open/webhook/subscription/update/:subscriptionid",
+            "Update a webhook subscription, omitted fields are kept",
+            false,
+            Some(OpenApiDocSchema::WebhookSubscription),
+            OpenApiDocSchema::WebhookSubscription,
+        ),
+        doc(
+            "OpenAPI - Webhook",
+            "POST",
+            "/open/webhook/subscription/delete/:subscriptionid",
+            "Delete a webhook subscription",
+            false,
+            None,
+            OpenApiDocSchema::Bool,
+        ),
         doc(
             "OpenAPI - User",
             "POST",
@@ -1215,6 +2011,33 @@ pub async fn docs() -> Json<Vec<OpenApiDocItem>> {
             Some(OpenApiDocSchema::String),
             OpenApiDocSchema::User,
         ),
+        doc(
+            "OpenAPI - User",
+            "POST",
+            "/open/user/sessions/:userid",
+            "List user device sessions",
+            false,
+            None,
+            OpenApiDocSchema::DeviceSession,
+        ),
+        doc(
+            "OpenAPI - User",
+            "POST",
+            "/open/user/sessions/revoke/:userid/:sessionid",
+            "Revoke a device session and kick its connection",
+            false,
+            None,
+            OpenApiDocSchema::Bool,
+        ),
+        doc(
+            "OpenAPI - User",
+            "POST",
+            "/open/user/sessions/revoke_all/:userid",
+            "Revoke all device sessions of user",
+            false,
+            None,
+            OpenApiDocSchema::Bool,
+        ),
         doc(
             "OpenAPI - User",
             "POST",
@@ -1503,6 +2326,114 @@ pub async fn docs() -> Json<Vec<OpenApiDocItem>> {
             Some(OpenApiDocSchema::String),
             OpenApiDocSchema::Bool,
         ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/invite/create/:topicid",
+            "Create topic invite link",
+            false,
+            Some(OpenApiDocSchema::TopicInvite),
+            OpenApiDocSchema::TopicInvite,
+        ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/invite/list/:topicid",
+            "List active topic invite links",
+            false,
+            None,
+            OpenApiDocSchema::TopicInvite,
+        ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/invite/revoke/:topicid/:token",
+            "Revoke topic invite link",
+            false,
+            None,
+            OpenApiDocSchema::Bool,
+        ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/knock_rule/get/:topicid",
+            "Get topic knock auto-approval rule",
+            false,
+            None,
+            OpenApiDocSchema::TopicKnockRule,
+        ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/knock_rule/update/:topicid",
+            "Update topic knock auto-approval rule (sources, attributes, ownerContacts)",
+            false,
+            Some(OpenApiDocSchema::TopicKnockRule),
+            OpenApiDocSchema::TopicKnockRule,
+        ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/role/list/:topicid",
+            "List topic roles and permissions",
+            false,
+            None,
+            OpenApiDocSchema::TopicRole,
+        ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/role/update/:topicid/:role",
+            "Create or update topic role permissions",
+            false,
+            Some(OpenApiDocSchema::TopicRole),
+            OpenApiDocSchema::TopicRole,
+        ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/role/remove/:topicid/:role",
+            "Remove topic role",
+            false,
+            None,
+            OpenApiDocSchema::Bool,
+        ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/role/assign/:topicid/:userid",
+            "Assign role to topic member",
+            false,
+            Some(OpenApiDocSchema::String),
+            OpenApiDocSchema::TopicMember,
+        ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/ban/:topicid/:userid",
+            "Ban user from topic",
+            false,
+            Some(OpenApiDocSchema::TopicBan),
+            OpenApiDocSchema::TopicBan,
+        ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/unban/:topicid/:userid",
+            "Unban user from topic",
+            false,
+            None,
+            OpenApiDocSchema::Bool,
+        ),
+        doc(
+            "OpenAPI - Topic",
+            "POST",
+            "/open/topic/bans/:topicid",
+            "List active topic bans",
+            false,
+            None,
+            OpenApiDocSchema::TopicBan,
+        ),
         doc(
             "OpenAPI - Attachment",
             "POST",
@@ -1678,6 +2609,7 @@ fn map_domain_error(err: DomainError) -> ApiError {
         DomainError::Conflict => ApiError::bad_request("resource already exists"),
         DomainError::Forbidden => ApiError::Unauthorized,
         DomainError::Storage(msg) => ApiError::internal(msg),
+        DomainError::Rejected { code, reason } => ApiError::Rejected(code, reason),
     }
 }
 
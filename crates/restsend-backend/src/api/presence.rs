@@ -0,0 +1,246 @@
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::Utc;
+use std::time::{Duration, Instant};
+
+use crate::api::auth_ctx::AuthCtx;
+use crate::api::error::{ApiError, ApiResult};
+use crate::app::AppState;
+use crate::infra::event::{BackendEvent, UserPresenceEvent};
+use crate::services::{DomainError, PRESENCE_INVISIBLE};
+use crate::{PresenceForm, UserPresence};
+
+const PRESENCE_OFFLINE: &str = "offline";
+const MAX_PRESENCE_SUBSCRIPTIONS: usize = 1000;
+// topic subscriptions without explicit users only follow the first members
+const MAX_PRESENCE_TOPIC_PAGE: u64 = 200;
+
+pub(crate) async fn resolve_presence(
+    state: &AppState,
+    viewer_id: &str,
+    user_id: &str,
+) -> UserPresence {
+    let presence = load_presence(state, user_id).await;
+    presence_for_viewer(presence, viewer_id)
+}
+
+async fn load_presence(state: &AppState, user_id: &str) -> UserPresence {
+    let mut presence = state
+        .user_service
+        .get_presence(user_id)
+        .await
+        .unwrap_or_else(|_| UserPresence {
+            user_id: user_id.to_string(),
+            ..UserPresence::default()
+        });
+    presence.online = state.presence_hub.snapshot(user_id).await.online;
+    presence
+}
+
+fn presence_for_viewer(mut presence: UserPresence, viewer_id: &str) -> UserPresence {
+    if viewer_id == presence.user_id {
+        return presence;
+    }
+    if presence.status == PRESENCE_INVISIBLE {
+        presence.online = false;
+    }
+    if !presence.online {
+        presence.status = PRESENCE_OFFLINE.to_string();
+        presence.status_text.clear();
+        presence.status_expires_at.clear();
+    }
+    presence
+}
+
+fn presence_payload(presence: &UserPresence) -> String {
+    serde_json::json!({
+        "type": "presence",
+        "attendee": presence.user_id,
+        "createdAt": Utc::now().to_rfc3339(),
+        "content": {
+            "type": "presence",
+            "text": presence.status,
+            "extra": {
+                "online": presence.online.to_string(),
+                "statusText": presence.status_text,
+                "statusExpiresAt": presence.status_expires_at,
+                "lastSeenAt": presence.last_seen_at,
+            },
+        },
+    })
+    .to_string()
+}
+
+// settles after the debounce window, so a quick reconnect or a device switch
+// neither flaps subscribers nor emits online/offline events
+pub(crate) async fn notify_presence_change(
+    state: &AppState,
+    user_id: &str,
+    device: &str,
+    was_online: bool,
+) {
+    if !state
+        .presence_hub
+        .mark_changed(user_id, device, was_online)
+        .await
+    {
+        return;
+    }
+    let state = state.clone();
+    let user_id = user_id.to_string();
+    tokio::spawn(async move {
+        if state.config.presence_debounce_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(state.config.presence_debounce_ms)).await;
+        }
+        let Some((online, device)) = state.presence_hub.settle_change(&user_id).await else {
+            return;
+        };
+        let event = UserPresenceEvent {
+            user_id: user_id.clone(),
+            device,
+            node_id: state.config.presence_node_id.clone(),
+        };
+        state.event_bus.publish(match online {
+            true => BackendEvent::UserOnline(event),
+            false => BackendEvent::UserOffline(event),
+        });
+
+        let subscribers = state.presence_hub.subscribers(&user_id).await;
+        if subscribers.is_empty() {
+            return;
+        }
+        let presence = load_presence(&state, &user_id).await;
+        for (subscriber, device) in subscribers.iter() {
+            let payload = presence_payload(&presence_for_viewer(presence.clone(), subscriber));
+            crate::api::push::push_local_device(&state, subscriber, device, &payload).await;
+        }
+        tracing::info!(
+            user_id = %user_id,
+            online,
+            subscribers = subscribers.len(),
+            "presence change pushed"
+        );
+    });
+}
+
+// replaces the session subscription. Explicit users are kept only when they are
+// contacts or members of `topic_id`; without them a topic subscribes its first
+// member page and no topic means the user's contacts
+pub(crate) async fn subscribe_presence(
+    state: &AppState,
+    user_id: &str,
+    device: &str,
+    topic_id: &str,
+    mut users: Vec<String>,
+) -> ApiResult<usize> {
+    users.retain(|target| target != user_id && !target.is_empty());
+    users.sort();
+    users.dedup();
+    users.truncate(MAX_PRESENCE_SUBSCRIPTIONS);
+
+    let mut targets = Vec::new();
+    if !topic_id.is_empty() {
+        let is_member = !state
+            .topic_service
+            .filter_members(topic_id, &[user_id.to_string()])
+            .await
+            .map_err(map_domain_error)?
+            .is_empty();
+        if !is_member {
+            return Err(ApiError::Unauthorized);
+        }
+        targets = if users.is_empty() {
+            state
+                .topic_service
+                .list_members_page(topic_id, MAX_PRESENCE_TOPIC_PAGE)
+                .await
+                .map_err(map_domain_error)?
+        } else {
+            state
+                .topic_service
+                .filter_members(topic_id, &users)
+                .await
+                .map_err(map_domain_error)?
+        };
+    }
+    if topic_id.is_empty() || !users.is_empty() {
+        let contacts = state
+            .relation_service
+            .list_contacts(user_id)
+            .await
+            .map_err(map_domain_error)?;
+        if users.is_empty() {
+            targets = contacts;
+        } else {
+            targets.extend(users.into_iter().filter(|target| contacts.contains(target)));
+        }
+    }
+    targets.retain(|target| target != user_id && !target.is_empty());
+    targets.sort();
+    targets.dedup();
+    targets.truncate(MAX_PRESENCE_SUBSCRIPTIONS);
+
+    state
+        .presence_hub
+        .subscribe(user_id, device, targets.clone())
+        .await;
+    let presences = state
+        .user_service
+        .get_presences(&targets)
+        .await
+        .map_err(map_domain_error)?;
+    for mut presence in presences {
+        presence.online = state.presence_hub.snapshot(&presence.user_id).await.online;
+        let payload = presence_payload(&presence_for_viewer(presence, user_id));
+        crate::api::push::push_local_device(state, user_id, device, &payload).await;
+    }
+    Ok(targets.len())
+}
+
+pub async fn get_presence(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(userid): Path<String>,
+) -> ApiResult<Json<UserPresence>> {
+    let st = Instant::now();
+    let presence = resolve_presence(&state, auth.user_id(), &userid).await;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        target_user_id = %userid,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "user presence fetched"
+    );
+    Ok(Json(presence))
+}
+
+pub async fn update_presence(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Json(form): Json<PresenceForm>,
+) -> ApiResult<Json<UserPresence>> {
+    let st = Instant::now();
+    let mut presence = state
+        .user_service
+        .set_presence(auth.user_id(), form)
+        .await
+        .map_err(map_domain_error)?;
+    presence.online = state.presence_hub.snapshot(auth.user_id()).await.online;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        status = %presence.status,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "user presence updated"
+    );
+    Ok(Json(presence))
+}
+
+fn map_domain_error(err: DomainError) -> ApiError {
+    match err {
+        DomainError::NotFound => ApiError::NotFound,
+        DomainError::Validation(msg) => ApiError::bad_request(msg),
+        DomainError::Conflict => ApiError::bad_request("resource already exists"),
+        DomainError::Forbidden => ApiError::Unauthorized,
+        DomainError::Storage(msg) => ApiError::internal(msg),
+        DomainError::Rejected { code, reason } => ApiError::Rejected(code, reason),
+    }
+}
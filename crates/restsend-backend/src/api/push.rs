@@ -13,7 +13,7 @@ pub async fn broadcast_to_user(state: &AppState, user_id: &str, payload: &str) {
     if let Err(err) = push_pool
         .submit(async move {
             push_local_user_now(&state, &user_id, &payload).await;
-            push_remote_user_now(&state, &user_id, None, &payload).await;
+            push_remote_user_now(&state, &user_id, None, None, &payload).await;
         })
         .await
     {
@@ -21,6 +21,38 @@ pub async fn broadcast_to_user(state: &AppState, user_id: &str, payload: &str) {
     }
 }
 
+// syncs the user's other sessions, skipping the device the change came from
+pub async fn broadcast_to_other_devices(
+    state: &AppState,
+    user_id: &str,
+    except_device: &str,
+    payload: &str,
+) {
+    let state = state.clone();
+    let push_pool = state.push_pool.clone();
+    let user_id = user_id.to_string();
+    let except_device = except_device.to_string();
+    let payload = payload.to_string();
+    let log_user_id = user_id.clone();
+    if let Err(err) = push_pool
+        .submit(async move {
+            state
+                .ws_hub
+                .broadcast_to_other_devices(
+                    &user_id,
+                    &except_device,
+                    &payload,
+                    state.config.ws_drop_on_backpressure,
+                )
+                .await;
+            push_remote_user_now(&state, &user_id, None, Some(&except_device), &payload).await;
+        })
+        .await
+    {
+        tracing::warn!(user_id = %log_user_id, error = %err, "enqueue device sync push failed");
+    }
+}
+
 pub async fn send_to_device(state: &AppState, user_id: &str, device: &str, payload: &str) {
     let state = state.clone();
     let push_pool = state.push_pool.clone();
@@ -32,7 +64,7 @@ pub async fn send_to_device(state: &AppState, user_id: &str, device: &str, paylo
     if let Err(err) = push_pool
         .submit(async move {
             push_local_device_now(&state, &user_id, &device, &payload).await;
-            push_remote_user_now(&state, &user_id, Some(&device), &payload).await;
+            push_remote_user_now(&state, &user_id, Some(&device), None, &payload).await;
         })
         .await
     {
@@ -97,6 +129,7 @@ async fn push_remote_user_now(
     state: &AppState,
     user_id: &str,
     device: Option<&str>,
+    except_device: Option<&str>,
     payload: &str,
 ) {
     if state.config.presence_backend != "db" || state.config.endpoint.is_empty() {
@@ -124,6 +157,9 @@ async fn push_remote_user_now(
         if row.endpoint.trim().is_empty() || row.endpoint == state.config.endpoint {
             continue;
         }
+        if except_device.is_some_and(|device| device == row.device) {
+            continue;
+        }
         if let Err(err) =
             send_remote_push(state, &row.endpoint, &row.user_id, &row.device, payload).await
         {
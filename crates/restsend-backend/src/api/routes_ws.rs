@@ -1,6 +1,8 @@
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 use axum::extract::{Query, State};
+use axum::http::{HeaderMap, Uri};
 use axum::response::Response;
+use axum::{Extension, Json};
 use chrono::Utc;
 use futures_util::{sink::SinkExt, stream::StreamExt};
 use serde::{Deserialize, Serialize};
@@ -8,12 +10,16 @@ use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+use crate::api::access_log::AccessLogUserId;
 use crate::api::auth_ctx::AuthCtx;
 use crate::api::chat::send_chat_message;
-use crate::api::error::ApiError;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::middleware_auth::{authenticate_user_token, request_token};
+use crate::api::presence::{notify_presence_change, subscribe_presence};
 use crate::app::AppState;
 use crate::infra::event::{BackendEvent, ReadEvent, TypingEvent};
 use crate::infra::websocket::SessionSender;
+use crate::services::DomainError;
 
 struct PresenceSessionGuard {
     state: AppState,
@@ -66,7 +72,14 @@ impl Drop for PresenceSessionGuard {
         let device = self.device.clone();
         tokio::spawn(async move {
             state.ws_hub.unregister(&user_id, &device).await;
+            state.presence_hub.unsubscribe(&user_id, &device).await;
             state.presence_hub.remove_session(&user_id, &device).await;
+            if !state.presence_hub.snapshot(&user_id).await.online {
+                if let Err(err) = state.user_service.touch_last_seen(&user_id).await {
+                    tracing::warn!(user_id = %user_id, error = %err, "presence last seen update failed");
+                }
+                notify_presence_change(&state, &user_id, &device, true).await;
+            }
             tracing::info!(user_id = %user_id, device = %device, "ws session unregistered");
         });
     }
@@ -74,9 +87,9 @@ impl Drop for PresenceSessionGuard {
 
 #[derive(Debug, Deserialize)]
 pub struct WsConnectQuery {
-    pub user_id: Option<String>,
     pub device: Option<String>,
     pub nonce: Option<String>,
+    pub ticket: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -98,40 +111,123 @@ struct WsEnvelope {
     seq: i64,
     #[serde(default)]
     content: Option<crate::Content>,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    status_text: String,
+    #[serde(default)]
+    expire_secs: u64,
+    // presence subscription targets
+    #[serde(default)]
+    users: Vec<String>,
 }
 
 pub async fn ws_upgrade(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Query(query): Query<WsConnectQuery>,
-) -> Response {
-    ws.on_upgrade(move |socket| ws_session_loop(state, query, socket))
+) -> Result<Response, ApiError> {
+    let Some(ticket) = query.ticket.as_deref().filter(|v| !v.is_empty()) else {
+        tracing::warn!("ws connect rejected: missing ticket");
+        return Err(ApiError::Unauthorized);
+    };
+    let (user_id, device) = redeem_ticket(&state, ticket, &query).await?;
+    Ok(ws.on_upgrade(move |socket| ws_session_loop(state, user_id, device, socket)))
 }
 
 pub async fn ws_connect(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
-    auth: AuthCtx,
+    headers: HeaderMap,
+    uri: Uri,
+    access_log: Option<Extension<AccessLogUserId>>,
     Query(query): Query<WsConnectQuery>,
-) -> Response {
-    let query = WsConnectQuery {
-        user_id: query.user_id.clone().or(Some(auth.user_id.clone())),
-        device: query.device,
-        nonce: query.nonce,
+) -> Result<Response, ApiError> {
+    // the session always belongs to whoever the ticket or token authenticates
+    let (user_id, device) = match query.ticket.as_deref().filter(|v| !v.is_empty()) {
+        Some(ticket) => redeem_ticket(&state, ticket, &query).await?,
+        None => {
+            if state.config.ws_require_ticket {
+                tracing::warn!("ws connect rejected: ticket required");
+                return Err(ApiError::Unauthorized);
+            }
+            let token = request_token(&headers, &uri).ok_or_else(|| {
+                tracing::warn!("ws connect rejected: missing token or ticket");
+                ApiError::Unauthorized
+            })?;
+            let user_id = authenticate_user_token(&state, &token).await?;
+            let device = session_device(&query);
+            if let Err(err) = state.auth_service.bind_device(&token, &device).await {
+                tracing::warn!(user_id = %user_id, error = %err, "ws bind token device failed");
+            }
+            (user_id, device)
+        }
     };
-    ws.on_upgrade(move |socket| ws_session_loop(state, query, socket))
+    if let Some(Extension(slot)) = access_log {
+        if let Ok(mut guard) = slot.0.lock() {
+            *guard = Some(user_id.clone());
+        }
+    }
+    Ok(ws.on_upgrade(move |socket| ws_session_loop(state, user_id, device, socket)))
 }
 
-async fn ws_session_loop(state: AppState, query: WsConnectQuery, socket: WebSocket) {
-    let base_device = query.device.unwrap_or_else(|| "web".to_string());
-    let device = if let Some(nonce) = query.nonce.filter(|v| !v.is_empty()) {
-        format!("{}:{}", base_device, nonce)
-    } else {
-        base_device
-    };
-    let Some(user_id) = query.user_id else {
-        return;
+// issues a short lived single use ticket so browsers never put the bearer token in a url
+pub async fn ws_connect_ticket(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Query(query): Query<WsConnectQuery>,
+) -> ApiResult<Json<crate::WsConnectTicket>> {
+    let device = session_device(&query);
+    let ticket = state
+        .ws_tickets
+        .issue(auth.user_id(), &device)
+        .await
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+    if let Err(err) = state.auth_service.bind_device(&auth.token, &device).await {
+        tracing::warn!(user_id = %auth.user_id(), error = %err, "ws bind token device failed");
+    }
+    Ok(Json(crate::WsConnectTicket {
+        ticket: ticket.ticket,
+        device,
+        expires_in: state.ws_tickets.ttl_secs(),
+    }))
+}
+
+async fn redeem_ticket(
+    state: &AppState,
+    ticket: &str,
+    query: &WsConnectQuery,
+) -> Result<(String, String), ApiError> {
+    let Some(ticket) = state
+        .ws_tickets
+        .redeem(ticket)
+        .await
+        .map_err(|err| ApiError::internal(err.to_string()))?
+    else {
+        tracing::warn!("ws connect rejected: invalid or used ticket");
+        return Err(ApiError::Unauthorized);
     };
+    // a device in the url must match the one the ticket was issued for
+    if query.device.is_some() && session_device(query) != ticket.device {
+        tracing::warn!(
+            user_id = %ticket.user_id,
+            device = %ticket.device,
+            "ws connect rejected: ticket device mismatch"
+        );
+        return Err(ApiError::Unauthorized);
+    }
+    Ok((ticket.user_id, ticket.device))
+}
+
+fn session_device(query: &WsConnectQuery) -> String {
+    let base_device = query.device.clone().unwrap_or_else(|| "web".to_string());
+    match query.nonce.as_deref().filter(|v| !v.is_empty()) {
+        Some(nonce) => format!("{}:{}", base_device, nonce),
+        None => base_device,
+    }
+}
+
+async fn ws_session_loop(state: AppState, user_id: String, device: String, socket: WebSocket) {
     let session_state = WsSessionState::new(&state.config);
     let (sender_handle, mut rx) = if state.config.ws_client_queue_size > 0 {
         let (tx, rx) = mpsc::channel::<String>(state.config.ws_client_queue_size);
@@ -145,7 +241,11 @@ async fn ws_session_loop(state: AppState, query: WsConnectQuery, socket: WebSock
         .ws_hub
         .register(&user_id, &device, sender_handle)
         .await;
+    let was_online = state.presence_hub.snapshot(&user_id).await.online;
     state.presence_hub.upsert_session(&user_id, &device).await;
+    if !was_online {
+        notify_presence_change(&state, &user_id, &device, false).await;
+    }
     tracing::info!(user_id = %user_id, device = %device, "ws session registered");
 
     let _guard = PresenceSessionGuard {
@@ -441,6 +541,14 @@ async fn handle_ws_envelope(
                 last_read_seq: conversation.last_read_seq,
             }));
 
+            let sync_payload = crate::api::chat::build_conversation_update_payload(
+                user_id,
+                &req.topic_id,
+                &crate::api::chat::conversation_read_fields(&conversation),
+            );
+            crate::api::push::broadcast_to_other_devices(state, user_id, device, &sync_payload)
+                .await;
+
             let read_payload = serde_json::to_string(&serde_json::json!({
                 "type": "read",
                 "topicId": req.topic_id,
@@ -475,6 +583,65 @@ async fn handle_ws_envelope(
                 )
                 .await;
         }
+        "presence" => {
+            let form = crate::PresenceForm {
+                status: req.status,
+                status_text: req.status_text,
+                expire_secs: req.expire_secs,
+            };
+            let code = match state.user_service.set_presence(user_id, form).await {
+                Ok(_) => 200,
+                Err(DomainError::Validation(_)) => 400,
+                Err(err) => {
+                    tracing::warn!(error = %err, user_id = %user_id, "ws presence update error");
+                    500
+                }
+            };
+            let payload = serde_json::to_string(&serde_json::json!({
+                "type": "resp",
+                "chatId": req.chat_id,
+                "code": code,
+                "createdAt": Utc::now().to_rfc3339(),
+            }))
+            .unwrap_or_default();
+            state
+                .ws_hub
+                .send_to_device(
+                    user_id,
+                    device,
+                    &payload,
+                    state.config.ws_drop_on_backpressure,
+                )
+                .await;
+        }
+        "subscribe" | "unsubscribe" => {
+            let code = if req.r#type == "unsubscribe" {
+                state.presence_hub.unsubscribe(user_id, device).await;
+                200
+            } else {
+                match subscribe_presence(state, user_id, device, &req.topic_id, req.users).await {
+                    Ok(_) => 200,
+                    Err(err) => map_ws_error_code(&err).unwrap_or(500),
+                }
+            };
+            let payload = serde_json::to_string(&serde_json::json!({
+                "type": "resp",
+                "chatId": req.chat_id,
+                "topicId": req.topic_id,
+                "code": code,
+                "createdAt": Utc::now().to_rfc3339(),
+            }))
+            .unwrap_or_default();
+            state
+                .ws_hub
+                .send_to_device(
+                    user_id,
+                    device,
+                    &payload,
+                    state.config.ws_drop_on_backpressure,
+                )
+                .await;
+        }
         "chat" => {
             if let Some(limiter) = &session_state.chat_limiter {
                 let mut guard = limiter.lock().await;
@@ -552,6 +719,8 @@ async fn handle_ws_envelope(
                         "seq": resp.seq,
                         "chatId": resp.chat_id,
                         "code": resp.code,
+                        // only set when the pre-send hook rewrote the message
+                        "content": resp.content,
                         "attendee": effective_form.attendee,
                         "createdAt": effective_form.created_at.clone().unwrap_or_else(|| Utc::now().to_rfc3339()),
                     }))
@@ -569,11 +738,17 @@ async fn handle_ws_envelope(
                 Err(err) => {
                     tracing::warn!(error = %err, user_id = %user_id, topic_id = %req_topic_id, "ws chat message error");
                     let code = map_ws_error_code(&err).unwrap_or(500);
+                    // the sdk hands this to on_fail
+                    let message = match &err {
+                        ApiError::Rejected(_, reason) => Some(reason.clone()),
+                        _ => None,
+                    };
                     let payload = serde_json::to_string(&serde_json::json!({
                         "type": "resp",
                         "chatId": req_chat_id,
                         "topicId": req_topic_id,
                         "code": code,
+                        "message": message,
                         "createdAt": Utc::now().to_rfc3339(),
                     }))
                     .unwrap_or_default();
@@ -596,10 +771,12 @@ async fn handle_ws_envelope(
 fn map_ws_error_code(err: &ApiError) -> Option<u16> {
     match err {
         ApiError::NotFound => Some(404),
-        ApiError::Unauthorized => Some(403),
+        ApiError::Unauthorized | ApiError::InsufficientScope(_) => Some(403),
         ApiError::BadRequest(_) => Some(400),
-        ApiError::InvalidToken => Some(401),
+        ApiError::InvalidToken | ApiError::TokenExpired(_) => Some(401),
         ApiError::Internal(_) => Some(500),
         ApiError::NotImplemented(_) => Some(501),
+        ApiError::TooManyRequests | ApiError::LockedOut(_) => Some(429),
+        ApiError::Rejected(code, _) => Some(*code),
     }
 }
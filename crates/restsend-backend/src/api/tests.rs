@@ -32,6 +32,18 @@ mod tests {
             webhook_timeout_secs: 5,
             webhook_retries: 2,
             webhook_targets: vec![],
+            webhook_secret: None,
+            webhook_target_secrets: vec![],
+            webhook_outbox: true,
+            webhook_max_attempts: 10,
+            webhook_backoff_secs: 2,
+            webhook_backoff_max_secs: 3600,
+            webhook_circuit_threshold: 5,
+            webhook_circuit_cooldown_secs: 60,
+            webhook_event_retention_secs: 604800,
+            presend_hook_url: None,
+            presend_hook_timeout_ms: 1000,
+            presend_hook_fail_open: false,
             event_bus_size: 256,
             message_worker_count: 2,
             message_queue_size: 64,
@@ -44,10 +56,33 @@ mod tests {
             presence_node_id: "test-node".to_string(),
             presence_ttl_secs: 90,
             presence_heartbeat_secs: 10,
+            presence_debounce_ms: 0,
             ws_per_user_limit: 0,
             ws_client_queue_size: 0,
             ws_typing_interval_ms: 1000,
             ws_drop_on_backpressure: true,
+            ws_ticket_ttl_secs: 30,
+            ws_require_ticket: false,
+            knock_expire_secs: 7 * 24 * 3600,
+            dm_contacts_only: false,
+            user_search_rate_limit: 30,
+            token_ttl_secs: 30 * 24 * 3600,
+            token_idle_secs: 0,
+            refresh_token_ttl_secs: 90 * 24 * 3600,
+            token_gc_interval_secs: 300,
+            token_touch_flush_secs: 30,
+            signed_token_alg: "HS256".to_string(),
+            signed_token_keys: vec![],
+            token_revocation_refresh_secs: 30,
+            password_min_length: 4,
+            login_max_failures: 5,
+            login_ip_max_failures: 20,
+            login_lockout_secs: 60,
+            login_lockout_max_secs: 3600,
+            login_failure_window_secs: 900,
+            trusted_proxies: vec![],
+            oidc_providers: vec![],
+            openapi_allow_anonymous: false,
         }
     }
 
@@ -273,6 +308,262 @@ mod tests {
         assert_eq!(resp_ok.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn openapi_denies_anonymous_unless_allowed() {
+        let mut config = test_config();
+        config.openapi_token = None;
+        let (app, state) = build_router(config.clone()).await.expect("build router");
+        let req = Request::builder()
+            .uri("/open/docs")
+            .method("GET")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.with_state(state).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        config.openapi_allow_anonymous = true;
+        let (app, state) = build_router(config).await.expect("build router");
+        let req = Request::builder()
+            .uri("/open/docs")
+            .method("GET")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.with_state(state).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn api_keys_act_as_super_token_only_with_admin_scope() {
+        use axum::extract::FromRequestParts;
+
+        let (_, state) = build_router(test_config()).await.expect("build router");
+        let topic = crate::Topic {
+            owner_id: "someone".to_string(),
+            ..crate::Topic::default()
+        };
+        for (scope, is_super) in [
+            (crate::services::ApiScope::TopicsWrite, false),
+            (crate::services::ApiScope::Admin, true),
+        ] {
+            let (mut parts, _) = Request::builder()
+                .uri("/open/topic/info/t1")
+                .body(())
+                .unwrap()
+                .into_parts();
+            parts.extensions.insert(crate::services::ApiKeyGrant {
+                key_id: "k1".to_string(),
+                name: "k1".to_string(),
+                scopes: vec![scope],
+            });
+            let auth = crate::api::auth_ctx::AuthCtx::from_request_parts(&mut parts, &state)
+                .await
+                .unwrap();
+            assert_eq!(auth.is_super_openapi, is_super);
+            assert_eq!(auth.ensure_topic_owner(&topic).is_ok(), is_super);
+            // the route scope already admitted the key
+            assert!(auth.ensure_staff().is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn openapi_keys_enforce_scopes_ips_and_rotation() {
+        let mut config = test_config();
+        config.trusted_proxies = vec!["127.0.0.1".to_string()];
+        let (app, state) = build_router(config).await.expect("build router");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let app = app
+                .with_state(state)
+                .into_make_service_with_connect_info::<std::net::SocketAddr>();
+            axum::serve(listener, app).await.unwrap();
+        });
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let create = |form: serde_json::Value| {
+            client
+                .post(format!("{endpoint}/open/apikey/create"))
+                .bearer_auth("test-token")
+                .json(&form)
+                .send()
+        };
+
+        let resp = create(serde_json::json!({"name": "bad", "scopes": ["users:everything"]}))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let created: serde_json::Value = create(serde_json::json!({
+            "name": "reader",
+            "scopes": ["users:read", "helpdesk"],
+        }))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+        let key_id = created["id"].as_str().unwrap().to_string();
+        let secret = created["secret"].as_str().unwrap().to_string();
+        assert!(secret.starts_with("rsk_"));
+
+        let call = |path: &str, token: &str| {
+            client
+                .post(format!("{endpoint}{path}"))
+                .bearer_auth(token)
+                .json(&serde_json::json!({}))
+                .send()
+        };
+        let resp = call("/open/user/list", &secret).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let resp = call("/open/user/register/key-user", &secret).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::FORBIDDEN);
+        let resp = call("/open/apikey/list", &secret).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::FORBIDDEN);
+        let resp = client
+            .get(format!("{endpoint}/helpdesk/labels"))
+            .bearer_auth(&secret)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+
+        let listed: serde_json::Value = call("/open/apikey/list", "test-token")
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let listed = listed.as_array().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].get("secret").is_none());
+        assert!(!listed[0]["lastUsedAt"].as_str().unwrap().is_empty());
+
+        let rotated: serde_json::Value =
+            call(&format!("/open/apikey/rotate/{key_id}"), "test-token")
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+        let rotated_secret = rotated["secret"].as_str().unwrap().to_string();
+        let resp = call("/open/user/list", &secret).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let resp = call("/open/user/list", &rotated_secret).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+
+        let resp = call(&format!("/open/apikey/revoke/{key_id}"), "test-token")
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let resp = call("/open/user/list", &rotated_secret).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let fenced: serde_json::Value = create(serde_json::json!({
+            "name": "fenced",
+            "scopes": ["admin"],
+            "allowedIps": ["10.0.0.0/8"],
+        }))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+        let fenced_secret = fenced["secret"].as_str().unwrap().to_string();
+        let from_ip = |ip: &'static str| {
+            client
+                .post(format!("{endpoint}/open/user/register/key-user"))
+                .bearer_auth(&fenced_secret)
+                .header("x-forwarded-for", ip)
+                .json(&serde_json::json!({"password": "key-pass"}))
+                .send()
+        };
+        let resp = from_ip("192.168.1.10").await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let resp = from_ip("10.1.2.3").await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        // without a forwarded address the loopback peer is checked
+        let resp = client
+            .post(format!("{endpoint}/open/user/list"))
+            .bearer_auth(&fenced_secret)
+            .json(&serde_json::json!({}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        // minting a token for a staff account needs the admin scope
+        let writer: serde_json::Value =
+            create(serde_json::json!({"name": "writer", "scopes": ["users:write"]}))
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+        let writer_secret = writer["secret"].as_str().unwrap().to_string();
+        for user_id in ["scope-plain", "scope-staff"] {
+            let resp = call(&format!("/open/user/register/{user_id}"), "test-token")
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        }
+        let resp = client
+            .post(format!("{endpoint}/open/user/staff/scope-staff"))
+            .bearer_auth("test-token")
+            .json(&serde_json::json!({"isStaff": true}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let auth_as =
+            |user_id: &str, token: &str| call(&format!("/open/user/auth/{user_id}"), token);
+        let resp = auth_as("scope-staff", &writer_secret).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::FORBIDDEN);
+        let plain: serde_json::Value = auth_as("scope-plain", &writer_secret)
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let plain_token = plain["authToken"].as_str().unwrap().to_string();
+        let staff: serde_json::Value = client
+            .post(format!("{endpoint}/open/user/auth/scope-staff"))
+            .bearer_auth(&fenced_secret)
+            .header("x-forwarded-for", "10.1.2.3")
+            .json(&serde_json::json!({}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let staff_token = staff["authToken"].as_str().unwrap().to_string();
+
+        // user tokens go through the route scope too, admin routes need a staff account
+        let resp = call("/open/apikey/list", &plain_token).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::FORBIDDEN);
+        let resp = auth_as("scope-plain", &plain_token).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let resp = call("/open/apikey/list", &staff_token).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+
+        let expired: serde_json::Value = create(serde_json::json!({
+            "name": "expired",
+            "scopes": ["admin"],
+            "duration": "0m",
+        }))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+        let resp = call("/open/user/list", expired["secret"].as_str().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        server.abort();
+    }
+
     #[tokio::test]
     async fn custom_api_and_openapi_prefixes_are_supported() {
         let mut config = test_config();
@@ -396,7 +687,11 @@ mod tests {
             .unwrap()
             .unwrap();
         assert_ne!(stored_user.password, "sdk-pass");
-        assert_eq!(
+        assert!(crate::api::auth::verify_password(
+            &stored_user.password,
+            "sdk-pass"
+        ));
+        assert_ne!(
             stored_user.password,
             crate::api::auth::hash_password("sdk-pass")
         );
@@ -798,16 +1093,35 @@ mod tests {
 
     #[tokio::test]
     async fn guest_login_roundtrip() {
-        let (app, state) = build_router(test_config()).await.expect("build router");
+        let mut config = test_config();
+        config.login_max_failures = 2;
+        let (app, state) = build_router(config).await.expect("build router");
         let app = app.with_state(state);
 
+        // guest ids cannot log into regular accounts, and guessing them counts as failures
+        let _ = register_and_auth(&app, "guest-member").await;
+        for status in [
+            StatusCode::UNAUTHORIZED,
+            StatusCode::UNAUTHORIZED,
+            StatusCode::TOO_MANY_REQUESTS,
+        ] {
+            let req = Request::builder()
+                .uri("/api/guest/login")
+                .method("POST")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"guestId":"guest-member"}"#))
+                .unwrap();
+            let resp = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), status);
+        }
+
         let guest_req = Request::builder()
             .uri("/api/guest/login")
             .method("POST")
             .header("content-type", "application/json")
             .body(Body::from(r#"{"guestId":"guest-a","remember":true}"#))
             .unwrap();
-        let guest_resp = app.oneshot(guest_req).await.unwrap();
+        let guest_resp = app.clone().oneshot(guest_req).await.unwrap();
         assert_eq!(guest_resp.status(), StatusCode::OK);
         let guest_body = guest_resp.into_body().collect().await.unwrap().to_bytes();
         let guest_json: serde_json::Value = serde_json::from_slice(&guest_body).unwrap();
@@ -816,6 +1130,16 @@ mod tests {
             Some("guest-a")
         );
         assert!(guest_json.get("token").and_then(|v| v.as_str()).is_some());
+
+        // returning guests get back into their own account
+        let again_req = Request::builder()
+            .uri("/api/guest/login")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"guestId":"guest-a"}"#))
+            .unwrap();
+        let again_resp = app.oneshot(again_req).await.unwrap();
+        assert_eq!(again_resp.status(), StatusCode::OK);
     }
 
     #[tokio::test]
@@ -1173,76 +1497,2506 @@ mod tests {
             .and_then(|v| v.as_str())
             .unwrap()
             .to_string();
-
-        let knock_req = Request::builder()
-            .uri(format!("/api/topic/knock/{topic_id}"))
-            .method("POST")
-            .header("Authorization", format!("Bearer {joiner_token}"))
-            .header("content-type", "application/json")
-            .body(Body::from(r#"{"message":"let me in","source":"mobile"}"#))
-            .unwrap();
-        let knock_resp = app.clone().oneshot(knock_req).await.unwrap();
-        assert_eq!(knock_resp.status(), StatusCode::OK);
-
-        let list_knocks_req = Request::builder()
-            .uri(format!("/api/topic/admin/list_knock/{topic_id}"))
-            .method("POST")
-            .header("Authorization", format!("Bearer {owner_token}"))
-            .body(Body::empty())
+
+        let knock_req = Request::builder()
+            .uri(format!("/api/topic/knock/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {joiner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"message":"let me in","source":"mobile"}"#))
+            .unwrap();
+        let knock_resp = app.clone().oneshot(knock_req).await.unwrap();
+        assert_eq!(knock_resp.status(), StatusCode::OK);
+
+        let list_knocks_req = Request::builder()
+            .uri(format!("/api/topic/admin/list_knock/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let list_knocks_resp = app.clone().oneshot(list_knocks_req).await.unwrap();
+        if list_knocks_resp.status() != StatusCode::OK {
+            let body = list_knocks_resp
+                .into_body()
+                .collect()
+                .await
+                .unwrap()
+                .to_bytes();
+            panic!(
+                "list_knock failed: {}",
+                String::from_utf8(body.to_vec()).unwrap()
+            );
+        }
+        let knocks_body = list_knocks_resp
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .to_bytes();
+        let knocks_text = String::from_utf8(knocks_body.to_vec()).unwrap();
+        assert!(knocks_text.contains("joiner1"));
+
+        let accept_req = Request::builder()
+            .uri(format!("/api/topic/admin/knock/accept/{topic_id}/joiner1"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let accept_resp = app.clone().oneshot(accept_req).await.unwrap();
+        assert_eq!(accept_resp.status(), StatusCode::OK);
+
+        let notice_req = Request::builder()
+            .uri(format!("/api/topic/admin/notice/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"text":"welcome"}"#))
+            .unwrap();
+        let notice_resp = app.clone().oneshot(notice_req).await.unwrap();
+        assert_eq!(notice_resp.status(), StatusCode::OK);
+
+        let info_req = Request::builder()
+            .uri(format!("/api/topic/info/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {joiner_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let info_resp = app.oneshot(info_req).await.unwrap();
+        assert_eq!(info_resp.status(), StatusCode::OK);
+        let info_body = info_resp.into_body().collect().await.unwrap().to_bytes();
+        let info_text = String::from_utf8(info_body.to_vec()).unwrap();
+        assert!(info_text.contains("welcome"));
+    }
+
+    #[tokio::test]
+    async fn topic_invite_link_flow() {
+        let (app, state) = build_router(test_config()).await.expect("build router");
+        let app = app.with_state(state);
+
+        let owner_token = register_and_auth(&app, "inv-owner").await;
+        let alice_token = register_and_auth(&app, "inv-alice").await;
+        let bob_token = register_and_auth(&app, "inv-bob").await;
+
+        let create_req = Request::builder()
+            .uri("/api/topic/create")
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"name":"invite-group","members":["inv-owner"],"knockNeedVerify":true}"#,
+            ))
+            .unwrap();
+        let create_resp = app.clone().oneshot(create_req).await.unwrap();
+        assert_eq!(create_resp.status(), StatusCode::OK);
+        let create_body = create_resp.into_body().collect().await.unwrap().to_bytes();
+        let create_json: serde_json::Value = serde_json::from_slice(&create_body).unwrap();
+        let topic_id = create_json["id"].as_str().unwrap().to_string();
+
+        let forbidden_req = Request::builder()
+            .uri(format!("/api/topic/admin/invite/create/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let forbidden_resp = app.clone().oneshot(forbidden_req).await.unwrap();
+        assert_eq!(forbidden_resp.status(), StatusCode::UNAUTHORIZED);
+
+        let invite_req = Request::builder()
+            .uri(format!("/api/topic/admin/invite/create/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"duration":"1d","maxUses":1,"autoApprove":true}"#,
+            ))
+            .unwrap();
+        let invite_resp = app.clone().oneshot(invite_req).await.unwrap();
+        assert_eq!(invite_resp.status(), StatusCode::OK);
+        let invite_body = invite_resp.into_body().collect().await.unwrap().to_bytes();
+        let invite_json: serde_json::Value = serde_json::from_slice(&invite_body).unwrap();
+        let token = invite_json["token"].as_str().unwrap().to_string();
+        assert_eq!(invite_json["createdBy"], "inv-owner");
+        assert!(!invite_json["expiresAt"].as_str().unwrap().is_empty());
+
+        let preview_req = Request::builder()
+            .uri(format!("/api/topic/invite_link/preview/{token}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let preview_resp = app.clone().oneshot(preview_req).await.unwrap();
+        assert_eq!(preview_resp.status(), StatusCode::OK);
+        let preview_body = preview_resp.into_body().collect().await.unwrap().to_bytes();
+        let preview_json: serde_json::Value = serde_json::from_slice(&preview_body).unwrap();
+        assert_eq!(preview_json["name"], "invite-group");
+        assert_eq!(preview_json["needVerify"], false);
+
+        let join_req = Request::builder()
+            .uri(format!("/api/topic/invite_link/join/{token}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let join_resp = app.clone().oneshot(join_req).await.unwrap();
+        assert_eq!(join_resp.status(), StatusCode::OK);
+        let join_body = join_resp.into_body().collect().await.unwrap().to_bytes();
+        let join_json: serde_json::Value = serde_json::from_slice(&join_body).unwrap();
+        assert_eq!(join_json["status"], "joined");
+
+        let exhausted_req = Request::builder()
+            .uri(format!("/api/topic/invite_link/join/{token}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {bob_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let exhausted_resp = app.clone().oneshot(exhausted_req).await.unwrap();
+        assert_eq!(exhausted_resp.status(), StatusCode::BAD_REQUEST);
+
+        let verify_req = Request::builder()
+            .uri(format!("/open/topic/invite/create/{topic_id}"))
+            .method("POST")
+            .header("Authorization", "Bearer test-token")
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let verify_resp = app.clone().oneshot(verify_req).await.unwrap();
+        assert_eq!(verify_resp.status(), StatusCode::OK);
+        let verify_body = verify_resp.into_body().collect().await.unwrap().to_bytes();
+        let verify_json: serde_json::Value = serde_json::from_slice(&verify_body).unwrap();
+        let verify_token = verify_json["token"].as_str().unwrap().to_string();
+
+        let pending_req = Request::builder()
+            .uri(format!("/api/topic/invite_link/join/{verify_token}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {bob_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"message":"via link"}"#))
+            .unwrap();
+        let pending_resp = app.clone().oneshot(pending_req).await.unwrap();
+        assert_eq!(pending_resp.status(), StatusCode::OK);
+        let pending_body = pending_resp.into_body().collect().await.unwrap().to_bytes();
+        let pending_json: serde_json::Value = serde_json::from_slice(&pending_body).unwrap();
+        assert_eq!(pending_json["status"], "pending");
+
+        let knocks_req = Request::builder()
+            .uri(format!("/api/topic/admin/list_knock/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let knocks_resp = app.clone().oneshot(knocks_req).await.unwrap();
+        let knocks_body = knocks_resp.into_body().collect().await.unwrap().to_bytes();
+        let knocks_text = String::from_utf8(knocks_body.to_vec()).unwrap();
+        assert!(knocks_text.contains("inv-bob"));
+
+        let revoke_req = Request::builder()
+            .uri(format!(
+                "/api/topic/admin/invite/revoke/{topic_id}/{verify_token}"
+            ))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let revoke_resp = app.clone().oneshot(revoke_req).await.unwrap();
+        assert_eq!(revoke_resp.status(), StatusCode::OK);
+
+        let list_req = Request::builder()
+            .uri(format!("/api/topic/admin/invite/list/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let list_resp = app.clone().oneshot(list_req).await.unwrap();
+        assert_eq!(list_resp.status(), StatusCode::OK);
+        let list_body = list_resp.into_body().collect().await.unwrap().to_bytes();
+        let list_json: serde_json::Value = serde_json::from_slice(&list_body).unwrap();
+        assert_eq!(list_json.as_array().map(|v| v.len()), Some(0));
+
+        let revoked_preview_req = Request::builder()
+            .uri(format!("/api/topic/invite_link/preview/{verify_token}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let revoked_preview_resp = app.oneshot(revoked_preview_req).await.unwrap();
+        assert_eq!(revoked_preview_resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn topic_roles_gate_admin_endpoints_and_send() {
+        let (app, state) = build_router(test_config()).await.expect("build router");
+        let app = app.with_state(state);
+
+        let owner_token = register_and_auth(&app, "role-owner").await;
+        let mod_token = register_and_auth(&app, "role-mod").await;
+        let member_token = register_and_auth(&app, "role-member").await;
+        let _ = register_and_auth(&app, "role-target").await;
+        let _ = register_and_auth(&app, "role-admin").await;
+
+        let create_req = Request::builder()
+            .uri("/api/topic/create")
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"name":"roles","members":["role-owner","role-mod","role-member","role-target","role-admin"]}"#,
+            ))
+            .unwrap();
+        let create_resp = app.clone().oneshot(create_req).await.unwrap();
+        assert_eq!(create_resp.status(), StatusCode::OK);
+        let create_body = create_resp.into_body().collect().await.unwrap().to_bytes();
+        let create_json: serde_json::Value = serde_json::from_slice(&create_body).unwrap();
+        let topic_id = create_json["id"].as_str().unwrap().to_string();
+
+        let role_req = Request::builder()
+            .uri(format!("/api/topic/admin/role/update/{topic_id}/moderator"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"permissions":["send","kick","manage_roles"]}"#))
+            .unwrap();
+        let role_resp = app.clone().oneshot(role_req).await.unwrap();
+        assert_eq!(role_resp.status(), StatusCode::OK);
+
+        let assign_req = Request::builder()
+            .uri(format!("/open/topic/role/assign/{topic_id}/role-mod"))
+            .method("POST")
+            .header("Authorization", "Bearer test-token")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"role":"moderator"}"#))
+            .unwrap();
+        let assign_resp = app.clone().oneshot(assign_req).await.unwrap();
+        assert_eq!(assign_resp.status(), StatusCode::OK);
+
+        let member_kick_req = Request::builder()
+            .uri(format!("/api/topic/admin/kickout/{topic_id}/role-target"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {member_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let member_kick_resp = app.clone().oneshot(member_kick_req).await.unwrap();
+        assert_eq!(member_kick_resp.status(), StatusCode::UNAUTHORIZED);
+
+        let mod_kick_req = Request::builder()
+            .uri(format!("/api/topic/admin/kickout/{topic_id}/role-target"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {mod_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let mod_kick_resp = app.clone().oneshot(mod_kick_req).await.unwrap();
+        assert_eq!(mod_kick_resp.status(), StatusCode::OK);
+
+        let add_admin_req = Request::builder()
+            .uri(format!("/api/topic/admin/add_admin/{topic_id}/role-admin"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let add_admin_resp = app.clone().oneshot(add_admin_req).await.unwrap();
+        assert_eq!(add_admin_resp.status(), StatusCode::OK);
+
+        // kick permission does not reach the owner or admins
+        for (path, target) in [
+            ("kickout", "role-admin"),
+            ("ban", "role-admin"),
+            ("kickout", "role-owner"),
+            ("ban", "role-owner"),
+        ] {
+            let req = Request::builder()
+                .uri(format!("/api/topic/admin/{path}/{topic_id}/{target}"))
+                .method("POST")
+                .header("Authorization", format!("Bearer {mod_token}"))
+                .body(Body::empty())
+                .unwrap();
+            let resp = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED, "{path} {target}");
+        }
+
+        let owner_ban_req = Request::builder()
+            .uri(format!("/api/topic/admin/ban/{topic_id}/role-admin"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let owner_ban_resp = app.clone().oneshot(owner_ban_req).await.unwrap();
+        assert_eq!(owner_ban_resp.status(), StatusCode::OK);
+
+        // manage_roles covers custom roles only, ownership and admins stay with owner/admins
+        for path in ["transfer", "add_admin", "remove_admin"] {
+            let req = Request::builder()
+                .uri(format!("/api/topic/admin/{path}/{topic_id}/role-mod"))
+                .method("POST")
+                .header("Authorization", format!("Bearer {mod_token}"))
+                .body(Body::empty())
+                .unwrap();
+            let resp = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED, "{path}");
+        }
+
+        let mod_notice_req = Request::builder()
+            .uri(format!("/api/topic/admin/notice/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {mod_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"text":"nope"}"#))
+            .unwrap();
+        let mod_notice_resp = app.clone().oneshot(mod_notice_req).await.unwrap();
+        assert_eq!(mod_notice_resp.status(), StatusCode::UNAUTHORIZED);
+
+        let mention_req = Request::builder()
+            .uri(format!("/api/chat/send/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {mod_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"type":"chat","chatId":"r1","content":{"type":"text","text":"all","mentionAll":true}}"#,
+            ))
+            .unwrap();
+        let mention_resp = app.clone().oneshot(mention_req).await.unwrap();
+        assert_eq!(mention_resp.status(), StatusCode::UNAUTHORIZED);
+
+        let mute_members_req = Request::builder()
+            .uri(format!("/open/topic/role/update/{topic_id}/member"))
+            .method("POST")
+            .header("Authorization", "Bearer test-token")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"permissions":[]}"#))
+            .unwrap();
+        let mute_members_resp = app.clone().oneshot(mute_members_req).await.unwrap();
+        assert_eq!(mute_members_resp.status(), StatusCode::OK);
+
+        let member_send_req = Request::builder()
+            .uri(format!("/api/chat/send/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {member_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"type":"chat","chatId":"r2","message":"hi"}"#,
+            ))
+            .unwrap();
+        let member_send_resp = app.clone().oneshot(member_send_req).await.unwrap();
+        assert_eq!(member_send_resp.status(), StatusCode::UNAUTHORIZED);
+
+        let mod_send_req = Request::builder()
+            .uri(format!("/api/chat/send/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {mod_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"type":"chat","chatId":"r3","message":"hi"}"#,
+            ))
+            .unwrap();
+        let mod_send_resp = app.clone().oneshot(mod_send_req).await.unwrap();
+        assert_eq!(mod_send_resp.status(), StatusCode::OK);
+
+        let list_req = Request::builder()
+            .uri(format!("/api/topic/admin/role/list/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let list_resp = app.oneshot(list_req).await.unwrap();
+        assert_eq!(list_resp.status(), StatusCode::OK);
+        let list_body = list_resp.into_body().collect().await.unwrap().to_bytes();
+        let list_json: serde_json::Value = serde_json::from_slice(&list_body).unwrap();
+        let names: Vec<&str> = list_json
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|v| v["name"].as_str())
+            .collect();
+        assert!(names.contains(&"owner"));
+        assert!(names.contains(&"admin"));
+        assert!(names.contains(&"moderator"));
+        let member_role = list_json
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|v| v["name"] == "member")
+            .unwrap();
+        assert_eq!(member_role["builtin"], false);
+    }
+
+    #[tokio::test]
+    async fn topic_ban_blocks_rejoin_until_unbanned() {
+        let (app, state) = build_router(test_config()).await.expect("build router");
+        let mut events = state.event_bus.subscribe();
+        let app = app.with_state(state);
+
+        let owner_token = register_and_auth(&app, "ban-owner").await;
+        let target_token = register_and_auth(&app, "ban-target").await;
+
+        let create_req = Request::builder()
+            .uri("/api/topic/create")
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"name":"bans","members":["ban-owner","ban-target"]}"#,
+            ))
+            .unwrap();
+        let create_resp = app.clone().oneshot(create_req).await.unwrap();
+        assert_eq!(create_resp.status(), StatusCode::OK);
+        let create_body = create_resp.into_body().collect().await.unwrap().to_bytes();
+        let create_json: serde_json::Value = serde_json::from_slice(&create_body).unwrap();
+        let topic_id = create_json["id"].as_str().unwrap().to_string();
+
+        let ban_req = Request::builder()
+            .uri(format!("/api/topic/admin/ban/{topic_id}/ban-target"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"reason":"spam","duration":"1d"}"#))
+            .unwrap();
+        let ban_resp = app.clone().oneshot(ban_req).await.unwrap();
+        assert_eq!(ban_resp.status(), StatusCode::OK);
+        let ban_body = ban_resp.into_body().collect().await.unwrap().to_bytes();
+        let ban_json: serde_json::Value = serde_json::from_slice(&ban_body).unwrap();
+        assert_eq!(ban_json["adminId"], "ban-owner");
+        assert_eq!(ban_json["reason"], "spam");
+
+        let mut seen_ban = false;
+        while let Ok(event) = events.try_recv() {
+            if event.event_name() == "topic.ban" {
+                assert_eq!(event.data_payload()["userId"], "ban-target");
+                seen_ban = true;
+            }
+        }
+        assert!(seen_ban);
+
+        let member_req = Request::builder()
+            .uri(format!("/open/topic/member_info/{topic_id}/ban-target"))
+            .method("POST")
+            .header("Authorization", "Bearer test-token")
+            .body(Body::empty())
+            .unwrap();
+        let member_resp = app.clone().oneshot(member_req).await.unwrap();
+        assert_eq!(member_resp.status(), StatusCode::NOT_FOUND);
+
+        let knock_req = Request::builder()
+            .uri(format!("/api/topic/knock/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {target_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"message":"sorry"}"#))
+            .unwrap();
+        let knock_resp = app.clone().oneshot(knock_req).await.unwrap();
+        assert_eq!(knock_resp.status(), StatusCode::UNAUTHORIZED);
+
+        let invite_req = Request::builder()
+            .uri(format!("/api/topic/invite/{topic_id}/ban-target"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let invite_resp = app.clone().oneshot(invite_req).await.unwrap();
+        assert_eq!(invite_resp.status(), StatusCode::BAD_REQUEST);
+
+        let join_req = Request::builder()
+            .uri(format!("/open/topic/join/{topic_id}"))
+            .method("POST")
+            .header("Authorization", "Bearer test-token")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"userIds":["ban-target"]}"#))
+            .unwrap();
+        let join_resp = app.clone().oneshot(join_req).await.unwrap();
+        assert_eq!(join_resp.status(), StatusCode::BAD_REQUEST);
+
+        let list_req = Request::builder()
+            .uri(format!("/open/topic/bans/{topic_id}"))
+            .method("POST")
+            .header("Authorization", "Bearer test-token")
+            .body(Body::empty())
+            .unwrap();
+        let list_resp = app.clone().oneshot(list_req).await.unwrap();
+        assert_eq!(list_resp.status(), StatusCode::OK);
+        let list_body = list_resp.into_body().collect().await.unwrap().to_bytes();
+        let list_text = String::from_utf8(list_body.to_vec()).unwrap();
+        assert!(list_text.contains("ban-target"));
+
+        let unban_req = Request::builder()
+            .uri(format!("/open/topic/unban/{topic_id}/ban-target"))
+            .method("POST")
+            .header("Authorization", "Bearer test-token")
+            .body(Body::empty())
+            .unwrap();
+        let unban_resp = app.clone().oneshot(unban_req).await.unwrap();
+        assert_eq!(unban_resp.status(), StatusCode::OK);
+
+        let rejoin_req = Request::builder()
+            .uri(format!("/api/topic/knock/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {target_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"message":"back"}"#))
+            .unwrap();
+        let rejoin_resp = app.oneshot(rejoin_req).await.unwrap();
+        assert_eq!(rejoin_resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn topic_directory_search_and_join() {
+        let (app, state) = build_router(test_config()).await.expect("build router");
+        let app = app.with_state(state);
+
+        let owner_token = register_and_auth(&app, "dir-owner").await;
+        let alice_token = register_and_auth(&app, "dir-alice").await;
+
+        let mut topic_ids = Vec::new();
+        for body in [
+            r#"{"name":"dir-open","members":["dir-owner","dir-alice"],"extra":{"tags":"rust"}}"#,
+            r#"{"name":"dir-verify","members":["dir-owner"],"knockNeedVerify":true}"#,
+            r#"{"name":"dir-hidden","members":["dir-owner"],"private":true}"#,
+        ] {
+            let req = Request::builder()
+                .uri("/api/topic/create")
+                .method("POST")
+                .header("Authorization", format!("Bearer {owner_token}"))
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            let resp = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let body = resp.into_body().collect().await.unwrap().to_bytes();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            topic_ids.push(json["id"].as_str().unwrap().to_string());
+        }
+
+        let search_req = Request::builder()
+            .uri("/api/topic/directory")
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"keyword":"dir-","limit":1}"#))
+            .unwrap();
+        let search_resp = app.clone().oneshot(search_req).await.unwrap();
+        assert_eq!(search_resp.status(), StatusCode::OK);
+        let search_body = search_resp.into_body().collect().await.unwrap().to_bytes();
+        let search_json: serde_json::Value = serde_json::from_slice(&search_body).unwrap();
+        assert_eq!(search_json["total"], 2);
+        assert_eq!(search_json["hasMore"], true);
+        assert_eq!(search_json["items"][0]["name"], "dir-open");
+        assert_eq!(search_json["items"][0]["members"], 2);
+        assert_eq!(search_json["items"][0]["isMember"], true);
+
+        let tag_req = Request::builder()
+            .uri("/api/topic/directory")
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"keyword":"rust","sort":"active"}"#))
+            .unwrap();
+        let tag_resp = app.clone().oneshot(tag_req).await.unwrap();
+        let tag_body = tag_resp.into_body().collect().await.unwrap().to_bytes();
+        let tag_json: serde_json::Value = serde_json::from_slice(&tag_body).unwrap();
+        assert_eq!(tag_json["total"], 1);
+        assert_eq!(tag_json["items"][0]["topicId"], topic_ids[0].as_str());
+
+        let hidden_req = Request::builder()
+            .uri(format!("/api/topic/directory/preview/{}", topic_ids[2]))
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let hidden_resp = app.clone().oneshot(hidden_req).await.unwrap();
+        assert_eq!(hidden_resp.status(), StatusCode::NOT_FOUND);
+
+        let join_req = Request::builder()
+            .uri(format!("/api/topic/directory/join/{}", topic_ids[1]))
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"message":"let me in"}"#))
+            .unwrap();
+        let join_resp = app.clone().oneshot(join_req).await.unwrap();
+        assert_eq!(join_resp.status(), StatusCode::OK);
+        let join_body = join_resp.into_body().collect().await.unwrap().to_bytes();
+        let join_json: serde_json::Value = serde_json::from_slice(&join_body).unwrap();
+        assert_eq!(join_json["status"], "pending");
+
+        let preview_req = Request::builder()
+            .uri(format!("/api/topic/directory/preview/{}", topic_ids[1]))
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let preview_resp = app.clone().oneshot(preview_req).await.unwrap();
+        let preview_body = preview_resp.into_body().collect().await.unwrap().to_bytes();
+        let preview_json: serde_json::Value = serde_json::from_slice(&preview_body).unwrap();
+        assert_eq!(preview_json["needVerify"], true);
+        assert_eq!(preview_json["isMember"], false);
+    }
+
+    #[tokio::test]
+    async fn topic_knock_rule_auto_approve_and_result_notification() {
+        let (app, state) = build_router(test_config()).await.expect("build router");
+        let app = app.with_state(state.clone());
+
+        let owner_token = register_and_auth(&app, "knock-owner").await;
+        let carol_token = register_and_auth(&app, "knock-carol").await;
+        let reg_req = Request::builder()
+            .uri("/open/user/register/knock-bob")
+            .method("POST")
+            .header("Authorization", "Bearer test-token")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"city":"paris"}"#))
+            .unwrap();
+        let reg_resp = app.clone().oneshot(reg_req).await.unwrap();
+        let reg_body = reg_resp.into_body().collect().await.unwrap().to_bytes();
+        let reg_json: serde_json::Value = serde_json::from_slice(&reg_body).unwrap();
+        let bob_token = reg_json["authToken"].as_str().unwrap().to_string();
+
+        let create_req = Request::builder()
+            .uri("/api/topic/create")
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"name":"knock-group","members":["knock-owner"],"knockNeedVerify":true}"#,
+            ))
+            .unwrap();
+        let create_resp = app.clone().oneshot(create_req).await.unwrap();
+        let create_body = create_resp.into_body().collect().await.unwrap().to_bytes();
+        let create_json: serde_json::Value = serde_json::from_slice(&create_body).unwrap();
+        let topic_id = create_json["id"].as_str().unwrap().to_string();
+
+        let rule_req = Request::builder()
+            .uri(format!("/api/topic/admin/knock_rule/update/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {carol_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"attributes":{"city":"paris"}}"#))
+            .unwrap();
+        let rule_resp = app.clone().oneshot(rule_req).await.unwrap();
+        assert_eq!(rule_resp.status(), StatusCode::UNAUTHORIZED);
+
+        let rule_req = Request::builder()
+            .uri(format!("/api/topic/admin/knock_rule/update/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"attributes":{"city":"paris"}}"#))
+            .unwrap();
+        let rule_resp = app.clone().oneshot(rule_req).await.unwrap();
+        assert_eq!(rule_resp.status(), StatusCode::OK);
+        let rule_body = rule_resp.into_body().collect().await.unwrap().to_bytes();
+        let rule_json: serde_json::Value = serde_json::from_slice(&rule_body).unwrap();
+        assert_eq!(rule_json["attributes"]["city"], "paris");
+
+        for token in [&bob_token, &carol_token] {
+            let knock_req = Request::builder()
+                .uri(format!("/api/topic/knock/{topic_id}"))
+                .method("POST")
+                .header("Authorization", format!("Bearer {token}"))
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"message":"hi"}"#))
+                .unwrap();
+            let knock_resp = app.clone().oneshot(knock_req).await.unwrap();
+            assert_eq!(knock_resp.status(), StatusCode::OK);
+        }
+        assert!(state
+            .topic_service
+            .get_member(&topic_id, "knock-bob")
+            .await
+            .is_ok());
+        let pending = state
+            .topic_service
+            .list_pending_knocks(&topic_id)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].user_id, "knock-carol");
+
+        let reject_req = Request::builder()
+            .uri(format!(
+                "/api/topic/admin/knock/reject/{topic_id}/knock-carol"
+            ))
+            .method("POST")
+            .header("Authorization", format!("Bearer {owner_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"message":"group is full"}"#))
+            .unwrap();
+        let reject_resp = app.clone().oneshot(reject_req).await.unwrap();
+        assert_eq!(reject_resp.status(), StatusCode::OK);
+
+        let conv = state
+            .conversation_service
+            .get_conversation("knock-carol", &topic_id)
+            .await
+            .unwrap();
+        assert_eq!(conv.name, "knock-group");
+        assert_eq!(conv.unread, 1);
+        assert_eq!(conv.last_sender_id, "knock-owner");
+        let last_message = conv.last_message.unwrap();
+        assert_eq!(last_message.content_type, "topic.knock.reject");
+        assert_eq!(last_message.text, "group is full");
+    }
+
+    #[tokio::test]
+    async fn contact_request_flow_with_contacts_only_dm() {
+        let mut config = test_config();
+        config.dm_contacts_only = true;
+        let (app, state) = build_router(config).await.expect("build router");
+        let app = app.with_state(state.clone());
+
+        let alice_token = register_and_auth(&app, "contact-alice").await;
+        let bob_token = register_and_auth(&app, "contact-bob").await;
+
+        let topic_req = Request::builder()
+            .uri("/api/topic/create/contact-bob")
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let topic_resp = app.clone().oneshot(topic_req).await.unwrap();
+        assert_eq!(topic_resp.status(), StatusCode::OK);
+        let topic_body = topic_resp.into_body().collect().await.unwrap().to_bytes();
+        let topic_json: serde_json::Value = serde_json::from_slice(&topic_body).unwrap();
+        let topic_id = topic_json["id"].as_str().unwrap().to_string();
+
+        let send_req = Request::builder()
+            .uri(format!("/api/chat/send/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"type":"chat","chatId":"c1","message":"hi"}"#,
+            ))
+            .unwrap();
+        let send_resp = app.clone().oneshot(send_req).await.unwrap();
+        assert_eq!(send_resp.status(), StatusCode::UNAUTHORIZED);
+
+        let request_req = Request::builder()
+            .uri("/api/contact/request/contact-bob")
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"message":"it's alice"}"#))
+            .unwrap();
+        let request_resp = app.clone().oneshot(request_req).await.unwrap();
+        assert_eq!(request_resp.status(), StatusCode::OK);
+
+        let list_req = Request::builder()
+            .uri("/api/contact/requests")
+            .method("POST")
+            .header("Authorization", format!("Bearer {bob_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let list_resp = app.clone().oneshot(list_req).await.unwrap();
+        assert_eq!(list_resp.status(), StatusCode::OK);
+        let list_body = list_resp.into_body().collect().await.unwrap().to_bytes();
+        let list_json: serde_json::Value = serde_json::from_slice(&list_body).unwrap();
+        assert_eq!(list_json.as_array().unwrap().len(), 1);
+        assert_eq!(list_json[0]["senderId"], "contact-alice");
+        assert_eq!(list_json[0]["message"], "it's alice");
+
+        let accept_req = Request::builder()
+            .uri("/api/contact/accept/contact-alice")
+            .method("POST")
+            .header("Authorization", format!("Bearer {bob_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let accept_resp = app.clone().oneshot(accept_req).await.unwrap();
+        assert_eq!(accept_resp.status(), StatusCode::OK);
+        assert!(state
+            .relation_service
+            .is_contact("contact-alice", "contact-bob")
+            .await
+            .unwrap());
+
+        let again_req = Request::builder()
+            .uri("/api/contact/accept/contact-alice")
+            .method("POST")
+            .header("Authorization", format!("Bearer {bob_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let again_resp = app.clone().oneshot(again_req).await.unwrap();
+        assert_eq!(again_resp.status(), StatusCode::BAD_REQUEST);
+
+        let send_req = Request::builder()
+            .uri(format!("/api/chat/send/{topic_id}"))
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"type":"chat","chatId":"c2","message":"hi"}"#,
+            ))
+            .unwrap();
+        let send_resp = app.clone().oneshot(send_req).await.unwrap();
+        assert_eq!(send_resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn user_directory_search_respects_privacy_blocks_and_rate_limit() {
+        let mut config = test_config();
+        config.user_search_rate_limit = 3;
+        let (app, state) = build_router(config).await.expect("build router");
+        let app = app.with_state(state);
+
+        let alice_token = register_and_auth(&app, "dir-alice").await;
+        let erin_token = register_and_auth(&app, "dir-erin").await;
+        for (user_id, body) in [
+            ("dir-bob", r#"{"city":"Lisbon"}"#),
+            ("dir-carol", r#"{"extra":{"team":"lisbon-ops"}}"#),
+            ("dir-dave", r#"{"city":"Lisbon"}"#),
+            ("dir-erin", r#"{"city":"Lisbon"}"#),
+        ] {
+            let _ = register_and_auth(&app, user_id).await;
+            let update_req = Request::builder()
+                .uri(format!("/open/user/update/{user_id}"))
+                .method("POST")
+                .header("Authorization", "Bearer test-token")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            let update_resp = app.clone().oneshot(update_req).await.unwrap();
+            assert_eq!(update_resp.status(), StatusCode::OK);
+        }
+        let dave_token = register_and_auth(&app, "dir-dave").await;
+        let opt_out_req = Request::builder()
+            .uri("/api/profile/update")
+            .method("POST")
+            .header("Authorization", format!("Bearer {dave_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"searchOptOut":true}"#))
+            .unwrap();
+        let opt_out_resp = app.clone().oneshot(opt_out_req).await.unwrap();
+        assert_eq!(opt_out_resp.status(), StatusCode::OK);
+
+        let block_req = Request::builder()
+            .uri("/api/block/dir-alice")
+            .method("POST")
+            .header("Authorization", format!("Bearer {erin_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let block_resp = app.clone().oneshot(block_req).await.unwrap();
+        assert_eq!(block_resp.status(), StatusCode::OK);
+
+        let search_req = Request::builder()
+            .uri("/api/users/search")
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"keyword":"lisbon"}"#))
+            .unwrap();
+        let search_resp = app.clone().oneshot(search_req).await.unwrap();
+        assert_eq!(search_resp.status(), StatusCode::OK);
+        let search_body = search_resp.into_body().collect().await.unwrap().to_bytes();
+        let search_json: serde_json::Value = serde_json::from_slice(&search_body).unwrap();
+        let found: Vec<&str> = search_json["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["userId"].as_str().unwrap())
+            .collect();
+        assert_eq!(found, vec!["dir-bob", "dir-carol"]);
+        assert_eq!(search_json["total"], 2);
+
+        let empty_req = Request::builder()
+            .uri("/api/users/search")
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"keyword":" "}"#))
+            .unwrap();
+        let empty_resp = app.clone().oneshot(empty_req).await.unwrap();
+        assert_eq!(empty_resp.status(), StatusCode::BAD_REQUEST);
+
+        let admin_req = Request::builder()
+            .uri("/open/user/search")
+            .method("POST")
+            .header("Authorization", "Bearer test-token")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"keyword":"lisbon","isStaff":false,"enabled":true}"#,
+            ))
+            .unwrap();
+        let admin_resp = app.clone().oneshot(admin_req).await.unwrap();
+        assert_eq!(admin_resp.status(), StatusCode::OK);
+        let admin_body = admin_resp.into_body().collect().await.unwrap().to_bytes();
+        let admin_json: serde_json::Value = serde_json::from_slice(&admin_body).unwrap();
+        assert_eq!(admin_json["total"], 4);
+
+        for expected in [StatusCode::OK, StatusCode::TOO_MANY_REQUESTS] {
+            let req = Request::builder()
+                .uri("/api/users/search")
+                .method("POST")
+                .header("Authorization", format!("Bearer {alice_token}"))
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"keyword":"dir-"}"#))
+                .unwrap();
+            let resp = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn user_presence_status_invisible_and_last_seen() {
+        let config = test_config();
+        let (app, state) = build_router(config).await.expect("build router");
+        let app = app.with_state(state.clone());
+
+        let alice_token = register_and_auth(&app, "presence-alice").await;
+        let bob_token = register_and_auth(&app, "presence-bob").await;
+
+        let update_req = Request::builder()
+            .uri("/api/presence/update")
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"status":"away","statusText":"lunch","expireSecs":600}"#,
+            ))
+            .unwrap();
+        let update_resp = app.clone().oneshot(update_req).await.unwrap();
+        assert_eq!(update_resp.status(), StatusCode::OK);
+        let update_body = update_resp.into_body().collect().await.unwrap().to_bytes();
+        let update_json: serde_json::Value = serde_json::from_slice(&update_body).unwrap();
+        assert_eq!(update_json["status"], "away");
+        assert!(!update_json["statusExpiresAt"].as_str().unwrap().is_empty());
+
+        let invalid_req = Request::builder()
+            .uri("/api/presence/update")
+            .method("POST")
+            .header("Authorization", format!("Bearer {alice_token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"status":"sleeping"}"#))
+            .unwrap();
+        let invalid_resp = app.clone().oneshot(invalid_req).await.unwrap();
+        assert_eq!(invalid_resp.status(), StatusCode::BAD_REQUEST);
+
+        let fetch = |token: String| {
+            let app = app.clone();
+            async move {
+                let req = Request::builder()
+                    .uri("/api/presence/presence-alice")
+                    .method("POST")
+                    .header("Authorization", format!("Bearer {token}"))
+                    .body(Body::empty())
+                    .unwrap();
+                let resp = app.oneshot(req).await.unwrap();
+                assert_eq!(resp.status(), StatusCode::OK);
+                let body = resp.into_body().collect().await.unwrap().to_bytes();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        let offline = fetch(bob_token.clone()).await;
+        assert_eq!(offline["online"], false);
+        assert_eq!(offline["status"], "offline");
+        assert_eq!(offline["statusText"], "");
+
+        state
+            .presence_hub
+            .upsert_session("presence-alice", "web")
+            .await;
+        let away = fetch(bob_token.clone()).await;
+        assert_eq!(away["online"], true);
+        assert_eq!(away["status"], "away");
+        assert_eq!(away["statusText"], "lunch");
+
+        let profile_req = Request::builder()
+            .uri("/api/profile/presence-alice")
+            .method("POST")
+            .header("Authorization", format!("Bearer {bob_token}"))
+            .body(Body::empty())
+            .unwrap();
+        let profile_resp = app.clone().oneshot(profile_req).await.unwrap();
+        let profile_body = profile_resp.into_body().collect().await.unwrap().to_bytes();
+        let profile_json: serde_json::Value = serde_json::from_slice(&profile_body).unwrap();
+        assert_eq!(profile_json["presence"]["status"], "away");
+
+        state
+            .user_service
+            .set_presence(
+                "presence-alice",
+                crate::PresenceForm {
+                    status: "invisible".to_string(),
+                    ..crate::PresenceForm::default()
+                },
+            )
+            .await
+            .unwrap();
+        let hidden = fetch(bob_token.clone()).await;
+        assert_eq!(hidden["online"], false);
+        assert_eq!(hidden["status"], "offline");
+        let own = fetch(alice_token.clone()).await;
+        assert_eq!(own["online"], true);
+        assert_eq!(own["status"], "invisible");
+
+        state
+            .user_service
+            .touch_last_seen("presence-alice")
+            .await
+            .unwrap();
+        assert_eq!(fetch(bob_token.clone()).await["lastSeenAt"], "");
+
+        state
+            .user_service
+            .set_presence(
+                "presence-alice",
+                crate::PresenceForm {
+                    status: "online".to_string(),
+                    ..crate::PresenceForm::default()
+                },
+            )
+            .await
+            .unwrap();
+        state
+            .presence_hub
+            .remove_session("presence-alice", "web")
+            .await;
+        state
+            .user_service
+            .touch_last_seen("presence-alice")
+            .await
+            .unwrap();
+        let last_seen = fetch(bob_token).await;
+        assert_eq!(last_seen["status"], "offline");
+        assert!(!last_seen["lastSeenAt"].as_str().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn websocket_presence_subscription_pushes_online_changes() {
+        let mut config = test_config();
+        config.presence_debounce_ms = 50;
+        let (app, state) = build_router(config).await.expect("build router");
+        let hub = state.presence_hub.clone();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let alice_token = register_and_auth_http(&client, &endpoint, "sub-alice").await;
+        let bob_token = register_and_auth_http(&client, &endpoint, "sub-bob").await;
+        let _ = register_and_auth_http(&client, &endpoint, "sub-carol").await;
+        let _ = register_and_auth_http(&client, &endpoint, "sub-dave").await;
+        let resp = client
+            .post(format!("{endpoint}/open/user/relation/sub-alice/sub-bob"))
+            .bearer_auth("test-token")
+            .json(&serde_json::json!({"isContact": true}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let topic: serde_json::Value = client
+            .post(format!("{endpoint}/api/topic/create"))
+            .bearer_auth(&alice_token)
+            .json(&serde_json::json!({"name": "sub", "members": ["sub-alice", "sub-dave"]}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let topic_id = topic["id"].as_str().unwrap().to_string();
+
+        let connect = |token: String, device: &str| {
+            let mut req = format!("ws://{}/api/connect?device={}", addr, device)
+                .into_client_request()
+                .unwrap();
+            req.headers_mut()
+                .insert("Authorization", format!("Bearer {token}").parse().unwrap());
+            tokio_tungstenite::connect_async(req)
+        };
+        let (mut alice_ws, _) = connect(alice_token.clone(), "web").await.unwrap();
+        alice_ws
+            .send(tokio_tungstenite::tungstenite::Message::Text(
+                serde_json::json!({
+                    "type": "subscribe",
+                    "chatId": "sub-1",
+                    "users": ["sub-bob", "sub-carol"],
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+
+        async fn expect_presence<S>(ws: &mut S, online: &str) -> serde_json::Value
+        where
+            S: futures_util::Stream<
+                    Item = Result<
+                        tokio_tungstenite::tungstenite::Message,
+                        tokio_tungstenite::tungstenite::Error,
+                    >,
+                > + Unpin,
+        {
+            tokio::time::timeout(std::time::Duration::from_secs(5), async {
+                loop {
+                    let msg = ws.next().await.unwrap().unwrap().into_text().unwrap();
+                    let json: serde_json::Value = serde_json::from_str(&msg).unwrap();
+                    if json["type"] == "presence"
+                        && json["attendee"] == "sub-bob"
+                        && json["content"]["extra"]["online"] == online
+                    {
+                        return json;
+                    }
+                }
+            })
+            .await
+            .expect("presence push")
+        }
+
+        let initial = expect_presence(&mut alice_ws, "false").await;
+        assert_eq!(initial["content"]["text"], "offline");
+        // explicit users must be contacts or members of the given topic
+        assert_eq!(hub.subscribers("sub-bob").await.len(), 1);
+        assert!(hub.subscribers("sub-carol").await.is_empty());
+
+        let (mut bob_ws, _) = connect(bob_token, "ios").await.unwrap();
+        let online = expect_presence(&mut alice_ws, "true").await;
+        assert_eq!(online["content"]["text"], "online");
+
+        bob_ws.close(None).await.unwrap();
+        let offline = expect_presence(&mut alice_ws, "false").await;
+        assert!(!offline["content"]["extra"]["lastSeenAt"]
+            .as_str()
+            .unwrap()
+            .is_empty());
+
+        alice_ws
+            .send(tokio_tungstenite::tungstenite::Message::Text(
+                serde_json::json!({
+                    "type": "subscribe",
+                    "chatId": "sub-2",
+                    "topicId": topic_id,
+                    "users": ["sub-bob", "sub-carol", "sub-dave"],
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                let msg = alice_ws.next().await.unwrap().unwrap().into_text().unwrap();
+                let json: serde_json::Value = serde_json::from_str(&msg).unwrap();
+                if json["type"] == "resp" && json["chatId"] == "sub-2" {
+                    assert_eq!(json["code"], 200);
+                    return;
+                }
+            }
+        })
+        .await
+        .expect("subscribe resp");
+        assert_eq!(hub.subscribers("sub-bob").await.len(), 1);
+        assert_eq!(hub.subscribers("sub-dave").await.len(), 1);
+        assert!(hub.subscribers("sub-carol").await.is_empty());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn websocket_user_online_offline_events_debounce_devices() {
+        let mut config = test_config();
+        config.presence_debounce_ms = 100;
+        let (app, state) = build_router(config).await.expect("build router");
+        let mut events = state.event_bus.subscribe();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let token = register_and_auth_http(&client, &endpoint, "online-bob").await;
+        let connect = |device: &str| {
+            let mut req = format!("ws://{}/api/connect?device={}", addr, device)
+                .into_client_request()
+                .unwrap();
+            req.headers_mut()
+                .insert("Authorization", format!("Bearer {token}").parse().unwrap());
+            tokio_tungstenite::connect_async(req)
+        };
+
+        let (mut web_ws, _) = connect("web").await.unwrap();
+        let (mut ios_ws, _) = connect("ios").await.unwrap();
+        web_ws.close(None).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        ios_ws.close(None).await.unwrap();
+
+        let mut received = Vec::new();
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                match events.recv().await.unwrap() {
+                    crate::infra::event::BackendEvent::UserOnline(v) => {
+                        received.push(("online", v))
+                    }
+                    crate::infra::event::BackendEvent::UserOffline(v) => {
+                        received.push(("offline", v));
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        })
+        .await
+        .expect("user offline event");
+
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].0, "online");
+        assert_eq!(received[0].1.user_id, "online-bob");
+        assert_eq!(received[0].1.device, "web");
+        assert_eq!(received[0].1.node_id, "test-node");
+        assert_eq!(received[1].1.device, "ios");
+        assert_eq!(
+            crate::infra::event::BackendEvent::UserOffline(received[1].1.clone()).event_name(),
+            "user.offline"
+        );
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn conversation_changes_sync_to_other_devices_only() {
+        let (app, state) = build_router(test_config()).await.expect("build router");
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let token = register_and_auth_http(&client, &endpoint, "sync-alice").await;
+        let _ = register_and_auth_http(&client, &endpoint, "sync-bob").await;
+        let conv: serde_json::Value = client
+            .post(format!("{endpoint}/api/chat/create/sync-bob"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let topic_id = conv["topicId"].as_str().unwrap().to_string();
+
+        let connect = |device: &str| {
+            let mut req = format!("ws://{}/api/connect?device={}", addr, device)
+                .into_client_request()
+                .unwrap();
+            req.headers_mut()
+                .insert("Authorization", format!("Bearer {token}").parse().unwrap());
+            tokio_tungstenite::connect_async(req)
+        };
+        let (mut web_ws, _) = connect("web").await.unwrap();
+        let (mut ios_ws, _) = connect("ios").await.unwrap();
+
+        async fn recv_conversation_update<S>(ws: &mut S) -> Option<serde_json::Value>
+        where
+            S: futures_util::Stream<
+                    Item = Result<
+                        tokio_tungstenite::tungstenite::Message,
+                        tokio_tungstenite::tungstenite::Error,
+                    >,
+                > + Unpin,
+        {
+            tokio::time::timeout(std::time::Duration::from_millis(500), async {
+                loop {
+                    let msg = ws.next().await.unwrap().unwrap().into_text().unwrap();
+                    let json: serde_json::Value = serde_json::from_str(&msg).unwrap();
+                    if json["content"]["type"] == "conversation.update" {
+                        let text = json["content"]["text"].as_str().unwrap();
+                        return serde_json::from_str(text).unwrap();
+                    }
+                }
+            })
+            .await
+            .ok()
+        }
+
+        ios_ws
+            .send(tokio_tungstenite::tungstenite::Message::Text(
+                serde_json::json!({"type": "read", "chatId": "read-1", "topicId": topic_id})
+                    .to_string(),
+            ))
+            .await
+            .unwrap();
+        let (_, ack) = recv_until_chat_id(&mut ios_ws, "read-1").await;
+        assert_eq!(ack["code"], 200);
+        let fields = recv_conversation_update(&mut web_ws)
+            .await
+            .expect("read synced to web");
+        assert_eq!(fields["lastReadSeq"], 0);
+        assert_eq!(fields["unread"], 0);
+
+        let resp = client
+            .post(format!("{endpoint}/api/chat/update/{topic_id}"))
+            .bearer_auth(&token)
+            .header("X-Restsend-Device", "ios")
+            .json(&serde_json::json!({
+                "sticky": true,
+                "tags": [{"id": "work", "type": "label", "label": "Work"}],
+                "extra": {"color": "red"},
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let fields = recv_conversation_update(&mut web_ws)
+            .await
+            .expect("update synced to web");
+        assert_eq!(fields["sticky"], true);
+        assert_eq!(fields["tags"][0]["id"], "work");
+        assert_eq!(fields["extra"]["color"], "red");
+
+        assert!(recv_conversation_update(&mut ios_ws).await.is_none());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn ws_connect_tickets_are_single_use_and_bound_to_device() {
+        let config = test_config();
+        let (app, state) = build_router(config.clone()).await.expect("build router");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let token = register_and_auth_http(&client, &endpoint, "ticket-alice").await;
+        let issue = |query: &'static str| {
+            client
+                .post(format!("{endpoint}/api/connect/ticket?{query}"))
+                .bearer_auth(&token)
+                .send()
+        };
+        let resp = client
+            .post(format!("{endpoint}/api/connect/ticket"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let ticket: crate::WsConnectTicket = issue("device=web&nonce=ab1")
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(ticket.device, "web:ab1");
+        assert_eq!(ticket.expires_in, 30);
+
+        let url = format!("ws://{}/api/connect?ticket={}", addr, ticket.ticket);
+        let (mut ws, _) = tokio_tungstenite::connect_async(url.clone()).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let sessions: Vec<crate::DeviceSession> = client
+            .post(format!("{endpoint}/api/sessions"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let bound = sessions.iter().find(|s| s.device == "web:ab1").unwrap();
+        assert!(bound.online);
+        assert!(tokio_tungstenite::connect_async(url).await.is_err());
+        let _ = ws.close(None).await;
+
+        let ticket: crate::WsConnectTicket = issue("device=web")
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let mismatch = format!("ws://{}/ws?ticket={}&device=ios", addr, ticket.ticket);
+        assert!(tokio_tungstenite::connect_async(mismatch).await.is_err());
+        let spoofed = format!("ws://{}/ws?user_id=ticket-alice", addr);
+        assert!(tokio_tungstenite::connect_async(spoofed).await.is_err());
+
+        let ticket: crate::WsConnectTicket = issue("device=web")
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let url = format!("ws://{}/ws?ticket={}&device=web", addr, ticket.ticket);
+        let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let _ = ws.close(None).await;
+
+        let mut strict_config = config;
+        strict_config.run_migrations = false;
+        strict_config.ws_require_ticket = true;
+        let (app, state) = build_router(strict_config).await.expect("build router");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let strict_addr = listener.local_addr().unwrap();
+        let strict = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+        let resp = client
+            .post(format!("http://{strict_addr}/api/sessions"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let url = format!("ws://{}/api/connect?token={}", strict_addr, token);
+        assert!(tokio_tungstenite::connect_async(url).await.is_err());
+
+        strict.abort();
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn device_sessions_list_and_revoke_kicks_connection() {
+        let (app, state) = build_router(test_config()).await.expect("build router");
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let web_token = register_and_auth_http(&client, &endpoint, "sess-alice").await;
+        let issue = |device_name: &str, platform: &str| {
+            client
+                .post(format!("{endpoint}/open/user/auth/sess-alice"))
+                .bearer_auth("test-token")
+                .json(&serde_json::json!({
+                    "clientIp": "10.0.0.2",
+                    "deviceName": device_name,
+                    "platform": platform,
+                    "appVersion": "1.2.0",
+                }))
+                .send()
+        };
+        let resp: serde_json::Value = issue("iPhone", "ios").await.unwrap().json().await.unwrap();
+        let ios_token = resp["authToken"].as_str().unwrap().to_string();
+        let resp: serde_json::Value = issue("Pixel", "android")
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let android_token = resp["authToken"].as_str().unwrap().to_string();
+
+        let mut req = format!("ws://{}/api/connect?device=ios", addr)
+            .into_client_request()
+            .unwrap();
+        req.headers_mut().insert(
+            "Authorization",
+            format!("Bearer {ios_token}").parse().unwrap(),
+        );
+        let (mut ios_ws, _) = tokio_tungstenite::connect_async(req).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let list_sessions = |token: String| {
+            client
+                .post(format!("{endpoint}/api/sessions"))
+                .bearer_auth(token)
+                .send()
+        };
+        let sessions: Vec<crate::DeviceSession> = list_sessions(web_token.clone())
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        // register also issues a token, so alice starts with four sessions
+        assert_eq!(sessions.len(), 4);
+        assert_eq!(sessions.iter().filter(|s| s.current).count(), 1);
+        let ios = sessions.iter().find(|s| s.device_name == "iPhone").unwrap();
+        assert_eq!(ios.platform, "ios");
+        assert_eq!(ios.app_version, "1.2.0");
+        assert_eq!(ios.ip, "10.0.0.2");
+        assert_eq!(ios.device, "ios");
+        assert!(ios.online);
+        assert!(!ios.current);
+
+        let resp = client
+            .post(format!("{endpoint}/api/sessions/revoke/{}", ios.session_id))
+            .bearer_auth(&web_token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+
+        let kicked = tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            let mut kickout = false;
+            while let Some(Ok(msg)) = ios_ws.next().await {
+                if let Ok(text) = msg.into_text() {
+                    if text.contains("\"kickout\"") {
+                        kickout = true;
+                    }
+                }
+            }
+            kickout
+        })
+        .await
+        .expect("ios connection closed");
+        assert!(kicked);
+        let resp = list_sessions(ios_token).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let resp = client
+            .post(format!("{endpoint}/api/sessions/revoke/unknown"))
+            .bearer_auth(&web_token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let resp = client
+            .post(format!("{endpoint}/api/sessions/revoke_others"))
+            .bearer_auth(&web_token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let resp = list_sessions(android_token).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let sessions: Vec<crate::DeviceSession> = client
+            .post(format!("{endpoint}/open/user/sessions/sess-alice"))
+            .bearer_auth("test-token")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert!(!sessions[0].current);
+
+        let resp = client
+            .post(format!(
+                "{endpoint}/open/user/sessions/revoke_all/sess-alice"
+            ))
+            .bearer_auth("test-token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let resp = list_sessions(web_token).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn token_expiry_and_refresh_rotation() {
+        let mut config = test_config();
+        config.token_ttl_secs = 2;
+        let (app, state) = build_router(config).await.expect("build router");
+        let auth_service = state.auth_service.clone();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let login: serde_json::Value = client
+            .post(format!("{endpoint}/auth/register"))
+            .json(&serde_json::json!({"email": "ttl-alice", "password": "pass-1"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let token = login["token"].as_str().unwrap().to_string();
+        let refresh_token = login["refreshToken"].as_str().unwrap().to_string();
+        assert!(!login["expiresAt"].as_str().unwrap().is_empty());
+
+        let resp = client
+            .post(format!("{endpoint}/api/sessions"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+
+        tokio::time::sleep(std::time::Duration::from_millis(3100)).await;
+        let resp = client
+            .post(format!("{endpoint}/api/sessions"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["error"], "token expired: expired");
+
+        let refreshed: serde_json::Value = client
+            .post(format!("{endpoint}/auth/refresh"))
+            .json(&serde_json::json!({"refreshToken": refresh_token}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(refreshed["email"], "ttl-alice");
+        let new_token = refreshed["token"].as_str().unwrap().to_string();
+        assert_ne!(new_token, token);
+        assert_ne!(refreshed["refreshToken"].as_str().unwrap(), refresh_token);
+
+        let resp = client
+            .post(format!("{endpoint}/auth/refresh"))
+            .json(&serde_json::json!({"refreshToken": refresh_token}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let resp = client
+            .post(format!("{endpoint}/api/sessions"))
+            .bearer_auth(&new_token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let sessions: Vec<crate::DeviceSession> = resp.json().await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert!(sessions[0].current);
+
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        assert_eq!(auth_service.purge_expired().await.unwrap(), 0);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn token_idle_timeout_buffers_last_seen_and_gc() {
+        let mut config = test_config();
+        config.token_ttl_secs = 0;
+        config.token_idle_secs = 1;
+        config.refresh_token_ttl_secs = 0;
+        let (_app, state) = build_router(config).await.expect("build router");
+        let auth = state.auth_service.clone();
+
+        let idle = auth.issue_token("idle-alice").await.unwrap();
+        let active = auth.issue_token("idle-alice").await.unwrap();
+        assert_eq!(
+            auth.validate_status(&active).await.unwrap(),
+            crate::services::TokenStatus::Valid("idle-alice".to_string())
+        );
+        assert_eq!(auth.flush_last_seen().await.unwrap(), 1);
+        assert_eq!(auth.flush_last_seen().await.unwrap(), 0);
+
+        tokio::time::sleep(std::time::Duration::from_millis(1200)).await;
+        assert!(auth.validate(&active).await.unwrap().is_none());
+        assert_eq!(
+            auth.validate_status(&idle).await.unwrap(),
+            crate::services::TokenStatus::IdleTimeout
+        );
+        assert_eq!(auth.purge_expired().await.unwrap(), 2);
+        assert_eq!(
+            auth.validate_status(&idle).await.unwrap(),
+            crate::services::TokenStatus::Invalid
+        );
+    }
+
+    #[tokio::test]
+    async fn signed_tokens_rotate_keys_and_honor_revocations() {
+        let db_url = format!(
+            "sqlite:file:signed-tokens-{}?mode=memory&cache=shared",
+            Uuid::new_v4().simple()
+        );
+        let mut config = test_config();
+        config.database_url = db_url.clone();
+        config.signed_token_keys = vec!["k2:new-secret".to_string(), "k1:old-secret".to_string()];
+        let (app, state) = build_router(config.clone()).await.expect("build router");
+        let auth = state.auth_service.clone();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let login: serde_json::Value = client
+            .post(format!("{endpoint}/auth/register"))
+            .json(&serde_json::json!({"email": "jwt-alice", "password": "pass-1"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let token = login["token"].as_str().unwrap().to_string();
+        assert_eq!(token.split('.').count(), 3);
+        assert!(login["refreshToken"].as_str().unwrap_or_default().is_empty());
+
+        let resp = client
+            .post(format!("{endpoint}/api/sessions"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+
+        // tokens signed by the retired key still verify while it is listed
+        let old_signer =
+            crate::services::TokenSigner::new("HS256", &["k1:old-secret".to_string()]).unwrap();
+        let claims = crate::services::TokenClaims {
+            sub: "jwt-alice".to_string(),
+            iat: chrono::Utc::now().timestamp() - 10,
+            jti: "old-key-token".to_string(),
+            ..Default::default()
+        };
+        let old_token = old_signer.sign(&claims);
+        assert_eq!(
+            auth.validate_status(&old_token).await.unwrap(),
+            crate::services::TokenStatus::Valid("jwt-alice".to_string())
+        );
+        let unknown_signer =
+            crate::services::TokenSigner::new("HS256", &["k1:other-secret".to_string()]).unwrap();
+        assert_eq!(
+            auth.validate_status(&unknown_signer.sign(&claims)).await.unwrap(),
+            crate::services::TokenStatus::Invalid
+        );
+        let (head, _) = token.rsplit_once('.').unwrap();
+        let tampered = format!("{head}.{}", old_token.rsplit_once('.').unwrap().1);
+        assert_eq!(
+            auth.validate_status(&tampered).await.unwrap(),
+            crate::services::TokenStatus::Invalid
+        );
+
+        let resp = client
+            .get(format!("{endpoint}/auth/logout"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let resp = client
+            .post(format!("{endpoint}/api/sessions"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        // a second node only sees revocations once its cache refreshes
+        let mut config_b = config;
+        config_b.run_migrations = false;
+        let (_app_b, state_b) = build_router(config_b).await.expect("build router b");
+        let auth_b = state_b.auth_service.clone();
+        assert!(matches!(
+            auth_b.validate_status(&old_token).await.unwrap(),
+            crate::services::TokenStatus::Valid(_)
+        ));
+        auth.revoke_by_user("jwt-alice").await.unwrap();
+        assert_eq!(
+            auth.validate_status(&old_token).await.unwrap(),
+            crate::services::TokenStatus::Invalid
+        );
+        // a login in the same second as the revocation is not caught by it
+        let fresh = auth.issue_token("jwt-alice").await.unwrap();
+        assert_eq!(
+            auth.validate_status(&fresh).await.unwrap(),
+            crate::services::TokenStatus::Valid("jwt-alice".to_string())
+        );
+        assert!(auth_b.refresh_revocations().await.unwrap() >= 2);
+        assert_eq!(
+            auth_b.validate_status(&token).await.unwrap(),
+            crate::services::TokenStatus::Invalid
+        );
+        assert_eq!(
+            auth_b.validate_status(&old_token).await.unwrap(),
+            crate::services::TokenStatus::Invalid
+        );
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn signed_tokens_revoke_per_device_and_keep_current() {
+        let mut config = test_config();
+        config.signed_token_keys = vec!["k1:secret".to_string()];
+        let (_app, state) = build_router(config).await.expect("build router");
+        let auth = state.auth_service.clone();
+
+        let signer =
+            crate::services::TokenSigner::new("HS256", &["k1:secret".to_string()]).unwrap();
+        let mint = |jti: &str| {
+            signer.sign(&crate::services::TokenClaims {
+                sub: "jwt-bob".to_string(),
+                iat: chrono::Utc::now().timestamp() - 10,
+                jti: jti.to_string(),
+                ..Default::default()
+            })
+        };
+        let current = mint("current");
+        let phone = mint("phone");
+        let tablet = mint("tablet");
+        let offline = mint("offline");
+        auth.bind_device(&phone, "dev-phone").await.unwrap();
+        auth.bind_device(&tablet, "dev-tablet").await.unwrap();
+
+        let sessions = auth.list_sessions("jwt-bob").await.unwrap();
+        assert_eq!(sessions.len(), 2);
+        let phone_session = crate::services::session_id_of(&phone);
+        assert_eq!(
+            auth.revoke_session("jwt-bob", &phone_session)
+                .await
+                .unwrap(),
+            "dev-phone"
+        );
+        assert_eq!(
+            auth.validate_status(&phone).await.unwrap(),
+            crate::services::TokenStatus::Invalid
+        );
+        assert!(auth.validate(&tablet).await.unwrap().is_some());
+
+        let devices = auth
+            .revoke_other_sessions("jwt-bob", &current)
+            .await
+            .unwrap();
+        assert_eq!(devices, vec!["dev-tablet".to_string()]);
+        assert!(auth.validate(&current).await.unwrap().is_some());
+        assert!(auth.validate(&tablet).await.unwrap().is_none());
+        assert!(auth.validate(&offline).await.unwrap().is_none());
+
+        // a later full revoke drops the exemption again
+        auth.revoke_other_sessions("jwt-bob", "").await.unwrap();
+        assert!(auth.validate(&current).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn signed_tokens_eddsa_expiry() {
+        use base64::Engine;
+
+        let seed = base64::engine::general_purpose::STANDARD.encode([7u8; 32]);
+        let mut config = test_config();
+        config.token_ttl_secs = 2;
+        config.signed_token_alg = "EdDSA".to_string();
+        config.signed_token_keys = vec![format!("ed1:{seed}")];
+        let (_app, state) = build_router(config).await.expect("build router");
+        let auth = state.auth_service.clone();
+
+        let issued = auth
+            .issue_token_with_device("ed-alice", &Default::default(), "127.0.0.1")
+            .await
+            .unwrap();
+        assert!(issued.refresh_token.is_empty());
+        assert_eq!(
+            auth.validate_status(&issued.token).await.unwrap(),
+            crate::services::TokenStatus::Valid("ed-alice".to_string())
+        );
+        let hs_signer =
+            crate::services::TokenSigner::new("HS256", &["ed1:plain-secret".to_string()]).unwrap();
+        let forged = hs_signer.sign(&crate::services::TokenClaims {
+            sub: "ed-alice".to_string(),
+            jti: "forged".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(
+            auth.validate_status(&forged).await.unwrap(),
+            crate::services::TokenStatus::Invalid
+        );
+
+        tokio::time::sleep(std::time::Duration::from_millis(3100)).await;
+        assert_eq!(
+            auth.validate_status(&issued.token).await.unwrap(),
+            crate::services::TokenStatus::Expired
+        );
+    }
+
+    #[tokio::test]
+    async fn password_policy_and_legacy_hash_upgrade() {
+        let mut config = test_config();
+        config.password_min_length = 8;
+        let (app, state) = build_router(config).await.expect("build router");
+        let db = state.db.clone();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        for password in ["short", "pw-alice", " padded-pass "] {
+            let resp = client
+                .post(format!("{endpoint}/auth/register"))
+                .json(&serde_json::json!({"email": "pw-alice", "password": password}))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST);
+        }
+        let resp = client
+            .post(format!("{endpoint}/auth/register"))
+            .json(&serde_json::json!({"email": "pw-alice", "password": "long-pass-1"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+
+        let resp = client
+            .post(format!("{endpoint}/open/user/update/pw-alice"))
+            .bearer_auth("test-token")
+            .json(&serde_json::json!({"password": "abc"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let salt = std::env::var("PASSWORD_SALT").unwrap_or_default();
+        let stored = crate::entity::user::Entity::find_by_id("pw-alice".to_string())
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut active = sea_orm::IntoActiveModel::into_active_model(stored);
+        active.password = sea_orm::ActiveValue::Set(crate::api::auth::hash_password_with_salt(
+            "legacy-pass-1",
+            &salt,
+        ));
+        active.update(&db).await.unwrap();
+
+        let login = |password: &'static str| {
+            client
+                .post(format!("{endpoint}/auth/login"))
+                .json(&serde_json::json!({"email": "pw-alice", "password": password}))
+                .send()
+        };
+        assert_eq!(
+            login("long-pass-1").await.unwrap().status(),
+            reqwest::StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            login("legacy-pass-1").await.unwrap().status(),
+            reqwest::StatusCode::OK
+        );
+        let upgraded = crate::entity::user::Entity::find_by_id("pw-alice".to_string())
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(upgraded.password.starts_with("$argon2id$"));
+        assert!(!crate::api::auth::password_needs_rehash(&upgraded.password));
+        assert_eq!(
+            login("legacy-pass-1").await.unwrap().status(),
+            reqwest::StatusCode::OK
+        );
+
+        server.abort();
+    }
+
+    #[test]
+    fn client_ip_honors_forwarding_headers_only_from_trusted_proxies() {
+        use crate::api::access_log::resolve_client_ip;
+        use std::net::IpAddr;
+
+        let headers = |pairs: &[(&'static str, &str)]| {
+            let mut headers = axum::http::HeaderMap::new();
+            for (key, value) in pairs {
+                headers.append(*key, value.parse().unwrap());
+            }
+            headers
+        };
+        let ip = |value: &str| value.parse::<IpAddr>().ok();
+        let peer = ip("10.0.0.5");
+        let proxies = vec!["10.0.0.0/24".to_string()];
+
+        let spoofed = headers(&[("x-forwarded-for", "1.1.1.1")]);
+        assert_eq!(resolve_client_ip(&spoofed, None, &proxies), None);
+        assert_eq!(resolve_client_ip(&spoofed, peer, &[]), peer);
+
+        let chain = headers(&[("x-forwarded-for", "1.1.1.1, 2.2.2.2, 10.0.0.7")]);
+        assert_eq!(resolve_client_ip(&chain, peer, &proxies), ip("2.2.2.2"));
+        assert_eq!(
+            resolve_client_ip(&chain, ip("::ffff:10.0.0.5"), &proxies),
+            ip("2.2.2.2")
+        );
+        let split = headers(&[
+            ("x-forwarded-for", "1.1.1.1"),
+            ("x-forwarded-for", "3.3.3.3:4567"),
+        ]);
+        assert_eq!(resolve_client_ip(&split, peer, &proxies), ip("3.3.3.3"));
+        let real_ip = headers(&[("x-real-ip", "4.4.4.4")]);
+        assert_eq!(resolve_client_ip(&real_ip, peer, &proxies), ip("4.4.4.4"));
+        let garbage = headers(&[("x-forwarded-for", "unknown, 10.0.0.7")]);
+        assert_eq!(resolve_client_ip(&garbage, peer, &proxies), ip("10.0.0.7"));
+    }
+
+    #[tokio::test]
+    async fn login_lockout_is_shared_across_db_nodes() {
+        let db_url = format!(
+            "sqlite:file:login-lockout-{}?mode=memory&cache=shared",
+            Uuid::new_v4().simple()
+        );
+        let mut config = test_config();
+        config.database_url = db_url;
+        config.presence_backend = "db".to_string();
+        config.login_max_failures = 2;
+        config.login_ip_max_failures = 3;
+        config.trusted_proxies = vec!["127.0.0.1".to_string()];
+        let (app, state) = build_router(config.clone()).await.expect("build router");
+        let mut events = state.event_bus.subscribe();
+        let mut config_b = config;
+        config_b.run_migrations = false;
+        let (_app_b, state_b) = build_router(config_b).await.expect("build router b");
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let app = app
+                .with_state(state)
+                .into_make_service_with_connect_info::<std::net::SocketAddr>();
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let resp = client
+            .post(format!("{endpoint}/auth/register"))
+            .json(&serde_json::json!({"email": "lock-alice", "password": "pass-1"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+
+        let login = |email: &'static str, password: &'static str, ip: &'static str| {
+            client
+                .post(format!("{endpoint}/auth/login"))
+                .header("x-forwarded-for", ip)
+                .json(&serde_json::json!({"email": email, "password": password}))
+                .send()
+        };
+        for _ in 0..2 {
+            let resp = login("lock-alice", "bad-pass", "10.0.0.1").await.unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST);
+        }
+        let resp = login("lock-alice", "pass-1", "10.0.0.2").await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert!(resp.headers().contains_key("retry-after"));
+
+        let event = tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            loop {
+                if let Ok(crate::infra::event::BackendEvent::AuthLockout(v)) = events.recv().await
+                {
+                    return v;
+                }
+            }
+        })
+        .await
+        .expect("auth.lockout event");
+        assert_eq!(event.kind, "account");
+        assert_eq!(event.subject, "lock-alice");
+        assert_eq!(event.lockouts, 1);
+
+        let account_key = crate::infra::lockout::LoginGuard::account_key("lock-alice");
+        assert!(state_b.login_guard.retry_after(&[account_key]).await.unwrap() > 0);
+
+        // unknown accounts still count against the ip
+        let resp = login("lock-nobody", "bad-pass", "10.0.0.1").await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let resp = login("lock-nobody", "bad-pass", "10.0.0.1").await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+
+        let lockouts: Vec<crate::LoginLockout> = client
+            .post(format!("{endpoint}/open/auth/lockouts"))
+            .bearer_auth("test-token")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(lockouts
+            .iter()
+            .any(|v| v.kind == "account" && v.subject == "lock-alice"));
+        assert!(lockouts
+            .iter()
+            .any(|v| v.kind == "ip" && v.subject == "10.0.0.1"));
+
+        let cleared: bool = client
+            .post(format!("{endpoint}/open/auth/lockouts/clear"))
+            .bearer_auth("test-token")
+            .json(&serde_json::json!({"userId": "lock-alice", "ip": "10.0.0.1"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(cleared);
+        let resp = login("lock-alice", "pass-1", "10.0.0.1").await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn login_lockout_ignores_forwarded_for_from_untrusted_peers() {
+        let mut config = test_config();
+        config.login_ip_max_failures = 2;
+        let (app, state) = build_router(config).await.expect("build router");
+        let guard = state.login_guard.clone();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let app = app
+                .with_state(state)
+                .into_make_service_with_connect_info::<std::net::SocketAddr>();
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let login = |ip: &'static str| {
+            client
+                .post(format!("{endpoint}/auth/login"))
+                .header("x-forwarded-for", ip)
+                .json(&serde_json::json!({"email": "spoof-nobody", "password": "bad-pass"}))
+                .send()
+        };
+        // rotating the header does not get a fresh bucket, the peer address is the key
+        for ip in ["10.0.0.1", "10.0.0.2"] {
+            let resp = login(ip).await.unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+        }
+        let resp = login("10.0.0.3").await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        let peer_key = crate::infra::lockout::LoginGuard::ip_key("127.0.0.1");
+        assert!(guard.retry_after(&[peer_key]).await.unwrap() > 0);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn oidc_login_with_pkce_against_mock_idp() {
+        use base64::Engine;
+        use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+        use sha2::Digest;
+
+        let b64 = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let key = std::sync::Arc::new(
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+                .unwrap(),
+        );
+        let point = key.public_key().as_ref().to_vec();
+        let jwks = serde_json::json!({"keys": [{
+            "kty": "EC",
+            "crv": "P-256",
+            "kid": "mock-1",
+            "x": b64.encode(&point[1..33]),
+            "y": b64.encode(&point[33..]),
+        }]});
+
+        let idp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", idp_listener.local_addr().unwrap());
+        // (nonce, code_challenge) of the authorization request the test is completing
+        let authorize = std::sync::Arc::new(std::sync::Mutex::new((String::new(), String::new())));
+        let subject = std::sync::Arc::new(std::sync::Mutex::new("oidc-alice".to_string()));
+        let discovery = serde_json::json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{issuer}/authorize"),
+            "token_endpoint": format!("{issuer}/token"),
+            "jwks_uri": format!("{issuer}/jwks"),
+        });
+        let token_issuer = issuer.clone();
+        let token_authorize = authorize.clone();
+        let token_subject = subject.clone();
+        let idp = axum::Router::new()
+            .route(
+                "/.well-known/openid-configuration",
+                axum::routing::get(move || async move { axum::Json(discovery) }),
+            )
+            .route(
+                "/jwks",
+                axum::routing::get(move || async move { axum::Json(jwks) }),
+            )
+            .route(
+                "/token",
+                axum::routing::post(
+                    move |axum::Form(form): axum::Form<
+                        std::collections::HashMap<String, String>,
+                    >| async move {
+                        let (nonce, challenge) = token_authorize.lock().unwrap().clone();
+                        let verifier = form.get("code_verifier").cloned().unwrap_or_default();
+                        if form.get("code").map(String::as_str) != Some("mock-code")
+                            || b64.encode(sha2::Sha256::digest(verifier.as_bytes())) != challenge
+                        {
+                            return (
+                                StatusCode::BAD_REQUEST,
+                                axum::Json(serde_json::json!({"error": "invalid_grant"})),
+                            );
+                        }
+                        let sub = token_subject.lock().unwrap().clone();
+                        let header = b64.encode(r#"{"alg":"ES256","kid":"mock-1"}"#);
+                        let claims = b64.encode(
+                            serde_json::json!({
+                                "iss": token_issuer,
+                                "aud": "restsend",
+                                "sub": sub,
+                                "exp": chrono::Utc::now().timestamp() + 300,
+                                "nonce": nonce,
+                                "email": "alice@corp.example",
+                                "groups": ["eng"],
+                            })
+                            .to_string(),
+                        );
+                        let message = format!("{header}.{claims}");
+                        let sig = key
+                            .sign(&ring::rand::SystemRandom::new(), message.as_bytes())
+                            .unwrap();
+                        let id_token = format!("{message}.{}", b64.encode(sig.as_ref()));
+                        (
+                            StatusCode::OK,
+                            axum::Json(serde_json::json!({"id_token": id_token})),
+                        )
+                    },
+                ),
+            );
+        let idp_server = tokio::spawn(async move {
+            axum::serve(idp_listener, idp).await.unwrap();
+        });
+
+        let mut config = test_config();
+        config.token_ttl_secs = 3600;
+        config.oidc_providers = vec![crate::app::OidcProviderConfig {
+            name: "corp".to_string(),
+            issuer: issuer.clone(),
+            client_id: "restsend".to_string(),
+            client_secret: "s3cret".to_string(),
+            redirect_url: "http://app.test/auth/oidc/corp/callback".to_string(),
+            scopes: vec!["openid".to_string(), "email".to_string()],
+            user_claim: "sub".to_string(),
+            extra_claims: vec!["email".to_string(), "groups".to_string()],
+            create_users: true,
+        }];
+        let (app, state) = build_router(config).await.expect("build router");
+        let auth = state.auth_service.clone();
+        let db = state.db.clone();
+        let app = app.with_state(state);
+
+        let get = |uri: String, cookie: String| {
+            let mut req = Request::builder().uri(uri).method("GET");
+            if !cookie.is_empty() {
+                req = req.header("cookie", cookie);
+            }
+            app.clone().oneshot(req.body(Body::empty()).unwrap())
+        };
+        let resp = get(
+            "/auth/oidc/corp/start?redirect=https://evil.example".to_string(),
+            String::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        for redirect in ["//evil.example", "/%5Cevil.example", "/%09/evil.example"] {
+            let resp = get(
+                format!("/auth/oidc/corp/start?redirect={redirect}"),
+                String::new(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{redirect}");
+        }
+        let resp = get("/auth/oidc/other/start".to_string(), String::new())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = get(
+            "/auth/oidc/corp/start?redirect=/chat".to_string(),
+            String::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::FOUND);
+        let location = reqwest::Url::parse(resp.headers()["location"].to_str().unwrap()).unwrap();
+        assert!(location
+            .as_str()
+            .starts_with(&format!("{issuer}/authorize")));
+        let params: std::collections::HashMap<String, String> =
+            location.query_pairs().into_owned().collect();
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert_eq!(params["client_id"], "restsend");
+        let pending_cookie = resp.headers()["set-cookie"]
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string();
+        *authorize.lock().unwrap() = (params["nonce"].clone(), params["code_challenge"].clone());
+
+        let resp = get(
+            "/auth/oidc/corp/callback?code=mock-code&state=forged".to_string(),
+            pending_cookie.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // the pending cookie is client supplied, a rewritten redirect is refused
+        let mut forged = crate::infra::oidc::OidcPending::decode(
+            pending_cookie.strip_prefix("oidc_pending=").unwrap(),
+        )
+        .unwrap();
+        forged.redirect = "/\\evil.example".to_string();
+        let resp = get(
+            format!(
+                "/auth/oidc/corp/callback?code=mock-code&state={}",
+                params["state"]
+            ),
+            format!("oidc_pending={}", forged.encode()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = get(
+            format!(
+                "/auth/oidc/corp/callback?code=mock-code&state={}",
+                params["state"]
+            ),
+            pending_cookie,
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::FOUND);
+        assert_eq!(resp.headers()["location"], "/chat");
+        let token_cookie = resp
+            .headers()
+            .get_all("set-cookie")
+            .iter()
+            .find_map(|v| v.to_str().unwrap().strip_prefix("token="))
+            .unwrap()
+            .to_string();
+        let token = token_cookie.split(';').next().unwrap().to_string();
+        // the cookie follows the access token ttl
+        let max_age: i64 = token_cookie
+            .split("Max-Age=")
+            .nth(1)
+            .unwrap()
+            .parse()
             .unwrap();
-        let list_knocks_resp = app.clone().oneshot(list_knocks_req).await.unwrap();
-        if list_knocks_resp.status() != StatusCode::OK {
-            let body = list_knocks_resp
-                .into_body()
-                .collect()
+        assert!((3590..=3600).contains(&max_age), "{max_age}");
+        assert_eq!(
+            auth.validate_status(&token).await.unwrap(),
+            crate::services::TokenStatus::Valid("oidc:corp:oidc-alice".to_string())
+        );
+        let user: crate::User =
+            crate::entity::user::Entity::find_by_id("oidc:corp:oidc-alice".to_string())
+                .one(&db)
                 .await
                 .unwrap()
-                .to_bytes();
-            panic!(
-                "list_knock failed: {}",
-                String::from_utf8(body.to_vec()).unwrap()
-            );
-        }
-        let knocks_body = list_knocks_resp
-            .into_body()
-            .collect()
+                .unwrap()
+                .into();
+        let extra = user.extra.unwrap();
+        assert_eq!(extra["email"], "alice@corp.example");
+        assert_eq!(extra["groups"], r#"["eng"]"#);
+
+        // without a redirect the callback answers with the login response
+        let resp = get("/auth/oidc/corp/start".to_string(), String::new())
             .await
+            .unwrap();
+        let location = reqwest::Url::parse(resp.headers()["location"].to_str().unwrap()).unwrap();
+        let params: std::collections::HashMap<String, String> =
+            location.query_pairs().into_owned().collect();
+        let pending_cookie = resp.headers()["set-cookie"]
+            .to_str()
             .unwrap()
-            .to_bytes();
-        let knocks_text = String::from_utf8(knocks_body.to_vec()).unwrap();
-        assert!(knocks_text.contains("joiner1"));
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string();
+        *authorize.lock().unwrap() = (params["nonce"].clone(), params["code_challenge"].clone());
+        let resp = get(
+            format!(
+                "/auth/oidc/corp/callback?code=mock-code&state={}",
+                params["state"]
+            ),
+            pending_cookie,
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let login: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(login["email"], "oidc:corp:oidc-alice");
+        assert!(!login["token"].as_str().unwrap().is_empty());
+
+        let callback = |sub: &str| {
+            *subject.lock().unwrap() = sub.to_string();
+            let get = &get;
+            let authorize = authorize.clone();
+            async move {
+                let resp = get("/auth/oidc/corp/start".to_string(), String::new())
+                    .await
+                    .unwrap();
+                let location =
+                    reqwest::Url::parse(resp.headers()["location"].to_str().unwrap()).unwrap();
+                let params: std::collections::HashMap<String, String> =
+                    location.query_pairs().into_owned().collect();
+                let pending_cookie = resp.headers()["set-cookie"]
+                    .to_str()
+                    .unwrap()
+                    .split(';')
+                    .next()
+                    .unwrap()
+                    .to_string();
+                *authorize.lock().unwrap() =
+                    (params["nonce"].clone(), params["code_challenge"].clone());
+                get(
+                    format!(
+                        "/auth/oidc/corp/callback?code=mock-code&state={}",
+                        params["state"]
+                    ),
+                    pending_cookie,
+                )
+                .await
+                .unwrap()
+            }
+        };
 
-        let accept_req = Request::builder()
-            .uri(format!("/api/topic/admin/knock/accept/{topic_id}/joiner1"))
-            .method("POST")
-            .header("Authorization", format!("Bearer {owner_token}"))
-            .header("content-type", "application/json")
-            .body(Body::from("{}"))
+        // a subject equal to a local user id gets its own account, never the local one
+        let users = crate::services::UserService::new(db.clone());
+        users
+            .register("local-admin", crate::OpenApiUserForm::default())
+            .await
             .unwrap();
-        let accept_resp = app.clone().oneshot(accept_req).await.unwrap();
-        assert_eq!(accept_resp.status(), StatusCode::OK);
+        let resp = callback("local-admin").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let login: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(login["email"], "oidc:corp:local-admin");
+        assert_eq!(
+            auth.validate_status(login["token"].as_str().unwrap())
+                .await
+                .unwrap(),
+            crate::services::TokenStatus::Valid("oidc:corp:local-admin".to_string())
+        );
 
-        let notice_req = Request::builder()
-            .uri(format!("/api/topic/admin/notice/{topic_id}"))
-            .method("POST")
-            .header("Authorization", format!("Bearer {owner_token}"))
-            .header("content-type", "application/json")
-            .body(Body::from(r#"{"text":"welcome"}"#))
+        // an unlinked account squatting on the namespaced id is not adopted either
+        users
+            .register("oidc:corp:mallory", crate::OpenApiUserForm::default())
+            .await
             .unwrap();
-        let notice_resp = app.clone().oneshot(notice_req).await.unwrap();
-        assert_eq!(notice_resp.status(), StatusCode::OK);
+        let resp = callback("mallory").await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
 
-        let info_req = Request::builder()
-            .uri(format!("/api/topic/info/{topic_id}"))
-            .method("POST")
-            .header("Authorization", format!("Bearer {joiner_token}"))
-            .body(Body::empty())
-            .unwrap();
-        let info_resp = app.oneshot(info_req).await.unwrap();
-        assert_eq!(info_resp.status(), StatusCode::OK);
-        let info_body = info_resp.into_body().collect().await.unwrap().to_bytes();
-        let info_text = String::from_utf8(info_body.to_vec()).unwrap();
-        assert!(info_text.contains("welcome"));
+        idp_server.abort();
     }
 
     #[tokio::test]
@@ -2342,47 +5096,273 @@ mod tests {
             ))
             .await
             .unwrap();
-        let read_forbid_ack = bob_ws.next().await.unwrap().unwrap().into_text().unwrap();
-        let read_forbid_json: serde_json::Value = serde_json::from_str(&read_forbid_ack).unwrap();
-        assert_eq!(
-            read_forbid_json.get("code").and_then(|v| v.as_i64()),
-            Some(403)
-        );
+        let read_forbid_ack = bob_ws.next().await.unwrap().unwrap().into_text().unwrap();
+        let read_forbid_json: serde_json::Value = serde_json::from_str(&read_forbid_ack).unwrap();
+        assert_eq!(
+            read_forbid_json.get("code").and_then(|v| v.as_i64()),
+            Some(403)
+        );
+
+        let _ = alice_ws.close(None).await;
+        let _ = bob_ws.close(None).await;
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn topic_chat_webhook_event_is_delivered() {
+        use std::sync::{Arc, Mutex};
+
+        let hook_payloads: Arc<Mutex<Vec<serde_json::Value>>> = Arc::new(Mutex::new(Vec::new()));
+        let hook_payloads_cloned = hook_payloads.clone();
+
+        let hook_app = axum::Router::new().route(
+            "/hook",
+            axum::routing::post(move |body: String| {
+                let hook_payloads = hook_payloads_cloned.clone();
+                async move {
+                    if let Ok(value) = serde_json::from_str::<serde_json::Value>(&body) {
+                        hook_payloads.lock().unwrap().push(value);
+                    }
+                    axum::http::StatusCode::OK
+                }
+            }),
+        );
+
+        let hook_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hook_addr = hook_listener.local_addr().unwrap();
+        let hook_server = tokio::spawn(async move {
+            axum::serve(hook_listener, hook_app).await.unwrap();
+        });
+
+        let config = test_config();
+        let (app, state) = build_router(config).await.expect("build router");
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+
+        let webhook_url = format!("http://{}/hook", hook_addr);
+        let topic_resp: serde_json::Value = client
+            .post(format!("{endpoint}/open/topic/create/topic-webhook-1"))
+            .bearer_auth("test-token")
+            .json(&serde_json::json!({
+                "senderId": "hook-alice",
+                "members": ["hook-alice", "hook-bob"],
+                "multiple": true,
+                "webhooks": [webhook_url]
+            }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let topic_id = topic_resp.get("id").and_then(|v| v.as_str()).unwrap();
+
+        let send_resp = client
+            .post(format!("{endpoint}/open/topic/send/{topic_id}"))
+            .bearer_auth("test-token")
+            .json(&serde_json::json!({
+                "senderId": "hook-alice",
+                "type": "chat",
+                "chatId": "hook-chat-1",
+                "message": "hello webhook"
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(send_resp.status(), reqwest::StatusCode::OK);
+
+        for _ in 0..50 {
+            if hook_payloads
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|payload| payload.get("name").and_then(|n| n.as_str()) == Some("chat"))
+            {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        let payloads = hook_payloads.lock().unwrap().clone();
+        assert!(!payloads.is_empty(), "expected webhook payloads");
+        let event = payloads
+            .iter()
+            .find(|v| v.get("name").and_then(|n| n.as_str()) == Some("chat"))
+            .cloned()
+            .expect("chat webhook not found");
+        assert_eq!(
+            event.get("topicId").and_then(|v| v.as_str()),
+            Some(topic_id)
+        );
+        assert_eq!(
+            event
+                .get("data")
+                .and_then(|d| d.get("senderId"))
+                .and_then(|v| v.as_str()),
+            Some("hook-alice")
+        );
+
+        server.abort();
+        hook_server.abort();
+    }
+
+    #[tokio::test]
+    async fn webhook_deliveries_are_signed_per_target() {
+        use crate::infra::webhook::{
+            sign_payload, EVENT_ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+        };
+        use std::sync::{Arc, Mutex};
+
+        type Seen = Arc<Mutex<Vec<(String, axum::http::HeaderMap, String)>>>;
+        let seen: Seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_cloned = seen.clone();
+        let hook_app = axum::Router::new().route(
+            "/:name",
+            axum::routing::post(
+                move |axum::extract::Path(name): axum::extract::Path<String>,
+                      headers: axum::http::HeaderMap,
+                      body: String| {
+                    let seen = seen_cloned.clone();
+                    async move {
+                        seen.lock().unwrap().push((name, headers, body));
+                        axum::http::StatusCode::OK
+                    }
+                },
+            ),
+        );
+        let hook_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hook_addr = hook_listener.local_addr().unwrap();
+        let hook_server = tokio::spawn(async move {
+            axum::serve(hook_listener, hook_app).await.unwrap();
+        });
+
+        let mut config = test_config();
+        config.webhook_targets = vec![format!("http://{}/global", hook_addr)];
+        config.webhook_secret = Some("global-secret".to_string());
+        let (app, state) = build_router(config).await.expect("build router");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let topic_resp: serde_json::Value = client
+            .post(format!("{endpoint}/open/topic/create/topic-signed-hook"))
+            .bearer_auth("test-token")
+            .json(&serde_json::json!({
+                "senderId": "sig-alice",
+                "members": ["sig-alice"],
+                "webhooks": [format!("http://{}/topic", hook_addr)],
+                "webhookSecret": "topic-secret"
+            }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(topic_resp.get("webhookSecret").is_none());
+
+        let send_resp = client
+            .post(format!("{endpoint}/open/topic/send/topic-signed-hook"))
+            .bearer_auth("test-token")
+            .json(&serde_json::json!({
+                "senderId": "sig-alice",
+                "type": "chat",
+                "chatId": "sig-chat-1",
+                "message": "signed"
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(send_resp.status(), reqwest::StatusCode::OK);
+
+        let chat_deliveries = || {
+            seen.lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, _, body)| body.contains("\"name\":\"chat\""))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        for _ in 0..50 {
+            if chat_deliveries().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        let deliveries = chat_deliveries();
+        assert_eq!(deliveries.len(), 2, "expected global and topic deliveries");
+
+        let mut event_ids = std::collections::HashSet::new();
+        for (name, headers, body) in &deliveries {
+            let secret = if name == "topic" {
+                "topic-secret"
+            } else {
+                "global-secret"
+            };
+            let event_id = headers[EVENT_ID_HEADER].to_str().unwrap().to_string();
+            let ts: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+            assert_eq!(
+                headers[SIGNATURE_HEADER].to_str().unwrap(),
+                sign_payload(secret, ts, body.as_bytes()),
+                "bad signature for {name}"
+            );
+            let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+            assert_eq!(payload["id"].as_str(), Some(event_id.as_str()));
+            event_ids.insert(event_id);
+        }
+        // one event fans out to every target under the same id
+        assert_eq!(event_ids.len(), 1);
 
-        let _ = alice_ws.close(None).await;
-        let _ = bob_ws.close(None).await;
         server.abort();
+        hook_server.abort();
     }
 
     #[tokio::test]
-    async fn topic_chat_webhook_event_is_delivered() {
-        use std::sync::{Arc, Mutex};
-
-        let hook_payloads: Arc<Mutex<Vec<serde_json::Value>>> = Arc::new(Mutex::new(Vec::new()));
-        let hook_payloads_cloned = hook_payloads.clone();
+    async fn webhook_outbox_dead_letters_retry_and_replay() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::sync::Arc;
 
+        let healthy = Arc::new(AtomicBool::new(false));
+        let delivered = Arc::new(AtomicUsize::new(0));
+        let (healthy_cloned, delivered_cloned) = (healthy.clone(), delivered.clone());
         let hook_app = axum::Router::new().route(
             "/hook",
             axum::routing::post(move |body: String| {
-                let hook_payloads = hook_payloads_cloned.clone();
+                let healthy = healthy_cloned.clone();
+                let delivered = delivered_cloned.clone();
                 async move {
-                    if let Ok(value) = serde_json::from_str::<serde_json::Value>(&body) {
-                        hook_payloads.lock().unwrap().push(value);
+                    if !healthy.load(Ordering::SeqCst) {
+                        return axum::http::StatusCode::INTERNAL_SERVER_ERROR;
+                    }
+                    if body.contains("\"name\":\"chat\"") {
+                        delivered.fetch_add(1, Ordering::SeqCst);
                     }
                     axum::http::StatusCode::OK
                 }
             }),
         );
-
         let hook_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
         let hook_addr = hook_listener.local_addr().unwrap();
         let hook_server = tokio::spawn(async move {
             axum::serve(hook_listener, hook_app).await.unwrap();
         });
 
-        let config = test_config();
+        let mut config = test_config();
+        config.webhook_targets = vec![format!("http://{}/hook", hook_addr)];
+        config.webhook_max_attempts = 2;
+        config.webhook_backoff_secs = 0;
         let (app, state) = build_router(config).await.expect("build router");
-
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
         let addr = listener.local_addr().unwrap();
         let server = tokio::spawn(async move {
@@ -2391,71 +5371,422 @@ mod tests {
 
         let endpoint = format!("http://{}", addr);
         let client = reqwest::Client::new();
+        let open = |path: &str, body: serde_json::Value| {
+            client
+                .post(format!("{endpoint}/open/{path}"))
+                .bearer_auth("test-token")
+                .json(&body)
+                .send()
+        };
+        let resp = open(
+            "topic/create/topic-outbox",
+            serde_json::json!({"senderId": "ob-alice", "members": ["ob-alice"]}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let resp = open(
+            "topic/send/topic-outbox",
+            serde_json::json!({
+                "senderId": "ob-alice",
+                "type": "chat",
+                "chatId": "ob-chat-1",
+                "message": "durable"
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
 
-        let webhook_url = format!("http://{}/hook", hook_addr);
-        let topic_resp: serde_json::Value = client
-            .post(format!("{endpoint}/open/topic/create/topic-webhook-1"))
-            .bearer_auth("test-token")
-            .json(&serde_json::json!({
-                "senderId": "hook-alice",
-                "members": ["hook-alice", "hook-bob"],
-                "multiple": true,
-                "webhooks": [webhook_url]
-            }))
-            .send()
+        // both attempts fail, the delivery lands in the dead letters
+        let event_id = crate::services::chat_event_id("topic-outbox", "ob-chat-1");
+        let mut dead = Vec::new();
+        for _ in 0..50 {
+            dead = open("webhook/dead", serde_json::json!({}))
+                .await
+                .unwrap()
+                .json::<Vec<serde_json::Value>>()
+                .await
+                .unwrap();
+            dead.retain(|v| v["eventId"].as_str() == Some(event_id.as_str()));
+            if !dead.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(dead.len(), 1, "expected one chat dead letter");
+        assert_eq!(dead[0]["attempts"], 2);
+        assert!(!dead[0]["lastError"].as_str().unwrap().is_empty());
+
+        let events: Vec<serde_json::Value> =
+            open("webhook/events", serde_json::json!({"ids": [event_id]}))
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["data"]["chatId"], "ob-chat-1");
+
+        let resp = open("webhook/dead/retry", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let wait_delivered = |count: usize| {
+            let delivered = delivered.clone();
+            async move {
+                for _ in 0..50 {
+                    if delivered.load(Ordering::SeqCst) >= count {
+                        break;
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                }
+                delivered.load(Ordering::SeqCst)
+            }
+        };
+
+        healthy.store(true, Ordering::SeqCst);
+        let retried: serde_json::Value =
+            open("webhook/dead/retry", serde_json::json!({"ids": [dead[0]["id"]]}))
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+        assert_eq!(retried["count"], 1);
+        assert_eq!(wait_delivered(1).await, 1);
+        let dead: Vec<serde_json::Value> = open("webhook/dead", serde_json::json!({}))
             .await
             .unwrap()
             .json()
             .await
             .unwrap();
-        let topic_id = topic_resp.get("id").and_then(|v| v.as_str()).unwrap();
+        assert!(!dead
+            .iter()
+            .any(|v| v["eventId"].as_str() == Some(event_id.as_str())));
 
-        let send_resp = client
-            .post(format!("{endpoint}/open/topic/send/{topic_id}"))
-            .bearer_auth("test-token")
-            .json(&serde_json::json!({
-                "senderId": "hook-alice",
-                "type": "chat",
-                "chatId": "hook-chat-1",
-                "message": "hello webhook"
-            }))
-            .send()
+        let replayed: serde_json::Value =
+            open("webhook/replay", serde_json::json!({"ids": [event_id]}))
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+        assert_eq!(replayed["count"], 1);
+        assert_eq!(wait_delivered(2).await, 2);
+
+        let now = chrono::Utc::now();
+        let replayed: serde_json::Value = open(
+            "webhook/replay",
+            serde_json::json!({
+                "since": (now - chrono::Duration::minutes(5)).to_rfc3339(),
+                "until": (now + chrono::Duration::minutes(5)).to_rfc3339(),
+            }),
+        )
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+        assert!(replayed["count"].as_u64().unwrap() >= 1);
+        assert_eq!(wait_delivered(3).await, 3);
+
+        server.abort();
+        hook_server.abort();
+    }
+
+    #[tokio::test]
+    async fn webhook_subscriptions_filter_events_and_report_stats() {
+        use crate::infra::webhook::{sign_payload, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+        use std::sync::{Arc, Mutex};
+
+        type Seen = Arc<Mutex<Vec<(String, axum::http::HeaderMap, serde_json::Value)>>>;
+        let seen: Seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_cloned = seen.clone();
+        let hook_app = axum::Router::new().route(
+            "/:name",
+            axum::routing::post(
+                move |axum::extract::Path(name): axum::extract::Path<String>,
+                      headers: axum::http::HeaderMap,
+                      body: String| {
+                    let seen = seen_cloned.clone();
+                    async move {
+                        let ts: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+                        if name == "a" {
+                            assert_eq!(
+                                headers[SIGNATURE_HEADER].to_str().unwrap(),
+                                sign_payload("sub-secret", ts, body.as_bytes())
+                            );
+                        }
+                        let body = serde_json::from_str(&body).unwrap();
+                        seen.lock().unwrap().push((name, headers, body));
+                        axum::http::StatusCode::OK
+                    }
+                },
+            ),
+        );
+        let hook_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hook_addr = hook_listener.local_addr().unwrap();
+        let hook_server = tokio::spawn(async move {
+            axum::serve(hook_listener, hook_app).await.unwrap();
+        });
+
+        let (app, state) = build_router(test_config()).await.expect("build router");
+        let metrics = state.metrics.clone();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app.with_state(state)).await.unwrap();
+        });
+
+        let endpoint = format!("http://{}", addr);
+        let client = reqwest::Client::new();
+        let open = |path: String, body: serde_json::Value| {
+            client
+                .post(format!("{endpoint}/open/{path}"))
+                .bearer_auth("test-token")
+                .json(&body)
+                .send()
+        };
+        let hook = |name: &str| format!("http://{}/{}", hook_addr, name);
+
+        let mut ids = Vec::new();
+        for form in [
+            serde_json::json!({"url": hook("a"), "events": ["topic.*"], "secret": "sub-secret"}),
+            serde_json::json!({"url": hook("b"), "events": ["chat"], "topicIds": ["topic-sub-1"]}),
+            serde_json::json!({"url": hook("c"), "events": ["chat"], "kinds": ["no-such-kind"]}),
+            serde_json::json!({"url": hook("d"), "enabled": false}),
+        ] {
+            let resp = open("webhook/subscription/create".to_string(), form)
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::OK);
+            let sub: serde_json::Value = resp.json().await.unwrap();
+            assert!(sub.get("secret").is_none());
+            ids.push(sub["id"].as_str().unwrap().to_string());
+        }
+        let resp = open(
+            "webhook/subscription/create".to_string(),
+            serde_json::json!({"url": "ftp://nope", "events": ["chat"]}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        for topic_id in ["topic-sub-1", "topic-sub-2"] {
+            let resp = open(
+                format!("topic/create/{topic_id}"),
+                serde_json::json!({"senderId": "sub-alice", "members": ["sub-alice"]}),
+            )
             .await
             .unwrap();
-        assert_eq!(send_resp.status(), reqwest::StatusCode::OK);
+            assert_eq!(resp.status(), reqwest::StatusCode::OK);
+            let resp = open(
+                format!("topic/send/{topic_id}"),
+                serde_json::json!({
+                    "senderId": "sub-alice",
+                    "type": "chat",
+                    "chatId": format!("{topic_id}-chat"),
+                    "message": "filtered"
+                }),
+            )
+            .await
+            .unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        }
 
-        for _ in 0..50 {
-            if hook_payloads
-                .lock()
+        let received = |name: &str| {
+            seen.lock()
                 .unwrap()
                 .iter()
-                .any(|payload| payload.get("name").and_then(|n| n.as_str()) == Some("chat"))
-            {
+                .filter(|(n, _, _)| n == name)
+                .map(|(_, _, body)| body.clone())
+                .collect::<Vec<_>>()
+        };
+        for _ in 0..50 {
+            if received("a").len() >= 2 && !received("b").is_empty() {
                 break;
             }
             tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         }
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+
+        let a = received("a");
+        assert_eq!(a.len(), 2, "topic.* matches both topic.create events");
+        assert!(a.iter().all(|body| body["name"] == "topic.create"));
+        let b = received("b");
+        assert_eq!(b.len(), 1, "chat is filtered by topic id");
+        assert_eq!(b[0]["data"]["chatId"], "topic-sub-1-chat");
+        assert!(received("c").is_empty());
+        assert!(received("d").is_empty());
+
+        let stats = metrics.subscription_stats(&ids[0]);
+        assert_eq!((stats.deliveries, stats.failures), (2, 0));
+        assert_eq!(metrics.subscription_stats(&ids[1]).deliveries, 1);
+        assert_eq!(metrics.subscription_stats(&ids[3]).deliveries, 0);
+
+        let resp = open(
+            format!("webhook/subscription/update/{}", ids[3]),
+            serde_json::json!({"enabled": true, "events": ["chat"]}),
+        )
+        .await
+        .unwrap();
+        let updated: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(updated["enabled"], true);
+        assert_eq!(updated["url"], hook("d"));
 
-        let payloads = hook_payloads.lock().unwrap().clone();
-        assert!(!payloads.is_empty(), "expected webhook payloads");
-        let event = payloads
-            .iter()
-            .find(|v| v.get("name").and_then(|n| n.as_str()) == Some("chat"))
-            .cloned()
-            .expect("chat webhook not found");
-        assert_eq!(
-            event.get("topicId").and_then(|v| v.as_str()),
-            Some(topic_id)
+        let resp = open(
+            format!("webhook/subscription/delete/{}", ids[2]),
+            serde_json::json!({}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let list: Vec<serde_json::Value> = open(
+            "webhook/subscription/list".to_string(),
+            serde_json::json!({}),
+        )
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+        assert_eq!(list.len(), 3);
+
+        server.abort();
+        hook_server.abort();
+    }
+
+    #[tokio::test]
+    async fn presend_hook_rejects_rewrites_and_fails_closed() {
+        use crate::{PreSendHookRequest, PreSendHookResponse};
+        use axum::Json;
+
+        let hook_app = axum::Router::new()
+            .route(
+                "/review",
+                axum::routing::post(|Json(req): Json<PreSendHookRequest>| async move {
+                    let resp = if req.content.text.contains("phone") {
+                        PreSendHookResponse {
+                            action: "reject".to_string(),
+                            code: 451,
+                            reason: "no phone numbers".to_string(),
+                            ..Default::default()
+                        }
+                    } else if req.content.text.contains("http://") {
+                        PreSendHookResponse {
+                            action: "rewrite".to_string(),
+                            content: Some(crate::Content {
+                                content_type: req.content.content_type.clone(),
+                                text: "[link removed]".to_string(),
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        }
+                    } else {
+                        PreSendHookResponse::default()
+                    };
+                    Json(resp)
+                }),
+            )
+            .route(
+                "/slow",
+                axum::routing::post(|| async {
+                    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                    Json(PreSendHookResponse::default())
+                }),
+            );
+        let hook_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hook_addr = hook_listener.local_addr().unwrap();
+        let hook_server = tokio::spawn(async move {
+            axum::serve(hook_listener, hook_app).await.unwrap();
+        });
+
+        let mut config = test_config();
+        config.presend_hook_url = Some(format!("http://{hook_addr}/review"));
+        let (app, state) = build_router(config).await.expect("build router");
+        let app = app.with_state(state);
+
+        let send = |app: axum::Router, chat_id: &str, message: &str| {
+            let body = serde_json::json!({
+                "senderId": "hook-alice",
+                "type": "chat",
+                "chatId": chat_id,
+                "message": message,
+                "ensure": true,
+                "members": ["hook-alice"]
+            });
+            let req = Request::builder()
+                .uri("/open/topic/send/topic-hook")
+                .method("POST")
+                .header("Authorization", "Bearer test-token")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            async move {
+                let resp = app.oneshot(req).await.unwrap();
+                let status = resp.status();
+                let body = resp.into_body().collect().await.unwrap().to_bytes();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+                )
+            }
+        };
+
+        let (status, body) = send(app.clone(), "hook-1", "hello").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(
+            body.get("content").is_none(),
+            "unchanged content is not echoed"
         );
+
+        let (status, body) = send(app.clone(), "hook-2", "my phone is 555").await;
+        assert_eq!(status.as_u16(), 451);
+        assert_eq!(body["error"], "no phone numbers");
+
+        let (status, body) = send(app.clone(), "hook-3", "see http://spam").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["content"]["text"], "[link removed]");
+
+        let logs_req = Request::builder()
+            .uri("/open/topic/logs/topic-hook")
+            .method("POST")
+            .header("Authorization", "Bearer test-token")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"limit":10}"#))
+            .unwrap();
+        let logs_resp = app.clone().oneshot(logs_req).await.unwrap();
+        let body = logs_resp.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("hello"));
+        assert!(text.contains("[link removed]"));
+        assert!(!text.contains("phone"));
+        assert!(!text.contains("http://spam"));
+
+        let mut config = test_config();
+        config.presend_hook_url = Some(format!("http://{hook_addr}/slow"));
+        config.presend_hook_timeout_ms = 100;
+        let (app, state) = build_router(config).await.expect("build router");
+        let app = app.with_state(state);
+        let (status, body) = send(app.clone(), "hook-4", "hello").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"], "message review unavailable");
+
+        let mut config = test_config();
+        config.presend_hook_url = Some(format!("http://{hook_addr}/slow"));
+        config.presend_hook_timeout_ms = 100;
+        config.presend_hook_fail_open = true;
+        let (app, state) = build_router(config).await.expect("build router");
+        let app = app.with_state(state);
+        let (status, _) = send(app.clone(), "hook-5", "hello").await;
         assert_eq!(
-            event
-                .get("data")
-                .and_then(|d| d.get("senderId"))
-                .and_then(|v| v.as_str()),
-            Some("hook-alice")
+            status,
+            StatusCode::OK,
+            "fail open delivers when the hook times out"
         );
 
-        server.abort();
         hook_server.abort();
     }
 
@@ -2673,10 +6004,11 @@ mod tests {
             .await
             .unwrap()
             .unwrap();
-        assert_eq!(
-            updated_user.password,
-            crate::api::auth::hash_password("xxxxx")
-        );
+        assert!(updated_user.password.starts_with("$argon2id$"));
+        assert!(crate::api::auth::verify_password(
+            &updated_user.password,
+            "xxxxx"
+        ));
 
         let auth_hello1 = register_and_auth(&app, "hello1").await;
         let _auth_alice = register_and_auth(&app, "alice").await;
@@ -5715,6 +9047,15 @@ mod tests {
             Some(0)
         );
 
+        let weak_req = Request::builder()
+            .uri("/admin/api/bootstrap")
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"userId":"root-admin","password":"root-admin"}"#))
+            .unwrap();
+        let weak_resp = app.clone().oneshot(weak_req).await.unwrap();
+        assert_eq!(weak_resp.status(), StatusCode::BAD_REQUEST);
+
         let init_req = Request::builder()
             .uri("/admin/api/bootstrap")
             .method("POST")
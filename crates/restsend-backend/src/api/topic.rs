@@ -1,15 +1,18 @@
 use axum::extract::{Path, State};
 use axum::Json;
+use chrono::Utc;
+use serde_json::json;
 use std::time::Instant;
 
 use crate::api::auth_ctx::AuthCtx;
 use crate::api::error::{ApiError, ApiResult};
 use crate::app::AppState;
 use crate::infra::event::{
-    BackendEvent, TopicChangeOwnerEvent, TopicKnockEvent, TopicNoticeEvent, TopicSilentEvent,
-    TopicSimpleEvent, TopicUserEvent,
+    BackendEvent, TopicBanEvent, TopicChangeOwnerEvent, TopicKnockEvent, TopicNoticeEvent,
+    TopicSilentEvent, TopicSimpleEvent, TopicUserEvent,
 };
 use crate::services::DomainError;
+use crate::TopicPermission;
 
 pub async fn topic_info(
     State(state): State<AppState>,
@@ -113,11 +116,13 @@ pub async fn topic_members(
     State(state): State<AppState>,
     auth: AuthCtx,
     Path(topic_id): Path<String>,
+    payload: Option<Json<crate::TopicMemberListForm>>,
 ) -> ApiResult<Json<crate::ListUserResult>> {
     let st = Instant::now();
+    let form = payload.map(|v| v.0).unwrap_or_default();
     let result = state
         .topic_service
-        .list_members_detailed(&topic_id, None, None)
+        .list_members_detailed(&topic_id, auth.user_id(), form)
         .await
         .map_err(map_domain_error)?;
     tracing::info!(
@@ -141,7 +146,8 @@ pub async fn topic_invite(
         .get_by_id(&topic_id)
         .await
         .map_err(map_domain_error)?;
-    auth.ensure_topic_admin(&topic)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Invite)
+        .await?;
 
     let users = state
         .topic_service
@@ -239,22 +245,34 @@ pub async fn topic_knock(
     let st = Instant::now();
     let message = form.message.clone();
     let source = form.source.clone();
-    state
+    let result = state
         .topic_service
         .add_knock(&topic_id, auth.user_id(), form)
         .await
         .map_err(map_domain_error)?;
-    state
-        .event_bus
-        .publish(BackendEvent::TopicKnock(TopicKnockEvent {
-            topic_id,
-            admin_id: String::new(),
-            user_id: auth.user_id().to_string(),
-            message,
-            source,
-        }));
+    match result.status.as_str() {
+        "joined" => state
+            .event_bus
+            .publish(BackendEvent::TopicJoin(TopicUserEvent {
+                topic_id,
+                admin_id: String::new(),
+                user_id: auth.user_id().to_string(),
+                source,
+            })),
+        "pending" => state
+            .event_bus
+            .publish(BackendEvent::TopicKnock(TopicKnockEvent {
+                topic_id,
+                admin_id: String::new(),
+                user_id: auth.user_id().to_string(),
+                message,
+                source,
+            })),
+        _ => {}
+    }
     tracing::info!(
         user_id = %auth.user_id(),
+        status = %result.status,
         elapsed_ms = st.elapsed().as_millis() as u64,
         "topic knock sent"
     );
@@ -272,7 +290,8 @@ pub async fn topic_admin_add_member(
         .get_by_id(&topic_id)
         .await
         .map_err(map_domain_error)?;
-    auth.ensure_topic_admin(&topic)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Invite)
+        .await?;
     let users = state
         .topic_service
         .join_members(&topic_id, vec![user_id.clone()], "api".to_string())
@@ -318,7 +337,8 @@ pub async fn topic_admin_update(
         .get_by_id(&topic_id)
         .await
         .map_err(map_domain_error)?;
-    auth.ensure_topic_admin(&topic)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::EditNotice)
+        .await?;
     let topic = state
         .topic_service
         .update_topic(&topic_id, form)
@@ -352,7 +372,7 @@ pub async fn topic_admin_transfer_owner(
         .get_by_id(&topic_id)
         .await
         .map_err(map_domain_error)?;
-    auth.ensure_topic_admin(&topic)?;
+    auth.ensure_topic_owner(&topic)?;
     state
         .topic_service
         .transfer_owner(&topic_id, &user_id)
@@ -439,7 +459,8 @@ pub async fn topic_admin_list_knock(
         .get_by_id(&topic_id)
         .await
         .map_err(map_domain_error)?;
-    auth.ensure_topic_admin(&topic)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Invite)
+        .await?;
     let knocks = state
         .topic_service
         .list_pending_knocks(&topic_id)
@@ -467,8 +488,13 @@ pub async fn topic_admin_accept_knock(
         .get_by_id(&topic_id)
         .await
         .map_err(map_domain_error)?;
-    auth.ensure_topic_admin(&topic)?;
-    state
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Invite)
+        .await?;
+    let message = payload
+        .as_ref()
+        .map(|v| v.0.message.clone())
+        .unwrap_or_default();
+    let accepted = state
         .topic_service
         .accept_knock(
             &topic_id,
@@ -478,6 +504,17 @@ pub async fn topic_admin_accept_knock(
         )
         .await
         .map_err(map_domain_error)?;
+    if accepted {
+        notify_knock_result(
+            &state,
+            &topic,
+            auth.user_id(),
+            &user_id,
+            "topic.knock.accept",
+            &message,
+        )
+        .await;
+    }
     state
         .event_bus
         .publish(BackendEvent::TopicKnockAccept(TopicKnockEvent {
@@ -507,12 +544,13 @@ pub async fn topic_admin_reject_knock(
         .get_by_id(&topic_id)
         .await
         .map_err(map_domain_error)?;
-    auth.ensure_topic_admin(&topic)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Invite)
+        .await?;
     let message = payload
         .as_ref()
         .map(|v| v.0.message.clone())
         .unwrap_or_default();
-    state
+    let rejected = state
         .topic_service
         .reject_knock(
             &topic_id,
@@ -522,6 +560,17 @@ pub async fn topic_admin_reject_knock(
         )
         .await
         .map_err(map_domain_error)?;
+    if rejected {
+        notify_knock_result(
+            &state,
+            &topic,
+            auth.user_id(),
+            &user_id,
+            "topic.knock.reject",
+            &message,
+        )
+        .await;
+    }
     state
         .event_bus
         .publish(BackendEvent::TopicKnockReject(TopicKnockEvent {
@@ -539,6 +588,414 @@ pub async fn topic_admin_reject_knock(
     Ok(Json(true))
 }
 
+pub async fn topic_admin_create_invite(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+    payload: Option<Json<crate::TopicInviteForm>>,
+) -> ApiResult<Json<crate::TopicInvite>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Invite)
+        .await?;
+    let mut form = payload.map(|v| v.0).unwrap_or_default();
+    form.created_by = auth.user_id().to_string();
+    let invite = state
+        .topic_service
+        .create_invite(&topic_id, form)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        max_uses = invite.max_uses,
+        auto_approve = invite.auto_approve,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic invite link created"
+    );
+    Ok(Json(invite))
+}
+
+pub async fn topic_admin_list_invite(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+) -> ApiResult<Json<Vec<crate::TopicInvite>>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Invite)
+        .await?;
+    let invites = state
+        .topic_service
+        .list_invites(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        count = invites.len(),
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic invite links listed"
+    );
+    Ok(Json(invites))
+}
+
+pub async fn topic_admin_revoke_invite(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((topic_id, token)): Path<(String, String)>,
+) -> ApiResult<Json<bool>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Invite)
+        .await?;
+    state
+        .topic_service
+        .revoke_invite(&topic_id, &token)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic invite link revoked"
+    );
+    Ok(Json(true))
+}
+
+pub async fn topic_admin_get_knock_rule(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+) -> ApiResult<Json<crate::TopicKnockRule>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Invite)
+        .await?;
+    let rule = state
+        .topic_service
+        .get_knock_rule(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic knock rule fetched"
+    );
+    Ok(Json(rule))
+}
+
+pub async fn topic_admin_update_knock_rule(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+    payload: Option<Json<crate::TopicKnockRuleForm>>,
+) -> ApiResult<Json<crate::TopicKnockRule>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Invite)
+        .await?;
+    let rule = state
+        .topic_service
+        .update_knock_rule(&topic_id, payload.map(|v| v.0).unwrap_or_default())
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic knock rule updated"
+    );
+    Ok(Json(rule))
+}
+
+pub async fn topic_invite_preview(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(token): Path<String>,
+) -> ApiResult<Json<crate::TopicInvitePreview>> {
+    let st = Instant::now();
+    let preview = state
+        .topic_service
+        .preview_invite(&token)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %preview.topic_id,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic invite link previewed"
+    );
+    Ok(Json(preview))
+}
+
+pub async fn topic_invite_join(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(token): Path<String>,
+    payload: Option<Json<crate::TopicKnockForm>>,
+) -> ApiResult<Json<crate::TopicInviteJoinResult>> {
+    let st = Instant::now();
+    let form = payload.map(|v| v.0).unwrap_or_default();
+    let message = form.message.clone();
+    let result = state
+        .topic_service
+        .join_by_invite(&token, auth.user_id(), form)
+        .await
+        .map_err(map_domain_error)?;
+    match result.status.as_str() {
+        "pending" => state
+            .event_bus
+            .publish(BackendEvent::TopicKnock(TopicKnockEvent {
+                topic_id: result.topic_id.clone(),
+                admin_id: String::new(),
+                user_id: auth.user_id().to_string(),
+                message,
+                source: "invite".to_string(),
+            })),
+        "joined" => state
+            .event_bus
+            .publish(BackendEvent::TopicJoin(TopicUserEvent {
+                topic_id: result.topic_id.clone(),
+                admin_id: String::new(),
+                user_id: auth.user_id().to_string(),
+                source: "invite".to_string(),
+            })),
+        _ => {}
+    }
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %result.topic_id,
+        status = %result.status,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic invite link used"
+    );
+    Ok(Json(result))
+}
+
+pub async fn topic_directory(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    payload: Option<Json<crate::TopicDirectoryForm>>,
+) -> ApiResult<Json<crate::TopicDirectoryResult>> {
+    let st = Instant::now();
+    let form = payload.map(|v| v.0).unwrap_or_default();
+    let keyword = form.keyword.clone();
+    let result = state
+        .topic_service
+        .search_directory(auth.user_id(), form)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        keyword = %keyword,
+        total = result.total,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic directory searched"
+    );
+    Ok(Json(result))
+}
+
+pub async fn topic_directory_preview(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+) -> ApiResult<Json<crate::TopicDirectoryItem>> {
+    let st = Instant::now();
+    let item = state
+        .topic_service
+        .preview_directory(&topic_id, auth.user_id())
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic directory previewed"
+    );
+    Ok(Json(item))
+}
+
+pub async fn topic_directory_join(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+    payload: Option<Json<crate::TopicKnockForm>>,
+) -> ApiResult<Json<crate::TopicInviteJoinResult>> {
+    let st = Instant::now();
+    let form = payload.map(|v| v.0).unwrap_or_default();
+    let message = form.message.clone();
+    let result = state
+        .topic_service
+        .join_from_directory(&topic_id, auth.user_id(), form)
+        .await
+        .map_err(map_domain_error)?;
+    match result.status.as_str() {
+        "pending" => state
+            .event_bus
+            .publish(BackendEvent::TopicKnock(TopicKnockEvent {
+                topic_id: result.topic_id.clone(),
+                admin_id: String::new(),
+                user_id: auth.user_id().to_string(),
+                message,
+                source: "directory".to_string(),
+            })),
+        "joined" => state
+            .event_bus
+            .publish(BackendEvent::TopicJoin(TopicUserEvent {
+                topic_id: result.topic_id.clone(),
+                admin_id: String::new(),
+                user_id: auth.user_id().to_string(),
+                source: "directory".to_string(),
+            })),
+        _ => {}
+    }
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %result.topic_id,
+        status = %result.status,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic joined from directory"
+    );
+    Ok(Json(result))
+}
+
+pub async fn topic_admin_list_role(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+) -> ApiResult<Json<Vec<crate::TopicRole>>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::ManageRoles)
+        .await?;
+    let roles = state
+        .topic_service
+        .list_roles(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        count = roles.len(),
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic roles listed"
+    );
+    Ok(Json(roles))
+}
+
+pub async fn topic_admin_update_role(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((topic_id, role)): Path<(String, String)>,
+    Json(form): Json<crate::TopicRoleForm>,
+) -> ApiResult<Json<crate::TopicRole>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::ManageRoles)
+        .await?;
+    let role = state
+        .topic_service
+        .update_role(&topic_id, &role, form)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        role = %role.name,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic role updated"
+    );
+    Ok(Json(role))
+}
+
+pub async fn topic_admin_remove_role(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((topic_id, role)): Path<(String, String)>,
+) -> ApiResult<Json<bool>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::ManageRoles)
+        .await?;
+    state
+        .topic_service
+        .remove_role(&topic_id, &role)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        role = %role,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic role removed"
+    );
+    Ok(Json(true))
+}
+
+pub async fn topic_admin_assign_role(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((topic_id, user_id)): Path<(String, String)>,
+    Json(form): Json<crate::TopicRoleAssignForm>,
+) -> ApiResult<Json<crate::TopicMember>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::ManageRoles)
+        .await?;
+    let member = state
+        .topic_service
+        .assign_role(&topic_id, &user_id, &form.role)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        target_user_id = %user_id,
+        role = %member.role,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic member role assigned"
+    );
+    Ok(Json(member))
+}
+
 pub async fn topic_admin_notice(
     State(state): State<AppState>,
     auth: AuthCtx,
@@ -551,12 +1008,13 @@ pub async fn topic_admin_notice(
         .get_by_id(&topic_id)
         .await
         .map_err(map_domain_error)?;
-    auth.ensure_topic_admin(&topic)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::EditNotice)
+        .await?;
     state
         .topic_service
         .update_notice(&topic_id, auth.user_id(), form)
         .await
-        .map_err(|e| ApiError::internal(e.to_string()))?;
+        .map_err(map_domain_error)?;
     state
         .event_bus
         .publish(BackendEvent::TopicNotice(TopicNoticeEvent {
@@ -583,7 +1041,9 @@ pub async fn topic_admin_kickout(
         .get_by_id(&topic_id)
         .await
         .map_err(|e| ApiError::internal(e.to_string()))?;
-    auth.ensure_topic_admin(&topic)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Kick)
+        .await?;
+    auth.ensure_outranks(&topic, &user_id)?;
     let _ = state
         .topic_service
         .quit_members(&topic_id, vec![user_id.clone()])
@@ -605,6 +1065,124 @@ pub async fn topic_admin_kickout(
     Ok(Json(true))
 }
 
+pub async fn topic_admin_ban(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((topic_id, user_id)): Path<(String, String)>,
+    payload: Option<Json<crate::TopicBanForm>>,
+) -> ApiResult<Json<crate::TopicBan>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Kick)
+        .await?;
+    auth.ensure_outranks(&topic, &user_id)?;
+    let mut form = payload.map(|v| v.0).unwrap_or_default();
+    form.admin_id = auth.user_id().to_string();
+    let (ban, removed) = state
+        .topic_service
+        .ban_member(&topic_id, &user_id, form)
+        .await
+        .map_err(map_domain_error)?;
+    if removed {
+        state
+            .event_bus
+            .publish(BackendEvent::TopicKickout(TopicUserEvent {
+                topic_id: topic_id.clone(),
+                admin_id: auth.user_id().to_string(),
+                user_id: user_id.clone(),
+                source: "api".to_string(),
+            }));
+    }
+    state
+        .event_bus
+        .publish(BackendEvent::TopicBan(TopicBanEvent {
+            topic_id,
+            admin_id: auth.user_id().to_string(),
+            user_id,
+            reason: ban.reason.clone(),
+            expires_at: ban.expires_at.clone(),
+            source: "api".to_string(),
+        }));
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %ban.topic_id,
+        target_user_id = %ban.user_id,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic member banned"
+    );
+    Ok(Json(ban))
+}
+
+pub async fn topic_admin_unban(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path((topic_id, user_id)): Path<(String, String)>,
+) -> ApiResult<Json<bool>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Kick)
+        .await?;
+    state
+        .topic_service
+        .unban_member(&topic_id, &user_id)
+        .await
+        .map_err(map_domain_error)?;
+    state
+        .event_bus
+        .publish(BackendEvent::TopicUnban(TopicBanEvent {
+            topic_id: topic_id.clone(),
+            admin_id: auth.user_id().to_string(),
+            user_id: user_id.clone(),
+            reason: String::new(),
+            expires_at: String::new(),
+            source: "api".to_string(),
+        }));
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        target_user_id = %user_id,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic member unbanned"
+    );
+    Ok(Json(true))
+}
+
+pub async fn topic_admin_list_ban(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(topic_id): Path<String>,
+) -> ApiResult<Json<Vec<crate::TopicBan>>> {
+    let st = Instant::now();
+    let topic = state
+        .topic_service
+        .get_by_id(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Kick)
+        .await?;
+    let bans = state
+        .topic_service
+        .list_bans(&topic_id)
+        .await
+        .map_err(map_domain_error)?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        topic_id = %topic_id,
+        count = bans.len(),
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "topic bans listed"
+    );
+    Ok(Json(bans))
+}
+
 pub async fn topic_admin_silent_user(
     State(state): State<AppState>,
     auth: AuthCtx,
@@ -617,7 +1195,8 @@ pub async fn topic_admin_silent_user(
         .get_by_id(&topic_id)
         .await
         .map_err(map_domain_error)?;
-    auth.ensure_topic_admin(&topic)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Silence)
+        .await?;
     let duration = payload
         .get("duration")
         .and_then(|v| v.as_str())
@@ -664,7 +1243,8 @@ pub async fn topic_admin_silent_topic(
         .get_by_id(&topic_id)
         .await
         .map_err(map_domain_error)?;
-    auth.ensure_topic_admin(&topic)?;
+    auth.ensure_topic_permission(&state, &topic, TopicPermission::Silence)
+        .await?;
     let duration = payload
         .get("duration")
         .and_then(|v| v.as_str())
@@ -698,6 +1278,69 @@ pub async fn topic_admin_silent_topic(
     Ok(Json(true))
 }
 
+// the applicant is usually not a member yet, so the result lands in their
+// conversation list instead of the topic history
+pub(crate) async fn notify_knock_result(
+    state: &AppState,
+    topic: &crate::Topic,
+    admin_id: &str,
+    user_id: &str,
+    content_type: &str,
+    text: &str,
+) {
+    let now = Utc::now().to_rfc3339();
+    let mut conversation = state
+        .conversation_service
+        .get_conversation(user_id, &topic.id)
+        .await
+        .unwrap_or_else(|_| crate::Conversation {
+            owner_id: user_id.to_string(),
+            topic_id: topic.id.clone(),
+            ..Default::default()
+        });
+    conversation.multiple = topic.multiple;
+    conversation.name = topic.name.clone();
+    conversation.icon = topic.icon.clone();
+    conversation.kind = topic.kind.clone();
+    conversation.members = topic.members as i64;
+    conversation.unread += 1;
+    conversation.last_sender_id = admin_id.to_string();
+    conversation.last_message_at = now.clone();
+    conversation.last_message = Some(crate::Content {
+        content_type: content_type.to_string(),
+        text: text.to_string(),
+        unreadable: true,
+        ..Default::default()
+    });
+    if let Err(e) = state
+        .conversation_service
+        .create_or_update(conversation)
+        .await
+    {
+        tracing::warn!(
+            user_id = %user_id,
+            topic_id = %topic.id,
+            error = ?e,
+            "knock result conversation update failed"
+        );
+    }
+
+    let payload = serde_json::to_string(&json!({
+        "type": "chat",
+        "topicId": topic.id,
+        "chatId": format!("knock-{}", uuid::Uuid::new_v4().simple()),
+        "attendee": admin_id,
+        "createdAt": now,
+        "content": {
+            "type": content_type,
+            "text": text,
+            "unreadable": true,
+        }
+    }))
+    .unwrap_or_default();
+    crate::api::push::broadcast_to_user(state, user_id, &payload).await;
+}
+
 fn map_domain_error(err: DomainError) -> ApiError {
     match err {
         DomainError::NotFound => ApiError::NotFound,
@@ -705,5 +1348,6 @@ fn map_domain_error(err: DomainError) -> ApiError {
         DomainError::Conflict => ApiError::bad_request("resource already exists"),
         DomainError::Forbidden => ApiError::Unauthorized,
         DomainError::Storage(msg) => ApiError::internal(msg),
+        DomainError::Rejected { code, reason } => ApiError::Rejected(code, reason),
     }
 }
@@ -6,6 +6,7 @@ use std::time::Instant;
 use crate::api::auth_ctx::AuthCtx;
 use crate::api::error::{ApiError, ApiResult};
 use crate::app::AppState;
+use crate::services::DomainError;
 
 pub async fn devices(State(state): State<AppState>, auth: AuthCtx) -> ApiResult<Json<Vec<String>>> {
     let st = Instant::now();
@@ -44,6 +45,111 @@ pub async fn kick(
     Ok(Json(true))
 }
 
+pub async fn sessions(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+) -> ApiResult<Json<Vec<crate::DeviceSession>>> {
+    let st = Instant::now();
+    let items = list_device_sessions(&state, auth.user_id(), &auth.token)
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        session_count = items.len(),
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "user sessions fetched"
+    );
+    Ok(Json(items))
+}
+
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Path(session_id): Path<String>,
+) -> ApiResult<Json<bool>> {
+    let st = Instant::now();
+    let device = state
+        .auth_service
+        .revoke_session(auth.user_id(), &session_id)
+        .await
+        .map_err(|e| match e {
+            DomainError::NotFound => ApiError::NotFound,
+            _ => ApiError::internal(e.to_string()),
+        })?;
+    if !device.is_empty() {
+        kick_device(&state, auth.user_id(), &device).await;
+    }
+    tracing::info!(
+        user_id = %auth.user_id(),
+        session_id = %session_id,
+        device = %device,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "user session revoked"
+    );
+    Ok(Json(true))
+}
+
+pub async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+) -> ApiResult<Json<bool>> {
+    let st = Instant::now();
+    let devices = state
+        .auth_service
+        .revoke_other_sessions(auth.user_id(), &auth.token)
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+    for device in &devices {
+        kick_device(&state, auth.user_id(), device).await;
+    }
+    tracing::info!(
+        user_id = %auth.user_id(),
+        kicked = devices.len(),
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "user other sessions revoked"
+    );
+    Ok(Json(true))
+}
+
+pub(crate) async fn list_device_sessions(
+    state: &AppState,
+    user_id: &str,
+    current_token: &str,
+) -> Result<Vec<crate::DeviceSession>, DomainError> {
+    let mut items = state.auth_service.list_sessions(user_id).await?;
+    let online_devices = state.presence_hub.snapshot(user_id).await.devices;
+    let current = if current_token.is_empty() {
+        String::new()
+    } else {
+        crate::services::session_id_of(current_token)
+    };
+    for item in items.iter_mut() {
+        item.online = !item.device.is_empty() && online_devices.contains(&item.device);
+        item.current = item.session_id == current;
+    }
+    Ok(items)
+}
+
+// the local session is dropped right away so the socket closes after the kickout frame
+pub(crate) async fn kick_device(state: &AppState, user_id: &str, device: &str) {
+    let payload = serde_json::json!({
+        "type": "kickout",
+        "message": "Your session has been revoked",
+    })
+    .to_string();
+    state
+        .ws_hub
+        .send_to_device(
+            user_id,
+            device,
+            &payload,
+            state.config.ws_drop_on_backpressure,
+        )
+        .await;
+    state.ws_hub.unregister(user_id, device).await;
+    crate::api::push::send_to_device(state, user_id, device, &payload).await;
+}
+
 pub async fn profiles(
     State(state): State<AppState>,
     auth: AuthCtx,
@@ -62,7 +168,10 @@ pub async fn profiles(
         .unwrap_or_default();
     let mut out = Vec::new();
     for user_id in user_ids {
-        if let Ok(user) = state.user_service.get_by_user_id(&user_id).await {
+        if let Ok(mut user) = state.user_service.get_by_user_id(&user_id).await {
+            user.presence = Some(
+                crate::api::presence::resolve_presence(&state, auth.user_id(), &user_id).await,
+            );
             out.push(user);
         }
     }
@@ -100,6 +209,8 @@ pub async fn single_profile(
             user.is_blocked = relation.is_blocked;
         }
     }
+    user.presence =
+        Some(crate::api::presence::resolve_presence(&state, auth.user_id(), &userid).await);
     tracing::info!(
         user_id = %auth.user_id(),
         target_user_id = %userid,
@@ -155,6 +266,54 @@ pub async fn update_relation(
     Ok(Json(true))
 }
 
+pub async fn contacts(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    payload: Option<Json<crate::ContactSyncForm>>,
+) -> ApiResult<Json<crate::ListUserResult>> {
+    let st = Instant::now();
+    let result = state
+        .relation_service
+        .sync_contacts(auth.user_id(), payload.map(|v| v.0).unwrap_or_default())
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        count = result.items.len(),
+        removed = result.removed.len(),
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "user contacts synced"
+    );
+    Ok(Json(result))
+}
+
+pub async fn search(
+    State(state): State<AppState>,
+    auth: AuthCtx,
+    Json(form): Json<crate::UserDirectoryForm>,
+) -> ApiResult<Json<crate::UserDirectoryResult>> {
+    let st = Instant::now();
+    if !state.search_limiter.allow(auth.user_id()) {
+        return Err(ApiError::TooManyRequests);
+    }
+    let result = state
+        .user_service
+        .search_directory(auth.user_id(), form)
+        .await
+        .map_err(|e| match e {
+            DomainError::Validation(msg) => ApiError::bad_request(msg),
+            e => ApiError::internal(e.to_string()),
+        })?;
+    tracing::info!(
+        user_id = %auth.user_id(),
+        count = result.items.len(),
+        total = result.total,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        "user directory searched"
+    );
+    Ok(Json(result))
+}
+
 pub async fn list_blocked(
     State(state): State<AppState>,
     auth: AuthCtx,
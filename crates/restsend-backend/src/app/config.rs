@@ -13,6 +13,27 @@ pub struct AppConfig {
     pub webhook_timeout_secs: u64,
     pub webhook_retries: usize,
     pub webhook_targets: Vec<String>,
+    // default hmac secret for webhook deliveries, unsigned when unset
+    pub webhook_secret: Option<String>,
+    // (target url, secret) pairs that override every other secret for that url
+    pub webhook_target_secrets: Vec<(String, String)>,
+    // deliver through the db outbox instead of straight from the in-memory pool
+    pub webhook_outbox: bool,
+    // outbox attempts per target before the delivery moves to the dead letters
+    pub webhook_max_attempts: u32,
+    pub webhook_backoff_secs: u64,
+    pub webhook_backoff_max_secs: u64,
+    // consecutive failures that open a target's circuit, 0 disables it
+    pub webhook_circuit_threshold: u32,
+    pub webhook_circuit_cooldown_secs: u64,
+    // delivered events are kept this long for replay
+    pub webhook_event_retention_secs: u64,
+    // synchronous callback that can veto or rewrite messages before they are stored
+    pub presend_hook_url: Option<String>,
+    pub presend_hook_timeout_ms: u64,
+    // deliver the message when the hook is unreachable instead of rejecting it with 503,
+    // off unless PRESEND_HOOK_FAIL_OPEN is set; every bypass is logged
+    pub presend_hook_fail_open: bool,
     pub event_bus_size: usize,
     pub message_worker_count: usize,
     pub message_queue_size: usize,
@@ -25,10 +46,51 @@ pub struct AppConfig {
     pub presence_node_id: String,
     pub presence_ttl_secs: u64,
     pub presence_heartbeat_secs: u64,
+    pub presence_debounce_ms: u64,
     pub ws_per_user_limit: usize,
     pub ws_client_queue_size: usize,
     pub ws_typing_interval_ms: u64,
     pub ws_drop_on_backpressure: bool,
+    pub ws_ticket_ttl_secs: u64,
+    // reject websocket connects that authenticate with a bearer token instead of a ticket
+    pub ws_require_ticket: bool,
+    pub knock_expire_secs: u64,
+    pub dm_contacts_only: bool,
+    pub user_search_rate_limit: u32,
+    pub token_ttl_secs: u64,
+    pub token_idle_secs: u64,
+    pub refresh_token_ttl_secs: u64,
+    pub token_gc_interval_secs: u64,
+    pub token_touch_flush_secs: u64,
+    pub signed_token_alg: String,
+    pub signed_token_keys: Vec<String>,
+    pub token_revocation_refresh_secs: u64,
+    pub password_min_length: usize,
+    pub login_max_failures: u32,
+    pub login_ip_max_failures: u32,
+    pub login_lockout_secs: u64,
+    pub login_lockout_max_secs: u64,
+    pub login_failure_window_secs: u64,
+    // addresses or cidrs whose x-forwarded-for / x-real-ip are honored, empty trusts none
+    pub trusted_proxies: Vec<String>,
+    pub oidc_providers: Vec<OidcProviderConfig>,
+    // without OPENAPI_TOKEN or a key, /open and /helpdesk only serve anonymous callers when set
+    pub openapi_allow_anonymous: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OidcProviderConfig {
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_url: String,
+    pub scopes: Vec<String>,
+    // id_token claim identifying the account, new accounts get the id `oidc:<provider>:<claim>`
+    pub user_claim: String,
+    // id_token claims copied into User.extra
+    pub extra_claims: Vec<String>,
+    pub create_users: bool,
 }
 
 impl AppConfig {
@@ -83,6 +145,55 @@ impl AppConfig {
                     .collect::<Vec<_>>()
             })
             .unwrap_or_default();
+        let webhook_secret = std::env::var("WEBHOOK_SECRET")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        // `https://a/hook|secret-a,https://b/hook|secret-b`
+        let webhook_target_secrets = std::env::var("WEBHOOK_TARGET_SECRETS")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .filter_map(|item| item.split_once('|'))
+                    .map(|(url, secret)| (url.trim().to_string(), secret.trim().to_string()))
+                    .filter(|(url, secret)| !url.is_empty() && !secret.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let webhook_outbox = env_bool("WEBHOOK_OUTBOX", true);
+        let webhook_max_attempts = std::env::var("WEBHOOK_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(10);
+        let webhook_backoff_secs = std::env::var("WEBHOOK_BACKOFF_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(2);
+        let webhook_backoff_max_secs = std::env::var("WEBHOOK_BACKOFF_MAX_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(3600);
+        let webhook_circuit_threshold = std::env::var("WEBHOOK_CIRCUIT_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(5);
+        let webhook_circuit_cooldown_secs = std::env::var("WEBHOOK_CIRCUIT_COOLDOWN_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(60);
+        let webhook_event_retention_secs = std::env::var("WEBHOOK_EVENT_RETENTION_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(604800);
+        let presend_hook_url = std::env::var("PRESEND_HOOK_URL")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let presend_hook_timeout_ms = std::env::var("PRESEND_HOOK_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(1000);
+        let presend_hook_fail_open = env_bool("PRESEND_HOOK_FAIL_OPEN", false);
         let event_bus_size = std::env::var("EVENT_BUS_SIZE")
             .ok()
             .and_then(|v| v.parse::<usize>().ok())
@@ -135,6 +246,10 @@ impl AppConfig {
             .unwrap_or(30)
             .max(1)
             .min(presence_ttl_secs);
+        let presence_debounce_ms = std::env::var("PRESENCE_DEBOUNCE_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(1000);
         let ws_per_user_limit = std::env::var("WS_PER_USER_LIMIT")
             .ok()
             .and_then(|v| v.parse::<usize>().ok())
@@ -148,6 +263,109 @@ impl AppConfig {
             .and_then(|v| v.parse::<u64>().ok())
             .unwrap_or(1000);
         let ws_drop_on_backpressure = env_bool("WS_DROP_ON_BACKPRESSURE", true);
+        let ws_ticket_ttl_secs = std::env::var("WS_TICKET_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(30);
+        let ws_require_ticket = env_bool("WS_REQUIRE_TICKET", false);
+        let knock_expire_secs = std::env::var("KNOCK_EXPIRE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(7 * 24 * 3600);
+        let dm_contacts_only = env_bool("DM_CONTACTS_ONLY", false);
+        let user_search_rate_limit = std::env::var("USER_SEARCH_RATE_LIMIT")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(30);
+        // 0 keeps access tokens valid until revoked
+        let token_ttl_secs = std::env::var("TOKEN_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(30 * 24 * 3600);
+        let token_idle_secs = std::env::var("TOKEN_IDLE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0);
+        let refresh_token_ttl_secs = std::env::var("REFRESH_TOKEN_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(90 * 24 * 3600);
+        let token_gc_interval_secs = std::env::var("TOKEN_GC_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(300)
+            .max(1);
+        let token_touch_flush_secs = std::env::var("TOKEN_TOUCH_FLUSH_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(30)
+            .max(1);
+        let signed_token_alg = std::env::var("SIGNED_TOKEN_ALG")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "HS256".to_string());
+        // kid:secret pairs, the first one signs; empty keeps opaque db tokens
+        let signed_token_keys = std::env::var("SIGNED_TOKEN_KEYS")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let token_revocation_refresh_secs = std::env::var("TOKEN_REVOCATION_REFRESH_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(30)
+            .max(1);
+        let password_min_length = std::env::var("PASSWORD_MIN_LENGTH")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(8);
+        // 0 disables the per-account / per-ip lockout
+        let login_max_failures = std::env::var("LOGIN_MAX_FAILURES")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(5);
+        let login_ip_max_failures = std::env::var("LOGIN_IP_MAX_FAILURES")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(20);
+        let login_lockout_secs = std::env::var("LOGIN_LOCKOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(60)
+            .max(1);
+        let login_lockout_max_secs = std::env::var("LOGIN_LOCKOUT_MAX_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(3600);
+        let login_failure_window_secs = std::env::var("LOGIN_FAILURE_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(900);
+        let trusted_proxies = std::env::var("TRUSTED_PROXIES")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let oidc_providers = std::env::var("OIDC_PROVIDERS")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .map(|name| parse_oidc_provider(&name, &openapi_schema, &endpoint))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let openapi_allow_anonymous = env_bool("OPENAPI_ALLOW_ANONYMOUS", false);
 
         Ok(Self {
             addr,
@@ -163,6 +381,18 @@ impl AppConfig {
             webhook_timeout_secs,
             webhook_retries,
             webhook_targets,
+            webhook_secret,
+            webhook_target_secrets,
+            webhook_outbox,
+            webhook_max_attempts,
+            webhook_backoff_secs,
+            webhook_backoff_max_secs,
+            webhook_circuit_threshold,
+            webhook_circuit_cooldown_secs,
+            webhook_event_retention_secs,
+            presend_hook_url,
+            presend_hook_timeout_ms,
+            presend_hook_fail_open,
             event_bus_size,
             message_worker_count,
             message_queue_size,
@@ -175,10 +405,33 @@ impl AppConfig {
             presence_node_id,
             presence_ttl_secs,
             presence_heartbeat_secs,
+            presence_debounce_ms,
             ws_per_user_limit,
             ws_client_queue_size,
             ws_typing_interval_ms,
             ws_drop_on_backpressure,
+            ws_ticket_ttl_secs,
+            ws_require_ticket,
+            knock_expire_secs,
+            dm_contacts_only,
+            user_search_rate_limit,
+            token_ttl_secs,
+            token_idle_secs,
+            refresh_token_ttl_secs,
+            token_gc_interval_secs,
+            token_touch_flush_secs,
+            signed_token_alg,
+            signed_token_keys,
+            token_revocation_refresh_secs,
+            password_min_length,
+            login_max_failures,
+            login_ip_max_failures,
+            login_lockout_secs,
+            login_lockout_max_secs,
+            login_failure_window_secs,
+            trusted_proxies,
+            oidc_providers,
+            openapi_allow_anonymous,
         })
     }
 }
@@ -224,6 +477,48 @@ fn build_endpoint(addr: String) -> String {
     format!("{host}:{port}")
 }
 
+// OIDC_<NAME>_ISSUER, _CLIENT_ID, _CLIENT_SECRET, _REDIRECT_URL, _SCOPES,
+// _USER_CLAIM, _EXTRA_CLAIMS and _CREATE_USERS configure provider <name>
+fn parse_oidc_provider(name: &str, schema: &str, endpoint: &str) -> OidcProviderConfig {
+    let prefix = format!("OIDC_{}_", name.to_ascii_uppercase().replace('-', "_"));
+    let var = |key: &str| {
+        std::env::var(format!("{prefix}{key}"))
+            .ok()
+            .map(|v| v.trim().to_string())
+            .unwrap_or_default()
+    };
+    let list = |key: &str| {
+        var(key)
+            .split([',', ' '])
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect::<Vec<_>>()
+    };
+    let mut scopes = list("SCOPES");
+    if scopes.is_empty() {
+        scopes = vec!["openid".to_string(), "profile".to_string(), "email".to_string()];
+    }
+    let mut redirect_url = var("REDIRECT_URL");
+    if redirect_url.is_empty() {
+        redirect_url = format!("{schema}://{endpoint}/auth/oidc/{name}/callback");
+    }
+    let mut user_claim = var("USER_CLAIM");
+    if user_claim.is_empty() {
+        user_claim = "sub".to_string();
+    }
+    OidcProviderConfig {
+        name: name.to_string(),
+        issuer: var("ISSUER"),
+        client_id: var("CLIENT_ID"),
+        client_secret: var("CLIENT_SECRET"),
+        redirect_url,
+        scopes,
+        user_claim,
+        extra_claims: list("EXTRA_CLAIMS"),
+        create_users: env_bool(&format!("{prefix}CREATE_USERS"), true),
+    }
+}
+
 fn env_bool(name: &str, default: bool) -> bool {
     match std::env::var(name) {
         Ok(v) => matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"),
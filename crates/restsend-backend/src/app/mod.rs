@@ -3,6 +3,7 @@ pub mod state;
 
 use axum::routing::{delete, get, post, put};
 use axum::Router;
+use sea_orm::EntityTrait;
 use std::collections::HashSet;
 use tower_http::cors::CorsLayer;
 use tower_http::services::ServeDir;
@@ -14,6 +15,8 @@ use tracing_subscriber::EnvFilter;
 
 use crate::api;
 use crate::api::admin::hinit_static_path;
+use crate::api::middleware_auth::scoped;
+use crate::entity::helpdesk_inbox;
 
 fn find_static_dir(subdir: &str) -> Option<std::path::PathBuf> {
     for dir in [
@@ -39,19 +42,31 @@ fn find_static_dir(subdir: &str) -> Option<std::path::PathBuf> {
     }
 }
 use crate::infra::db::{connect_db, run_migrations};
-use crate::infra::event::{BackendEvent, EventBus};
+use crate::infra::event::{BackendEvent, EventBus, TopicKnockEvent};
+use crate::infra::lockout::{
+    DbLockoutStore, LockoutPolicy, LockoutStore, LoginGuard, MemoryLockoutStore,
+};
 use crate::infra::metrics::RuntimeMetrics;
+use crate::infra::oidc::OidcClient;
 use crate::infra::presence::{DbPresenceStore, MemoryPresenceStore, PresenceHub, PresenceStore};
+use crate::infra::presend_hook::HttpPreSendHook;
+use crate::infra::rate_limit::RateLimiter;
 use crate::infra::task_pool::TaskPool;
-use crate::infra::webhook::WebhookSender;
+use crate::infra::webhook::{CircuitBreaker, WebhookSender};
 use crate::infra::websocket::WsHub;
+use crate::infra::ws_ticket::{
+    DbWsTicketStore, MemoryWsTicketStore, WsTicketIssuer, WsTicketStore,
+};
 use crate::model::{Content, Conversation};
 use crate::openapi::OpenApiChatMessageForm;
 use crate::services::{
-    AuthService, ChatService, ConversationService, RelationService, TopicService, UserService,
+    chat_event_id, delivery_body, explicit_targets, ApiKeyService, ApiScope, AuthService,
+    ChatService, ConversationService, DomainResult, NewWebhookEvent, RelationService, TokenPolicy,
+    TokenSigner, TopicService, UserService, WebhookOutboxService, WebhookSubscriptionService,
+    WebhookTarget,
 };
 
-pub use config::AppConfig;
+pub use config::{AppConfig, OidcProviderConfig};
 pub use state::AppState;
 
 pub async fn build_router(
@@ -76,6 +91,29 @@ pub async fn build_router(
         _ => std::sync::Arc::new(MemoryPresenceStore::new(config.presence_ttl_secs)),
     };
     let presence_hub = std::sync::Arc::new(PresenceHub::new(presence_store));
+    // lockouts follow the presence backend so db mode shares them across nodes
+    let lockout_store: std::sync::Arc<dyn LockoutStore> = match config.presence_backend.as_str() {
+        "db" => std::sync::Arc::new(DbLockoutStore::new(db.clone())),
+        _ => std::sync::Arc::new(MemoryLockoutStore::default()),
+    };
+    let ws_ticket_store: std::sync::Arc<dyn WsTicketStore> = match config.presence_backend.as_str() {
+        "db" => std::sync::Arc::new(DbWsTicketStore::new(db.clone())),
+        _ => std::sync::Arc::new(MemoryWsTicketStore::default()),
+    };
+    let ws_tickets = std::sync::Arc::new(WsTicketIssuer::new(
+        ws_ticket_store,
+        config.ws_ticket_ttl_secs,
+    ));
+    let login_guard = std::sync::Arc::new(LoginGuard::new(
+        lockout_store,
+        LockoutPolicy {
+            account_max_failures: config.login_max_failures,
+            ip_max_failures: config.login_ip_max_failures,
+            lockout_secs: config.login_lockout_secs,
+            max_lockout_secs: config.login_lockout_max_secs,
+            window_secs: config.login_failure_window_secs,
+        },
+    ));
     let message_pool = std::sync::Arc::new(TaskPool::new(
         config.message_worker_count,
         config.message_queue_size,
@@ -90,16 +128,45 @@ pub async fn build_router(
     ));
     let event_bus = std::sync::Arc::new(EventBus::new(config.event_bus_size));
     let metrics = std::sync::Arc::new(RuntimeMetrics::default());
-    let webhook_sender = std::sync::Arc::new(WebhookSender::new(
-        config.webhook_timeout_secs,
-        config.webhook_retries,
-    ));
+    let webhook_sender = std::sync::Arc::new(
+        WebhookSender::new(config.webhook_timeout_secs, config.webhook_retries).with_secrets(
+            config.webhook_secret.clone(),
+            config.webhook_target_secrets.clone(),
+        ),
+    );
     let user_service = std::sync::Arc::new(UserService::new(db.clone()));
-    let auth_service = std::sync::Arc::new(AuthService::new(db.clone()));
+    let mut auth_service = AuthService::new(db.clone()).with_policy(TokenPolicy {
+        ttl_secs: config.token_ttl_secs,
+        idle_secs: config.token_idle_secs,
+        refresh_ttl_secs: config.refresh_token_ttl_secs,
+    });
+    if !config.signed_token_keys.is_empty() {
+        let signer = TokenSigner::new(&config.signed_token_alg, &config.signed_token_keys)
+            .map_err(sea_orm::DbErr::Custom)?;
+        auth_service = auth_service.with_signer(signer);
+    }
+    let auth_service = std::sync::Arc::new(auth_service);
     let relation_service = std::sync::Arc::new(RelationService::new(db.clone()));
     let topic_service = std::sync::Arc::new(TopicService::new(db.clone()));
     let conversation_service = std::sync::Arc::new(ConversationService::new(db.clone()));
-    let chat_service = std::sync::Arc::new(ChatService::new(db.clone()));
+    let mut chat_service = ChatService::new(db.clone())
+        .with_dm_contacts_only(config.dm_contacts_only)
+        .with_webhook_outbox(config.webhook_outbox);
+    if let Some(url) = config.presend_hook_url.clone() {
+        chat_service = chat_service.with_presend_hook(std::sync::Arc::new(HttpPreSendHook::new(
+            url,
+            config.presend_hook_timeout_ms,
+            config.presend_hook_fail_open,
+            config.webhook_secret.clone(),
+        )));
+    }
+    let chat_service = std::sync::Arc::new(chat_service);
+    let webhook_outbox = std::sync::Arc::new(WebhookOutboxService::new(db.clone()));
+    let webhook_subscriptions = std::sync::Arc::new(WebhookSubscriptionService::new(db.clone()));
+    let api_key_service = std::sync::Arc::new(ApiKeyService::new(db.clone()));
+    if config.openapi_allow_anonymous && config.openapi_token.is_none() {
+        tracing::warn!("openapi accepts anonymous requests, set OPENAPI_TOKEN or create api keys");
+    }
 
     let state = AppState {
         config: config.clone(),
@@ -120,6 +187,20 @@ pub async fn build_router(
         topic_service,
         conversation_service,
         chat_service,
+        search_limiter: std::sync::Arc::new(RateLimiter::new(
+            config.user_search_rate_limit,
+            std::time::Duration::from_secs(60),
+        )),
+        login_guard,
+        oidc: std::sync::Arc::new(OidcClient::new(&config.oidc_providers)),
+        api_key_service,
+        ws_tickets,
+        webhook_outbox,
+        webhook_circuit: std::sync::Arc::new(CircuitBreaker::new(
+            config.webhook_circuit_threshold,
+            config.webhook_circuit_cooldown_secs,
+        )),
+        webhook_subscriptions,
     };
 
     if AppConfig::is_demo() {
@@ -129,133 +210,407 @@ pub async fn build_router(
     }
 
     start_webhook_worker(state.clone());
-    state
-        .presence_hub
-        .start_cleanup_loop(config.presence_heartbeat_secs);
+    if state.config.webhook_outbox {
+        start_webhook_dispatcher(state.clone());
+    }
+    start_knock_cleanup_loop(state.clone());
+    start_presence_cleanup_loop(state.clone());
+    start_token_maintenance_loop(state.clone());
+    if state.auth_service.signs_tokens() {
+        start_revocation_refresh_loop(state.clone());
+    }
 
     let openapi = Router::new()
-        .route("/user/online/:userid", post(api::openapi::user_online))
-        .route("/user/push/:userid", post(api::openapi::user_push))
+        .route(
+            "/user/online/:userid",
+            scoped(ApiScope::UsersRead, post(api::openapi::user_online)),
+        )
+        .route(
+            "/user/push/:userid",
+            scoped(ApiScope::ChatSend, post(api::openapi::user_push)),
+        )
         .route(
             "/user/push/:userid/:cid",
-            post(api::openapi::user_push_with_cid),
+            scoped(ApiScope::ChatSend, post(api::openapi::user_push_with_cid)),
+        )
+        .route(
+            "/user/register/:userid",
+            scoped(ApiScope::UsersWrite, post(api::openapi::user_register)),
+        )
+        .route(
+            "/user/list",
+            scoped(ApiScope::UsersRead, post(api::openapi::user_list)),
+        )
+        .route(
+            "/user/search",
+            scoped(ApiScope::UsersRead, post(api::openapi::user_search)),
+        )
+        .route(
+            "/user/auth/:userid",
+            scoped(ApiScope::UsersWrite, post(api::openapi::user_auth)),
+        )
+        .route(
+            "/user/sessions/:userid",
+            scoped(ApiScope::UsersRead, post(api::openapi::user_sessions)),
+        )
+        .route(
+            "/user/sessions/revoke/:userid/:sessionid",
+            scoped(
+                ApiScope::UsersWrite,
+                post(api::openapi::user_revoke_session),
+            ),
+        )
+        .route(
+            "/user/sessions/revoke_all/:userid",
+            scoped(
+                ApiScope::UsersWrite,
+                post(api::openapi::user_revoke_all_sessions),
+            ),
+        )
+        .route(
+            "/user/update/:userid",
+            scoped(ApiScope::UsersWrite, post(api::openapi::user_update)),
+        )
+        .route(
+            "/auth/lockouts",
+            scoped(ApiScope::Admin, post(api::openapi::auth_lockouts)),
+        )
+        .route(
+            "/auth/lockouts/clear",
+            scoped(ApiScope::Admin, post(api::openapi::auth_lockouts_clear)),
         )
-        .route("/user/register/:userid", post(api::openapi::user_register))
-        .route("/user/list", post(api::openapi::user_list))
-        .route("/user/auth/:userid", post(api::openapi::user_auth))
-        .route("/user/update/:userid", post(api::openapi::user_update))
         .route(
             "/user/enabled/:userid",
-            post(api::openapi::user_set_enabled),
+            scoped(ApiScope::UsersWrite, post(api::openapi::user_set_enabled)),
+        )
+        .route(
+            "/user/staff/:userid",
+            scoped(ApiScope::Admin, post(api::openapi::user_set_staff)),
         )
-        .route("/user/staff/:userid", post(api::openapi::user_set_staff))
         .route(
             "/user/relation/:userid/:targetid",
-            post(api::openapi::user_relation_update),
+            scoped(
+                ApiScope::UsersWrite,
+                post(api::openapi::user_relation_update),
+            ),
+        )
+        .route(
+            "/user/delete/:userid",
+            scoped(ApiScope::UsersWrite, post(api::openapi::user_deactive)),
         )
-        .route("/user/delete/:userid", post(api::openapi::user_deactive))
         .route(
             "/user/blacklist/get/:userid",
-            post(api::openapi::user_blacklist_get),
+            scoped(ApiScope::UsersRead, post(api::openapi::user_blacklist_get)),
         )
         .route(
             "/user/blacklist/add/:userid",
-            post(api::openapi::user_blacklist_add),
+            scoped(ApiScope::UsersWrite, post(api::openapi::user_blacklist_add)),
         )
         .route(
             "/user/blacklist/remove/:userid",
-            post(api::openapi::user_blacklist_remove),
+            scoped(
+                ApiScope::UsersWrite,
+                post(api::openapi::user_blacklist_remove),
+            ),
+        )
+        .route(
+            "/topic/create",
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_create_auto)),
+        )
+        .route(
+            "/topic/create/:topicid",
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_create)),
+        )
+        .route(
+            "/topic/list",
+            scoped(ApiScope::TopicsRead, post(api::openapi::topic_list)),
+        )
+        .route(
+            "/topic/info/:topicid",
+            scoped(ApiScope::TopicsRead, post(api::openapi::topic_info)),
+        )
+        .route(
+            "/topic/update/:topicid",
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_update)),
         )
-        .route("/topic/create", post(api::openapi::topic_create_auto))
-        .route("/topic/create/:topicid", post(api::openapi::topic_create))
-        .route("/topic/list", post(api::openapi::topic_list))
-        .route("/topic/info/:topicid", post(api::openapi::topic_info))
-        .route("/topic/update/:topicid", post(api::openapi::topic_update))
         .route(
             "/topic/enabled/:topicid",
-            post(api::openapi::topic_set_enabled),
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_set_enabled)),
         )
         .route(
             "/topic/update_extra/:topicid",
-            post(api::openapi::topic_update_extra),
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_update_extra),
+            ),
+        )
+        .route(
+            "/topic/logs/:topicid",
+            scoped(ApiScope::TopicsRead, post(api::openapi::topic_logs)),
         )
-        .route("/topic/logs/:topicid", post(api::openapi::topic_logs))
         .route(
             "/topic/import/:topicid",
-            post(api::openapi::topic_import_message),
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_import_message),
+            ),
         )
         .route(
             "/topic/send/:topicid",
-            post(api::openapi::topic_send_message),
+            scoped(ApiScope::ChatSend, post(api::openapi::topic_send_message)),
         )
         .route(
             "/topic/send/:topicid/:format",
-            post(api::openapi::topic_send_message_with_format),
+            scoped(
+                ApiScope::ChatSend,
+                post(api::openapi::topic_send_message_with_format),
+            ),
+        )
+        .route(
+            "/chat/:senderid",
+            scoped(ApiScope::ChatSend, post(api::openapi::chat_send_message)),
         )
-        .route("/chat/:senderid", post(api::openapi::chat_send_message))
         .route(
             "/chat/:senderid/:format",
-            post(api::openapi::chat_send_message_with_format),
+            scoped(
+                ApiScope::ChatSend,
+                post(api::openapi::chat_send_message_with_format),
+            ),
+        )
+        .route(
+            "/topic/members/:topicid",
+            scoped(ApiScope::TopicsRead, post(api::openapi::topic_members)),
+        )
+        .route(
+            "/topic/join/:topicid",
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_join)),
+        )
+        .route(
+            "/topic/quit/:topicid",
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_quit)),
+        )
+        .route(
+            "/topic/dismiss/:topicid",
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_dismiss)),
         )
-        .route("/topic/members/:topicid", post(api::openapi::topic_members))
-        .route("/topic/join/:topicid", post(api::openapi::topic_join))
-        .route("/topic/quit/:topicid", post(api::openapi::topic_quit))
-        .route("/topic/dismiss/:topicid", post(api::openapi::topic_dismiss))
         .route(
             "/topic/member/:topicid/:userid",
-            post(api::openapi::topic_update_member),
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_update_member),
+            ),
         )
         .route(
             "/topic/member_info/:topicid/:userid",
-            post(api::openapi::topic_member_info),
+            scoped(ApiScope::TopicsRead, post(api::openapi::topic_member_info)),
         )
         .route(
             "/topic/kickout/:topicid/:userid",
-            post(api::openapi::topic_kickout_member),
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_kickout_member),
+            ),
         )
         .route(
             "/topic/transfer/:topicid/:userid",
-            post(api::openapi::topic_transfer_owner),
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_transfer_owner),
+            ),
         )
         .route(
             "/topic/admin/add/:topicid/:userid",
-            post(api::openapi::topic_add_admin),
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_add_admin)),
         )
         .route(
             "/topic/admin/remove/:topicid/:userid",
-            post(api::openapi::topic_remove_admin),
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_remove_admin),
+            ),
         )
         .route(
             "/topic/silent/member/:topicid",
-            post(api::openapi::topic_silent_member),
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_silent_member),
+            ),
         )
         .route(
             "/topic/silent/whitelist/add/:topicid",
-            post(api::openapi::topic_add_silent_whitelist),
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_add_silent_whitelist),
+            ),
         )
         .route(
             "/topic/silent/whitelist/remove/:topicid",
-            post(api::openapi::topic_remove_silent_whitelist),
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_remove_silent_whitelist),
+            ),
         )
         .route(
             "/topic/silent/topic/:topicid",
-            post(api::openapi::topic_silent),
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_silent)),
+        )
+        .route(
+            "/topic/invite/create/:topicid",
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_invite_create),
+            ),
+        )
+        .route(
+            "/topic/invite/list/:topicid",
+            scoped(ApiScope::TopicsRead, post(api::openapi::topic_invite_list)),
+        )
+        .route(
+            "/topic/invite/revoke/:topicid/:token",
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_invite_revoke),
+            ),
+        )
+        .route(
+            "/topic/knock_rule/get/:topicid",
+            scoped(
+                ApiScope::TopicsRead,
+                post(api::openapi::topic_knock_rule_get),
+            ),
+        )
+        .route(
+            "/topic/knock_rule/update/:topicid",
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_knock_rule_update),
+            ),
+        )
+        .route(
+            "/topic/ban/:topicid/:userid",
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_ban_member)),
+        )
+        .route(
+            "/topic/unban/:topicid/:userid",
+            scoped(
+                ApiScope::TopicsWrite,
+                post(api::openapi::topic_unban_member),
+            ),
+        )
+        .route(
+            "/topic/bans/:topicid",
+            scoped(ApiScope::TopicsRead, post(api::openapi::topic_ban_list)),
+        )
+        .route(
+            "/topic/role/list/:topicid",
+            scoped(ApiScope::TopicsRead, post(api::openapi::topic_role_list)),
+        )
+        .route(
+            "/topic/role/update/:topicid/:role",
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_role_update)),
+        )
+        .route(
+            "/topic/role/remove/:topicid/:role",
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_role_remove)),
+        )
+        .route(
+            "/topic/role/assign/:topicid/:userid",
+            scoped(ApiScope::TopicsWrite, post(api::openapi::topic_role_assign)),
         )
         .route(
             "/conversation/info/:userid/:topicid",
-            post(api::openapi::conversation_info),
+            scoped(
+                ApiScope::ConversationsRead,
+                post(api::openapi::conversation_info),
+            ),
         )
         .route(
             "/conversation/remove/:userid/:topicid",
-            post(api::openapi::conversation_remove),
+            scoped(
+                ApiScope::ConversationsWrite,
+                post(api::openapi::conversation_remove),
+            ),
         )
         .route(
             "/conversation/unread/:userid/:topicid",
-            post(api::openapi::conversation_mark_unread),
+            scoped(
+                ApiScope::ConversationsWrite,
+                post(api::openapi::conversation_mark_unread),
+            ),
         )
         .route(
             "/conversation/update/:userid/:topicid",
-            post(api::openapi::conversation_update),
+            scoped(
+                ApiScope::ConversationsWrite,
+                post(api::openapi::conversation_update),
+            ),
+        )
+        .route(
+            "/apikey/list",
+            scoped(ApiScope::Admin, post(api::openapi::apikey_list)),
+        )
+        .route(
+            "/apikey/create",
+            scoped(ApiScope::Admin, post(api::openapi::apikey_create)),
+        )
+        .route(
+            "/apikey/rotate/:keyid",
+            scoped(ApiScope::Admin, post(api::openapi::apikey_rotate)),
+        )
+        .route(
+            "/apikey/revoke/:keyid",
+            scoped(ApiScope::Admin, post(api::openapi::apikey_revoke)),
+        )
+        .route(
+            "/webhook/events",
+            scoped(ApiScope::Admin, post(api::openapi::webhook_events)),
+        )
+        .route(
+            "/webhook/outbox",
+            scoped(ApiScope::Admin, post(api::openapi::webhook_outbox)),
+        )
+        .route(
+            "/webhook/dead",
+            scoped(ApiScope::Admin, post(api::openapi::webhook_dead_letters)),
+        )
+        .route(
+            "/webhook/dead/retry",
+            scoped(
+                ApiScope::Admin,
+                post(api::openapi::webhook_retry_dead_letters),
+            ),
+        )
+        .route(
+            "/webhook/replay",
+            scoped(ApiScope::Admin, post(api::openapi::webhook_replay)),
+        )
+        .route(
+            "/webhook/subscription/list",
+            scoped(
+                ApiScope::Admin,
+                post(api::openapi::webhook_subscription_list),
+            ),
+        )
+        .route(
+            "/webhook/subscription/create",
+            scoped(
+                ApiScope::Admin,
+                post(api::openapi::webhook_subscription_create),
+            ),
+        )
+        .route(
+            "/webhook/subscription/update/:subscriptionid",
+            scoped(
+                ApiScope::Admin,
+                post(api::openapi::webhook_subscription_update),
+            ),
+        )
+        .route(
+            "/webhook/subscription/delete/:subscriptionid",
+            scoped(
+                ApiScope::Admin,
+                post(api::openapi::webhook_subscription_delete),
+            ),
         )
         .route("/docs", get(api::openapi::docs));
 
@@ -263,12 +618,23 @@ pub async fn build_router(
         .route("/health", get(api::health::health))
         .route("/live", get(api::health::live))
         .route("/ready", get(api::health::ready))
-        .route("/guest/login", post(api::auth::guest_login));
+        .route("/guest/login", post(api::auth::guest_login))
+        // authenticates itself, with a connect ticket or the bearer token
+        .route("/connect", get(api::routes_ws::ws_connect));
 
     let api_protected = Router::new()
         .route("/devices", get(api::user::devices))
-        .route("/connect", get(api::routes_ws::ws_connect))
+        .route("/connect/ticket", post(api::routes_ws::ws_connect_ticket))
         .route("/kick/:cid", post(api::user::kick))
+        .route("/sessions", post(api::user::sessions))
+        .route(
+            "/sessions/revoke/:sessionid",
+            post(api::user::revoke_session),
+        )
+        .route(
+            "/sessions/revoke_others",
+            post(api::user::revoke_other_sessions),
+        )
         .route("/attachment/upload", post(api::attachment::upload))
         .route(
             "/attachment/*filepath",
@@ -278,6 +644,27 @@ pub async fn build_router(
         .route("/profile/:userid", post(api::user::single_profile))
         .route("/profile/update", post(api::user::update_profile))
         .route("/relation/:userid", post(api::user::update_relation))
+        .route("/contacts", post(api::user::contacts))
+        .route("/users/search", post(api::user::search))
+        .route("/presence/update", post(api::presence::update_presence))
+        .route("/presence/:userid", post(api::presence::get_presence))
+        .route(
+            "/contact/request/:userid",
+            post(api::contact::contact_request_send),
+        )
+        .route("/contact/requests", post(api::contact::contact_request_list))
+        .route(
+            "/contact/accept/:userid",
+            post(api::contact::contact_request_accept),
+        )
+        .route(
+            "/contact/decline/:userid",
+            post(api::contact::contact_request_decline),
+        )
+        .route(
+            "/contact/cancel/:userid",
+            post(api::contact::contact_request_cancel),
+        )
         .route("/chat/list", post(api::chat::chat_list))
         .route("/chat/info/:topicid", post(api::chat::chat_info))
         .route("/chat/remove/:topicid", post(api::chat::chat_remove))
@@ -347,6 +734,59 @@ pub async fn build_router(
             "/topic/admin/knock/reject/:topicid/:userid",
             post(api::topic::topic_admin_reject_knock),
         )
+        .route(
+            "/topic/admin/invite/create/:topicid",
+            post(api::topic::topic_admin_create_invite),
+        )
+        .route(
+            "/topic/admin/invite/list/:topicid",
+            post(api::topic::topic_admin_list_invite),
+        )
+        .route(
+            "/topic/admin/invite/revoke/:topicid/:token",
+            post(api::topic::topic_admin_revoke_invite),
+        )
+        .route(
+            "/topic/admin/knock_rule/get/:topicid",
+            post(api::topic::topic_admin_get_knock_rule),
+        )
+        .route(
+            "/topic/admin/knock_rule/update/:topicid",
+            post(api::topic::topic_admin_update_knock_rule),
+        )
+        .route(
+            "/topic/admin/role/list/:topicid",
+            post(api::topic::topic_admin_list_role),
+        )
+        .route(
+            "/topic/admin/role/update/:topicid/:role",
+            post(api::topic::topic_admin_update_role),
+        )
+        .route(
+            "/topic/admin/role/remove/:topicid/:role",
+            post(api::topic::topic_admin_remove_role),
+        )
+        .route(
+            "/topic/admin/role/assign/:topicid/:userid",
+            post(api::topic::topic_admin_assign_role),
+        )
+        .route(
+            "/topic/invite_link/preview/:token",
+            post(api::topic::topic_invite_preview),
+        )
+        .route(
+            "/topic/invite_link/join/:token",
+            post(api::topic::topic_invite_join),
+        )
+        .route("/topic/directory", post(api::topic::topic_directory))
+        .route(
+            "/topic/directory/preview/:topicid",
+            post(api::topic::topic_directory_preview),
+        )
+        .route(
+            "/topic/directory/join/:topicid",
+            post(api::topic::topic_directory_join),
+        )
         .route(
             "/topic/admin/notice/:topicid",
             post(api::topic::topic_admin_notice),
@@ -355,6 +795,18 @@ pub async fn build_router(
             "/topic/admin/kickout/:topicid/:userid",
             post(api::topic::topic_admin_kickout),
         )
+        .route(
+            "/topic/admin/ban/:topicid/:userid",
+            post(api::topic::topic_admin_ban),
+        )
+        .route(
+            "/topic/admin/unban/:topicid/:userid",
+            post(api::topic::topic_admin_unban),
+        )
+        .route(
+            "/topic/admin/list_ban/:topicid",
+            post(api::topic::topic_admin_list_ban),
+        )
         .route(
             "/topic/admin/silent/:topicid/:userid",
             post(api::topic::topic_admin_silent_user),
@@ -388,10 +840,12 @@ pub async fn build_router(
             .route("/admin", get(api::admin::spa))
             .route(
                 "/admin/api/config",
-                get(api::admin::config_view).route_layer(axum::middleware::from_fn_with_state(
-                    state.clone(),
-                    api::middleware_auth::openapi_auth,
-                )),
+                scoped(ApiScope::Admin, get(api::admin::config_view)).route_layer(
+                    axum::middleware::from_fn_with_state(
+                        state.clone(),
+                        api::middleware_auth::openapi_auth,
+                    ),
+                ),
             )
             .route(
                 "/admin/api/bootstrap",
@@ -399,10 +853,12 @@ pub async fn build_router(
             )
             .route(
                 "/admin/api/perf",
-                get(api::admin::perf_stats).route_layer(axum::middleware::from_fn_with_state(
-                    state.clone(),
-                    api::middleware_auth::openapi_auth,
-                )),
+                scoped(ApiScope::Admin, get(api::admin::perf_stats)).route_layer(
+                    axum::middleware::from_fn_with_state(
+                        state.clone(),
+                        api::middleware_auth::openapi_auth,
+                    ),
+                ),
             );
     }
     if chat_enabled {
@@ -444,6 +900,10 @@ pub async fn build_router(
         .route("/canned-responses/:id", put(api::helpdesk::update_canned_response).delete(api::helpdesk::delete_canned_response))
         .route("/labels", get(api::helpdesk::list_labels).post(api::helpdesk::create_label))
         .route("/labels/:id", delete(api::helpdesk::delete_label))
+        .route_layer(axum::middleware::from_fn_with_state(
+            ApiScope::Helpdesk,
+            api::middleware_auth::require_scope,
+        ))
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
             api::middleware_auth::openapi_auth,
@@ -463,6 +923,9 @@ pub async fn build_router(
     let app = app
         .route("/auth/register", post(api::auth::register))
         .route("/auth/login", post(api::auth::login))
+        .route("/auth/refresh", post(api::auth::refresh))
+        .route("/auth/oidc/:provider/start", get(api::oidc::start))
+        .route("/auth/oidc/:provider/callback", get(api::oidc::callback))
         .route(
             "/auth/logout",
             get(api::auth::logout).route_layer(axum::middleware::from_fn_with_state(
@@ -515,6 +978,131 @@ fn start_webhook_worker(state: AppState) {
     });
 }
 
+fn start_knock_cleanup_loop(state: AppState) {
+    let expire_secs = state.config.knock_expire_secs;
+    if expire_secs == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(
+            expire_secs.clamp(1, 60),
+        ));
+        loop {
+            ticker.tick().await;
+            let before = (chrono::Utc::now()
+                - chrono::Duration::seconds(expire_secs.min(i64::MAX as u64) as i64))
+            .to_rfc3339();
+            let knocks = match state.topic_service.expire_knocks(&before).await {
+                Ok(knocks) => knocks,
+                Err(err) => {
+                    tracing::warn!(error = ?err, "knock cleanup failed");
+                    continue;
+                }
+            };
+            for knock in knocks.iter() {
+                if let Ok(topic) = state.topic_service.get_by_id(&knock.topic_id).await {
+                    api::topic::notify_knock_result(
+                        &state,
+                        &topic,
+                        "",
+                        &knock.user_id,
+                        "topic.knock.reject",
+                        "expired",
+                    )
+                    .await;
+                }
+                state
+                    .event_bus
+                    .publish(BackendEvent::TopicKnockReject(TopicKnockEvent {
+                        topic_id: knock.topic_id.clone(),
+                        admin_id: String::new(),
+                        user_id: knock.user_id.clone(),
+                        message: "expired".to_string(),
+                        source: "expired".to_string(),
+                    }));
+            }
+            if !knocks.is_empty() {
+                tracing::info!(
+                    expired = knocks.len(),
+                    "knock cleanup expired pending knocks"
+                );
+            }
+        }
+    });
+}
+
+fn start_presence_cleanup_loop(state: AppState) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(
+            state.config.presence_heartbeat_secs.max(1),
+        ));
+        loop {
+            ticker.tick().await;
+            for (user_id, device) in state.presence_hub.cleanup_expired().await {
+                if state.presence_hub.snapshot(&user_id).await.online {
+                    continue;
+                }
+                if let Err(err) = state.user_service.touch_last_seen(&user_id).await {
+                    tracing::warn!(user_id = %user_id, error = %err, "presence last seen update failed");
+                }
+                api::presence::notify_presence_change(&state, &user_id, &device, true).await;
+            }
+        }
+    });
+}
+
+fn start_token_maintenance_loop(state: AppState) {
+    let flush_secs = state.config.token_touch_flush_secs.max(1);
+    let gc_secs = state.config.token_gc_interval_secs.max(flush_secs);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(flush_secs));
+        let mut last_gc = std::time::Instant::now();
+        loop {
+            ticker.tick().await;
+            if let Err(err) = state.auth_service.flush_last_seen().await {
+                tracing::warn!(error = %err, "token last seen flush failed");
+            }
+            if last_gc.elapsed().as_secs() < gc_secs {
+                continue;
+            }
+            last_gc = std::time::Instant::now();
+            match state.auth_service.purge_expired().await {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!(removed, "expired auth tokens purged"),
+                Err(err) => tracing::warn!(error = %err, "token gc failed"),
+            }
+            if let Err(err) = state.login_guard.cleanup().await {
+                tracing::warn!(error = %err, "login lockout cleanup failed");
+            }
+            if let Err(err) = state.ws_tickets.cleanup().await {
+                tracing::warn!(error = %err, "ws ticket cleanup failed");
+            }
+            let retention = state
+                .config
+                .webhook_event_retention_secs
+                .min(i64::MAX as u64) as i64;
+            let before = chrono::Utc::now().timestamp().saturating_sub(retention);
+            if let Err(err) = state.webhook_outbox.cleanup(before).await {
+                tracing::warn!(error = ?err, "webhook event cleanup failed");
+            }
+        }
+    });
+}
+
+// other nodes revoke signed tokens through the db, pick those up periodically
+fn start_revocation_refresh_loop(state: AppState) {
+    let refresh_secs = state.config.token_revocation_refresh_secs.max(1);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(refresh_secs));
+        loop {
+            ticker.tick().await;
+            if let Err(err) = state.auth_service.refresh_revocations().await {
+                tracing::warn!(error = %err, "token revocation refresh failed");
+            }
+        }
+    });
+}
+
 async fn handle_event_webhooks(state: AppState, event: BackendEvent) {
     if !event.should_send_webhook() {
         return;
@@ -524,18 +1112,38 @@ async fn handle_event_webhooks(state: AppState, event: BackendEvent) {
     let topic_id = event.topic_id().map(|v| v.to_string());
     let data = event.data_payload();
 
-    let mut targets: Vec<String> = state.webhook_targets.as_ref().clone();
-    targets.extend(event.explicit_webhooks().iter().cloned());
-    if event.use_topic_webhooks() {
-        if let Some(topic_id) = topic_id.as_deref() {
-            if let Ok(topic) = state.topic_service.get_by_id(topic_id).await {
-                targets.extend(topic.webhooks);
-            }
+    if state.config.webhook_outbox {
+        // chat events were already written with the chat log, this insert is a no-op for them
+        let id = match &event {
+            BackendEvent::Chat(v) => chat_event_id(&v.topic_id, &v.chat_id),
+            _ => uuid::Uuid::new_v4().simple().to_string(),
+        };
+        let record = NewWebhookEvent {
+            id,
+            name: event_name.to_string(),
+            topic_id: topic_id.clone().unwrap_or_default(),
+            data,
+            explicit_targets: event.explicit_webhooks().to_vec(),
+            use_topic_webhooks: event.use_topic_webhooks(),
+        };
+        if let Err(err) = state
+            .webhook_outbox
+            .record(&record, chrono::Utc::now().timestamp())
+            .await
+        {
+            tracing::warn!(event = event_name, error = ?err, "webhook outbox write failed");
         }
+        return;
     }
-    let mut seen = HashSet::new();
-    targets.retain(|target| !target.trim().is_empty() && seen.insert(target.clone()));
 
+    let targets = resolve_webhook_targets(
+        &state,
+        event_name,
+        topic_id.as_deref(),
+        event.explicit_webhooks(),
+        event.use_topic_webhooks(),
+    )
+    .await;
     if targets.is_empty() {
         return;
     }
@@ -547,22 +1155,42 @@ async fn handle_event_webhooks(state: AppState, event: BackendEvent) {
         "dispatch webhook event"
     );
 
+    let event_id = uuid::Uuid::new_v4().simple().to_string();
     let payload = serde_json::json!({
+        "id": event_id,
         "name": event_name,
         "topicId": topic_id,
         "data": data,
     });
-    for target in targets {
+    for WebhookTarget {
+        url: target,
+        owner_secret,
+        subscription_id,
+    } in targets
+    {
         let state = state.clone();
         let webhook_pool = state.webhook_pool.clone();
-        let target = target.clone();
         let submit_target = target.clone();
         let topic_id = topic_id.clone();
         let payload = payload.clone();
+        let event_id = event_id.clone();
         if let Err(err) = webhook_pool
             .submit(async move {
                 let st = std::time::Instant::now();
-                if let Err(err) = state.webhook_sender.send_json(&target, &payload).await {
+                let secret = state
+                    .webhook_sender
+                    .secret_for(&target, owner_secret.as_deref());
+                let result = state
+                    .webhook_sender
+                    .send_signed(&target, &event_id, &payload, secret.as_deref())
+                    .await;
+                if !subscription_id.is_empty() {
+                    let error = result.as_ref().err().map(|err| err.to_string());
+                    state
+                        .metrics
+                        .record_subscription_delivery(&subscription_id, error.as_deref());
+                }
+                if let Err(err) = result {
                     state.metrics.incr_webhook_failures();
                     tracing::warn!(
                         target = %target,
@@ -590,6 +1218,232 @@ async fn handle_event_webhooks(state: AppState, event: BackendEvent) {
     }
 }
 
+// subscriptions first so their secret and stats win over a duplicate global or topic
+// target, then deduplicated by url
+async fn resolve_webhook_targets(
+    state: &AppState,
+    event_name: &str,
+    topic_id: Option<&str>,
+    explicit: &[String],
+    use_topic_webhooks: bool,
+) -> Vec<WebhookTarget> {
+    let mut targets = match state
+        .webhook_subscriptions
+        .targets_for(event_name, topic_id, || async {
+            let topic_id = topic_id?;
+            let topic = state.topic_service.get_any_by_id(topic_id).await.ok()?;
+            Some(topic.kind)
+        })
+        .await
+    {
+        Ok(targets) => targets,
+        Err(err) => {
+            tracing::warn!(event = event_name, error = ?err, "webhook subscriptions load failed");
+            Vec::new()
+        }
+    };
+    targets.extend(
+        state
+            .webhook_targets
+            .iter()
+            .chain(explicit)
+            .map(|url| WebhookTarget {
+                url: url.clone(),
+                ..Default::default()
+            }),
+    );
+    if use_topic_webhooks {
+        if let Some(topic_id) = topic_id {
+            if let Ok(hooks) = state.topic_service.webhooks(topic_id).await {
+                let secret = Some(hooks.secret).filter(|v| !v.is_empty());
+                targets.extend(hooks.targets.into_iter().map(|url| WebhookTarget {
+                    url,
+                    owner_secret: secret.clone(),
+                    subscription_id: String::new(),
+                }));
+                // helpdesk inboxes sign their offline webhook with their own secret
+                if let Some(inbox_id) = hooks.inbox_id {
+                    if let Ok(Some(inbox)) = helpdesk_inbox::Entity::find_by_id(inbox_id)
+                        .one(&state.db)
+                        .await
+                    {
+                        for target in targets.iter_mut() {
+                            if target.subscription_id.is_empty()
+                                && target.url == inbox.offline_webhook_url
+                                && !inbox.offline_webhook_secret.is_empty()
+                            {
+                                target.owner_secret = Some(inbox.offline_webhook_secret.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let mut seen = HashSet::new();
+    targets.retain(|target| !target.url.trim().is_empty() && seen.insert(target.url.clone()));
+    targets
+}
+
+// drains the webhook outbox: fans recorded events out to their targets and delivers
+// due rows, woken on new events and otherwise polling so other nodes' rows get picked up
+fn start_webhook_dispatcher(state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = dispatch_webhooks(&state).await {
+                tracing::warn!(error = ?err, "webhook dispatch failed");
+            }
+            tokio::select! {
+                _ = state.webhook_outbox.woken() => {}
+                _ = tokio::time::sleep(std::time::Duration::from_secs(1)) => {}
+            }
+        }
+    });
+}
+
+async fn dispatch_webhooks(state: &AppState) -> DomainResult<()> {
+    let now = chrono::Utc::now().timestamp();
+    for event in state.webhook_outbox.pending_events(100).await? {
+        let targets = resolve_webhook_targets(
+            state,
+            &event.event_name,
+            Some(event.topic_id.as_str()).filter(|v| !v.is_empty()),
+            &explicit_targets(&event),
+            event.use_topic_webhooks,
+        )
+        .await;
+        state
+            .webhook_outbox
+            .fan_out(&event.id, &targets, now)
+            .await?;
+    }
+
+    // long enough for every attempt of a slow target before another node may take it over
+    let lease = now + state.config.webhook_timeout_secs.max(1) as i64 + 30;
+    for row in state.webhook_outbox.due(now, 100).await? {
+        if let Some(until) = state.webhook_circuit.open_until(&row.target, now) {
+            state.webhook_outbox.claim(&row, until).await?;
+            continue;
+        }
+        if !state.webhook_outbox.claim(&row, lease).await? {
+            continue;
+        }
+        let task_state = state.clone();
+        let target = row.target.clone();
+        if let Err(err) = state
+            .webhook_pool
+            .submit(async move {
+                deliver_outbox_row(task_state, row).await;
+            })
+            .await
+        {
+            // the lease runs out and the row is picked up again
+            tracing::warn!(target = %target, error = %err, "webhook delivery task submit failed");
+        }
+    }
+    Ok(())
+}
+
+async fn deliver_outbox_row(state: AppState, row: crate::entity::webhook_outbox::Model) {
+    let event = match state.webhook_outbox.event(&row.event_id).await {
+        Ok(Some(event)) => event,
+        Ok(None) => {
+            let now = chrono::Utc::now().timestamp();
+            let attempts = row.attempts;
+            if let Err(err) = state
+                .webhook_outbox
+                .bury(row, attempts, "event not found", now)
+                .await
+            {
+                tracing::warn!(error = ?err, "webhook dead letter write failed");
+            }
+            return;
+        }
+        Err(err) => {
+            tracing::warn!(event_id = %row.event_id, error = ?err, "webhook event load failed");
+            return;
+        }
+    };
+
+    let st = std::time::Instant::now();
+    let secret = state
+        .webhook_sender
+        .secret_for(&row.target, Some(row.owner_secret.as_str()));
+    let result = state
+        .webhook_sender
+        .deliver(
+            &row.target,
+            &event.id,
+            &delivery_body(&event),
+            secret.as_deref(),
+        )
+        .await;
+    let now = chrono::Utc::now().timestamp();
+    state
+        .webhook_circuit
+        .record(&row.target, result.is_ok(), now);
+    if !row.subscription_id.is_empty() {
+        let error = result.as_ref().err().map(|err| err.to_string());
+        state
+            .metrics
+            .record_subscription_delivery(&row.subscription_id, error.as_deref());
+    }
+
+    let err = match result {
+        Ok(()) => {
+            state.metrics.incr_webhook_deliveries();
+            tracing::info!(
+                target = %row.target,
+                event = %event.event_name,
+                event_id = %event.id,
+                elapsed_ms = st.elapsed().as_millis() as u64,
+                "webhook delivered"
+            );
+            if let Err(err) = state.webhook_outbox.complete(&row.id).await {
+                tracing::warn!(error = ?err, "webhook outbox complete failed");
+            }
+            return;
+        }
+        Err(err) => err.to_string(),
+    };
+
+    state.metrics.incr_webhook_failures();
+    let attempts = row.attempts.saturating_add(1);
+    tracing::warn!(
+        target = %row.target,
+        event = %event.event_name,
+        event_id = %event.id,
+        attempts,
+        elapsed_ms = st.elapsed().as_millis() as u64,
+        error = %err,
+        "send webhook failed"
+    );
+    let result = if attempts as u32 >= state.config.webhook_max_attempts {
+        state.webhook_outbox.bury(row, attempts, &err, now).await
+    } else {
+        let next_at = now + webhook_backoff_secs(&state.config, attempts);
+        let result = state
+            .webhook_outbox
+            .reschedule(&row.id, attempts, next_at, &err)
+            .await;
+        state.webhook_outbox.wake();
+        result
+    };
+    if let Err(err) = result {
+        tracing::warn!(error = ?err, "webhook outbox update failed");
+    }
+}
+
+// base * 2^(attempts - 1), capped
+fn webhook_backoff_secs(config: &AppConfig, attempts: i32) -> i64 {
+    let exp = attempts.saturating_sub(1).clamp(0, 30) as u32;
+    config
+        .webhook_backoff_secs
+        .saturating_mul(1u64 << exp)
+        .min(config.webhook_backoff_max_secs)
+        .min(i64::MAX as u64) as i64
+}
+
 async fn create_demo_accounts(db: &sea_orm::DatabaseConnection) -> Result<(), sea_orm::DbErr> {
     use crate::entity::user;
     use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait, IntoActiveModel};
@@ -605,7 +1459,7 @@ async fn create_demo_accounts(db: &sea_orm::DatabaseConnection) -> Result<(), se
     for (user_id, display_name) in &demo_users {
         let now = chrono::Utc::now().to_rfc3339();
         let password = format!("{}:demo", user_id);
-        let hashed = crate::api::auth::hash_password(&password);
+        let hashed = crate::api::auth::spawn_hash_password(&password).await;
         let existing = user::Entity::find_by_id(user_id.to_string())
             .one(db)
             .await?;
@@ -630,6 +1484,8 @@ async fn create_demo_accounts(db: &sea_orm::DatabaseConnection) -> Result<(), se
                 public_key: Set(String::new()),
                 is_staff: Set(false),
                 enabled: Set(true),
+                extra_json: Set("null".to_string()),
+                search_opt_out: Set(false),
                 created_at: Set(now.clone()),
                 updated_at: Set(now),
             };
@@ -643,7 +1499,7 @@ async fn create_demo_accounts(db: &sea_orm::DatabaseConnection) -> Result<(), se
         let now = chrono::Utc::now().to_rfc3339();
         let user_id = "admin";
         let password = "restsend";
-        let hashed = crate::api::auth::hash_password(password);
+        let hashed = crate::api::auth::spawn_hash_password(password).await;
         let existing = user::Entity::find_by_id(user_id.to_string())
             .one(db)
             .await?;
@@ -668,6 +1524,8 @@ async fn create_demo_accounts(db: &sea_orm::DatabaseConnection) -> Result<(), se
                 public_key: Set(String::new()),
                 is_staff: Set(true),
                 enabled: Set(true),
+                extra_json: Set("null".to_string()),
+                search_opt_out: Set(false),
                 created_at: Set(now.clone()),
                 updated_at: Set(now),
             };
@@ -4,13 +4,18 @@ use sea_orm::DatabaseConnection;
 
 use crate::app::AppConfig;
 use crate::infra::event::EventBus;
+use crate::infra::lockout::LoginGuard;
 use crate::infra::metrics::RuntimeMetrics;
+use crate::infra::oidc::OidcClient;
 use crate::infra::presence::PresenceHub;
+use crate::infra::rate_limit::RateLimiter;
 use crate::infra::task_pool::TaskPool;
-use crate::infra::webhook::WebhookSender;
+use crate::infra::webhook::{CircuitBreaker, WebhookSender};
 use crate::infra::websocket::WsHub;
+use crate::infra::ws_ticket::WsTicketIssuer;
 use crate::services::{
-    AuthService, ChatService, ConversationService, RelationService, TopicService, UserService,
+    ApiKeyService, AuthService, ChatService, ConversationService, RelationService, TopicService,
+    UserService, WebhookOutboxService, WebhookSubscriptionService,
 };
 
 #[derive(Clone)]
@@ -33,4 +38,12 @@ pub struct AppState {
     pub topic_service: Arc<TopicService>,
     pub conversation_service: Arc<ConversationService>,
     pub chat_service: Arc<ChatService>,
+    pub search_limiter: Arc<RateLimiter>,
+    pub login_guard: Arc<LoginGuard>,
+    pub oidc: Arc<OidcClient>,
+    pub api_key_service: Arc<ApiKeyService>,
+    pub ws_tickets: Arc<WsTicketIssuer>,
+    pub webhook_outbox: Arc<WebhookOutboxService>,
+    pub webhook_circuit: Arc<CircuitBreaker>,
+    pub webhook_subscriptions: Arc<WebhookSubscriptionService>,
 }
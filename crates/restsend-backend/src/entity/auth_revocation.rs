@@ -0,0 +1,19 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "auth_revocations")]
+pub struct Model {
+    // `jti:<id>` revokes one signed token, `user:<id>` every token issued before revoked_at
+    // `keep:<user>:<jti>` exempts the session that asked to log out its other devices
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub user_id: String,
+    pub revoked_at: i64,
+    // unix seconds after which the entry is useless, 0 keeps it forever
+    pub expires_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
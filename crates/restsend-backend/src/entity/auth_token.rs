@@ -8,9 +8,36 @@ pub struct Model {
     pub user_id: String,
     pub created_at: String,
     pub last_seen_at: String,
+    pub device: String,
+    pub device_name: String,
+    pub platform: String,
+    pub app_version: String,
+    pub ip: String,
+    // unix seconds, 0 never expires
+    pub expires_at: i64,
+    // sha256 of the refresh token, empty when none was issued
+    pub refresh_token: String,
+    pub refresh_expires_at: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
 impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for crate::DeviceSession {
+    fn from(model: Model) -> Self {
+        crate::DeviceSession {
+            session_id: crate::services::session_id_of(&model.token),
+            device: model.device,
+            device_name: model.device_name,
+            platform: model.platform,
+            app_version: model.app_version,
+            ip: model.ip,
+            created_at: model.created_at,
+            last_seen_at: model.last_seen_at,
+            online: false,
+            current: false,
+        }
+    }
+}
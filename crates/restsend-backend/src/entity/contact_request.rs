@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "contact_requests")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub sender_id: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub receiver_id: String,
+    pub message: String,
+    pub source: String,
+    pub status: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for crate::ContactRequest {
+    fn from(model: Model) -> Self {
+        crate::ContactRequest {
+            sender_id: model.sender_id,
+            receiver_id: model.receiver_id,
+            message: model.message,
+            source: model.source,
+            status: model.status,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
@@ -1,15 +1,30 @@
 pub mod attachment;
+pub mod auth_revocation;
 pub mod auth_token;
 pub mod chat_log;
+pub mod contact_request;
 pub mod conversation;
 pub mod helpdesk_inbox;
 pub mod helpdesk_inbox_member;
+pub mod login_lockout;
+pub mod oidc_identity;
+pub mod openapi_key;
 pub mod presence_session;
 pub mod relation;
 pub mod topic;
+pub mod topic_ban;
+pub mod topic_invite;
 pub mod topic_knock;
+pub mod topic_knock_rule;
 pub mod topic_member;
+pub mod topic_role;
 pub mod user;
+pub mod user_presence;
+pub mod webhook_dead_letter;
+pub mod webhook_event;
+pub mod webhook_outbox;
+pub mod webhook_subscription;
+pub mod ws_ticket;
 
 use serde::{de::DeserializeOwned, Serialize};
 
@@ -20,3 +35,9 @@ pub(crate) fn encode_json<T: Serialize>(value: &T) -> String {
 pub(crate) fn decode_json<T: DeserializeOwned + Default>(value: &str) -> T {
     serde_json::from_str(value).unwrap_or_default()
 }
+
+pub(crate) fn unix_to_rfc3339(secs: i64) -> String {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .map(|v| v.to_rfc3339())
+        .unwrap_or_default()
+}
@@ -0,0 +1,43 @@
+use sea_orm::entity::prelude::*;
+
+use crate::util::split_list;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "openapi_keys")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub name: String,
+    // sha256 hex of the secret, the plain secret is only returned on create and rotate
+    pub secret_hash: String,
+    // comma separated scope names
+    pub scopes: String,
+    // comma separated ips or cidrs, empty allows any address
+    pub allowed_ips: String,
+    pub expires_at: String,
+    pub last_used_at: String,
+    pub created_by: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for crate::OpenApiKey {
+    fn from(value: Model) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            scopes: split_list(&value.scopes),
+            allowed_ips: split_list(&value.allowed_ips),
+            expires_at: value.expires_at,
+            last_used_at: value.last_used_at,
+            created_by: value.created_by,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
@@ -21,6 +21,8 @@ pub struct Model {
     pub knock_need_verify: bool,
     pub admins_json: String,
     pub webhooks_json: String,
+    // signs deliveries to the topic webhooks, never part of crate::Topic
+    pub webhook_secret: String,
     pub notice_json: String,
     pub extra_json: String,
     pub silent_white_list_json: String,
@@ -132,6 +134,7 @@ impl From<(crate::Topic, &str)> for ActiveModel {
             enabled: Set(value.enabled),
             created_at: Set(created_at),
             updated_at: Set(now.to_string()),
+            ..Default::default()
         }
     }
 }
@@ -164,6 +167,7 @@ impl From<(&crate::Topic, &str)> for ActiveModel {
                 value.created_at.clone()
             }),
             updated_at: Set(now.to_string()),
+            ..Default::default()
         }
     }
 }
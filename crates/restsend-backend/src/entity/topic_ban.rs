@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "topic_bans")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub topic_id: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: String,
+    pub reason: String,
+    pub admin_id: String,
+    pub expires_at: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for crate::TopicBan {
+    fn from(model: Model) -> Self {
+        crate::TopicBan {
+            topic_id: model.topic_id,
+            user_id: model.user_id,
+            reason: model.reason,
+            admin_id: model.admin_id,
+            expires_at: model.expires_at,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
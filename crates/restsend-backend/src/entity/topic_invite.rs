@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "topic_invites")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub token: String,
+    pub topic_id: String,
+    pub created_by: String,
+    pub max_uses: i32,
+    pub used_count: i32,
+    pub expires_at: String,
+    pub auto_approve: bool,
+    pub revoked: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for crate::TopicInvite {
+    fn from(value: Model) -> Self {
+        Self {
+            token: value.token,
+            topic_id: value.topic_id,
+            created_by: value.created_by,
+            max_uses: value.max_uses.max(0) as u32,
+            used_count: value.used_count.max(0) as u32,
+            expires_at: value.expires_at,
+            auto_approve: value.auto_approve,
+            revoked: value.revoked,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+
+use crate::entity::decode_json;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "topic_knock_rules")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub topic_id: String,
+    pub sources_json: String,
+    pub attributes_json: String,
+    pub owner_contacts: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for crate::TopicKnockRule {
+    fn from(model: Model) -> Self {
+        crate::TopicKnockRule {
+            topic_id: model.topic_id,
+            sources: decode_json(&model.sources_json),
+            attributes: decode_json(&model.attributes_json),
+            owner_contacts: model.owner_contacts,
+            updated_at: model.updated_at,
+        }
+    }
+}
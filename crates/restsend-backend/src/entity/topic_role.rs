@@ -0,0 +1,33 @@
+use sea_orm::entity::prelude::*;
+
+use crate::entity::decode_json;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "topic_roles")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub topic_id: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub name: String,
+    pub permissions_json: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for crate::TopicRole {
+    fn from(model: Model) -> Self {
+        crate::TopicRole {
+            topic_id: model.topic_id,
+            name: model.name,
+            permissions: decode_json(&model.permissions_json),
+            builtin: false,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
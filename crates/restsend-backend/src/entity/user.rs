@@ -1,6 +1,8 @@
 use sea_orm::entity::prelude::*;
 use sea_orm::ActiveValue::Set;
 
+use crate::entity::{decode_json, encode_json};
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "users")]
 pub struct Model {
@@ -17,6 +19,8 @@ pub struct Model {
     pub public_key: String,
     pub is_staff: bool,
     pub enabled: bool,
+    pub extra_json: String,
+    pub search_opt_out: bool,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -40,6 +44,8 @@ impl From<Model> for crate::User {
             gender: model.gender,
             is_staff: model.is_staff,
             enabled: model.enabled,
+            extra: decode_json(&model.extra_json),
+            search_opt_out: model.search_opt_out,
             created_at: model.created_at,
             ..crate::User::default()
         }
@@ -60,6 +66,8 @@ impl From<&Model> for crate::User {
             gender: model.gender.clone(),
             is_staff: model.is_staff,
             enabled: model.enabled,
+            extra: decode_json(&model.extra_json),
+            search_opt_out: model.search_opt_out,
             created_at: model.created_at.clone(),
             ..crate::User::default()
         }
@@ -86,6 +94,8 @@ impl From<(crate::User, &str)> for ActiveModel {
             public_key: Set(value.public_key),
             is_staff: Set(value.is_staff),
             enabled: Set(value.enabled),
+            extra_json: Set(encode_json(&value.extra)),
+            search_opt_out: Set(value.search_opt_out),
             created_at: Set(created_at),
             updated_at: Set(now.to_string()),
         }
@@ -107,6 +117,8 @@ impl From<(&crate::User, &str)> for ActiveModel {
             public_key: Set(value.public_key.clone()),
             is_staff: Set(value.is_staff),
             enabled: Set(value.enabled),
+            extra_json: Set(encode_json(&value.extra)),
+            search_opt_out: Set(value.search_opt_out),
             created_at: Set(if value.created_at.is_empty() {
                 now.to_string()
             } else {
@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_presences")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: String,
+    pub status: String,
+    pub status_text: String,
+    pub status_expires_at: String,
+    pub last_seen_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for crate::UserPresence {
+    fn from(model: Model) -> Self {
+        crate::UserPresence {
+            user_id: model.user_id,
+            status: model.status,
+            status_text: model.status_text,
+            status_expires_at: model.status_expires_at,
+            last_seen_at: model.last_seen_at,
+            online: false,
+        }
+    }
+}
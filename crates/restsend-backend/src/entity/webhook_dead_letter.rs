@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+
+// deliveries that ran out of attempts, waiting for a manual retry
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "webhook_dead_letters")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub event_id: String,
+    pub target: String,
+    pub owner_secret: String,
+    pub attempts: i32,
+    pub last_error: String,
+    pub created_at: i64,
+    pub failed_at: i64,
+    // empty for global, topic and explicit targets
+    pub subscription_id: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for crate::WebhookDelivery {
+    fn from(model: Model) -> Self {
+        crate::WebhookDelivery {
+            id: model.id,
+            event_id: model.event_id,
+            target: model.target,
+            subscription_id: model.subscription_id,
+            attempts: model.attempts as u32,
+            last_error: model.last_error,
+            next_attempt_at: String::new(),
+            created_at: super::unix_to_rfc3339(model.created_at),
+            failed_at: super::unix_to_rfc3339(model.failed_at),
+        }
+    }
+}
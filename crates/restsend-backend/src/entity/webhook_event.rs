@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+
+// every webhook-worthy event, kept after delivery so it can be replayed
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "webhook_events")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub event_name: String,
+    pub topic_id: String,
+    pub payload: String,
+    pub explicit_targets_json: String,
+    pub use_topic_webhooks: bool,
+    // set once the event has been fanned out into outbox rows
+    pub dispatched: bool,
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,44 @@
+use sea_orm::entity::prelude::*;
+
+use crate::util::split_list;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "webhook_subscriptions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub url: String,
+    pub secret: String,
+    // comma separated event name patterns, empty matches every event
+    pub events: String,
+    // comma separated topic ids, empty matches any topic
+    pub topic_ids: String,
+    // comma separated topic kinds, empty matches any kind
+    pub kinds: String,
+    pub enabled: bool,
+    pub created_by: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for crate::WebhookSubscription {
+    fn from(value: Model) -> Self {
+        Self {
+            id: value.id,
+            url: value.url,
+            has_secret: !value.secret.is_empty(),
+            events: split_list(&value.events),
+            topic_ids: split_list(&value.topic_ids),
+            kinds: split_list(&value.kinds),
+            enabled: value.enabled,
+            created_by: value.created_by,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
@@ -14,7 +14,27 @@ pub struct Migrator;
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(InitSchema), Box::new(HelpdeskSchema)]
+        vec![
+            Box::new(InitSchema),
+            Box::new(HelpdeskSchema),
+            Box::new(TopicInviteSchema),
+            Box::new(TopicRoleSchema),
+            Box::new(TopicBanSchema),
+            Box::new(TopicKnockRuleSchema),
+            Box::new(ContactRequestSchema),
+            Box::new(UserDirectorySchema),
+            Box::new(UserPresenceSchema),
+            Box::new(AuthTokenDeviceSchema),
+            Box::new(AuthTokenExpirySchema),
+            Box::new(AuthRevocationSchema),
+            Box::new(LoginLockoutSchema),
+            Box::new(OpenApiKeySchema),
+            Box::new(WsTicketSchema),
+            Box::new(TopicWebhookSecretSchema),
+            Box::new(WebhookOutboxSchema),
+            Box::new(WebhookSubscriptionSchema),
+            Box::new(OidcIdentitySchema),
+        ]
     }
 }
 
@@ -907,6 +927,8 @@ enum Users {
     PublicKey,
     IsStaff,
     Enabled,
+    ExtraJson,
+    SearchOptOut,
     CreatedAt,
     UpdatedAt,
 }
@@ -928,6 +950,7 @@ enum Topics {
     KnockNeedVerify,
     AdminsJson,
     WebhooksJson,
+    WebhookSecret,
     NoticeJson,
     ExtraJson,
     SilentWhiteListJson,
@@ -1028,6 +1051,14 @@ enum AuthTokens {
     UserId,
     CreatedAt,
     LastSeenAt,
+    Device,
+    DeviceName,
+    Platform,
+    AppVersion,
+    Ip,
+    ExpiresAt,
+    RefreshToken,
+    RefreshExpiresAt,
 }
 
 #[derive(DeriveIden)]
@@ -1250,3 +1281,1521 @@ impl MigrationTrait for HelpdeskSchema {
         Ok(())
     }
 }
+
+#[derive(DeriveIden)]
+enum TopicInvites {
+    Table,
+    Token,
+    TopicId,
+    CreatedBy,
+    MaxUses,
+    UsedCount,
+    ExpiresAt,
+    AutoApprove,
+    Revoked,
+    CreatedAt,
+    UpdatedAt,
+}
+
+struct TopicInviteSchema;
+
+impl MigrationName for TopicInviteSchema {
+    fn name(&self) -> &str {
+        "m20260601_000001_topic_invites"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for TopicInviteSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TopicInvites::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TopicInvites::Token)
+                            .string_len(191)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TopicInvites::TopicId)
+                            .string_len(191)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TopicInvites::CreatedBy)
+                            .string_len(191)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(TopicInvites::MaxUses)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(TopicInvites::UsedCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(TopicInvites::ExpiresAt)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(TopicInvites::AutoApprove)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(TopicInvites::Revoked)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(TopicInvites::CreatedAt).text().not_null())
+                    .col(ColumnDef::new(TopicInvites::UpdatedAt).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_topic_invites_topic")
+                    .table(TopicInvites::Table)
+                    .if_not_exists()
+                    .col(TopicInvites::TopicId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TopicInvites::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TopicRoles {
+    Table,
+    TopicId,
+    Name,
+    PermissionsJson,
+    CreatedAt,
+    UpdatedAt,
+}
+
+struct TopicRoleSchema;
+
+impl MigrationName for TopicRoleSchema {
+    fn name(&self) -> &str {
+        "m20260602_000001_topic_roles"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for TopicRoleSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TopicRoles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TopicRoles::TopicId)
+                            .string_len(191)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TopicRoles::Name).string_len(191).not_null())
+                    .col(
+                        ColumnDef::new(TopicRoles::PermissionsJson)
+                            .text()
+                            .not_null()
+                            .default("[]"),
+                    )
+                    .col(ColumnDef::new(TopicRoles::CreatedAt).text().not_null())
+                    .col(ColumnDef::new(TopicRoles::UpdatedAt).text().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(TopicRoles::TopicId)
+                            .col(TopicRoles::Name),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TopicRoles::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TopicBans {
+    Table,
+    TopicId,
+    UserId,
+    Reason,
+    AdminId,
+    ExpiresAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+struct TopicBanSchema;
+
+impl MigrationName for TopicBanSchema {
+    fn name(&self) -> &str {
+        "m20260603_000001_topic_bans"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for TopicBanSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TopicBans::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TopicBans::TopicId)
+                            .string_len(191)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TopicBans::UserId).string_len(191).not_null())
+                    .col(
+                        ColumnDef::new(TopicBans::Reason)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(TopicBans::AdminId)
+                            .string_len(191)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(TopicBans::ExpiresAt)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(ColumnDef::new(TopicBans::CreatedAt).text().not_null())
+                    .col(ColumnDef::new(TopicBans::UpdatedAt).text().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(TopicBans::TopicId)
+                            .col(TopicBans::UserId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TopicBans::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TopicKnockRules {
+    Table,
+    TopicId,
+    SourcesJson,
+    AttributesJson,
+    OwnerContacts,
+    CreatedAt,
+    UpdatedAt,
+}
+
+struct TopicKnockRuleSchema;
+
+impl MigrationName for TopicKnockRuleSchema {
+    fn name(&self) -> &str {
+        "m20260604_000001_topic_knock_rules"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for TopicKnockRuleSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TopicKnockRules::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TopicKnockRules::TopicId)
+                            .string_len(191)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TopicKnockRules::SourcesJson)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(TopicKnockRules::AttributesJson)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(TopicKnockRules::OwnerContacts)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(TopicKnockRules::CreatedAt).text().not_null())
+                    .col(ColumnDef::new(TopicKnockRules::UpdatedAt).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TopicKnockRules::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ContactRequests {
+    Table,
+    SenderId,
+    ReceiverId,
+    Message,
+    Source,
+    Status,
+    CreatedAt,
+    UpdatedAt,
+}
+
+struct ContactRequestSchema;
+
+impl MigrationName for ContactRequestSchema {
+    fn name(&self) -> &str {
+        "m20260605_000001_contact_requests"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for ContactRequestSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ContactRequests::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ContactRequests::SenderId)
+                            .string_len(191)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ContactRequests::ReceiverId)
+                            .string_len(191)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ContactRequests::Message)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(ContactRequests::Source)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(ContactRequests::Status)
+                            .string_len(32)
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(ColumnDef::new(ContactRequests::CreatedAt).text().not_null())
+                    .col(ColumnDef::new(ContactRequests::UpdatedAt).text().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(ContactRequests::SenderId)
+                            .col(ContactRequests::ReceiverId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_contact_requests_receiver")
+                    .table(ContactRequests::Table)
+                    .if_not_exists()
+                    .col(ContactRequests::ReceiverId)
+                    .col(ContactRequests::Status)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ContactRequests::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+struct UserDirectorySchema;
+
+impl MigrationName for UserDirectorySchema {
+    fn name(&self) -> &str {
+        "m20260606_000001_user_directory"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for UserDirectorySchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_column("users", "extra_json").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Users::Table)
+                        .add_column(
+                            ColumnDef::new(Users::ExtraJson)
+                                .text()
+                                .not_null()
+                                .default("null"),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        if !manager.has_column("users", "search_opt_out").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Users::Table)
+                        .add_column(
+                            ColumnDef::new(Users::SearchOptOut)
+                                .boolean()
+                                .not_null()
+                                .default(false),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::ExtraJson)
+                    .drop_column(Users::SearchOptOut)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserPresences {
+    Table,
+    UserId,
+    Status,
+    StatusText,
+    StatusExpiresAt,
+    LastSeenAt,
+    UpdatedAt,
+}
+
+struct UserPresenceSchema;
+
+impl MigrationName for UserPresenceSchema {
+    fn name(&self) -> &str {
+        "m20260607_000001_user_presences"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for UserPresenceSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserPresences::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserPresences::UserId)
+                            .string_len(191)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UserPresences::Status)
+                            .string_len(32)
+                            .not_null()
+                            .default("online"),
+                    )
+                    .col(
+                        ColumnDef::new(UserPresences::StatusText)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(UserPresences::StatusExpiresAt)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(UserPresences::LastSeenAt)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(ColumnDef::new(UserPresences::UpdatedAt).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserPresences::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+struct AuthTokenDeviceSchema;
+
+impl MigrationName for AuthTokenDeviceSchema {
+    fn name(&self) -> &str {
+        "m20260608_000001_auth_token_devices"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for AuthTokenDeviceSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = [
+            ("device", AuthTokens::Device),
+            ("device_name", AuthTokens::DeviceName),
+            ("platform", AuthTokens::Platform),
+            ("app_version", AuthTokens::AppVersion),
+            ("ip", AuthTokens::Ip),
+        ];
+        for (name, column) in columns {
+            if manager.has_column("auth_tokens", name).await? {
+                continue;
+            }
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(AuthTokens::Table)
+                        .add_column(ColumnDef::new(column).text().not_null().default(""))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuthTokens::Table)
+                    .drop_column(AuthTokens::Device)
+                    .drop_column(AuthTokens::DeviceName)
+                    .drop_column(AuthTokens::Platform)
+                    .drop_column(AuthTokens::AppVersion)
+                    .drop_column(AuthTokens::Ip)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+struct AuthTokenExpirySchema;
+
+impl MigrationName for AuthTokenExpirySchema {
+    fn name(&self) -> &str {
+        "m20260609_000001_auth_token_expiry"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for AuthTokenExpirySchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_column("auth_tokens", "expires_at").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(AuthTokens::Table)
+                        .add_column(
+                            ColumnDef::new(AuthTokens::ExpiresAt)
+                                .big_integer()
+                                .not_null()
+                                .default(0),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        if !manager.has_column("auth_tokens", "refresh_token").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(AuthTokens::Table)
+                        .add_column(
+                            ColumnDef::new(AuthTokens::RefreshToken)
+                                .string_len(64)
+                                .not_null()
+                                .default(""),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        if !manager
+            .has_column("auth_tokens", "refresh_expires_at")
+            .await?
+        {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(AuthTokens::Table)
+                        .add_column(
+                            ColumnDef::new(AuthTokens::RefreshExpiresAt)
+                                .big_integer()
+                                .not_null()
+                                .default(0),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_auth_tokens_refresh")
+                    .table(AuthTokens::Table)
+                    .if_not_exists()
+                    .col(AuthTokens::RefreshToken)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_auth_tokens_refresh")
+                    .table(AuthTokens::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuthTokens::Table)
+                    .drop_column(AuthTokens::ExpiresAt)
+                    .drop_column(AuthTokens::RefreshToken)
+                    .drop_column(AuthTokens::RefreshExpiresAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuthRevocations {
+    Table,
+    Id,
+    UserId,
+    RevokedAt,
+    ExpiresAt,
+}
+
+struct AuthRevocationSchema;
+
+impl MigrationName for AuthRevocationSchema {
+    fn name(&self) -> &str {
+        "m20260610_000001_auth_revocations"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for AuthRevocationSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuthRevocations::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuthRevocations::Id)
+                            .string_len(191)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(AuthRevocations::UserId)
+                            .string_len(191)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AuthRevocations::RevokedAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(AuthRevocations::ExpiresAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuthRevocations::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum LoginLockouts {
+    Table,
+    Key,
+    Failures,
+    Lockouts,
+    LockedUntil,
+    UpdatedAt,
+}
+
+struct LoginLockoutSchema;
+
+impl MigrationName for LoginLockoutSchema {
+    fn name(&self) -> &str {
+        "m20260620_000001_login_lockouts"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for LoginLockoutSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LoginLockouts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LoginLockouts::Key)
+                            .string_len(191)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(LoginLockouts::Failures)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(LoginLockouts::Lockouts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(LoginLockouts::LockedUntil)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(LoginLockouts::UpdatedAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LoginLockouts::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum OpenapiKeys {
+    Table,
+    Id,
+    Name,
+    SecretHash,
+    Scopes,
+    AllowedIps,
+    ExpiresAt,
+    LastUsedAt,
+    CreatedBy,
+    CreatedAt,
+    UpdatedAt,
+}
+
+struct OpenApiKeySchema;
+
+impl MigrationName for OpenApiKeySchema {
+    fn name(&self) -> &str {
+        "m20260702_000001_openapi_keys"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for OpenApiKeySchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OpenapiKeys::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OpenapiKeys::Id)
+                            .string_len(64)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OpenapiKeys::Name)
+                            .string_len(191)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(OpenapiKeys::SecretHash)
+                            .string_len(128)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(OpenapiKeys::Scopes)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(OpenapiKeys::AllowedIps)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(OpenapiKeys::ExpiresAt)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(OpenapiKeys::LastUsedAt)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(OpenapiKeys::CreatedBy)
+                            .string_len(191)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(ColumnDef::new(OpenapiKeys::CreatedAt).text().not_null())
+                    .col(ColumnDef::new(OpenapiKeys::UpdatedAt).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OpenapiKeys::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum WsTickets {
+    Table,
+    Ticket,
+    UserId,
+    Device,
+    ExpiresAt,
+}
+
+struct WsTicketSchema;
+
+impl MigrationName for WsTicketSchema {
+    fn name(&self) -> &str {
+        "m20260715_000001_ws_tickets"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for WsTicketSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WsTickets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WsTickets::Ticket)
+                            .string_len(64)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WsTickets::UserId)
+                            .string_len(191)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WsTickets::Device)
+                            .string_len(191)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WsTickets::ExpiresAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WsTickets::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+struct TopicWebhookSecretSchema;
+
+impl MigrationName for TopicWebhookSecretSchema {
+    fn name(&self) -> &str {
+        "m20260722_000001_topic_webhook_secret"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for TopicWebhookSecretSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_column("topics", "webhook_secret").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Topics::Table)
+                        .add_column(
+                            ColumnDef::new(Topics::WebhookSecret)
+                                .string_len(255)
+                                .not_null()
+                                .default(""),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Topics::Table)
+                    .drop_column(Topics::WebhookSecret)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum WebhookEvents {
+    Table,
+    Id,
+    EventName,
+    TopicId,
+    Payload,
+    ExplicitTargetsJson,
+    UseTopicWebhooks,
+    Dispatched,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WebhookOutbox {
+    Table,
+    Id,
+    EventId,
+    Target,
+    OwnerSecret,
+    Attempts,
+    NextAttemptAt,
+    LastError,
+    CreatedAt,
+    SubscriptionId,
+}
+
+#[derive(DeriveIden)]
+enum WebhookDeadLetters {
+    Table,
+    Id,
+    EventId,
+    Target,
+    OwnerSecret,
+    Attempts,
+    LastError,
+    CreatedAt,
+    FailedAt,
+    SubscriptionId,
+}
+
+struct WebhookOutboxSchema;
+
+impl MigrationName for WebhookOutboxSchema {
+    fn name(&self) -> &str {
+        "m20260805_000001_webhook_outbox"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for WebhookOutboxSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WebhookEvents::Id)
+                            .string_len(64)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookEvents::EventName)
+                            .string_len(64)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookEvents::TopicId)
+                            .string_len(191)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookEvents::Payload)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookEvents::ExplicitTargetsJson)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookEvents::UseTopicWebhooks)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookEvents::Dispatched)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookEvents::CreatedAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_events_dispatched")
+                    .table(WebhookEvents::Table)
+                    .if_not_exists()
+                    .col(WebhookEvents::Dispatched)
+                    .col(WebhookEvents::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_events_created")
+                    .table(WebhookEvents::Table)
+                    .if_not_exists()
+                    .col(WebhookEvents::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookOutbox::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WebhookOutbox::Id)
+                            .string_len(64)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookOutbox::EventId)
+                            .string_len(64)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookOutbox::Target)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookOutbox::OwnerSecret)
+                            .string_len(255)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookOutbox::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookOutbox::NextAttemptAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookOutbox::LastError)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookOutbox::CreatedAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_outbox_next_attempt")
+                    .table(WebhookOutbox::Table)
+                    .if_not_exists()
+                    .col(WebhookOutbox::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookDeadLetters::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WebhookDeadLetters::Id)
+                            .string_len(64)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDeadLetters::EventId)
+                            .string_len(64)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDeadLetters::Target)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDeadLetters::OwnerSecret)
+                            .string_len(255)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDeadLetters::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDeadLetters::LastError)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDeadLetters::CreatedAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookDeadLetters::FailedAt)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_dead_letters_failed")
+                    .table(WebhookDeadLetters::Table)
+                    .if_not_exists()
+                    .col(WebhookDeadLetters::FailedAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(WebhookDeadLetters::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(WebhookOutbox::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(WebhookEvents::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum WebhookSubscriptions {
+    Table,
+    Id,
+    Url,
+    Secret,
+    Events,
+    TopicIds,
+    Kinds,
+    Enabled,
+    CreatedBy,
+    CreatedAt,
+    UpdatedAt,
+}
+
+struct WebhookSubscriptionSchema;
+
+impl MigrationName for WebhookSubscriptionSchema {
+    fn name(&self) -> &str {
+        "m20260820_000001_webhook_subscriptions"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for WebhookSubscriptionSchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookSubscriptions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WebhookSubscriptions::Id)
+                            .string_len(64)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookSubscriptions::Url)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookSubscriptions::Secret)
+                            .string_len(255)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookSubscriptions::Events)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookSubscriptions::TopicIds)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookSubscriptions::Kinds)
+                            .text()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookSubscriptions::Enabled)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookSubscriptions::CreatedBy)
+                            .string_len(191)
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookSubscriptions::CreatedAt)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookSubscriptions::UpdatedAt)
+                            .text()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // outbox rows remember the subscription that produced them for per-subscription stats
+        if !manager
+            .has_column("webhook_outbox", "subscription_id")
+            .await?
+        {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(WebhookOutbox::Table)
+                        .add_column(
+                            ColumnDef::new(WebhookOutbox::SubscriptionId)
+                                .string_len(64)
+                                .not_null()
+                                .default(""),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        if !manager
+            .has_column("webhook_dead_letters", "subscription_id")
+            .await?
+        {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(WebhookDeadLetters::Table)
+                        .add_column(
+                            ColumnDef::new(WebhookDeadLetters::SubscriptionId)
+                                .string_len(64)
+                                .not_null()
+                                .default(""),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WebhookDeadLetters::Table)
+                    .drop_column(WebhookDeadLetters::SubscriptionId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WebhookOutbox::Table)
+                    .drop_column(WebhookOutbox::SubscriptionId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(WebhookSubscriptions::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum OidcIdentities {
+    Table,
+    Provider,
+    Subject,
+    UserId,
+    CreatedAt,
+}
+
+struct OidcIdentitySchema;
+
+impl MigrationName for OidcIdentitySchema {
+    fn name(&self) -> &str {
+        "m20260901_000001_oidc_identities"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for OidcIdentitySchema {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OidcIdentities::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OidcIdentities::Provider)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OidcIdentities::Subject)
+                            .string_len(191)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OidcIdentities::UserId)
+                            .string_len(191)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(OidcIdentities::CreatedAt).text().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(OidcIdentities::Provider)
+                            .col(OidcIdentities::Subject),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OidcIdentities::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
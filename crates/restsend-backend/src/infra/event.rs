@@ -75,6 +75,17 @@ pub struct TopicSilentEvent {
     pub source: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicBanEvent {
+    pub topic_id: String,
+    pub admin_id: String,
+    pub user_id: String,
+    pub reason: String,
+    pub expires_at: String,
+    pub source: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TopicChangeOwnerEvent {
@@ -84,6 +95,15 @@ pub struct TopicChangeOwnerEvent {
     pub source: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactRequestEvent {
+    pub sender_id: String,
+    pub receiver_id: String,
+    pub message: String,
+    pub source: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReadEvent {
@@ -114,6 +134,23 @@ pub struct UserGuestCreateEvent {
     pub user_id: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthLockoutEvent {
+    pub kind: String,
+    pub subject: String,
+    pub lockouts: u32,
+    pub locked_until: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPresenceEvent {
+    pub user_id: String,
+    pub device: String,
+    pub node_id: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(clippy::large_enum_variant)]
 pub enum BackendEvent {
@@ -133,10 +170,19 @@ pub enum BackendEvent {
     TopicSilent(TopicSilentEvent),
     TopicSilentMember(TopicSilentEvent),
     TopicChangeOwner(TopicChangeOwnerEvent),
+    TopicBan(TopicBanEvent),
+    TopicUnban(TopicBanEvent),
+    ContactRequest(ContactRequestEvent),
+    ContactAccept(ContactRequestEvent),
+    ContactDecline(ContactRequestEvent),
+    ContactCancel(ContactRequestEvent),
     Read(ReadEvent),
     Typing(TypingEvent),
     UploadFile(UploadFileEvent),
     UserGuestCreate(UserGuestCreateEvent),
+    UserOnline(UserPresenceEvent),
+    UserOffline(UserPresenceEvent),
+    AuthLockout(AuthLockoutEvent),
 }
 
 impl BackendEvent {
@@ -158,10 +204,19 @@ impl BackendEvent {
             BackendEvent::TopicSilent(_) => "topic.silent",
             BackendEvent::TopicSilentMember(_) => "topic.silent.member",
             BackendEvent::TopicChangeOwner(_) => "topic.changeowner",
+            BackendEvent::TopicBan(_) => "topic.ban",
+            BackendEvent::TopicUnban(_) => "topic.unban",
+            BackendEvent::ContactRequest(_) => "contact.request",
+            BackendEvent::ContactAccept(_) => "contact.accept",
+            BackendEvent::ContactDecline(_) => "contact.decline",
+            BackendEvent::ContactCancel(_) => "contact.cancel",
             BackendEvent::Read(_) => "read",
             BackendEvent::Typing(_) => "typing",
             BackendEvent::UploadFile(_) => "upload.file",
             BackendEvent::UserGuestCreate(_) => "user.guest.create",
+            BackendEvent::UserOnline(_) => "user.online",
+            BackendEvent::UserOffline(_) => "user.offline",
+            BackendEvent::AuthLockout(_) => "auth.lockout",
         }
     }
 
@@ -182,10 +237,17 @@ impl BackendEvent {
             | BackendEvent::TopicKnockReject(v) => Some(&v.topic_id),
             BackendEvent::TopicSilent(v) | BackendEvent::TopicSilentMember(v) => Some(&v.topic_id),
             BackendEvent::TopicChangeOwner(v) => Some(&v.topic_id),
+            BackendEvent::TopicBan(v) | BackendEvent::TopicUnban(v) => Some(&v.topic_id),
             BackendEvent::Read(v) => Some(&v.topic_id),
             BackendEvent::Typing(v) => Some(&v.topic_id),
             BackendEvent::UploadFile(v) => Some(&v.topic_id),
+            BackendEvent::ContactRequest(_)
+            | BackendEvent::ContactAccept(_)
+            | BackendEvent::ContactDecline(_)
+            | BackendEvent::ContactCancel(_) => None,
             BackendEvent::UserGuestCreate(_) => None,
+            BackendEvent::UserOnline(_) | BackendEvent::UserOffline(_) => None,
+            BackendEvent::AuthLockout(_) => None,
         }
     }
 
@@ -244,6 +306,15 @@ impl BackendEvent {
             BackendEvent::TopicChangeOwner(v) => {
                 serde_json::to_value(v).unwrap_or_else(|_| serde_json::json!({}))
             }
+            BackendEvent::TopicBan(v) | BackendEvent::TopicUnban(v) => {
+                serde_json::to_value(v).unwrap_or_else(|_| serde_json::json!({}))
+            }
+            BackendEvent::ContactRequest(v)
+            | BackendEvent::ContactAccept(v)
+            | BackendEvent::ContactDecline(v)
+            | BackendEvent::ContactCancel(v) => {
+                serde_json::to_value(v).unwrap_or_else(|_| serde_json::json!({}))
+            }
             BackendEvent::Read(v) => {
                 serde_json::to_value(v).unwrap_or_else(|_| serde_json::json!({}))
             }
@@ -256,6 +327,12 @@ impl BackendEvent {
             BackendEvent::UserGuestCreate(v) => {
                 serde_json::to_value(v).unwrap_or_else(|_| serde_json::json!({}))
             }
+            BackendEvent::UserOnline(v) | BackendEvent::UserOffline(v) => {
+                serde_json::to_value(v).unwrap_or_else(|_| serde_json::json!({}))
+            }
+            BackendEvent::AuthLockout(v) => {
+                serde_json::to_value(v).unwrap_or_else(|_| serde_json::json!({}))
+            }
         }
     }
 }
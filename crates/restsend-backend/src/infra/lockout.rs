@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sea_orm::{
+    sea_query::OnConflict, ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection,
+    EntityTrait, IntoActiveModel, QueryFilter, QuerySelect, TransactionTrait,
+};
+use tokio::sync::RwLock;
+
+use crate::entity::login_lockout;
+
+const ACCOUNT_PREFIX: &str = "account:";
+const IP_PREFIX: &str = "ip:";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LockoutEntry {
+    pub key: String,
+    pub failures: u32,
+    pub lockouts: u32,
+    pub locked_until: i64,
+    pub updated_at: i64,
+}
+
+impl LockoutEntry {
+    // (kind, subject) from `account:<user_id>` / `ip:<addr>`
+    pub fn kind_and_subject(&self) -> (&str, &str) {
+        if let Some(subject) = self.key.strip_prefix(ACCOUNT_PREFIX) {
+            return ("account", subject);
+        }
+        if let Some(subject) = self.key.strip_prefix(IP_PREFIX) {
+            return ("ip", subject);
+        }
+        ("", &self.key)
+    }
+}
+
+impl From<LockoutEntry> for crate::LoginLockout {
+    fn from(entry: LockoutEntry) -> Self {
+        let (kind, subject) = entry.kind_and_subject();
+        crate::LoginLockout {
+            kind: kind.to_string(),
+            subject: subject.to_string(),
+            failures: entry.failures,
+            lockouts: entry.lockouts,
+            locked_until: chrono::DateTime::from_timestamp(entry.locked_until, 0)
+                .map(|v| v.to_rfc3339())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+pub type LockoutUpdate<'a> = dyn Fn(&mut LockoutEntry) + Send + Sync + 'a;
+
+#[async_trait]
+pub trait LockoutStore: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<LockoutEntry>, sea_orm::DbErr>;
+    // applies `apply` to the entry (a blank one when missing) and stores it, atomically
+    // with respect to other updates of the same key
+    async fn update(
+        &self,
+        key: &str,
+        apply: &LockoutUpdate<'_>,
+    ) -> Result<LockoutEntry, sea_orm::DbErr>;
+    async fn remove(&self, key: &str) -> Result<bool, sea_orm::DbErr>;
+    async fn list_locked(&self, now: i64) -> Result<Vec<LockoutEntry>, sea_orm::DbErr>;
+    // drops entries that are neither locked nor touched since `before`
+    async fn cleanup(&self, before: i64) -> Result<u64, sea_orm::DbErr>;
+}
+
+#[derive(Clone, Default)]
+pub struct MemoryLockoutStore {
+    entries: Arc<RwLock<HashMap<String, LockoutEntry>>>,
+}
+
+#[async_trait]
+impl LockoutStore for MemoryLockoutStore {
+    async fn get(&self, key: &str) -> Result<Option<LockoutEntry>, sea_orm::DbErr> {
+        Ok(self.entries.read().await.get(key).cloned())
+    }
+
+    async fn update(
+        &self,
+        key: &str,
+        apply: &LockoutUpdate<'_>,
+    ) -> Result<LockoutEntry, sea_orm::DbErr> {
+        let mut guard = self.entries.write().await;
+        let entry = guard
+            .entry(key.to_string())
+            .or_insert_with(|| LockoutEntry {
+                key: key.to_string(),
+                ..LockoutEntry::default()
+            });
+        apply(entry);
+        Ok(entry.clone())
+    }
+
+    async fn remove(&self, key: &str) -> Result<bool, sea_orm::DbErr> {
+        Ok(self.entries.write().await.remove(key).is_some())
+    }
+
+    async fn list_locked(&self, now: i64) -> Result<Vec<LockoutEntry>, sea_orm::DbErr> {
+        let guard = self.entries.read().await;
+        Ok(guard
+            .values()
+            .filter(|entry| entry.locked_until > now)
+            .cloned()
+            .collect())
+    }
+
+    async fn cleanup(&self, before: i64) -> Result<u64, sea_orm::DbErr> {
+        let mut guard = self.entries.write().await;
+        let count = guard.len();
+        guard.retain(|_, entry| entry.locked_until > before || entry.updated_at >= before);
+        Ok((count - guard.len()) as u64)
+    }
+}
+
+// shared by every node when presence runs on the database
+#[derive(Clone)]
+pub struct DbLockoutStore {
+    db: DatabaseConnection,
+}
+
+impl DbLockoutStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+impl From<login_lockout::Model> for LockoutEntry {
+    fn from(model: login_lockout::Model) -> Self {
+        Self {
+            key: model.key,
+            failures: model.failures.max(0) as u32,
+            lockouts: model.lockouts.max(0) as u32,
+            locked_until: model.locked_until,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+#[async_trait]
+impl LockoutStore for DbLockoutStore {
+    async fn get(&self, key: &str) -> Result<Option<LockoutEntry>, sea_orm::DbErr> {
+        Ok(login_lockout::Entity::find_by_id(key.to_string())
+            .one(&self.db)
+            .await?
+            .map(LockoutEntry::from))
+    }
+
+    async fn update(
+        &self,
+        key: &str,
+        apply: &LockoutUpdate<'_>,
+    ) -> Result<LockoutEntry, sea_orm::DbErr> {
+        let txn = self.db.begin().await?;
+        // the insert makes sure there is a row to lock and takes the write lock on sqlite,
+        // which ignores FOR UPDATE
+        login_lockout::Entity::insert(login_lockout::ActiveModel {
+            key: Set(key.to_string()),
+            failures: Set(0),
+            lockouts: Set(0),
+            locked_until: Set(0),
+            updated_at: Set(0),
+        })
+        .on_conflict(
+            OnConflict::column(login_lockout::Column::Key)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+        let row = login_lockout::Entity::find_by_id(key.to_string())
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| sea_orm::DbErr::RecordNotFound(key.to_string()))?;
+        let mut entry = LockoutEntry::from(row.clone());
+        apply(&mut entry);
+        let mut active = row.into_active_model();
+        active.failures = Set(entry.failures as i32);
+        active.lockouts = Set(entry.lockouts as i32);
+        active.locked_until = Set(entry.locked_until);
+        active.updated_at = Set(entry.updated_at);
+        let _ = active.update(&txn).await?;
+        txn.commit().await?;
+        Ok(entry)
+    }
+
+    async fn remove(&self, key: &str) -> Result<bool, sea_orm::DbErr> {
+        let result = login_lockout::Entity::delete_by_id(key.to_string())
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn list_locked(&self, now: i64) -> Result<Vec<LockoutEntry>, sea_orm::DbErr> {
+        let rows = login_lockout::Entity::find()
+            .filter(login_lockout::Column::LockedUntil.gt(now))
+            .all(&self.db)
+            .await?;
+        Ok(rows.into_iter().map(LockoutEntry::from).collect())
+    }
+
+    async fn cleanup(&self, before: i64) -> Result<u64, sea_orm::DbErr> {
+        let result = login_lockout::Entity::delete_many()
+            .filter(login_lockout::Column::LockedUntil.lte(before))
+            .filter(login_lockout::Column::UpdatedAt.lt(before))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+// max failures of 0 disables the counter for that kind
+#[derive(Clone, Copy, Debug)]
+pub struct LockoutPolicy {
+    pub account_max_failures: u32,
+    pub ip_max_failures: u32,
+    pub lockout_secs: u64,
+    pub max_lockout_secs: u64,
+    // failures and the lockout backoff reset after this long without failures
+    pub window_secs: u64,
+}
+
+#[derive(Clone)]
+pub struct LoginGuard {
+    store: Arc<dyn LockoutStore>,
+    policy: LockoutPolicy,
+}
+
+impl LoginGuard {
+    pub fn new(store: Arc<dyn LockoutStore>, policy: LockoutPolicy) -> Self {
+        Self { store, policy }
+    }
+
+    pub fn account_key(user_id: &str) -> String {
+        format!("{ACCOUNT_PREFIX}{}", user_id.trim())
+    }
+
+    pub fn ip_key(ip: &str) -> String {
+        format!("{IP_PREFIX}{}", ip.trim())
+    }
+
+    fn now_unix() -> i64 {
+        chrono::Utc::now().timestamp()
+    }
+
+    fn max_failures(&self, key: &str) -> u32 {
+        if key.starts_with(IP_PREFIX) {
+            self.policy.ip_max_failures
+        } else {
+            self.policy.account_max_failures
+        }
+    }
+
+    // seconds until the longest active lock on any of the keys ends, 0 when free
+    pub async fn retry_after(&self, keys: &[String]) -> Result<u64, sea_orm::DbErr> {
+        let now = Self::now_unix();
+        let mut until = now;
+        for key in keys {
+            if self.max_failures(key) == 0 {
+                continue;
+            }
+            if let Some(entry) = self.store.get(key).await? {
+                until = until.max(entry.locked_until);
+            }
+        }
+        Ok((until - now) as u64)
+    }
+
+    // returns the entry when this failure starts a new lockout
+    pub async fn record_failure(&self, key: &str) -> Result<Option<LockoutEntry>, sea_orm::DbErr> {
+        let max_failures = self.max_failures(key);
+        if max_failures == 0 {
+            return Ok(None);
+        }
+        let now = Self::now_unix();
+        let window_secs = self.policy.window_secs as i64;
+        let entry = self
+            .store
+            .update(key, &|entry: &mut LockoutEntry| {
+                if entry.locked_until <= now && now - entry.updated_at > window_secs {
+                    entry.failures = 0;
+                    entry.lockouts = 0;
+                }
+                entry.failures += 1;
+                entry.updated_at = now;
+                if entry.failures >= max_failures {
+                    entry.lockouts += 1;
+                    entry.failures = 0;
+                    entry.locked_until = now + self.lockout_secs(entry.lockouts) as i64;
+                }
+            })
+            .await?;
+        // only the failure that hit the threshold leaves the counter at zero
+        Ok((entry.failures == 0).then_some(entry))
+    }
+
+    pub async fn clear(&self, key: &str) -> Result<bool, sea_orm::DbErr> {
+        self.store.remove(key).await
+    }
+
+    pub async fn list_locked(&self) -> Result<Vec<LockoutEntry>, sea_orm::DbErr> {
+        self.store.list_locked(Self::now_unix()).await
+    }
+
+    pub async fn cleanup(&self) -> Result<u64, sea_orm::DbErr> {
+        let before = Self::now_unix() - self.policy.window_secs as i64;
+        self.store.cleanup(before).await
+    }
+
+    // doubles with every lockout in the window, capped at max_lockout_secs
+    fn lockout_secs(&self, lockouts: u32) -> u64 {
+        let factor = 1u64 << lockouts.saturating_sub(1).min(32);
+        self.policy
+            .lockout_secs
+            .saturating_mul(factor)
+            .min(self.policy.max_lockout_secs.max(self.policy.lockout_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard() -> LoginGuard {
+        LoginGuard::new(
+            Arc::new(MemoryLockoutStore::default()),
+            LockoutPolicy {
+                account_max_failures: 3,
+                ip_max_failures: 0,
+                lockout_secs: 60,
+                max_lockout_secs: 200,
+                window_secs: 900,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_lockout_backoff() {
+        let guard = guard();
+        let key = LoginGuard::account_key("alice");
+        assert!(guard.record_failure(&key).await.unwrap().is_none());
+        assert!(guard.record_failure(&key).await.unwrap().is_none());
+        let locked = guard.record_failure(&key).await.unwrap().unwrap();
+        assert_eq!(locked.lockouts, 1);
+        let retry = guard.retry_after(std::slice::from_ref(&key)).await.unwrap();
+        assert!((59..=60).contains(&retry));
+
+        for _ in 0..2 {
+            guard.record_failure(&key).await.unwrap();
+        }
+        let locked = guard.record_failure(&key).await.unwrap().unwrap();
+        assert_eq!(locked.lockouts, 2);
+        assert!(guard.retry_after(std::slice::from_ref(&key)).await.unwrap() > 100);
+        assert_eq!(guard.lockout_secs(3), 200);
+        assert_eq!(guard.list_locked().await.unwrap().len(), 1);
+
+        let ip = LoginGuard::ip_key("10.0.0.1");
+        for _ in 0..5 {
+            assert!(guard.record_failure(&ip).await.unwrap().is_none());
+        }
+        assert_eq!(guard.retry_after(&[ip]).await.unwrap(), 0);
+
+        assert!(guard.clear(&key).await.unwrap());
+        assert_eq!(guard.retry_after(&[key]).await.unwrap(), 0);
+    }
+
+    async fn concurrent_failures(store: Arc<dyn LockoutStore>) {
+        let guard = LoginGuard::new(
+            store,
+            LockoutPolicy {
+                account_max_failures: 5,
+                ip_max_failures: 0,
+                lockout_secs: 60,
+                max_lockout_secs: 600,
+                window_secs: 900,
+            },
+        );
+        let key = LoginGuard::account_key("bob");
+        let tasks = (0..20)
+            .map(|_| {
+                let guard = guard.clone();
+                let key = key.clone();
+                tokio::spawn(async move { guard.record_failure(&key).await.unwrap() })
+            })
+            .collect::<Vec<_>>();
+        let mut lockouts = 0;
+        for task in tasks {
+            if task.await.unwrap().is_some() {
+                lockouts += 1;
+            }
+        }
+        assert_eq!(lockouts, 4);
+        let entry = guard.store.get(&key).await.unwrap().unwrap();
+        assert_eq!((entry.failures, entry.lockouts), (0, 4));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_failures_are_counted() {
+        concurrent_failures(Arc::new(MemoryLockoutStore::default())).await;
+
+        let path = std::env::temp_dir().join(format!(
+            "restsend-lockout-{}.sqlite3",
+            uuid::Uuid::new_v4().simple()
+        ));
+        let db = crate::infra::db::connect_db(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        crate::infra::db::run_migrations(&db).await.unwrap();
+        concurrent_failures(Arc::new(DbLockoutStore::new(db.clone()))).await;
+        db.close().await.unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,5 +1,6 @@
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 #[derive(Clone, Default)]
 pub struct RuntimeMetrics {
@@ -7,6 +8,8 @@ pub struct RuntimeMetrics {
     outbound_ws_messages: Arc<AtomicU64>,
     webhook_deliveries: Arc<AtomicU64>,
     webhook_failures: Arc<AtomicU64>,
+    // keyed by webhook subscription id
+    webhook_subscriptions: Arc<Mutex<HashMap<String, WebhookSubscriptionStats>>>,
 }
 
 impl RuntimeMetrics {
@@ -26,6 +29,32 @@ impl RuntimeMetrics {
         self.webhook_failures.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub fn record_subscription_delivery(&self, subscription_id: &str, error: Option<&str>) {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut stats = self.webhook_subscriptions.lock().unwrap();
+        let entry = stats.entry(subscription_id.to_string()).or_default();
+        match error {
+            None => {
+                entry.deliveries += 1;
+                entry.last_delivered_at = now;
+            }
+            Some(error) => {
+                entry.failures += 1;
+                entry.last_failed_at = now;
+                entry.last_error = error.to_string();
+            }
+        }
+    }
+
+    pub fn subscription_stats(&self, subscription_id: &str) -> WebhookSubscriptionStats {
+        self.webhook_subscriptions
+            .lock()
+            .unwrap()
+            .get(subscription_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     pub fn snapshot(&self) -> RuntimeMetricsSnapshot {
         RuntimeMetricsSnapshot {
             inbound_ws_messages: self.inbound_ws_messages.load(Ordering::Relaxed),
@@ -44,3 +73,14 @@ pub struct RuntimeMetricsSnapshot {
     pub webhook_deliveries: u64,
     pub webhook_failures: u64,
 }
+
+// counters of this node since start, failed attempts are counted individually
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookSubscriptionStats {
+    pub deliveries: u64,
+    pub failures: u64,
+    pub last_delivered_at: String,
+    pub last_failed_at: String,
+    pub last_error: String,
+}
@@ -1,7 +1,12 @@
 pub mod db;
 pub mod event;
+pub mod lockout;
 pub mod metrics;
+pub mod oidc;
 pub mod presence;
+pub mod presend_hook;
+pub mod rate_limit;
 pub mod task_pool;
 pub mod webhook;
+pub mod ws_ticket;
 pub mod websocket;
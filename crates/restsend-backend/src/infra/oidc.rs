@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ring::rand::SecureRandom;
+use ring::signature;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+
+use crate::app::OidcProviderConfig;
+
+const METADATA_TTL: Duration = Duration::from_secs(3600);
+// a kid missing from the cached jwks triggers a refetch at most this often
+const JWKS_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
+const CLOCK_SKEW_SECS: i64 = 60;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct OidcDiscovery {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct Jwks {
+    #[serde(default)]
+    keys: Vec<Jwk>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct Jwk {
+    #[serde(default)]
+    kty: String,
+    #[serde(default)]
+    kid: String,
+    #[serde(default)]
+    crv: String,
+    #[serde(default)]
+    n: String,
+    #[serde(default)]
+    e: String,
+    #[serde(default)]
+    x: String,
+    #[serde(default)]
+    y: String,
+}
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+    #[serde(default)]
+    kid: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    #[serde(default)]
+    id_token: String,
+}
+
+// what the start step hands to the callback, kept client side in a cookie
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OidcPending {
+    pub provider: String,
+    pub state: String,
+    pub nonce: String,
+    pub verifier: String,
+    #[serde(default)]
+    pub redirect: String,
+    pub expires_at: i64,
+}
+
+struct ProviderMetadata {
+    discovery: OidcDiscovery,
+    jwks: Jwks,
+    fetched_at: Instant,
+    jwks_fetched_at: Instant,
+}
+
+pub struct OidcClient {
+    providers: HashMap<String, OidcProviderConfig>,
+    http: reqwest::Client,
+    metadata: RwLock<HashMap<String, ProviderMetadata>>,
+}
+
+impl OidcClient {
+    pub fn new(providers: &[OidcProviderConfig]) -> Self {
+        Self {
+            providers: providers
+                .iter()
+                .map(|p| (p.name.clone(), p.clone()))
+                .collect(),
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new()),
+            metadata: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn provider(&self, name: &str) -> Option<&OidcProviderConfig> {
+        self.providers.get(name)
+    }
+
+    // builds the authorization url and the pending state the callback must present
+    pub async fn start(
+        &self,
+        provider: &OidcProviderConfig,
+        redirect: &str,
+        ttl_secs: i64,
+    ) -> Result<(String, OidcPending), String> {
+        let discovery = self.discovery(provider).await?;
+        let pending = OidcPending {
+            provider: provider.name.clone(),
+            state: random_token()?,
+            nonce: random_token()?,
+            verifier: random_token()?,
+            redirect: redirect.to_string(),
+            expires_at: chrono::Utc::now().timestamp() + ttl_secs,
+        };
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(pending.verifier.as_bytes()));
+        let scope = provider.scopes.join(" ");
+        let url = reqwest::Url::parse_with_params(
+            &discovery.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", provider.client_id.as_str()),
+                ("redirect_uri", provider.redirect_url.as_str()),
+                ("scope", scope.as_str()),
+                ("state", pending.state.as_str()),
+                ("nonce", pending.nonce.as_str()),
+                ("code_challenge", challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| format!("invalid authorization endpoint: {e}"))?;
+        Ok((url.to_string(), pending))
+    }
+
+    // exchanges the code and returns the verified id_token claims
+    pub async fn exchange(
+        &self,
+        provider: &OidcProviderConfig,
+        pending: &OidcPending,
+        code: &str,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+        let discovery = self.discovery(provider).await?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", provider.redirect_url.as_str()),
+            ("client_id", provider.client_id.as_str()),
+            ("code_verifier", pending.verifier.as_str()),
+        ];
+        if !provider.client_secret.is_empty() {
+            form.push(("client_secret", provider.client_secret.as_str()));
+        }
+        let resp = self
+            .http
+            .post(&discovery.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| format!("token request failed: {e}"))?;
+        if !resp.status().is_success() {
+            return Err(format!("token endpoint returned {}", resp.status()));
+        }
+        let token: TokenResponse = resp
+            .json()
+            .await
+            .map_err(|e| format!("invalid token response: {e}"))?;
+        if token.id_token.is_empty() {
+            return Err("token response has no id_token".to_string());
+        }
+        let claims = self.verify_id_token(provider, &token.id_token).await?;
+        self.check_claims(provider, &discovery, &claims, &pending.nonce)?;
+        Ok(claims)
+    }
+
+    async fn discovery(&self, provider: &OidcProviderConfig) -> Result<OidcDiscovery, String> {
+        if let Some(meta) = self.metadata.read().await.get(&provider.name) {
+            if meta.fetched_at.elapsed() < METADATA_TTL {
+                return Ok(meta.discovery.clone());
+            }
+        }
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            provider.issuer.trim_end_matches('/')
+        );
+        let discovery: OidcDiscovery = self.get_json(&url).await?;
+        let jwks: Jwks = self.get_json(&discovery.jwks_uri).await?;
+        let now = Instant::now();
+        self.metadata.write().await.insert(
+            provider.name.clone(),
+            ProviderMetadata {
+                discovery: discovery.clone(),
+                jwks,
+                fetched_at: now,
+                jwks_fetched_at: now,
+            },
+        );
+        Ok(discovery)
+    }
+
+    async fn find_jwk(&self, provider: &OidcProviderConfig, kid: &str) -> Result<Jwk, String> {
+        let pick = |jwks: &Jwks| {
+            jwks.keys
+                .iter()
+                .find(|k| kid.is_empty() || k.kid == kid)
+                .cloned()
+        };
+        let jwks_uri = {
+            let guard = self.metadata.read().await;
+            let meta = guard
+                .get(&provider.name)
+                .ok_or_else(|| "provider metadata not loaded".to_string())?;
+            if let Some(jwk) = pick(&meta.jwks) {
+                return Ok(jwk);
+            }
+            if meta.jwks_fetched_at.elapsed() < JWKS_REFETCH_INTERVAL {
+                return Err(format!("unknown signing key {kid}"));
+            }
+            meta.discovery.jwks_uri.clone()
+        };
+        // the idp may have rotated its keys
+        let jwks: Jwks = self.get_json(&jwks_uri).await?;
+        let found = pick(&jwks);
+        if let Some(meta) = self.metadata.write().await.get_mut(&provider.name) {
+            meta.jwks = jwks;
+            meta.jwks_fetched_at = Instant::now();
+        }
+        found.ok_or_else(|| format!("unknown signing key {kid}"))
+    }
+
+    async fn verify_id_token(
+        &self,
+        provider: &OidcProviderConfig,
+        id_token: &str,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+        let mut parts = id_token.split('.');
+        let (Some(header), Some(payload), Some(sig), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err("malformed id_token".to_string());
+        };
+        let header: JwtHeader = decode_segment(header)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(sig)
+            .map_err(|_| "malformed id_token signature".to_string())?;
+        let jwk = self.find_jwk(provider, &header.kid).await?;
+        let message = &id_token[..header_and_payload_len(id_token)];
+        let verified = match (header.alg.as_str(), jwk.kty.as_str()) {
+            ("RS256", "RSA") => {
+                let n = decode_b64(&jwk.n)?;
+                let e = decode_b64(&jwk.e)?;
+                signature::RsaPublicKeyComponents { n: &n, e: &e }
+                    .verify(
+                        &signature::RSA_PKCS1_2048_8192_SHA256,
+                        message.as_bytes(),
+                        &signature,
+                    )
+                    .is_ok()
+            }
+            ("ES256", "EC") if jwk.crv == "P-256" => {
+                let mut point = vec![0x04];
+                point.extend(decode_b64(&jwk.x)?);
+                point.extend(decode_b64(&jwk.y)?);
+                signature::UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, point)
+                    .verify(message.as_bytes(), &signature)
+                    .is_ok()
+            }
+            ("EdDSA", "OKP") if jwk.crv == "Ed25519" => {
+                signature::UnparsedPublicKey::new(&signature::ED25519, decode_b64(&jwk.x)?)
+                    .verify(message.as_bytes(), &signature)
+                    .is_ok()
+            }
+            (alg, _) => return Err(format!("unsupported id_token alg {alg}")),
+        };
+        if !verified {
+            return Err("id_token signature mismatch".to_string());
+        }
+        decode_segment(payload)
+    }
+
+    fn check_claims(
+        &self,
+        provider: &OidcProviderConfig,
+        discovery: &OidcDiscovery,
+        claims: &serde_json::Map<String, serde_json::Value>,
+        nonce: &str,
+    ) -> Result<(), String> {
+        let claim_str = |name: &str| claims.get(name).and_then(|v| v.as_str()).unwrap_or("");
+        if claim_str("iss") != discovery.issuer {
+            return Err("id_token issuer mismatch".to_string());
+        }
+        let audience_ok = match claims.get("aud") {
+            Some(serde_json::Value::String(aud)) => *aud == provider.client_id,
+            Some(serde_json::Value::Array(items)) => items
+                .iter()
+                .any(|v| v.as_str() == Some(provider.client_id.as_str())),
+            _ => false,
+        };
+        if !audience_ok {
+            return Err("id_token audience mismatch".to_string());
+        }
+        let exp = claims.get("exp").and_then(|v| v.as_i64()).unwrap_or(0);
+        if exp + CLOCK_SKEW_SECS < chrono::Utc::now().timestamp() {
+            return Err("id_token expired".to_string());
+        }
+        if claim_str("nonce") != nonce {
+            return Err("id_token nonce mismatch".to_string());
+        }
+        Ok(())
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, String> {
+        let resp = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| format!("fetch {url} failed: {e}"))?;
+        if !resp.status().is_success() {
+            return Err(format!("fetch {url} returned {}", resp.status()));
+        }
+        resp.json()
+            .await
+            .map_err(|e| format!("decode {url} failed: {e}"))
+    }
+}
+
+impl OidcPending {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(value: &str) -> Option<Self> {
+        decode_segment(value).ok()
+    }
+}
+
+fn random_token() -> Result<String, String> {
+    let mut buf = [0u8; 32];
+    ring::rand::SystemRandom::new()
+        .fill(&mut buf)
+        .map_err(|_| "system random unavailable".to_string())?;
+    Ok(URL_SAFE_NO_PAD.encode(buf))
+}
+
+fn decode_b64(value: &str) -> Result<Vec<u8>, String> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| "invalid base64url value".to_string())
+}
+
+fn decode_segment<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, String> {
+    let raw = decode_b64(value)?;
+    serde_json::from_slice(&raw).map_err(|e| format!("invalid json segment: {e}"))
+}
+
+fn header_and_payload_len(token: &str) -> usize {
+    token.rfind('.').unwrap_or(token.len())
+}
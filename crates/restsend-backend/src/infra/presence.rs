@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -22,7 +22,8 @@ pub trait PresenceStore: Send + Sync {
     async fn remove_session(&self, user_id: &str, device: &str) -> Result<(), sea_orm::DbErr>;
     async fn list_devices(&self, user_id: &str) -> Result<Vec<String>, sea_orm::DbErr>;
     async fn is_online(&self, user_id: &str) -> Result<bool, sea_orm::DbErr>;
-    async fn cleanup_expired(&self) -> Result<u64, sea_orm::DbErr>;
+    // returns the (user_id, device) sessions removed by this call
+    async fn cleanup_expired(&self) -> Result<Vec<(String, String)>, sea_orm::DbErr>;
 }
 
 #[derive(Clone)]
@@ -73,11 +74,13 @@ impl PresenceStore for MemoryPresenceStore {
     }
 
     async fn list_devices(&self, user_id: &str) -> Result<Vec<String>, sea_orm::DbErr> {
-        let mut guard = self.sessions.write().await;
+        let guard = self.sessions.read().await;
         let cutoff = self.cutoff();
-        let devices = guard.get_mut(user_id).map(|m| {
-            m.retain(|_, updated| !Self::expired(cutoff, *updated));
-            m.keys().cloned().collect::<Vec<_>>()
+        let devices = guard.get(user_id).map(|m| {
+            m.iter()
+                .filter(|(_, updated)| !Self::expired(cutoff, **updated))
+                .map(|(device, _)| device.clone())
+                .collect::<Vec<_>>()
         });
         Ok(devices.unwrap_or_default())
     }
@@ -86,14 +89,18 @@ impl PresenceStore for MemoryPresenceStore {
         Ok(!self.list_devices(user_id).await?.is_empty())
     }
 
-    async fn cleanup_expired(&self) -> Result<u64, sea_orm::DbErr> {
+    async fn cleanup_expired(&self) -> Result<Vec<(String, String)>, sea_orm::DbErr> {
         let mut guard = self.sessions.write().await;
         let cutoff = self.cutoff();
-        let mut removed = 0u64;
-        guard.retain(|_, devices| {
-            let before = devices.len();
-            devices.retain(|_, updated| !Self::expired(cutoff, *updated));
-            removed += before.saturating_sub(devices.len()) as u64;
+        let mut removed = Vec::new();
+        guard.retain(|user_id, devices| {
+            devices.retain(|device, updated| {
+                if Self::expired(cutoff, *updated) {
+                    removed.push((user_id.clone(), device.clone()));
+                    return false;
+                }
+                true
+            });
             !devices.is_empty()
         });
         Ok(removed)
@@ -180,23 +187,127 @@ impl PresenceStore for DbPresenceStore {
         Ok(count > 0)
     }
 
-    async fn cleanup_expired(&self) -> Result<u64, sea_orm::DbErr> {
-        let result = presence_session::Entity::delete_many()
+    async fn cleanup_expired(&self) -> Result<Vec<(String, String)>, sea_orm::DbErr> {
+        let rows = presence_session::Entity::find()
             .filter(presence_session::Column::UpdatedAtUnix.lt(self.cutoff()))
-            .exec(&self.db)
+            .all(&self.db)
             .await?;
-        Ok(result.rows_affected)
+        let mut removed = Vec::new();
+        for row in rows {
+            // every node runs the cleanup, only the one that deletes the row reports it
+            let result =
+                presence_session::Entity::delete_by_id((row.user_id.clone(), row.device.clone()))
+                    .filter(presence_session::Column::UpdatedAtUnix.lt(self.cutoff()))
+                    .exec(&self.db)
+                    .await?;
+            if result.rows_affected > 0 {
+                removed.push((row.user_id, row.device));
+            }
+        }
+        Ok(removed)
     }
 }
 
+// subscriptions are kept per ws session (user_id, device) on the local node
+#[derive(Default)]
+struct PresenceSubscriptions {
+    by_target: HashMap<String, HashSet<(String, String)>>,
+    by_session: HashMap<(String, String), HashSet<String>>,
+}
+
+struct PendingChange {
+    was_online: bool,
+    device: String,
+}
+
+#[derive(Default)]
+struct PresenceChanges {
+    pending: HashMap<String, PendingChange>,
+}
+
 #[derive(Clone)]
 pub struct PresenceHub {
     store: Arc<dyn PresenceStore>,
+    subscriptions: Arc<RwLock<PresenceSubscriptions>>,
+    changes: Arc<RwLock<PresenceChanges>>,
 }
 
 impl PresenceHub {
     pub fn new(store: Arc<dyn PresenceStore>) -> Self {
-        Self { store }
+        Self {
+            store,
+            subscriptions: Arc::new(RwLock::new(PresenceSubscriptions::default())),
+            changes: Arc::new(RwLock::new(PresenceChanges::default())),
+        }
+    }
+
+    pub async fn subscribe(&self, user_id: &str, device: &str, targets: Vec<String>) {
+        let session = (user_id.to_string(), device.to_string());
+        let mut guard = self.subscriptions.write().await;
+        let targets: HashSet<String> = targets.into_iter().collect();
+        if let Some(previous) = guard.by_session.remove(&session) {
+            for target in previous.difference(&targets) {
+                if let Some(sessions) = guard.by_target.get_mut(target) {
+                    sessions.remove(&session);
+                    if sessions.is_empty() {
+                        guard.by_target.remove(target);
+                    }
+                }
+            }
+        }
+        if targets.is_empty() {
+            return;
+        }
+        for target in targets.iter() {
+            guard
+                .by_target
+                .entry(target.clone())
+                .or_default()
+                .insert(session.clone());
+        }
+        guard.by_session.insert(session, targets);
+    }
+
+    pub async fn unsubscribe(&self, user_id: &str, device: &str) {
+        self.subscribe(user_id, device, Vec::new()).await;
+    }
+
+    pub async fn subscribers(&self, target: &str) -> Vec<(String, String)> {
+        let guard = self.subscriptions.read().await;
+        guard
+            .by_target
+            .get(target)
+            .map(|sessions| sessions.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    // returns false when a change for the user is already waiting to settle,
+    // the first change keeps the state from before the window
+    pub async fn mark_changed(&self, user_id: &str, device: &str, was_online: bool) -> bool {
+        let mut guard = self.changes.write().await;
+        match guard.pending.get_mut(user_id) {
+            Some(pending) => {
+                pending.device = device.to_string();
+                false
+            }
+            None => {
+                guard.pending.insert(
+                    user_id.to_string(),
+                    PendingChange {
+                        was_online,
+                        device: device.to_string(),
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    // returns the online state and last device when it differs from the state before the window
+    pub async fn settle_change(&self, user_id: &str) -> Option<(bool, String)> {
+        let online = self.snapshot(user_id).await.online;
+        let pending = self.changes.write().await.pending.remove(user_id)?;
+        (pending.was_online != online).then_some((online, pending.device))
     }
 
     pub async fn upsert_session(&self, user_id: &str, device: &str) {
@@ -225,25 +336,47 @@ impl PresenceHub {
         }
     }
 
-    pub fn start_cleanup_loop(&self, interval_secs: u64) {
-        let this = self.clone();
-        tokio::spawn(async move {
-            let mut ticker =
-                tokio::time::interval(std::time::Duration::from_secs(interval_secs.max(1)));
-            loop {
-                ticker.tick().await;
-                match this.store.cleanup_expired().await {
-                    Ok(removed) => {
-                        if removed > 0 {
-                            tracing::info!(
-                                removed = removed,
-                                "presence cleanup removed expired sessions"
-                            );
-                        }
-                    }
-                    Err(err) => tracing::warn!(error = %err, "presence cleanup failed"),
+    pub async fn cleanup_expired(&self) -> Vec<(String, String)> {
+        match self.store.cleanup_expired().await {
+            Ok(removed) => {
+                if !removed.is_empty() {
+                    tracing::info!(
+                        removed = removed.len(),
+                        "presence cleanup removed expired sessions"
+                    );
                 }
+                removed
             }
-        });
+            Err(err) => {
+                tracing::warn!(error = %err, "presence cleanup failed");
+                Vec::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cleanup_expired_reports_sessions_and_settles_offline() {
+        let hub = PresenceHub::new(Arc::new(MemoryPresenceStore::new(0)));
+        hub.upsert_session("alice", "web").await;
+        assert!(hub.mark_changed("alice", "web", true).await);
+        assert!(!hub.mark_changed("alice", "ios", false).await);
+
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        assert!(!hub.snapshot("alice").await.online);
+        assert_eq!(
+            hub.cleanup_expired().await,
+            vec![("alice".to_string(), "web".to_string())]
+        );
+        assert!(hub.cleanup_expired().await.is_empty());
+        assert_eq!(
+            hub.settle_change("alice").await,
+            Some((false, "ios".to_string()))
+        );
+        assert_eq!(hub.settle_change("alice").await, None);
     }
 }
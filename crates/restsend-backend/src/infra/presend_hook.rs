@@ -0,0 +1,141 @@
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use std::time::Duration;
+
+use crate::infra::webhook::{sign_payload, EVENT_ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::services::{PreSendDecision, PreSendHook};
+use crate::{PreSendHookRequest, PreSendHookResponse};
+
+const DEFAULT_REJECT_CODE: u16 = 403;
+const UNAVAILABLE_CODE: u16 = 503;
+
+// posts every message to the business backend before it is stored, signed like
+// webhook deliveries so the receiver can use the same verification
+pub struct HttpPreSendHook {
+    client: Client,
+    url: String,
+    secret: Option<String>,
+    // allow the message when the hook times out or answers garbage, off by default
+    fail_open: bool,
+}
+
+impl HttpPreSendHook {
+    pub fn new(url: String, timeout_ms: u64, fail_open: bool, secret: Option<String>) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(timeout_ms.max(1)))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        Self {
+            client,
+            url,
+            secret,
+            fail_open,
+        }
+    }
+
+    async fn call(&self, request: &PreSendHookRequest) -> Result<PreSendDecision, String> {
+        let body = serde_json::to_vec(request).map_err(|e| e.to_string())?;
+        let timestamp = chrono::Utc::now().timestamp();
+        let mut req = self
+            .client
+            .post(&self.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(EVENT_ID_HEADER, request.chat_id.as_str())
+            .header(TIMESTAMP_HEADER, timestamp.to_string());
+        if let Some(secret) = self.secret.as_deref() {
+            req = req.header(SIGNATURE_HEADER, sign_payload(secret, timestamp, &body));
+        }
+        let resp = req
+            .body(body)
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .map_err(|e| e.to_string())?;
+        let resp = resp
+            .json::<PreSendHookResponse>()
+            .await
+            .map_err(|e| e.to_string())?;
+        decide(resp)
+    }
+}
+
+fn decide(resp: PreSendHookResponse) -> Result<PreSendDecision, String> {
+    match resp.action.as_str() {
+        "" | "allow" => Ok(PreSendDecision::Allow),
+        "reject" => Ok(PreSendDecision::Reject {
+            // only client errors reach the sender, anything else would read as a server fault
+            code: Some(resp.code)
+                .filter(|code| (400..500).contains(code))
+                .unwrap_or(DEFAULT_REJECT_CODE),
+            reason: if resp.reason.is_empty() {
+                "message rejected".to_string()
+            } else {
+                resp.reason
+            },
+        }),
+        "rewrite" => resp
+            .content
+            .map(|content| PreSendDecision::Rewrite(Box::new(content)))
+            .ok_or_else(|| "rewrite without content".to_string()),
+        other => Err(format!("unknown action {other}")),
+    }
+}
+
+#[async_trait::async_trait]
+impl PreSendHook for HttpPreSendHook {
+    async fn check(&self, request: &PreSendHookRequest) -> PreSendDecision {
+        let st = std::time::Instant::now();
+        match self.call(request).await {
+            Ok(decision) => decision,
+            Err(err) => {
+                tracing::warn!(
+                    topic_id = %request.topic_id,
+                    chat_id = %request.chat_id,
+                    fail_open = self.fail_open,
+                    elapsed_ms = st.elapsed().as_millis() as u64,
+                    error = %err,
+                    "presend hook failed"
+                );
+                if self.fail_open {
+                    tracing::warn!(
+                        topic_id = %request.topic_id,
+                        chat_id = %request.chat_id,
+                        sender_id = %request.sender_id,
+                        "presend hook bypassed, message stored without review"
+                    );
+                    PreSendDecision::Allow
+                } else {
+                    PreSendDecision::Reject {
+                        code: UNAVAILABLE_CODE,
+                        reason: "message review unavailable".to_string(),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decide() {
+        let resp = |action: &str, code: u16| PreSendHookResponse {
+            action: action.to_string(),
+            code,
+            ..Default::default()
+        };
+        assert!(matches!(decide(resp("", 0)), Ok(PreSendDecision::Allow)));
+        assert!(matches!(
+            decide(resp("reject", 451)),
+            Ok(PreSendDecision::Reject { code: 451, .. })
+        ));
+        assert!(matches!(
+            decide(resp("reject", 200)),
+            Ok(PreSendDecision::Reject { code: 403, .. })
+        ));
+        assert!(decide(resp("rewrite", 0)).is_err());
+        assert!(decide(resp("maybe", 0)).is_err());
+    }
+}
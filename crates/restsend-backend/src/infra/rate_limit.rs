@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const PRUNE_THRESHOLD: usize = 10_000;
+
+// fixed window counter keyed by caller, limit 0 disables the check
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    hits: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn allow(&self, key: &str) -> bool {
+        if self.limit == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        let mut hits = self.hits.lock().unwrap();
+        if hits.len() > PRUNE_THRESHOLD {
+            let window = self.window;
+            hits.retain(|_, (started, _)| now.duration_since(*started) < window);
+        }
+        let entry = hits.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(entry.0) >= self.window {
+            *entry = (now, 0);
+        }
+        if entry.1 >= self.limit {
+            return false;
+        }
+        entry.1 += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_window() {
+        let limiter = RateLimiter::new(2, Duration::from_millis(50));
+        assert!(limiter.allow("alice"));
+        assert!(limiter.allow("alice"));
+        assert!(!limiter.allow("alice"));
+        assert!(limiter.allow("bob"));
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(limiter.allow("alice"));
+
+        let unlimited = RateLimiter::new(0, Duration::from_secs(60));
+        assert!((0..100).all(|_| unlimited.allow("alice")));
+    }
+}
@@ -1,11 +1,34 @@
+use reqwest::header::CONTENT_TYPE;
 use reqwest::Client;
+use ring::hmac;
 use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use std::time::Duration;
 
+pub const EVENT_ID_HEADER: &str = "x-restsend-event-id";
+pub const TIMESTAMP_HEADER: &str = "x-restsend-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-restsend-signature";
+
+// `v1=<hex hmac-sha256 of "{timestamp}.{body}">`, binding the timestamp so a
+// captured delivery can't be replayed once the receiver's tolerance has passed
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let mut ctx = hmac::Context::with_key(&key);
+    ctx.update(timestamp.to_string().as_bytes());
+    ctx.update(b".");
+    ctx.update(body);
+    let digest = ctx.sign();
+    let hex: String = digest.as_ref().iter().map(|b| format!("{b:02x}")).collect();
+    format!("v1={hex}")
+}
+
 #[derive(Clone)]
 pub struct WebhookSender {
     client: Client,
     retries: usize,
+    default_secret: Option<String>,
+    target_secrets: HashMap<String, String>,
 }
 
 impl WebhookSender {
@@ -17,27 +40,55 @@ impl WebhookSender {
         Self {
             client,
             retries: retries.max(1),
+            default_secret: None,
+            target_secrets: HashMap::new(),
         }
     }
 
+    pub fn with_secrets(
+        mut self,
+        default_secret: Option<String>,
+        target_secrets: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        self.default_secret = default_secret;
+        self.target_secrets = target_secrets.into_iter().collect();
+        self
+    }
+
+    // a per-target secret wins, then the owner's (topic or inbox), then the global one
+    pub fn secret_for(&self, url: &str, owner_secret: Option<&str>) -> Option<String> {
+        self.target_secrets
+            .get(url)
+            .map(String::as_str)
+            .or(owner_secret.filter(|v| !v.is_empty()))
+            .or(self.default_secret.as_deref())
+            .map(str::to_string)
+    }
+
     pub async fn send_json<T: Serialize + ?Sized>(
         &self,
         url: &str,
         payload: &T,
     ) -> Result<(), reqwest::Error> {
+        let event_id = uuid::Uuid::new_v4().simple().to_string();
+        let secret = self.secret_for(url, None);
+        self.send_signed(url, &event_id, payload, secret.as_deref())
+            .await
+    }
+
+    // every attempt carries the same event id, receivers dedupe on it
+    pub async fn send_signed<T: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        event_id: &str,
+        payload: &T,
+        secret: Option<&str>,
+    ) -> Result<(), reqwest::Error> {
+        let body = serde_json::to_vec(payload).unwrap_or_default();
         let mut attempt = 0usize;
         loop {
-            let resp = self.client.post(url).json(payload).send().await;
-            match resp {
-                Ok(resp) => match resp.error_for_status() {
-                    Ok(_) => return Ok(()),
-                    Err(err) => {
-                        attempt += 1;
-                        if attempt >= self.retries {
-                            return Err(err);
-                        }
-                    }
-                },
+            match self.deliver(url, event_id, &body, secret).await {
+                Ok(()) => return Ok(()),
                 Err(err) => {
                     attempt += 1;
                     if attempt >= self.retries {
@@ -48,12 +99,80 @@ impl WebhookSender {
             tokio::time::sleep(Duration::from_secs(attempt as u64)).await;
         }
     }
+
+    // a single signed attempt, the outbox dispatcher owns retries and backoff
+    pub async fn deliver(
+        &self,
+        url: &str,
+        event_id: &str,
+        body: &[u8],
+        secret: Option<&str>,
+    ) -> Result<(), reqwest::Error> {
+        let timestamp = chrono::Utc::now().timestamp();
+        let mut req = self
+            .client
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(EVENT_ID_HEADER, event_id)
+            .header(TIMESTAMP_HEADER, timestamp.to_string());
+        if let Some(secret) = secret {
+            req = req.header(SIGNATURE_HEADER, sign_payload(secret, timestamp, body));
+        }
+        req.body(body.to_vec()).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+// per-target breaker: after `threshold` consecutive failures the target is skipped
+// for `cooldown_secs`, after that one more failure reopens it and a success closes it
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown_secs: i64,
+    // target -> (consecutive failures, open until unix secs)
+    targets: Mutex<HashMap<String, (u32, i64)>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown_secs: u64) -> Self {
+        Self {
+            threshold,
+            cooldown_secs: cooldown_secs.min(i64::MAX as u64) as i64,
+            targets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn open_until(&self, target: &str, now: i64) -> Option<i64> {
+        let targets = self.targets.lock().unwrap();
+        targets
+            .get(target)
+            .map(|(_, until)| *until)
+            .filter(|until| *until > now)
+    }
+
+    pub fn record(&self, target: &str, ok: bool, now: i64) {
+        let mut targets = self.targets.lock().unwrap();
+        if ok {
+            targets.remove(target);
+            return;
+        }
+        if self.threshold == 0 {
+            return;
+        }
+        let entry = targets.entry(target.to_string()).or_insert((0, 0));
+        entry.0 += 1;
+        if entry.0 >= self.threshold {
+            entry.1 = now + self.cooldown_secs;
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::WebhookSender;
-    use axum::http::StatusCode;
+    use super::{
+        sign_payload, CircuitBreaker, WebhookSender, EVENT_ID_HEADER, SIGNATURE_HEADER,
+        TIMESTAMP_HEADER,
+    };
+    use axum::http::{HeaderMap, StatusCode};
     use axum::routing::post;
     use axum::Router;
     use std::sync::atomic::{AtomicUsize, Ordering};
@@ -131,4 +250,100 @@ mod tests {
 
         server.abort();
     }
+
+    #[test]
+    fn sign_payload_matches_known_vector() {
+        // the sdk's verify_webhook test uses the same vector
+        let body = br#"{"id":"evt-1","name":"chat"}"#;
+        assert_eq!(
+            sign_payload("whsec-test", 1700000000, body),
+            "v1=b3c7b7ab4742459f7c97e5824960dd1b7ae5fab7216ec3547987d72b8fa9f482"
+        );
+    }
+
+    #[tokio::test]
+    async fn send_signed_sets_event_and_signature_headers() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let app = Router::new().route(
+            "/hook",
+            post({
+                let seen = seen.clone();
+                move |headers: HeaderMap, body: axum::body::Bytes| {
+                    let seen = seen.clone();
+                    async move {
+                        seen.lock().unwrap().push((headers, body));
+                        StatusCode::OK
+                    }
+                }
+            }),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let signed = format!("http://{addr}/hook");
+        let sender = WebhookSender::new(2, 1).with_secrets(
+            Some("global".to_string()),
+            [(signed.clone(), "target".to_string())],
+        );
+        assert_eq!(
+            sender.secret_for(&signed, Some("topic")).as_deref(),
+            Some("target")
+        );
+        assert_eq!(
+            sender.secret_for("http://other", Some("topic")).as_deref(),
+            Some("topic")
+        );
+        assert_eq!(
+            sender.secret_for("http://other", Some("")).as_deref(),
+            Some("global")
+        );
+
+        let payload = serde_json::json!({"name": "chat"});
+        let secret = sender.secret_for(&signed, None);
+        sender
+            .send_signed(&signed, "evt-1", &payload, secret.as_deref())
+            .await
+            .unwrap();
+        WebhookSender::new(2, 1)
+            .send_json(&signed, &payload)
+            .await
+            .unwrap();
+
+        let seen = seen.lock().unwrap();
+        let (headers, body) = &seen[0];
+        assert_eq!(headers[EVENT_ID_HEADER], "evt-1");
+        let ts: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        assert_eq!(
+            headers[SIGNATURE_HEADER].to_str().unwrap(),
+            sign_payload("target", ts, body)
+        );
+        // without any secret the delivery is still identified, just unsigned
+        let (headers, _) = &seen[1];
+        assert!(!headers[EVENT_ID_HEADER].is_empty());
+        assert!(headers.contains_key(TIMESTAMP_HEADER));
+        assert!(!headers.contains_key(SIGNATURE_HEADER));
+
+        server.abort();
+    }
+
+    #[test]
+    fn circuit_breaker_opens_after_threshold_and_closes_on_success() {
+        let breaker = CircuitBreaker::new(2, 60);
+        breaker.record("http://a", false, 100);
+        assert_eq!(breaker.open_until("http://a", 100), None);
+        breaker.record("http://a", false, 100);
+        assert_eq!(breaker.open_until("http://a", 100), Some(160));
+        assert_eq!(breaker.open_until("http://b", 100), None);
+        // half open after the cooldown, one more failure opens it again
+        assert_eq!(breaker.open_until("http://a", 160), None);
+        breaker.record("http://a", false, 160);
+        assert_eq!(breaker.open_until("http://a", 161), Some(220));
+        breaker.record("http://a", true, 221);
+        breaker.record("http://a", false, 221);
+        assert_eq!(breaker.open_until("http://a", 221), None);
+    }
 }
@@ -68,11 +68,26 @@ impl WsHub {
         user_id: &str,
         payload: &str,
         drop_on_backpressure: bool,
+    ) {
+        self.broadcast_to_other_devices(user_id, "", payload, drop_on_backpressure)
+            .await;
+    }
+
+    // an empty except_device reaches every session of the user
+    pub async fn broadcast_to_other_devices(
+        &self,
+        user_id: &str,
+        except_device: &str,
+        payload: &str,
+        drop_on_backpressure: bool,
     ) {
         let mut to_remove = Vec::new();
         let peers = self.peers.read().await;
         if let Some(clients) = peers.get(user_id) {
             for session in clients {
+                if !except_device.is_empty() && session.device == except_device {
+                    continue;
+                }
                 let outcome = session.sender.try_send(payload.to_string());
                 match outcome {
                     SendOutcome::Sent => {}
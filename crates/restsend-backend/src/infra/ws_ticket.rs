@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+};
+use tokio::sync::RwLock;
+
+use crate::entity::ws_ticket;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WsTicket {
+    pub ticket: String,
+    pub user_id: String,
+    // session device including the nonce, the connection must present the same one
+    pub device: String,
+    pub expires_at: i64,
+}
+
+#[async_trait]
+pub trait WsTicketStore: Send + Sync {
+    async fn insert(&self, ticket: &WsTicket) -> Result<(), sea_orm::DbErr>;
+    // removes and returns the ticket, only one caller can ever get it back
+    async fn take(&self, ticket: &str) -> Result<Option<WsTicket>, sea_orm::DbErr>;
+    async fn cleanup(&self, now: i64) -> Result<u64, sea_orm::DbErr>;
+}
+
+#[derive(Clone, Default)]
+pub struct MemoryWsTicketStore {
+    tickets: Arc<RwLock<HashMap<String, WsTicket>>>,
+}
+
+#[async_trait]
+impl WsTicketStore for MemoryWsTicketStore {
+    async fn insert(&self, ticket: &WsTicket) -> Result<(), sea_orm::DbErr> {
+        self.tickets
+            .write()
+            .await
+            .insert(ticket.ticket.clone(), ticket.clone());
+        Ok(())
+    }
+
+    async fn take(&self, ticket: &str) -> Result<Option<WsTicket>, sea_orm::DbErr> {
+        Ok(self.tickets.write().await.remove(ticket))
+    }
+
+    async fn cleanup(&self, now: i64) -> Result<u64, sea_orm::DbErr> {
+        let mut guard = self.tickets.write().await;
+        let count = guard.len();
+        guard.retain(|_, ticket| ticket.expires_at > now);
+        Ok((count - guard.len()) as u64)
+    }
+}
+
+// tickets issued on one node can be redeemed on any other
+#[derive(Clone)]
+pub struct DbWsTicketStore {
+    db: DatabaseConnection,
+}
+
+impl DbWsTicketStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl WsTicketStore for DbWsTicketStore {
+    async fn insert(&self, ticket: &WsTicket) -> Result<(), sea_orm::DbErr> {
+        let model = ws_ticket::ActiveModel {
+            ticket: Set(ticket.ticket.clone()),
+            user_id: Set(ticket.user_id.clone()),
+            device: Set(ticket.device.clone()),
+            expires_at: Set(ticket.expires_at),
+        };
+        let _ = model.insert(&self.db).await?;
+        Ok(())
+    }
+
+    async fn take(&self, ticket: &str) -> Result<Option<WsTicket>, sea_orm::DbErr> {
+        let Some(model) = ws_ticket::Entity::find_by_id(ticket.to_string())
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        // the delete decides the winner when two nodes redeem the same ticket
+        let result = ws_ticket::Entity::delete_by_id(ticket.to_string())
+            .exec(&self.db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+        Ok(Some(WsTicket {
+            ticket: model.ticket,
+            user_id: model.user_id,
+            device: model.device,
+            expires_at: model.expires_at,
+        }))
+    }
+
+    async fn cleanup(&self, now: i64) -> Result<u64, sea_orm::DbErr> {
+        let result = ws_ticket::Entity::delete_many()
+            .filter(ws_ticket::Column::ExpiresAt.lte(now))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+#[derive(Clone)]
+pub struct WsTicketIssuer {
+    store: Arc<dyn WsTicketStore>,
+    ttl_secs: u64,
+}
+
+impl WsTicketIssuer {
+    pub fn new(store: Arc<dyn WsTicketStore>, ttl_secs: u64) -> Self {
+        Self { store, ttl_secs }
+    }
+
+    pub fn ttl_secs(&self) -> u64 {
+        self.ttl_secs
+    }
+
+    pub async fn issue(&self, user_id: &str, device: &str) -> Result<WsTicket, sea_orm::DbErr> {
+        let ticket = WsTicket {
+            ticket: format!("wst-{}", uuid::Uuid::new_v4().simple()),
+            user_id: user_id.to_string(),
+            device: device.to_string(),
+            expires_at: chrono::Utc::now().timestamp() + self.ttl_secs as i64,
+        };
+        self.store.insert(&ticket).await?;
+        Ok(ticket)
+    }
+
+    // None for unknown, already used or expired tickets
+    pub async fn redeem(&self, ticket: &str) -> Result<Option<WsTicket>, sea_orm::DbErr> {
+        let now = chrono::Utc::now().timestamp();
+        Ok(self
+            .store
+            .take(ticket)
+            .await?
+            .filter(|ticket| ticket.expires_at > now))
+    }
+
+    pub async fn cleanup(&self) -> Result<u64, sea_orm::DbErr> {
+        self.store.cleanup(chrono::Utc::now().timestamp()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ws_ticket_single_use_and_expiry() {
+        let store = Arc::new(MemoryWsTicketStore::default());
+        let issuer = WsTicketIssuer::new(store.clone(), 30);
+        let ticket = issuer.issue("alice", "web:abcd").await.unwrap();
+        let redeemed = issuer.redeem(&ticket.ticket).await.unwrap().unwrap();
+        assert_eq!(redeemed.user_id, "alice");
+        assert_eq!(redeemed.device, "web:abcd");
+        assert!(issuer.redeem(&ticket.ticket).await.unwrap().is_none());
+
+        let expired = WsTicketIssuer::new(store, 0);
+        let ticket = expired.issue("alice", "web").await.unwrap();
+        assert!(expired.redeem(&ticket.ticket).await.unwrap().is_none());
+        assert_eq!(expired.cleanup().await.unwrap(), 0);
+    }
+}
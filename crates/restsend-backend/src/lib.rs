@@ -7,6 +7,7 @@ pub mod infra;
 pub mod model;
 pub mod openapi;
 pub mod services;
+pub mod util;
 
 pub use model::*;
 pub use openapi::*;
@@ -58,6 +58,10 @@ pub struct User {
     pub is_staff: bool,
     #[serde(default = "default_true")]
     pub enabled: bool,
+    #[serde(default)]
+    pub search_opt_out: bool,
+    #[serde(default)]
+    pub presence: Option<crate::UserPresence>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -143,6 +147,53 @@ pub struct TopicMember {
     pub extra: Option<Extra>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TopicPermission {
+    Send,
+    SendAttachment,
+    MentionAll,
+    Invite,
+    Kick,
+    Silence,
+    Pin,
+    EditNotice,
+    ManageRoles,
+}
+
+impl TopicPermission {
+    pub fn all() -> Vec<TopicPermission> {
+        vec![
+            TopicPermission::Send,
+            TopicPermission::SendAttachment,
+            TopicPermission::MentionAll,
+            TopicPermission::Invite,
+            TopicPermission::Kick,
+            TopicPermission::Silence,
+            TopicPermission::Pin,
+            TopicPermission::EditNotice,
+            TopicPermission::ManageRoles,
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicRole {
+    #[serde(default)]
+    pub topic_id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub permissions: Vec<TopicPermission>,
+    #[serde(default)]
+    pub builtin: bool,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Relation {
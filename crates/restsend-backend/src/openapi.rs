@@ -22,6 +22,129 @@ pub struct OpenApiAuthForm {
     pub client_ip: String,
     #[serde(default)]
     pub create_when_not_exist: bool,
+    #[serde(flatten)]
+    pub device: DeviceInfoForm,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceInfoForm {
+    #[serde(default)]
+    pub device_name: String,
+    #[serde(default)]
+    pub platform: String,
+    #[serde(default)]
+    pub app_version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WsConnectTicket {
+    // pass as `?ticket=` to /api/connect or /ws, usable once
+    #[serde(default)]
+    pub ticket: String,
+    #[serde(default)]
+    pub device: String,
+    #[serde(default)]
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSession {
+    #[serde(default)]
+    pub session_id: String,
+    // ws session id the token last connected as
+    #[serde(default)]
+    pub device: String,
+    #[serde(default)]
+    pub device_name: String,
+    #[serde(default)]
+    pub platform: String,
+    #[serde(default)]
+    pub app_version: String,
+    #[serde(default)]
+    pub ip: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub last_seen_at: String,
+    #[serde(default)]
+    pub online: bool,
+    #[serde(default)]
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginLockout {
+    // account or ip
+    #[serde(default)]
+    pub kind: String,
+    #[serde(default)]
+    pub subject: String,
+    #[serde(default)]
+    pub failures: u32,
+    #[serde(default)]
+    pub lockouts: u32,
+    #[serde(default)]
+    pub locked_until: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenApiLockoutClearForm {
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub ip: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenApiKey {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+    #[serde(default)]
+    pub expires_at: String,
+    #[serde(default)]
+    pub last_used_at: String,
+    #[serde(default)]
+    pub created_by: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenApiKeyForm {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+    // 30d, 12h, forever or empty for a key that never expires
+    #[serde(default)]
+    pub duration: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenApiKeySecret {
+    #[serde(flatten)]
+    pub key: OpenApiKey,
+    // full bearer value, only returned on create and rotate
+    #[serde(default)]
+    pub secret: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -51,6 +174,8 @@ pub struct OpenApiUserForm {
     pub source: String,
     #[serde(default)]
     pub public_key: String,
+    pub extra: Option<crate::Extra>,
+    pub search_opt_out: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -65,6 +190,122 @@ pub struct OpenApiRelationEditForm {
     pub source: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactRequest {
+    #[serde(default)]
+    pub sender_id: String,
+    #[serde(default)]
+    pub receiver_id: String,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub source: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactRequestForm {
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactRequestListForm {
+    // incoming (default) or outgoing
+    #[serde(default)]
+    pub direction: String,
+    // empty lists pending requests only
+    #[serde(default)]
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactSyncForm {
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub cursor: String,
+    #[serde(default)]
+    pub limit: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPresence {
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub status_text: String,
+    #[serde(default)]
+    pub status_expires_at: String,
+    #[serde(default)]
+    pub last_seen_at: String,
+    #[serde(default)]
+    pub online: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PresenceForm {
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub status_text: String,
+    #[serde(default)]
+    pub expire_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDirectoryForm {
+    #[serde(default)]
+    pub keyword: String,
+    #[serde(default)]
+    pub offset: u64,
+    #[serde(default)]
+    pub limit: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenApiUserSearchForm {
+    #[serde(default)]
+    pub keyword: String,
+    pub source: Option<String>,
+    pub is_staff: Option<bool>,
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub offset: u64,
+    #[serde(default)]
+    pub limit: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDirectoryResult {
+    #[serde(default)]
+    pub total: u64,
+    #[serde(default)]
+    pub offset: u64,
+    #[serde(default)]
+    pub has_more: bool,
+    #[serde(default)]
+    pub items: Vec<crate::User>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct OpenApiUserListForm {
@@ -102,6 +343,8 @@ pub struct OpenApiCreateTopicForm {
     pub admins: Vec<String>,
     #[serde(default)]
     pub webhooks: Vec<String>,
+    // hmac secret for the topic webhooks, falls back to the global webhook secret
+    pub webhook_secret: Option<String>,
     pub notice: Option<TopicNoticeInput>,
     pub extra: Option<std::collections::HashMap<String, String>>,
 }
@@ -134,6 +377,8 @@ pub struct OpenApiUpdateTopicForm {
     pub knock_need_verify: Option<bool>,
     #[serde(default)]
     pub webhooks: Vec<String>,
+    // hmac secret for the topic webhooks, falls back to the global webhook secret
+    pub webhook_secret: Option<String>,
     pub notice: Option<TopicNoticeInput>,
     pub extra: Option<std::collections::HashMap<String, String>>,
 }
@@ -201,6 +446,189 @@ pub struct TopicKnockForm {
 pub type TopicKnockAcceptedForm = TopicKnockForm;
 pub type TopicKnockRejectedForm = TopicKnockForm;
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicKnockRule {
+    #[serde(default)]
+    pub topic_id: String,
+    #[serde(default)]
+    pub sources: Vec<String>,
+    #[serde(default)]
+    pub attributes: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub owner_contacts: bool,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicKnockRuleForm {
+    #[serde(default)]
+    pub sources: Vec<String>,
+    #[serde(default)]
+    pub attributes: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub owner_contacts: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicInvite {
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub topic_id: String,
+    #[serde(default)]
+    pub created_by: String,
+    #[serde(default)]
+    pub max_uses: u32,
+    #[serde(default)]
+    pub used_count: u32,
+    #[serde(default)]
+    pub expires_at: String,
+    #[serde(default)]
+    pub auto_approve: bool,
+    #[serde(default)]
+    pub revoked: bool,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicInviteForm {
+    #[serde(default)]
+    pub duration: String,
+    #[serde(default)]
+    pub max_uses: u32,
+    #[serde(default)]
+    pub auto_approve: bool,
+    #[serde(default)]
+    pub created_by: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicInvitePreview {
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub topic_id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub icon: String,
+    #[serde(default)]
+    pub remark: String,
+    #[serde(default)]
+    pub members: u32,
+    #[serde(default)]
+    pub expires_at: String,
+    #[serde(default)]
+    pub need_verify: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicInviteJoinResult {
+    #[serde(default)]
+    pub topic_id: String,
+    #[serde(default)]
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicDirectoryForm {
+    #[serde(default)]
+    pub keyword: String,
+    #[serde(default)]
+    pub sort: String,
+    #[serde(default)]
+    pub offset: u64,
+    #[serde(default)]
+    pub limit: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicDirectoryItem {
+    #[serde(default)]
+    pub topic_id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub icon: String,
+    #[serde(default)]
+    pub notice: String,
+    #[serde(default)]
+    pub members: u32,
+    #[serde(default)]
+    pub need_verify: bool,
+    #[serde(default)]
+    pub is_member: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicDirectoryResult {
+    #[serde(default)]
+    pub total: u64,
+    #[serde(default)]
+    pub offset: u64,
+    #[serde(default)]
+    pub has_more: bool,
+    #[serde(default)]
+    pub items: Vec<TopicDirectoryItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicBan {
+    #[serde(default)]
+    pub topic_id: String,
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub admin_id: String,
+    #[serde(default)]
+    pub expires_at: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicBanForm {
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub duration: String,
+    #[serde(default)]
+    pub admin_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicRoleForm {
+    #[serde(default)]
+    pub permissions: Vec<crate::TopicPermission>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicRoleAssignForm {
+    #[serde(default)]
+    pub role: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateNoticeForm {
@@ -419,7 +847,28 @@ pub struct ListUserResult {
     #[serde(default)]
     pub updated_at: String,
     #[serde(default)]
+    pub cursor: String,
+    #[serde(default)]
     pub items: Vec<crate::User>,
+    #[serde(default)]
+    pub members: Vec<crate::TopicMember>,
+    #[serde(default)]
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicMemberListForm {
+    #[serde(default)]
+    pub cursor: String,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub keyword: String,
+    #[serde(default)]
+    pub role: String,
+    #[serde(default)]
+    pub limit: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -441,6 +890,147 @@ pub struct OpenApiSendMessageResponse {
     pub seq: i64,
     #[serde(default)]
     pub usage: i64,
+    // the stored content when the pre-send hook rewrote it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<crate::Content>,
+}
+
+// posted to PRESEND_HOOK_URL before a message is stored
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PreSendHookRequest {
+    #[serde(default)]
+    pub topic_id: String,
+    #[serde(default)]
+    pub sender_id: String,
+    // set for direct messages
+    #[serde(default)]
+    pub attendee_id: String,
+    #[serde(default)]
+    pub chat_id: String,
+    #[serde(default)]
+    pub content: crate::Content,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PreSendHookResponse {
+    // allow, reject or rewrite
+    #[serde(default, deserialize_with = "de_null_string")]
+    pub action: String,
+    // 4xx returned to the sender on reject, 403 when unset
+    #[serde(default)]
+    pub code: u16,
+    #[serde(default, deserialize_with = "de_null_string")]
+    pub reason: String,
+    // the content to store instead on rewrite
+    pub content: Option<crate::Content>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEventView {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub topic_id: String,
+    #[serde(default)]
+    pub data: serde_json::Value,
+    #[serde(default)]
+    pub dispatched: bool,
+    #[serde(default)]
+    pub created_at: String,
+}
+
+// a pending outbox row or a dead letter
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub event_id: String,
+    #[serde(default)]
+    pub target: String,
+    #[serde(default)]
+    pub subscription_id: String,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: String,
+    #[serde(default)]
+    pub next_attempt_at: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub failed_at: String,
+}
+
+// selects rows by id or by an rfc3339 time range, ids win when both are given
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookSelectForm {
+    #[serde(default)]
+    pub ids: Vec<String>,
+    #[serde(default, deserialize_with = "de_null_string")]
+    pub since: String,
+    #[serde(default, deserialize_with = "de_null_string")]
+    pub until: String,
+    #[serde(default, deserialize_with = "de_null_string")]
+    pub target: String,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookSubscription {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub url: String,
+    // the secret itself is never returned
+    #[serde(default)]
+    pub has_secret: bool,
+    #[serde(default)]
+    pub events: Vec<String>,
+    #[serde(default)]
+    pub topic_ids: Vec<String>,
+    #[serde(default)]
+    pub kinds: Vec<String>,
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub created_by: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+// on update omitted fields keep their current value
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookSubscriptionForm {
+    pub url: Option<String>,
+    // an empty string clears the secret
+    pub secret: Option<String>,
+    // event names as in `BackendEvent::event_name`, `topic.*` matches a prefix and `*` everything,
+    // empty subscribes to every event
+    pub events: Option<Vec<String>>,
+    // only events of these topics, empty for any
+    pub topic_ids: Option<Vec<String>>,
+    // only events of topics of these kinds, empty for any
+    pub kinds: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookRequeueResult {
+    #[serde(default)]
+    pub count: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -464,6 +1054,18 @@ pub enum OpenApiDocSchema {
     OpenApiSendMessageResponse,
     ChatLogSyncResult,
     Relation,
+    TopicInvite,
+    TopicRole,
+    TopicBan,
+    TopicKnockRule,
+    DeviceSession,
+    LoginLockout,
+    OpenApiKey,
+    WebhookEvent,
+    WebhookDelivery,
+    WebhookSelect,
+    WebhookRequeueResult,
+    WebhookSubscription,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -485,4 +1087,6 @@ pub struct UserPublicProfile {
     pub user: User,
     #[serde(default)]
     pub auth_token: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub refresh_token: String,
 }
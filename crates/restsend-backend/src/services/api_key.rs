@@ -0,0 +1,340 @@
+use std::net::IpAddr;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use ring::rand::SecureRandom;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, DatabaseConnection, EntityTrait, IntoActiveModel,
+    QueryOrder,
+};
+use sha2::{Digest, Sha256};
+
+use super::topic::parse_duration_to_time;
+use super::{DomainError, DomainResult};
+use crate::entity::openapi_key;
+use crate::util::split_list;
+use crate::{OpenApiKey, OpenApiKeyForm, OpenApiKeySecret};
+
+// bearer values look like `rsk_<id>.<secret>`
+pub const API_KEY_PREFIX: &str = "rsk_";
+// last_used_at is written at most this often per key
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiScope {
+    UsersRead,
+    UsersWrite,
+    TopicsRead,
+    TopicsWrite,
+    ConversationsRead,
+    ConversationsWrite,
+    ChatSend,
+    Helpdesk,
+    // grants every other scope
+    Admin,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 9] = [
+        ApiScope::UsersRead,
+        ApiScope::UsersWrite,
+        ApiScope::TopicsRead,
+        ApiScope::TopicsWrite,
+        ApiScope::ConversationsRead,
+        ApiScope::ConversationsWrite,
+        ApiScope::ChatSend,
+        ApiScope::Helpdesk,
+        ApiScope::Admin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::UsersRead => "users:read",
+            ApiScope::UsersWrite => "users:write",
+            ApiScope::TopicsRead => "topics:read",
+            ApiScope::TopicsWrite => "topics:write",
+            ApiScope::ConversationsRead => "conversations:read",
+            ApiScope::ConversationsWrite => "conversations:write",
+            ApiScope::ChatSend => "chat:send",
+            ApiScope::Helpdesk => "helpdesk",
+            ApiScope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        Self::ALL.into_iter().find(|scope| scope.as_str() == value)
+    }
+}
+
+// attached to the request by openapi_auth when an api key authenticated it
+#[derive(Clone, Debug)]
+pub struct ApiKeyGrant {
+    pub key_id: String,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+}
+
+impl ApiKeyGrant {
+    pub fn allows(&self, scope: ApiScope) -> bool {
+        self.scopes.contains(&ApiScope::Admin) || self.scopes.contains(&scope)
+    }
+}
+
+#[derive(Clone)]
+pub struct ApiKeyService {
+    db: DatabaseConnection,
+}
+
+impl ApiKeyService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub fn is_api_key(token: &str) -> bool {
+        token.starts_with(API_KEY_PREFIX)
+    }
+
+    pub async fn list(&self) -> DomainResult<Vec<OpenApiKey>> {
+        let rows = openapi_key::Entity::find()
+            .order_by_asc(openapi_key::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn create(
+        &self,
+        form: OpenApiKeyForm,
+        created_by: &str,
+    ) -> DomainResult<OpenApiKeySecret> {
+        let name = form.name.trim().to_string();
+        if name.is_empty() {
+            return Err(DomainError::Validation("name is required".to_string()));
+        }
+        let scopes = normalize_scopes(&form.scopes)?;
+        let allowed_ips = normalize_allowed_ips(&form.allowed_ips)?;
+        let expires_at = if form.duration.trim().is_empty() {
+            String::new()
+        } else {
+            parse_duration_to_time(&form.duration)
+                .ok_or_else(|| DomainError::Validation("invalid duration".to_string()))?
+        };
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let secret = random_secret()?;
+        let now_ts = Utc::now().to_rfc3339();
+        let row = openapi_key::ActiveModel {
+            id: Set(id.clone()),
+            name: Set(name),
+            secret_hash: Set(hash_secret(&secret)),
+            scopes: Set(scopes.join(",")),
+            allowed_ips: Set(allowed_ips.join(",")),
+            expires_at: Set(expires_at),
+            last_used_at: Set(String::new()),
+            created_by: Set(created_by.to_string()),
+            created_at: Set(now_ts.clone()),
+            updated_at: Set(now_ts),
+        }
+        .insert(&self.db)
+        .await?;
+        Ok(OpenApiKeySecret {
+            key: row.into(),
+            secret: format!("{API_KEY_PREFIX}{id}.{secret}"),
+        })
+    }
+
+    // replaces the secret, the previous bearer value stops working immediately
+    pub async fn rotate(&self, id: &str) -> DomainResult<OpenApiKeySecret> {
+        let row = openapi_key::Entity::find_by_id(id.to_string())
+            .one(&self.db)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+        let secret = random_secret()?;
+        let mut active = row.into_active_model();
+        active.secret_hash = Set(hash_secret(&secret));
+        active.updated_at = Set(Utc::now().to_rfc3339());
+        let row = active.update(&self.db).await?;
+        Ok(OpenApiKeySecret {
+            secret: format!("{API_KEY_PREFIX}{}.{secret}", row.id),
+            key: row.into(),
+        })
+    }
+
+    pub async fn revoke(&self, id: &str) -> DomainResult<()> {
+        let result = openapi_key::Entity::delete_by_id(id.to_string())
+            .exec(&self.db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(DomainError::NotFound);
+        }
+        Ok(())
+    }
+
+    // NotFound for unknown or mismatched keys, Forbidden for expired keys or a disallowed ip
+    pub async fn authenticate(&self, token: &str, ip: &str) -> DomainResult<ApiKeyGrant> {
+        let (id, secret) = token
+            .strip_prefix(API_KEY_PREFIX)
+            .and_then(|rest| rest.split_once('.'))
+            .ok_or(DomainError::NotFound)?;
+        let row = openapi_key::Entity::find_by_id(id.to_string())
+            .one(&self.db)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+        // both sides are digests of a random secret, so a plain compare leaks nothing useful
+        if row.secret_hash != hash_secret(secret) {
+            return Err(DomainError::NotFound);
+        }
+        let now = Utc::now();
+        if !row.expires_at.is_empty() && row.expires_at <= now.to_rfc3339() {
+            return Err(DomainError::Forbidden);
+        }
+        let allowed_ips = split_list(&row.allowed_ips);
+        if !ip_allowed(&allowed_ips, ip) {
+            return Err(DomainError::Forbidden);
+        }
+
+        let grant = ApiKeyGrant {
+            key_id: row.id.clone(),
+            name: row.name.clone(),
+            scopes: split_list(&row.scopes)
+                .iter()
+                .filter_map(|v| ApiScope::parse(v))
+                .collect(),
+        };
+        let stale = chrono::DateTime::parse_from_rfc3339(&row.last_used_at)
+            .map(|v| (now - v.with_timezone(&Utc)).num_seconds() >= TOUCH_INTERVAL_SECS)
+            .unwrap_or(true);
+        if stale {
+            let mut active = row.into_active_model();
+            active.last_used_at = Set(now.to_rfc3339());
+            if let Err(err) = active.update(&self.db).await {
+                tracing::warn!(key_id = %grant.key_id, error = %err, "api key touch failed");
+            }
+        }
+        Ok(grant)
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+fn random_secret() -> DomainResult<String> {
+    let mut buf = [0u8; 32];
+    ring::rand::SystemRandom::new()
+        .fill(&mut buf)
+        .map_err(|_| DomainError::Storage("system random unavailable".to_string()))?;
+    Ok(URL_SAFE_NO_PAD.encode(buf))
+}
+
+fn normalize_scopes(scopes: &[String]) -> DomainResult<Vec<String>> {
+    let mut out: Vec<String> = Vec::new();
+    for raw in scopes {
+        let scope = ApiScope::parse(raw)
+            .ok_or_else(|| DomainError::Validation(format!("unknown scope {}", raw.trim())))?;
+        if !out.iter().any(|v| v == scope.as_str()) {
+            out.push(scope.as_str().to_string());
+        }
+    }
+    if out.is_empty() {
+        return Err(DomainError::Validation(
+            "at least one scope is required".to_string(),
+        ));
+    }
+    Ok(out)
+}
+
+fn normalize_allowed_ips(entries: &[String]) -> DomainResult<Vec<String>> {
+    let mut out = Vec::new();
+    for raw in entries {
+        let entry = raw.trim();
+        if entry.is_empty() {
+            continue;
+        }
+        if parse_cidr(entry).is_none() {
+            return Err(DomainError::Validation(format!(
+                "invalid ip or cidr {entry}"
+            )));
+        }
+        out.push(entry.to_string());
+    }
+    Ok(out)
+}
+
+// `10.0.0.1` or `10.0.0.0/8`, a bare address matches only itself
+fn parse_cidr(entry: &str) -> Option<(IpAddr, u32)> {
+    let (addr, bits) = match entry.split_once('/') {
+        Some((addr, bits)) => (addr.parse::<IpAddr>().ok()?, bits.parse::<u32>().ok()?),
+        None => {
+            let addr = entry.parse::<IpAddr>().ok()?;
+            (addr, if addr.is_ipv4() { 32 } else { 128 })
+        }
+    };
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    (bits <= max).then_some((addr, bits))
+}
+
+fn ip_allowed(allowed: &[String], ip: &str) -> bool {
+    if allowed.is_empty() {
+        return true;
+    }
+    // the caller resolves the address through the trusted proxies first
+    ip.trim()
+        .parse::<IpAddr>()
+        .is_ok_and(|ip| ip_in_cidrs(allowed, ip))
+}
+
+pub fn ip_in_cidrs(list: &[String], ip: IpAddr) -> bool {
+    list.iter()
+        .filter_map(|v| parse_cidr(v))
+        .any(|(net, bits)| match (net, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - bits).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - bits).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_allowlist() {
+        let allowed = vec!["10.0.0.0/8".to_string(), "192.168.1.7".to_string()];
+        assert!(ip_allowed(&[], "1.2.3.4"));
+        assert!(ip_allowed(&allowed, "10.20.30.40"));
+        assert!(ip_allowed(&allowed, "192.168.1.7"));
+        assert!(!ip_allowed(&allowed, "10.0.0.1, 192.168.1.8"));
+        assert!(!ip_allowed(&allowed, "192.168.1.8"));
+        assert!(!ip_allowed(&allowed, ""));
+        assert!(ip_allowed(&["0.0.0.0/0".to_string()], "8.8.8.8"));
+        assert!(ip_allowed(&["fd00::/8".to_string()], "fd12::1"));
+        assert!(!ip_allowed(&["fd00::/8".to_string()], "10.0.0.1"));
+        assert!(parse_cidr("10.0.0.0/33").is_none());
+    }
+
+    #[test]
+    fn test_scope_grant() {
+        let grant = ApiKeyGrant {
+            key_id: "k".to_string(),
+            name: "ci".to_string(),
+            scopes: vec![ApiScope::UsersRead],
+        };
+        assert!(grant.allows(ApiScope::UsersRead));
+        assert!(!grant.allows(ApiScope::UsersWrite));
+        let admin = ApiKeyGrant {
+            scopes: vec![ApiScope::Admin],
+            ..grant
+        };
+        assert!(admin.allows(ApiScope::Helpdesk));
+        assert_eq!(ApiScope::parse(" chat:send "), Some(ApiScope::ChatSend));
+        assert!(normalize_scopes(&["nope".to_string()]).is_err());
+    }
+}
@@ -1,23 +1,120 @@
 use chrono::Utc;
+use sea_orm::sea_query::Expr;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait,
-    IntoActiveModel, QueryFilter,
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, DatabaseConnection, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, TransactionTrait,
 };
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
 
-use crate::entity::{auth_token, user};
-use crate::services::DomainResult;
+use crate::entity::{auth_revocation, auth_token, user};
+use crate::services::token_signer::{is_signed_token, TokenClaims, TokenSigner};
+use crate::services::{DomainError, DomainResult};
+use crate::{DeviceInfoForm, DeviceSession};
+
+fn hash_refresh_token(refresh_token: &str) -> String {
+    if refresh_token.is_empty() {
+        return String::new();
+    }
+    format!("{:x}", Sha256::digest(refresh_token.as_bytes()))
+}
+
+// exempts one signed token from the `user:<id>` revocation written with it
+fn keep_id(user_id: &str, jti: &str) -> String {
+    format!("keep:{}:{}", user_id, jti)
+}
+
+// sessions are addressed by a token digest so the token itself never leaves the server
+pub fn session_id_of(token: &str) -> String {
+    let digest = Sha256::digest(token.as_bytes());
+    format!("{:x}", digest)[..16].to_string()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenPolicy {
+    // 0 disables the corresponding limit
+    pub ttl_secs: u64,
+    pub idle_secs: u64,
+    pub refresh_ttl_secs: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct IssuedToken {
+    pub token: String,
+    pub refresh_token: String,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenStatus {
+    Valid(String),
+    Expired,
+    IdleTimeout,
+    Invalid,
+}
+
+impl TokenStatus {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            TokenStatus::Valid(_) => "valid",
+            TokenStatus::Expired => "expired",
+            TokenStatus::IdleTimeout => "idle timeout",
+            TokenStatus::Invalid => "invalid",
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct AuthService {
     db: DatabaseConnection,
+    policy: TokenPolicy,
+    // last_seen_at writes are buffered here and flushed periodically
+    touched: Arc<Mutex<HashMap<String, String>>>,
+    signer: Option<Arc<TokenSigner>>,
+    // mirror of auth_revocations so signed tokens validate without a db hit
+    revocations: Arc<RwLock<HashMap<String, i64>>>,
 }
 
 impl AuthService {
     pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+        Self {
+            db,
+            policy: TokenPolicy::default(),
+            touched: Arc::new(Mutex::new(HashMap::new())),
+            signer: None,
+            revocations: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: TokenPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    // new tokens are signed and carry their own claims instead of living in auth_tokens
+    pub fn with_signer(mut self, signer: TokenSigner) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    pub fn signs_tokens(&self) -> bool {
+        self.signer.is_some()
     }
 
     pub async fn issue_token(&self, user_id: &str) -> DomainResult<String> {
+        self.issue_token_with_device(user_id, &DeviceInfoForm::default(), "")
+            .await
+            .map(|issued| issued.token)
+    }
+
+    pub async fn issue_token_with_device(
+        &self,
+        user_id: &str,
+        info: &DeviceInfoForm,
+        ip: &str,
+    ) -> DomainResult<IssuedToken> {
         if let Some(model) = user::Entity::find_by_id(user_id.to_string())
             .one(&self.db)
             .await?
@@ -26,43 +123,341 @@ impl AuthService {
                 return Err(crate::services::DomainError::Forbidden);
             }
         }
-        let now = Utc::now().to_rfc3339();
-        let token = format!("{}_{}", user_id, uuid::Uuid::new_v4());
+        let now = Utc::now();
+        if let Some(signer) = &self.signer {
+            return Ok(self.issue_signed_token(signer, user_id, info, now.timestamp()));
+        }
+        let issued = self.new_issued_token(user_id, now.timestamp());
         auth_token::ActiveModel {
-            token: Set(token.clone()),
+            token: Set(issued.token.clone()),
             user_id: Set(user_id.to_string()),
-            created_at: Set(now.clone()),
-            last_seen_at: Set(now),
+            created_at: Set(now.to_rfc3339()),
+            last_seen_at: Set(now.to_rfc3339()),
+            device: Set(String::new()),
+            device_name: Set(info.device_name.trim().to_string()),
+            platform: Set(info.platform.trim().to_string()),
+            app_version: Set(info.app_version.trim().to_string()),
+            ip: Set(ip.trim().to_string()),
+            expires_at: Set(issued.expires_at),
+            refresh_token: Set(hash_refresh_token(&issued.refresh_token)),
+            refresh_expires_at: Set(self.refresh_expires_at(&issued, now.timestamp())),
         }
         .insert(&self.db)
         .await?;
-        Ok(token)
+        Ok(issued)
     }
 
     pub async fn validate(&self, token: &str) -> DomainResult<Option<String>> {
-        let found = auth_token::Entity::find_by_id(token.to_string())
+        match self.validate_status(token).await? {
+            TokenStatus::Valid(user_id) => Ok(Some(user_id)),
+            _ => Ok(None),
+        }
+    }
+
+    pub async fn validate_status(&self, token: &str) -> DomainResult<TokenStatus> {
+        if let Some(signer) = self.signer.as_ref().filter(|_| is_signed_token(token)) {
+            return Ok(self.validate_signed(signer, token).await);
+        }
+        // connected signed tokens have session rows too, they are never valid on their own
+        if is_signed_token(token) {
+            return Ok(TokenStatus::Invalid);
+        }
+        let Some(model) = auth_token::Entity::find_by_id(token.to_string())
             .one(&self.db)
+            .await?
+        else {
+            return Ok(TokenStatus::Invalid);
+        };
+        if !self.user_enabled(&model.user_id).await? {
+            return Ok(TokenStatus::Invalid);
+        }
+        let now = Utc::now();
+        if model.expires_at > 0 && model.expires_at <= now.timestamp() {
+            return Ok(TokenStatus::Expired);
+        }
+        if self.is_idle(&model, now).await {
+            return Ok(TokenStatus::IdleTimeout);
+        }
+        self.touched
+            .lock()
+            .await
+            .insert(model.token, now.to_rfc3339());
+        Ok(TokenStatus::Valid(model.user_id))
+    }
+
+    // rotates the pair: the old access and refresh tokens stop working
+    pub async fn refresh(
+        &self,
+        refresh_token: &str,
+    ) -> DomainResult<Result<(String, IssuedToken), TokenStatus>> {
+        if refresh_token.is_empty() {
+            return Ok(Err(TokenStatus::Invalid));
+        }
+        let Some(model) = auth_token::Entity::find()
+            .filter(auth_token::Column::RefreshToken.eq(hash_refresh_token(refresh_token)))
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(Err(TokenStatus::Invalid));
+        };
+        if !self.user_enabled(&model.user_id).await? {
+            return Ok(Err(TokenStatus::Invalid));
+        }
+        let now = Utc::now();
+        if model.refresh_expires_at > 0 && model.refresh_expires_at <= now.timestamp() {
+            return Ok(Err(TokenStatus::Expired));
+        }
+        if self.is_idle(&model, now).await {
+            return Ok(Err(TokenStatus::IdleTimeout));
+        }
+
+        let issued = self.new_issued_token(&model.user_id, now.timestamp());
+        let user_id = model.user_id.clone();
+        // only the caller that deletes the old row gets the new pair, a concurrent
+        // refresh with the same token finds nothing left to rotate
+        let txn = self.db.begin().await?;
+        let rows = auth_token::Entity::delete_many()
+            .filter(auth_token::Column::Token.eq(model.token.clone()))
+            .filter(auth_token::Column::RefreshToken.eq(model.refresh_token.clone()))
+            .exec(&txn)
+            .await?
+            .rows_affected;
+        if rows == 0 {
+            txn.rollback().await?;
+            return Ok(Err(TokenStatus::Invalid));
+        }
+        self.touched.lock().await.remove(&model.token);
+        auth_token::ActiveModel {
+            token: Set(issued.token.clone()),
+            user_id: Set(model.user_id),
+            created_at: Set(model.created_at),
+            last_seen_at: Set(now.to_rfc3339()),
+            device: Set(model.device),
+            device_name: Set(model.device_name),
+            platform: Set(model.platform),
+            app_version: Set(model.app_version),
+            ip: Set(model.ip),
+            expires_at: Set(issued.expires_at),
+            refresh_token: Set(hash_refresh_token(&issued.refresh_token)),
+            refresh_expires_at: Set(self.refresh_expires_at(&issued, now.timestamp())),
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+        Ok(Ok((user_id, issued)))
+    }
+
+    pub async fn flush_last_seen(&self) -> DomainResult<usize> {
+        let touched = std::mem::take(&mut *self.touched.lock().await);
+        let count = touched.len();
+        for (token, last_seen_at) in touched {
+            auth_token::Entity::update_many()
+                .col_expr(auth_token::Column::LastSeenAt, Expr::value(last_seen_at))
+                .filter(auth_token::Column::Token.eq(token))
+                .exec(&self.db)
+                .await?;
+        }
+        Ok(count)
+    }
+
+    pub async fn refresh_revocations(&self) -> DomainResult<usize> {
+        let now = Utc::now().timestamp();
+        let items = auth_revocation::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(auth_revocation::Column::ExpiresAt.eq(0))
+                    .add(auth_revocation::Column::ExpiresAt.gt(now)),
+            )
+            .all(&self.db)
             .await?;
+        let fresh: HashMap<String, i64> = items
+            .into_iter()
+            .map(|item| (item.id, item.revoked_at))
+            .collect();
+        let count = fresh.len();
+        *self.revocations.write().await = fresh;
+        Ok(count)
+    }
 
-        if let Some(model) = found {
-            if let Some(user) = user::Entity::find_by_id(model.user_id.clone())
-                .one(&self.db)
+    // drops tokens that can neither be used nor refreshed any more
+    pub async fn purge_expired(&self) -> DomainResult<u64> {
+        let now = Utc::now();
+        auth_revocation::Entity::delete_many()
+            .filter(auth_revocation::Column::ExpiresAt.gt(0))
+            .filter(auth_revocation::Column::ExpiresAt.lte(now.timestamp()))
+            .exec(&self.db)
+            .await?;
+        let mut removed = auth_token::Entity::delete_many()
+            .filter(auth_token::Column::ExpiresAt.gt(0))
+            .filter(auth_token::Column::ExpiresAt.lte(now.timestamp()))
+            .filter(auth_token::Column::RefreshExpiresAt.lte(now.timestamp()))
+            .exec(&self.db)
+            .await?
+            .rows_affected;
+        if self.policy.idle_secs > 0 {
+            let cutoff = now - chrono::Duration::seconds(self.policy.idle_secs as i64);
+            removed += auth_token::Entity::delete_many()
+                .filter(auth_token::Column::LastSeenAt.lt(cutoff.to_rfc3339()))
+                .exec(&self.db)
                 .await?
-            {
-                if !user.enabled {
-                    return Ok(None);
+                .rows_affected;
+        }
+        Ok(removed)
+    }
+
+    // signed tokens cannot be refreshed, clients log in again once they expire
+    fn issue_signed_token(
+        &self,
+        signer: &TokenSigner,
+        user_id: &str,
+        info: &DeviceInfoForm,
+        now: i64,
+    ) -> IssuedToken {
+        let claims = TokenClaims {
+            sub: user_id.to_string(),
+            dev: info.device_name.trim().to_string(),
+            iat: now,
+            exp: self.access_expires_at(now),
+            jti: uuid::Uuid::new_v4().simple().to_string(),
+        };
+        IssuedToken {
+            token: signer.sign(&claims),
+            refresh_token: String::new(),
+            expires_at: claims.exp,
+        }
+    }
+
+    async fn validate_signed(&self, signer: &TokenSigner, token: &str) -> TokenStatus {
+        let Some(claims) = signer.verify(token) else {
+            return TokenStatus::Invalid;
+        };
+        if claims.exp > 0 && claims.exp <= Utc::now().timestamp() {
+            return TokenStatus::Expired;
+        }
+        let revocations = self.revocations.read().await;
+        if revocations.contains_key(&format!("jti:{}", claims.jti)) {
+            return TokenStatus::Invalid;
+        }
+        // iat has second precision, a token issued in the same second as the
+        // revocation is a fresh login and stays valid
+        if revocations
+            .get(&format!("user:{}", claims.sub))
+            .is_some_and(|revoked_at| claims.iat < *revoked_at)
+            && !revocations.contains_key(&keep_id(&claims.sub, &claims.jti))
+        {
+            return TokenStatus::Invalid;
+        }
+        TokenStatus::Valid(claims.sub)
+    }
+
+    // a `keep:<jti>` left by an earlier revoke_other_sessions must not survive a new revoke
+    async fn revoke_signed_user(&self, user_id: &str) -> DomainResult<()> {
+        if self.signer.is_none() {
+            return Ok(());
+        }
+        let prefix = keep_id(user_id, "");
+        auth_revocation::Entity::delete_many()
+            .filter(auth_revocation::Column::UserId.eq(user_id.to_string()))
+            .filter(auth_revocation::Column::Id.starts_with(prefix.as_str()))
+            .exec(&self.db)
+            .await?;
+        self.revocations.write().await.retain(|id, _| {
+            // jti never contains ':', so the last segment is always the token id
+            id.strip_prefix(&prefix).is_none_or(|jti| jti.contains(':'))
+        });
+        let now = Utc::now().timestamp();
+        // tokens issued before now are expired by then anyway
+        let expires_at = self.access_expires_at(now);
+        self.add_revocation(format!("user:{}", user_id), user_id, now, expires_at)
+            .await
+    }
+
+    async fn add_revocation(
+        &self,
+        id: String,
+        user_id: &str,
+        revoked_at: i64,
+        expires_at: i64,
+    ) -> DomainResult<()> {
+        match auth_revocation::Entity::find_by_id(id.clone())
+            .one(&self.db)
+            .await?
+        {
+            Some(model) => {
+                let mut active = model.into_active_model();
+                active.revoked_at = Set(revoked_at);
+                active.expires_at = Set(expires_at);
+                active.update(&self.db).await?;
+            }
+            None => {
+                auth_revocation::ActiveModel {
+                    id: Set(id.clone()),
+                    user_id: Set(user_id.to_string()),
+                    revoked_at: Set(revoked_at),
+                    expires_at: Set(expires_at),
                 }
+                .insert(&self.db)
+                .await?;
             }
-            let mut active = model.into_active_model();
-            active.last_seen_at = Set(Utc::now().to_rfc3339());
-            let updated = active.update(&self.db).await?;
-            Ok(Some(updated.user_id))
+        }
+        self.revocations.write().await.insert(id, revoked_at);
+        Ok(())
+    }
+
+    fn access_expires_at(&self, now: i64) -> i64 {
+        if self.policy.ttl_secs > 0 {
+            now + self.policy.ttl_secs as i64
         } else {
-            Ok(None)
+            0
         }
     }
 
+    fn new_issued_token(&self, user_id: &str, now: i64) -> IssuedToken {
+        let expires_at = self.access_expires_at(now);
+        let refresh_token = if self.policy.refresh_ttl_secs > 0 {
+            uuid::Uuid::new_v4().simple().to_string()
+        } else {
+            String::new()
+        };
+        IssuedToken {
+            token: format!("{}_{}", user_id, uuid::Uuid::new_v4()),
+            refresh_token,
+            expires_at,
+        }
+    }
+
+    fn refresh_expires_at(&self, issued: &IssuedToken, now: i64) -> i64 {
+        if issued.refresh_token.is_empty() {
+            0
+        } else {
+            now + self.policy.refresh_ttl_secs as i64
+        }
+    }
+
+    async fn user_enabled(&self, user_id: &str) -> DomainResult<bool> {
+        Ok(user::Entity::find_by_id(user_id.to_string())
+            .one(&self.db)
+            .await?
+            .map(|user| user.enabled)
+            .unwrap_or(true))
+    }
+
+    async fn is_idle(&self, model: &auth_token::Model, now: chrono::DateTime<Utc>) -> bool {
+        if self.policy.idle_secs == 0 {
+            return false;
+        }
+        let buffered = self.touched.lock().await.get(&model.token).cloned();
+        let last_seen = buffered.as_deref().unwrap_or(&model.last_seen_at);
+        chrono::DateTime::parse_from_rfc3339(last_seen)
+            .map(|at| {
+                (now - at.with_timezone(&Utc)).num_milliseconds()
+                    > self.policy.idle_secs as i64 * 1000
+            })
+            .unwrap_or(false)
+    }
+
     pub async fn revoke_by_user(&self, user_id: &str) -> DomainResult<u64> {
+        self.revoke_signed_user(user_id).await?;
         let result = auth_token::Entity::delete_many()
             .filter(auth_token::Column::UserId.eq(user_id.to_string()))
             .exec(&self.db)
@@ -71,10 +466,211 @@ impl AuthService {
     }
 
     pub async fn revoke_token(&self, token: &str) -> DomainResult<bool> {
+        if let Some(signer) = self.signer.as_ref().filter(|_| is_signed_token(token)) {
+            let Some(claims) = signer.verify(token) else {
+                return Ok(false);
+            };
+            self.add_revocation(
+                format!("jti:{}", claims.jti),
+                &claims.sub,
+                Utc::now().timestamp(),
+                claims.exp,
+            )
+            .await?;
+            return Ok(true);
+        }
         let rows = auth_token::Entity::delete_by_id(token.to_string())
             .exec(&self.db)
             .await?
             .rows_affected;
         Ok(rows > 0)
     }
+
+    // remembers which ws session the token is connected as, so revoking can kick it
+    pub async fn bind_device(&self, token: &str, device: &str) -> DomainResult<()> {
+        if let Some(signer) = self.signer.as_ref().filter(|_| is_signed_token(token)) {
+            return self.bind_signed_device(signer, token, device).await;
+        }
+        let Some(model) = auth_token::Entity::find_by_id(token.to_string())
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(());
+        };
+        if model.device == device {
+            return Ok(());
+        }
+        let mut active = model.into_active_model();
+        active.device = Set(device.to_string());
+        active.update(&self.db).await?;
+        Ok(())
+    }
+
+    // signed tokens get a session row once they connect, so they can be listed and
+    // revoked per device; the row is bookkeeping only and never validates the token
+    async fn bind_signed_device(
+        &self,
+        signer: &TokenSigner,
+        token: &str,
+        device: &str,
+    ) -> DomainResult<()> {
+        let Some(claims) = signer.verify(token) else {
+            return Ok(());
+        };
+        let now = Utc::now().to_rfc3339();
+        match auth_token::Entity::find_by_id(token.to_string())
+            .one(&self.db)
+            .await?
+        {
+            Some(model) if model.device == device => {}
+            Some(model) => {
+                let mut active = model.into_active_model();
+                active.device = Set(device.to_string());
+                active.last_seen_at = Set(now);
+                active.update(&self.db).await?;
+            }
+            None => {
+                let created_at = chrono::DateTime::from_timestamp(claims.iat, 0)
+                    .map(|at| at.to_rfc3339())
+                    .unwrap_or_else(|| now.clone());
+                auth_token::ActiveModel {
+                    token: Set(token.to_string()),
+                    user_id: Set(claims.sub),
+                    created_at: Set(created_at),
+                    last_seen_at: Set(now),
+                    device: Set(device.to_string()),
+                    device_name: Set(claims.dev),
+                    platform: Set(String::new()),
+                    app_version: Set(String::new()),
+                    ip: Set(String::new()),
+                    expires_at: Set(claims.exp),
+                    refresh_token: Set(String::new()),
+                    refresh_expires_at: Set(0),
+                }
+                .insert(&self.db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn list_sessions(&self, user_id: &str) -> DomainResult<Vec<DeviceSession>> {
+        let items = auth_token::Entity::find()
+            .filter(auth_token::Column::UserId.eq(user_id.to_string()))
+            .order_by_desc(auth_token::Column::LastSeenAt)
+            .all(&self.db)
+            .await?;
+        Ok(items.into_iter().map(DeviceSession::from).collect())
+    }
+
+    // returns the ws device bound to the revoked session, empty if it never connected
+    pub async fn revoke_session(&self, user_id: &str, session_id: &str) -> DomainResult<String> {
+        let items = auth_token::Entity::find()
+            .filter(auth_token::Column::UserId.eq(user_id.to_string()))
+            .all(&self.db)
+            .await?;
+        let model = items
+            .into_iter()
+            .find(|item| session_id_of(&item.token) == session_id)
+            .ok_or(DomainError::NotFound)?;
+        // deleting the row only drops the listing for a signed token, revoke its jti too
+        if is_signed_token(&model.token) {
+            self.revoke_token(&model.token).await?;
+        }
+        auth_token::Entity::delete_by_id(model.token)
+            .exec(&self.db)
+            .await?;
+        Ok(model.device)
+    }
+
+    // an empty keep_token revokes every session of the user. Signed tokens cannot be
+    // enumerated, so they are revoked user-wide and a signed keep_token is exempted
+    pub async fn revoke_other_sessions(
+        &self,
+        user_id: &str,
+        keep_token: &str,
+    ) -> DomainResult<Vec<String>> {
+        self.revoke_signed_user(user_id).await?;
+        if let Some(signer) = self.signer.as_ref().filter(|_| is_signed_token(keep_token)) {
+            if let Some(claims) = signer.verify(keep_token).filter(|c| c.sub == user_id) {
+                self.add_revocation(
+                    keep_id(user_id, &claims.jti),
+                    user_id,
+                    Utc::now().timestamp(),
+                    claims.exp,
+                )
+                .await?;
+            }
+        }
+        let mut query =
+            auth_token::Entity::find().filter(auth_token::Column::UserId.eq(user_id.to_string()));
+        if !keep_token.is_empty() {
+            query = query.filter(auth_token::Column::Token.ne(keep_token.to_string()));
+        }
+        let items = query.all(&self.db).await?;
+        let mut devices = Vec::new();
+        for item in items {
+            auth_token::Entity::delete_by_id(item.token)
+                .exec(&self.db)
+                .await?;
+            if !item.device.is_empty() {
+                devices.push(item.device);
+            }
+        }
+        Ok(devices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_refresh_rotates_once() {
+        let path = std::env::temp_dir().join(format!(
+            "restsend-refresh-{}.sqlite3",
+            uuid::Uuid::new_v4().simple()
+        ));
+        let db = crate::infra::db::connect_db(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        crate::infra::db::run_migrations(&db).await.unwrap();
+        let auth = AuthService::new(db.clone()).with_policy(TokenPolicy {
+            ttl_secs: 3600,
+            idle_secs: 0,
+            refresh_ttl_secs: 3600,
+        });
+        let issued = auth
+            .issue_token_with_device("alice", &DeviceInfoForm::default(), "")
+            .await
+            .unwrap();
+
+        let tasks = (0..20)
+            .map(|_| {
+                let auth = auth.clone();
+                let refresh_token = issued.refresh_token.clone();
+                tokio::spawn(async move { auth.refresh(&refresh_token).await.unwrap() })
+            })
+            .collect::<Vec<_>>();
+        let mut rotated = Vec::new();
+        for task in tasks {
+            match task.await.unwrap() {
+                Ok((_, next)) => rotated.push(next),
+                Err(status) => assert_eq!(status, TokenStatus::Invalid),
+            }
+        }
+        assert_eq!(rotated.len(), 1);
+        assert_eq!(auth_token::Entity::find().all(&db).await.unwrap().len(), 1);
+        assert_eq!(
+            auth.validate_status(&rotated[0].token).await.unwrap(),
+            TokenStatus::Valid("alice".to_string())
+        );
+        assert_eq!(
+            auth.validate_status(&issued.token).await.unwrap(),
+            TokenStatus::Invalid
+        );
+
+        db.close().await.unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
 }
@@ -1,25 +1,83 @@
+use std::sync::Arc;
+
 use chrono::Utc;
 use sea_orm::sea_query::Expr;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
-    QueryOrder, QuerySelect,
+    QueryOrder, QuerySelect, TransactionTrait,
 };
 
-use crate::entity::{chat_log, topic};
-use crate::services::{DomainError, DomainResult};
+use crate::entity::{chat_log, relation, topic};
+use crate::services::{chat_event_id, record_event, DomainError, DomainResult, NewWebhookEvent};
 use crate::{
-    ChatLog, ChatLogSyncForm, ChatLogSyncResult, OpenApiChatMessageForm,
+    ChatLog, ChatLogSyncForm, ChatLogSyncResult, Content, OpenApiChatMessageForm,
     OpenApiImportTopicMessageForm, OpenApiImportTopicMessageResponse, OpenApiSendMessageResponse,
+    PreSendHookRequest,
 };
 
+pub enum PreSendDecision {
+    Allow,
+    Reject { code: u16, reason: String },
+    // boxed, a content is much larger than the other variants
+    Rewrite(Box<Content>),
+}
+
+// consulted before a message is stored, implementations apply their own
+// timeout and failure policy so a decision is always returned
+#[async_trait::async_trait]
+pub trait PreSendHook: Send + Sync {
+    async fn check(&self, request: &PreSendHookRequest) -> PreSendDecision;
+}
+
 #[derive(Clone)]
 pub struct ChatService {
     db: DatabaseConnection,
+    dm_contacts_only: bool,
+    webhook_outbox: bool,
+    presend_hook: Option<Arc<dyn PreSendHook>>,
 }
 
 impl ChatService {
     pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+        Self {
+            db,
+            dm_contacts_only: false,
+            webhook_outbox: false,
+            presend_hook: None,
+        }
+    }
+
+    pub fn with_dm_contacts_only(mut self, enabled: bool) -> Self {
+        self.dm_contacts_only = enabled;
+        self
+    }
+
+    // writes the chat webhook event in the same transaction as the chat log,
+    // so a crash after commit cannot lose the delivery
+    pub fn with_webhook_outbox(mut self, enabled: bool) -> Self {
+        self.webhook_outbox = enabled;
+        self
+    }
+
+    pub fn with_presend_hook(mut self, hook: Arc<dyn PreSendHook>) -> Self {
+        self.presend_hook = Some(hook);
+        self
+    }
+
+    // with the contacts-only policy the attendee must have accepted the sender
+    pub async fn ensure_dm_allowed(&self, sender_id: &str, attendee_id: &str) -> DomainResult<()> {
+        if !self.dm_contacts_only || sender_id == attendee_id {
+            return Ok(());
+        }
+        let allowed =
+            relation::Entity::find_by_id((attendee_id.to_string(), sender_id.to_string()))
+                .one(&self.db)
+                .await?
+                .is_some_and(|r| r.is_contact && !r.is_blocked);
+        if !allowed {
+            return Err(DomainError::Forbidden);
+        }
+        Ok(())
     }
 
     pub async fn send_to_topic(
@@ -153,9 +211,13 @@ impl ChatService {
                 "attendee id is required".to_string(),
             ));
         }
-        let result = self
-            .send_internal(None, sender_id, Some(attendee_id.to_string()), form)
-            .await;
+        let result = match self.ensure_dm_allowed(sender_id, attendee_id).await {
+            Ok(()) => {
+                self.send_internal(None, sender_id, Some(attendee_id.to_string()), form)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
         tracing::info!(
             sender_id = %sender_id,
             attendee_id = %attendee_id,
@@ -196,8 +258,7 @@ impl ChatService {
 
         self.ensure_topic_enabled(&target_topic).await?;
 
-        let seq = self.next_topic_seq(&target_topic).await?;
-        let content = form.content.clone().unwrap_or_else(|| crate::Content {
+        let mut content = form.content.clone().unwrap_or_else(|| crate::Content {
             content_type: if form.r#type.is_empty() {
                 "chat".to_string()
             } else {
@@ -206,6 +267,32 @@ impl ChatService {
             text: form.message.clone(),
             ..crate::Content::default()
         });
+        let mut rewritten = false;
+        if let Some(hook) = self.presend_hook.as_ref() {
+            let request = PreSendHookRequest {
+                topic_id: target_topic.clone(),
+                sender_id: sender_id.to_string(),
+                attendee_id: attendee_id.clone().unwrap_or_default(),
+                chat_id: chat_id.clone(),
+                content: content.clone(),
+            };
+            match hook.check(&request).await {
+                PreSendDecision::Allow => {}
+                PreSendDecision::Reject { code, reason } => {
+                    return Err(DomainError::Rejected { code, reason });
+                }
+                PreSendDecision::Rewrite(replaced) => {
+                    let mut replaced = *replaced;
+                    if replaced.content_type.is_empty() {
+                        replaced.content_type = content.content_type.clone();
+                    }
+                    content = replaced;
+                    rewritten = true;
+                }
+            }
+        }
+
+        let seq = self.next_topic_seq(&target_topic).await?;
 
         let log = ChatLog {
             topic_id: target_topic.clone(),
@@ -217,12 +304,34 @@ impl ChatService {
                 now.clone()
             },
             sender_id: sender_id.to_string(),
-            content,
+            content: content.clone(),
             ..ChatLog::default()
         };
 
+        let event = self.webhook_outbox.then(|| NewWebhookEvent {
+            id: chat_event_id(&log.topic_id, &log.id),
+            name: "chat".to_string(),
+            topic_id: log.topic_id.clone(),
+            data: serde_json::json!({
+                "topicId": log.topic_id,
+                "senderId": log.sender_id,
+                "chatId": log.id,
+                "seq": log.seq,
+                "createdAt": log.created_at,
+                "content": log.content,
+            }),
+            explicit_targets: vec![],
+            use_topic_webhooks: true,
+        });
         let active: chat_log::ActiveModel = log.into();
-        active.insert(&self.db).await?;
+        if let Some(event) = event {
+            let txn = self.db.begin().await?;
+            active.insert(&txn).await?;
+            record_event(&txn, &event, Utc::now().timestamp()).await?;
+            txn.commit().await?;
+        } else {
+            active.insert(&self.db).await?;
+        }
 
         Ok(OpenApiSendMessageResponse {
             sender_id: sender_id.to_string(),
@@ -233,6 +342,7 @@ impl ChatService {
             message: "ok".to_string(),
             seq,
             usage: 0,
+            content: rewritten.then_some(content),
         })
     }
 
@@ -13,6 +13,9 @@ pub enum DomainError {
     Validation(String),
     #[error("storage error: {0}")]
     Storage(String),
+    // vetoed by the pre-send hook, code and reason are shown to the sender
+    #[error("rejected: {reason}")]
+    Rejected { code: u16, reason: String },
 }
 
 impl From<DbErr> for DomainError {
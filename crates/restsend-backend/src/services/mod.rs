@@ -1,17 +1,28 @@
+mod api_key;
 mod auth;
 mod auth_policy;
 mod chat;
 mod conversation;
 mod error;
 mod relation;
+mod token_signer;
 mod topic;
 mod user;
+mod webhook_outbox;
+mod webhook_subscription;
 
-pub use auth::AuthService;
+pub use api_key::{ip_in_cidrs, ApiKeyGrant, ApiKeyService, ApiScope};
+pub use auth::{session_id_of, AuthService, IssuedToken, TokenPolicy, TokenStatus};
 pub use auth_policy::parse_bearer_token;
-pub use chat::ChatService;
+pub use chat::{ChatService, PreSendDecision, PreSendHook};
 pub use conversation::ConversationService;
 pub use error::{DomainError, DomainResult};
 pub use relation::RelationService;
+pub use token_signer::{TokenClaims, TokenSigner};
 pub use topic::TopicService;
-pub use user::UserService;
+pub use user::{UserService, PRESENCE_INVISIBLE};
+pub use webhook_outbox::{
+    chat_event_id, delivery_body, explicit_targets, record_event, NewWebhookEvent,
+    WebhookOutboxService, WebhookTarget,
+};
+pub use webhook_subscription::WebhookSubscriptionService;
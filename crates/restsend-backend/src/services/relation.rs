@@ -1,12 +1,15 @@
 use chrono::Utc;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait,
-    IntoActiveModel, QueryFilter,
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, DatabaseConnection, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, QuerySelect,
 };
 
-use crate::entity::relation;
+use crate::entity::{contact_request, relation, user};
 use crate::services::{DomainError, DomainResult};
-use crate::{OpenApiRelationEditForm, Relation};
+use crate::{
+    ContactRequest, ContactRequestForm, ContactRequestListForm, ContactSyncForm, ListUserResult,
+    OpenApiRelationEditForm, Relation,
+};
 
 #[derive(Clone)]
 pub struct RelationService {
@@ -81,6 +84,17 @@ impl RelationService {
         Ok(rows.into_iter().map(|r| r.target_id).collect())
     }
 
+    pub async fn list_contacts(&self, owner_id: &str) -> DomainResult<Vec<String>> {
+        let rows = relation::Entity::find()
+            .filter(relation::Column::OwnerId.eq(owner_id.to_string()))
+            .filter(relation::Column::IsContact.eq(true))
+            .filter(relation::Column::IsBlocked.eq(false))
+            .all(&self.db)
+            .await?;
+
+        Ok(rows.into_iter().map(|r| r.target_id).collect())
+    }
+
     pub async fn update_blocked(
         &self,
         owner_id: &str,
@@ -99,4 +113,405 @@ impl RelationService {
         }
         Ok(done)
     }
+
+    // a full sync returns contacts and starred users only; an incremental one
+    // also reports relations that stopped being either as removed
+    pub async fn sync_contacts(
+        &self,
+        owner_id: &str,
+        form: ContactSyncForm,
+    ) -> DomainResult<ListUserResult> {
+        let limit = if form.limit == 0 {
+            100
+        } else {
+            form.limit.clamp(1, 500)
+        };
+        let snapshot_at = Utc::now().to_rfc3339();
+        let mut query =
+            relation::Entity::find().filter(relation::Column::OwnerId.eq(owner_id.to_string()));
+        if form.updated_at.is_empty() {
+            query = query.filter(
+                Condition::any()
+                    .add(relation::Column::IsContact.eq(true))
+                    .add(relation::Column::IsStar.eq(true)),
+            );
+        } else {
+            query = query.filter(relation::Column::UpdatedAt.gt(form.updated_at.clone()));
+        }
+        if !form.cursor.is_empty() {
+            query = query.filter(relation::Column::TargetId.gt(form.cursor.clone()));
+        }
+        let mut rows = query
+            .order_by_asc(relation::Column::TargetId)
+            .limit(limit + 1)
+            .all(&self.db)
+            .await?;
+        let has_more = rows.len() as u64 > limit;
+        rows.truncate(limit as usize);
+        let cursor = rows.last().map(|r| r.target_id.clone()).unwrap_or_default();
+
+        let mut items = Vec::with_capacity(rows.len());
+        let mut removed = Vec::new();
+        for row in rows {
+            if !row.is_contact && !row.is_star {
+                removed.push(row.target_id);
+                continue;
+            }
+            let Some(model) = user::Entity::find_by_id(row.target_id.clone())
+                .one(&self.db)
+                .await?
+            else {
+                removed.push(row.target_id);
+                continue;
+            };
+            let mut user: crate::User = model.into();
+            user.remark = row.remark;
+            user.is_contact = row.is_contact;
+            user.is_star = row.is_star;
+            user.is_blocked = row.is_blocked;
+            items.push(user);
+        }
+        Ok(ListUserResult {
+            has_more,
+            updated_at: snapshot_at,
+            cursor,
+            items,
+            removed,
+            ..ListUserResult::default()
+        })
+    }
+
+    pub async fn is_contact(&self, owner_id: &str, target_id: &str) -> DomainResult<bool> {
+        let row = relation::Entity::find_by_id((owner_id.to_string(), target_id.to_string()))
+            .one(&self.db)
+            .await?;
+        Ok(row.is_some_and(|r| r.is_contact && !r.is_blocked))
+    }
+
+    // sending a request back to someone who already asked accepts theirs
+    pub async fn send_contact_request(
+        &self,
+        sender_id: &str,
+        receiver_id: &str,
+        form: ContactRequestForm,
+    ) -> DomainResult<ContactRequest> {
+        if sender_id.trim().is_empty() || receiver_id.trim().is_empty() {
+            return Err(DomainError::Validation(
+                "sender_id and receiver_id are required".to_string(),
+            ));
+        }
+        if sender_id == receiver_id {
+            return Err(DomainError::Validation(
+                "cannot add yourself as contact".to_string(),
+            ));
+        }
+        if user::Entity::find_by_id(receiver_id.to_string())
+            .one(&self.db)
+            .await?
+            .is_none()
+        {
+            return Err(DomainError::NotFound);
+        }
+        let blocked =
+            relation::Entity::find_by_id((receiver_id.to_string(), sender_id.to_string()))
+                .one(&self.db)
+                .await?
+                .is_some_and(|r| r.is_blocked);
+        if blocked {
+            return Err(DomainError::Forbidden);
+        }
+        if self.is_contact(sender_id, receiver_id).await?
+            && self.is_contact(receiver_id, sender_id).await?
+        {
+            return Err(DomainError::Validation("already contacts".to_string()));
+        }
+
+        let reverse =
+            contact_request::Entity::find_by_id((receiver_id.to_string(), sender_id.to_string()))
+                .one(&self.db)
+                .await?;
+        if reverse.is_some_and(|r| r.status == "pending") {
+            return self.accept_contact_request(sender_id, receiver_id).await;
+        }
+
+        let now = Utc::now().to_rfc3339();
+        let existing =
+            contact_request::Entity::find_by_id((sender_id.to_string(), receiver_id.to_string()))
+                .one(&self.db)
+                .await?;
+        let saved = match existing {
+            Some(row) => {
+                let mut active = row.into_active_model();
+                active.message = Set(form.message);
+                active.source = Set(form.source);
+                active.status = Set("pending".to_string());
+                active.updated_at = Set(now);
+                active.update(&self.db).await?
+            }
+            None => {
+                contact_request::ActiveModel {
+                    sender_id: Set(sender_id.to_string()),
+                    receiver_id: Set(receiver_id.to_string()),
+                    message: Set(form.message),
+                    source: Set(form.source),
+                    status: Set("pending".to_string()),
+                    created_at: Set(now.clone()),
+                    updated_at: Set(now),
+                }
+                .insert(&self.db)
+                .await?
+            }
+        };
+        Ok(saved.into())
+    }
+
+    pub async fn list_contact_requests(
+        &self,
+        user_id: &str,
+        form: ContactRequestListForm,
+    ) -> DomainResult<Vec<ContactRequest>> {
+        let mut query = contact_request::Entity::find();
+        query = match form.direction.as_str() {
+            "" | "incoming" => {
+                query.filter(contact_request::Column::ReceiverId.eq(user_id.to_string()))
+            }
+            "outgoing" => query.filter(contact_request::Column::SenderId.eq(user_id.to_string())),
+            _ => {
+                return Err(DomainError::Validation(
+                    "direction must be incoming or outgoing".to_string(),
+                ))
+            }
+        };
+        let status = if form.status.is_empty() {
+            "pending".to_string()
+        } else {
+            form.status
+        };
+        if status != "all" {
+            query = query.filter(contact_request::Column::Status.eq(status));
+        }
+        let rows = query
+            .order_by_desc(contact_request::Column::UpdatedAt)
+            .all(&self.db)
+            .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn accept_contact_request(
+        &self,
+        receiver_id: &str,
+        sender_id: &str,
+    ) -> DomainResult<ContactRequest> {
+        let accepted = self
+            .finish_contact_request(sender_id, receiver_id, "accepted")
+            .await?;
+        for (owner_id, target_id) in [(sender_id, receiver_id), (receiver_id, sender_id)] {
+            let form = OpenApiRelationEditForm {
+                is_contact: Some(true),
+                source: accepted.source.clone(),
+                ..OpenApiRelationEditForm::default()
+            };
+            let _ = self.update_relation(owner_id, target_id, form).await?;
+        }
+        Ok(accepted)
+    }
+
+    pub async fn decline_contact_request(
+        &self,
+        receiver_id: &str,
+        sender_id: &str,
+    ) -> DomainResult<ContactRequest> {
+        self.finish_contact_request(sender_id, receiver_id, "declined")
+            .await
+    }
+
+    pub async fn cancel_contact_request(
+        &self,
+        sender_id: &str,
+        receiver_id: &str,
+    ) -> DomainResult<ContactRequest> {
+        self.finish_contact_request(sender_id, receiver_id, "canceled")
+            .await
+    }
+
+    async fn finish_contact_request(
+        &self,
+        sender_id: &str,
+        receiver_id: &str,
+        status: &str,
+    ) -> DomainResult<ContactRequest> {
+        let row =
+            contact_request::Entity::find_by_id((sender_id.to_string(), receiver_id.to_string()))
+                .one(&self.db)
+                .await?
+                .ok_or(DomainError::NotFound)?;
+        if row.status != "pending" {
+            return Err(DomainError::Validation(format!(
+                "contact request already {}",
+                row.status
+            )));
+        }
+        let mut active = row.into_active_model();
+        active.status = Set(status.to_string());
+        active.updated_at = Set(Utc::now().to_rfc3339());
+        Ok(active.update(&self.db).await?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::db::run_migrations;
+    use sea_orm::Database;
+    use uuid::Uuid;
+
+    async fn setup() -> RelationService {
+        let db_url = format!(
+            "sqlite:file:test-{}?mode=memory&cache=shared",
+            Uuid::new_v4().simple()
+        );
+        let db = Database::connect(&db_url).await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let now = Utc::now().to_rfc3339();
+        for user_id in ["alice", "bob", "carol"] {
+            let u = crate::User {
+                user_id: user_id.to_string(),
+                enabled: true,
+                created_at: now.clone(),
+                ..crate::User::default()
+            };
+            user::ActiveModel::from((u, now.as_str()))
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+        RelationService::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_contact_request_flow() {
+        let service = setup().await;
+
+        let req = service
+            .send_contact_request("alice", "bob", ContactRequestForm::default())
+            .await
+            .unwrap();
+        assert_eq!(req.status, "pending");
+        assert!(!service.is_contact("alice", "bob").await.unwrap());
+
+        let incoming = service
+            .list_contact_requests("bob", ContactRequestListForm::default())
+            .await
+            .unwrap();
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming[0].sender_id, "alice");
+
+        let accepted = service
+            .accept_contact_request("bob", "alice")
+            .await
+            .unwrap();
+        assert_eq!(accepted.status, "accepted");
+        assert!(service.is_contact("alice", "bob").await.unwrap());
+        assert!(service.is_contact("bob", "alice").await.unwrap());
+        assert!(service
+            .send_contact_request("alice", "bob", ContactRequestForm::default())
+            .await
+            .is_err());
+
+        service
+            .send_contact_request("alice", "carol", ContactRequestForm::default())
+            .await
+            .unwrap();
+        service
+            .cancel_contact_request("alice", "carol")
+            .await
+            .unwrap();
+        assert!(service
+            .decline_contact_request("carol", "alice")
+            .await
+            .is_err());
+
+        // carol asks alice while alice has a pending request to carol
+        service
+            .send_contact_request("alice", "carol", ContactRequestForm::default())
+            .await
+            .unwrap();
+        let crossed = service
+            .send_contact_request("carol", "alice", ContactRequestForm::default())
+            .await
+            .unwrap();
+        assert_eq!(crossed.status, "accepted");
+        assert!(service.is_contact("carol", "alice").await.unwrap());
+
+        service
+            .update_blocked("bob", &["carol".to_string()], true)
+            .await
+            .unwrap();
+        assert!(matches!(
+            service
+                .send_contact_request("carol", "bob", ContactRequestForm::default())
+                .await,
+            Err(DomainError::Forbidden)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sync_contacts_reports_removed() {
+        let service = setup().await;
+        for target_id in ["bob", "carol"] {
+            let form = OpenApiRelationEditForm {
+                is_contact: Some(true),
+                ..OpenApiRelationEditForm::default()
+            };
+            service
+                .update_relation("alice", target_id, form)
+                .await
+                .unwrap();
+        }
+
+        let first = service
+            .sync_contacts(
+                "alice",
+                ContactSyncForm {
+                    limit: 1,
+                    ..ContactSyncForm::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(first.has_more);
+        assert_eq!(first.items[0].user_id, "bob");
+        assert!(first.items[0].is_contact);
+        let second = service
+            .sync_contacts(
+                "alice",
+                ContactSyncForm {
+                    cursor: first.cursor.clone(),
+                    limit: 1,
+                    ..ContactSyncForm::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(!second.has_more);
+        assert_eq!(second.items[0].user_id, "carol");
+
+        let form = OpenApiRelationEditForm {
+            is_contact: Some(false),
+            ..OpenApiRelationEditForm::default()
+        };
+        service.update_relation("alice", "bob", form).await.unwrap();
+        let delta = service
+            .sync_contacts(
+                "alice",
+                ContactSyncForm {
+                    updated_at: first.updated_at.clone(),
+                    ..ContactSyncForm::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(delta.items.is_empty());
+        assert_eq!(delta.removed, vec!["bob".to_string()]);
+    }
 }
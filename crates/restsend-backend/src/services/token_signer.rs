@@ -0,0 +1,152 @@
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use ring::hmac;
+use ring::signature::{self, Ed25519KeyPair, KeyPair};
+use serde::{Deserialize, Serialize};
+
+pub const ALG_HS256: &str = "HS256";
+pub const ALG_EDDSA: &str = "EdDSA";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenClaims {
+    pub sub: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub dev: String,
+    #[serde(default)]
+    pub iat: i64,
+    // 0 never expires
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub exp: i64,
+    pub jti: String,
+}
+
+fn is_zero(v: &i64) -> bool {
+    *v == 0
+}
+
+#[derive(Serialize, Deserialize)]
+struct TokenHeader {
+    alg: String,
+    #[serde(default)]
+    kid: String,
+    #[serde(default)]
+    typ: String,
+}
+
+enum SigningKey {
+    Hs256(hmac::Key),
+    EdDsa(Ed25519KeyPair),
+}
+
+struct KeyEntry {
+    kid: String,
+    key: SigningKey,
+}
+
+// the first key signs new tokens, the rest only verify so keys can be rotated
+pub struct TokenSigner {
+    alg: &'static str,
+    keys: Vec<KeyEntry>,
+}
+
+impl TokenSigner {
+    // keys are `kid:secret`; EdDSA secrets are base64 encoded 32 byte seeds
+    pub fn new(alg: &str, keys: &[String]) -> Result<Self, String> {
+        let alg = if alg.eq_ignore_ascii_case(ALG_HS256) {
+            ALG_HS256
+        } else if alg.eq_ignore_ascii_case(ALG_EDDSA) {
+            ALG_EDDSA
+        } else {
+            return Err(format!("unsupported signed token alg: {alg}"));
+        };
+        let mut entries = Vec::new();
+        for item in keys {
+            let Some((kid, secret)) = item.split_once(':') else {
+                return Err(format!("signed token key must be kid:secret, got {item}"));
+            };
+            let (kid, secret) = (kid.trim(), secret.trim());
+            if kid.is_empty() || secret.is_empty() {
+                return Err("signed token key id and secret must not be empty".to_string());
+            }
+            let key = match alg {
+                ALG_HS256 => {
+                    SigningKey::Hs256(hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()))
+                }
+                _ => {
+                    let seed = URL_SAFE_NO_PAD
+                        .decode(secret.trim_end_matches('='))
+                        .or_else(|_| STANDARD.decode(secret))
+                        .map_err(|_| format!("signed token key {kid} is not valid base64"))?;
+                    let pair = Ed25519KeyPair::from_seed_unchecked(&seed)
+                        .map_err(|_| format!("signed token key {kid} must be a 32 byte seed"))?;
+                    SigningKey::EdDsa(pair)
+                }
+            };
+            entries.push(KeyEntry {
+                kid: kid.to_string(),
+                key,
+            });
+        }
+        if entries.is_empty() {
+            return Err("signed tokens need at least one key".to_string());
+        }
+        Ok(Self { alg, keys: entries })
+    }
+
+    pub fn sign(&self, claims: &TokenClaims) -> String {
+        let entry = &self.keys[0];
+        let header = TokenHeader {
+            alg: self.alg.to_string(),
+            kid: entry.kid.clone(),
+            typ: "JWT".to_string(),
+        };
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header).unwrap_or_default()),
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).unwrap_or_default())
+        );
+        let signature = match &entry.key {
+            SigningKey::Hs256(key) => hmac::sign(key, signing_input.as_bytes()).as_ref().to_vec(),
+            SigningKey::EdDsa(pair) => pair.sign(signing_input.as_bytes()).as_ref().to_vec(),
+        };
+        format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    // checks the signature only, expiry and revocation are up to the caller
+    pub fn verify(&self, token: &str) -> Option<TokenClaims> {
+        let (signing_input, signature) = token.rsplit_once('.')?;
+        let (_, payload) = signing_input.split_once('.')?;
+        let header = parse_header(token)?;
+        if header.alg != self.alg {
+            return None;
+        }
+        let entry = self.keys.iter().find(|entry| entry.kid == header.kid)?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+        let verified = match &entry.key {
+            SigningKey::Hs256(key) => {
+                hmac::verify(key, signing_input.as_bytes(), &signature).is_ok()
+            }
+            SigningKey::EdDsa(pair) => {
+                signature::UnparsedPublicKey::new(&signature::ED25519, pair.public_key().as_ref())
+                    .verify(signing_input.as_bytes(), &signature)
+                    .is_ok()
+            }
+        };
+        if !verified {
+            return None;
+        }
+        let payload = URL_SAFE_NO_PAD.decode(payload).ok()?;
+        serde_json::from_slice(&payload).ok()
+    }
+}
+
+// opaque `userid_uuid` tokens may contain dots too, so look at the header
+pub fn is_signed_token(token: &str) -> bool {
+    token.split('.').count() == 3 && parse_header(token).is_some()
+}
+
+fn parse_header(token: &str) -> Option<TokenHeader> {
+    let header = token.split('.').next()?;
+    let header = URL_SAFE_NO_PAD.decode(header).ok()?;
+    serde_json::from_slice(&header).ok()
+}
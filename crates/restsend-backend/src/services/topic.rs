@@ -1,16 +1,23 @@
 use chrono::{Duration, Utc};
+use sea_orm::sea_query::{Expr, Query};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait,
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, DatabaseConnection, EntityTrait,
     IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
 };
 
-use crate::entity::{topic, topic_knock, topic_member};
+use crate::entity::{
+    decode_json, encode_json, relation, topic, topic_ban, topic_invite, topic_knock,
+    topic_knock_rule, topic_member, topic_role, user,
+};
 use crate::services::{DomainError, DomainResult};
 use crate::{
     ListUserResult, OpenApiCreateTopicForm, OpenApiSilentTopicForm, OpenApiSilentTopicMembersForm,
     OpenApiUpdateTopicExtraForm, OpenApiUpdateTopicForm, OpenApiUpdateTopicMemberForm, Topic,
-    TopicKnock, TopicKnockAcceptedForm, TopicKnockForm, TopicKnockRejectedForm, TopicMember,
-    UpdateNoticeForm,
+    TopicBan, TopicBanForm, TopicDirectoryForm, TopicDirectoryItem, TopicDirectoryResult,
+    TopicInvite, TopicInviteForm, TopicInviteJoinResult, TopicInvitePreview, TopicKnock,
+    TopicKnockAcceptedForm, TopicKnockForm, TopicKnockRejectedForm, TopicKnockRule,
+    TopicKnockRuleForm, TopicMember, TopicMemberListForm, TopicPermission, TopicRole,
+    TopicRoleForm, UpdateNoticeForm,
 };
 
 #[derive(Clone)]
@@ -18,6 +25,14 @@ pub struct TopicService {
     db: DatabaseConnection,
 }
 
+pub struct TopicWebhooks {
+    pub targets: Vec<String>,
+    // empty when the topic has no secret of its own
+    pub secret: String,
+    // set for helpdesk conversations
+    pub inbox_id: Option<String>,
+}
+
 impl TopicService {
     pub fn new(db: DatabaseConnection) -> Self {
         Self { db }
@@ -34,6 +49,24 @@ impl TopicService {
         Ok(model.into())
     }
 
+    // webhook urls of an enabled topic with the secret that signs them
+    pub async fn webhooks(&self, topic_id: &str) -> DomainResult<TopicWebhooks> {
+        let model = topic::Entity::find_by_id(topic_id.to_string())
+            .one(&self.db)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+        if !model.enabled {
+            return Err(DomainError::Forbidden);
+        }
+        let secret = model.webhook_secret.clone();
+        let topic: Topic = model.into();
+        Ok(TopicWebhooks {
+            targets: topic.webhooks,
+            secret,
+            inbox_id: topic.extra.and_then(|v| v.get("inbox_id").cloned()),
+        })
+    }
+
     pub async fn get_any_by_id(&self, topic_id: &str) -> DomainResult<Topic> {
         let model = topic::Entity::find_by_id(topic_id.to_string())
             .one(&self.db)
@@ -143,7 +176,10 @@ impl TopicService {
             ..Topic::default()
         };
 
-        let active: topic::ActiveModel = (topic, now.as_str()).into();
+        let mut active: topic::ActiveModel = (topic, now.as_str()).into();
+        if let Some(v) = form.webhook_secret {
+            active.webhook_secret = Set(v);
+        }
         let created = active.insert(&self.db).await?;
 
         if form.ensure_conversation.unwrap_or(false) || !filtered_members.is_empty() {
@@ -200,6 +236,9 @@ impl TopicService {
             active.webhooks_json =
                 Set(serde_json::to_string(&form.webhooks).unwrap_or_else(|_| "[]".to_string()));
         }
+        if let Some(v) = form.webhook_secret {
+            active.webhook_secret = Set(v);
+        }
         if let Some(v) = form.notice {
             active.notice_json = Set(serde_json::to_string(&crate::TopicNotice {
                 text: v.text,
@@ -256,53 +295,211 @@ impl TopicService {
     }
 
     pub async fn list_members(&self, topic_id: &str) -> DomainResult<Vec<String>> {
-        if let Some(topic) = topic::Entity::find_by_id(topic_id.to_string())
-            .one(&self.db)
-            .await?
-        {
-            if !topic.multiple {
-                let mut members = Vec::new();
-                if !topic.owner_id.is_empty() {
-                    members.push(topic.owner_id.clone());
-                }
-                if !topic.attendee_id.is_empty()
-                    && topic.attendee_id != topic.owner_id
-                {
-                    members.push(topic.attendee_id);
-                }
-                return Ok(members);
-            }
+        if let Some(members) = self.direct_members(topic_id).await? {
+            return Ok(members);
+        }
+
+        let user_ids: Vec<String> = topic_member::Entity::find()
+            .select_only()
+            .column(topic_member::Column::UserId)
+            .filter(topic_member::Column::TopicId.eq(topic_id.to_string()))
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+        Ok(user_ids)
+    }
+
+    // first `limit` members in join order
+    pub async fn list_members_page(&self, topic_id: &str, limit: u64) -> DomainResult<Vec<String>> {
+        if let Some(mut members) = self.direct_members(topic_id).await? {
+            members.truncate(limit as usize);
+            return Ok(members);
         }
+        let user_ids: Vec<String> = topic_member::Entity::find()
+            .select_only()
+            .column(topic_member::Column::UserId)
+            .filter(topic_member::Column::TopicId.eq(topic_id.to_string()))
+            .order_by_asc(topic_member::Column::JoinedAt)
+            .order_by_asc(topic_member::Column::UserId)
+            .limit(limit)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+        Ok(user_ids)
+    }
 
-        let rows = topic_member::Entity::find()
+    // the subset of `user_ids` that are members, without loading the member list
+    pub async fn filter_members(
+        &self,
+        topic_id: &str,
+        user_ids: &[String],
+    ) -> DomainResult<Vec<String>> {
+        if user_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        if let Some(members) = self.direct_members(topic_id).await? {
+            return Ok(user_ids
+                .iter()
+                .filter(|user_id| members.contains(user_id))
+                .cloned()
+                .collect());
+        }
+        let user_ids: Vec<String> = topic_member::Entity::find()
+            .select_only()
+            .column(topic_member::Column::UserId)
             .filter(topic_member::Column::TopicId.eq(topic_id.to_string()))
+            .filter(topic_member::Column::UserId.is_in(user_ids.to_vec()))
+            .into_tuple()
             .all(&self.db)
             .await?;
-        Ok(rows.into_iter().map(|v| v.user_id).collect())
+        Ok(user_ids)
+    }
+
+    // direct chats take their members from the topic row, None for group topics
+    async fn direct_members(&self, topic_id: &str) -> DomainResult<Option<Vec<String>>> {
+        let Some(topic) = topic::Entity::find_by_id(topic_id.to_string())
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+        if topic.multiple {
+            return Ok(None);
+        }
+        let mut members = Vec::new();
+        if !topic.owner_id.is_empty() {
+            members.push(topic.owner_id.clone());
+        }
+        if !topic.attendee_id.is_empty() && topic.attendee_id != topic.owner_id {
+            members.push(topic.attendee_id);
+        }
+        Ok(Some(members))
     }
 
     pub async fn list_members_detailed(
         &self,
         topic_id: &str,
-        _updated_at: Option<&str>,
-        limit: Option<u64>,
+        viewer_id: &str,
+        form: TopicMemberListForm,
     ) -> DomainResult<ListUserResult> {
-        let user_ids = self.list_members(topic_id).await?;
-        let max = limit.unwrap_or(100).clamp(1, 500) as usize;
-        let mut items = Vec::new();
-        for user_id in user_ids.into_iter().take(max) {
-            if let Ok(user) = crate::services::UserService::new(self.db.clone())
-                .get_by_user_id(&user_id)
-                .await
-            {
+        let limit = if form.limit == 0 {
+            100
+        } else {
+            form.limit.clamp(1, 500)
+        };
+        let snapshot_at = now();
+        let topic = topic::Entity::find_by_id(topic_id.to_string())
+            .one(&self.db)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+        let user_service = crate::services::UserService::new(self.db.clone());
+        if !topic.multiple {
+            let mut items = Vec::new();
+            for user_id in self.list_members(topic_id).await? {
+                if let Ok(user) = user_service.get_by_user_id(&user_id).await {
+                    items.push(user);
+                }
+            }
+            return Ok(ListUserResult {
+                has_more: false,
+                updated_at: snapshot_at,
+                items,
+                ..ListUserResult::default()
+            });
+        }
+
+        let mut query = topic_member::Entity::find()
+            .filter(topic_member::Column::TopicId.eq(topic_id.to_string()));
+        if !form.updated_at.is_empty() {
+            query = query.filter(topic_member::Column::UpdatedAt.gt(form.updated_at.clone()));
+        }
+        if let Some((joined_at, user_id)) = form.cursor.split_once('|') {
+            query = query.filter(
+                Condition::any()
+                    .add(topic_member::Column::JoinedAt.gt(joined_at.to_string()))
+                    .add(
+                        Condition::all()
+                            .add(topic_member::Column::JoinedAt.eq(joined_at.to_string()))
+                            .add(topic_member::Column::UserId.gt(user_id.to_string())),
+                    ),
+            );
+        }
+        let admins: Vec<String> = decode_json(&topic.admins_json);
+        query = match form.role.as_str() {
+            "" => query,
+            "owner" => query.filter(topic_member::Column::UserId.eq(topic.owner_id.clone())),
+            "admin" => query.filter(topic_member::Column::UserId.is_in(admins)),
+            role => {
+                let mut cond = Condition::any().add(topic_member::Column::Role.eq(role));
+                if role == "member" {
+                    cond = cond.add(topic_member::Column::Role.eq(""));
+                }
+                let mut query = query.filter(cond);
+                if role == "member" {
+                    query = query
+                        .filter(topic_member::Column::UserId.ne(topic.owner_id.clone()))
+                        .filter(topic_member::Column::UserId.is_not_in(admins));
+                }
+                query
+            }
+        };
+        if let Some(keyword) = Some(form.keyword.trim()).filter(|v| !v.is_empty()) {
+            let mut cond = Condition::any()
+                .add(topic_member::Column::Name.contains(keyword))
+                .add(topic_member::Column::UserId.contains(keyword))
+                .add(
+                    topic_member::Column::UserId.in_subquery(
+                        Query::select()
+                            .column(user::Column::UserId)
+                            .from(user::Entity)
+                            .and_where(user::Column::DisplayName.contains(keyword))
+                            .to_owned(),
+                    ),
+                );
+            if !viewer_id.is_empty() {
+                cond = cond.add(
+                    topic_member::Column::UserId.in_subquery(
+                        Query::select()
+                            .column(relation::Column::TargetId)
+                            .from(relation::Entity)
+                            .and_where(relation::Column::OwnerId.eq(viewer_id.to_string()))
+                            .and_where(relation::Column::Remark.contains(keyword))
+                            .to_owned(),
+                    ),
+                );
+            }
+            query = query.filter(cond);
+        }
+
+        let mut rows: Vec<topic_member::Model> = query
+            .order_by_asc(topic_member::Column::JoinedAt)
+            .order_by_asc(topic_member::Column::UserId)
+            .limit(limit + 1)
+            .all(&self.db)
+            .await?;
+        let has_more = rows.len() as u64 > limit;
+        rows.truncate(limit as usize);
+        let cursor = rows
+            .last()
+            .map(|v| format!("{}|{}", v.joined_at, v.user_id))
+            .unwrap_or_default();
+
+        let mut items = Vec::with_capacity(rows.len());
+        let mut members = Vec::with_capacity(rows.len());
+        for row in rows {
+            if let Ok(user) = user_service.get_by_user_id(&row.user_id).await {
                 items.push(user);
+                members.push(row.into());
             }
         }
 
         Ok(ListUserResult {
-            has_more: false,
-            updated_at: now(),
+            has_more,
+            updated_at: snapshot_at,
+            cursor,
             items,
+            members,
+            ..ListUserResult::default()
         })
     }
 
@@ -312,6 +509,13 @@ impl TopicService {
         user_ids: Vec<String>,
         source: String,
     ) -> DomainResult<Vec<String>> {
+        for user_id in &user_ids {
+            if self.is_banned(topic_id, user_id).await? {
+                return Err(DomainError::Validation(format!(
+                    "user {user_id} is banned from topic"
+                )));
+            }
+        }
         let mut out = Vec::with_capacity(user_ids.len());
         let now = now();
         for user_id in user_ids {
@@ -364,6 +568,9 @@ impl TopicService {
             .filter(topic_member::Column::TopicId.eq(topic_id.to_string()))
             .exec(&self.db)
             .await;
+        let _ = topic_knock_rule::Entity::delete_by_id(topic_id.to_string())
+            .exec(&self.db)
+            .await;
         if rows == 0 {
             return Err(DomainError::NotFound);
         }
@@ -566,14 +773,14 @@ impl TopicService {
         topic_id: &str,
         user_id: &str,
         form: TopicKnockForm,
-    ) -> DomainResult<()> {
+    ) -> DomainResult<TopicInviteJoinResult> {
         let topic = self.get_by_id(topic_id).await?;
         if !topic.multiple {
             return Err(DomainError::Validation(
                 "not multiple user topic".to_string(),
             ));
         }
-        if topic.private {
+        if topic.private || self.is_banned(topic_id, user_id).await? {
             return Err(DomainError::Forbidden);
         }
 
@@ -581,46 +788,27 @@ impl TopicService {
             .one(&self.db)
             .await?;
         if exists.is_some() {
-            return Ok(());
+            return Ok(TopicInviteJoinResult {
+                topic_id: topic.id,
+                status: "member".to_string(),
+            });
         }
 
-        if !topic.knock_need_verify {
+        if !topic.knock_need_verify || self.knock_auto_approved(&topic, user_id).await? {
             self.join_members(topic_id, vec![user_id.to_string()], form.source)
                 .await?;
-            return Ok(());
+            return Ok(TopicInviteJoinResult {
+                topic_id: topic.id,
+                status: "joined".to_string(),
+            });
         }
 
-        let now_ts = now();
-        let knock_message = form.message.clone();
-        let knock_source = form.source.clone();
-        let knock = topic_knock::ActiveModel {
-            topic_id: Set(topic_id.to_string()),
-            user_id: Set(user_id.to_string()),
-            created_at: Set(now_ts.clone()),
-            updated_at: Set(now_ts),
-            message: Set(knock_message.clone()),
-            source: Set(knock_source.clone()),
-            status: Set("pending".to_string()),
-            admin_id: Set(String::new()),
-        };
-        match knock.insert(&self.db).await {
-            Ok(_) => {}
-            Err(_) => {
-                let existing =
-                    topic_knock::Entity::find_by_id((topic_id.to_string(), user_id.to_string()))
-                        .one(&self.db)
-                        .await?
-                        .ok_or(DomainError::NotFound)?;
-                let mut active = existing.into_active_model();
-                active.updated_at = Set(now());
-                active.message = Set(knock_message);
-                active.source = Set(knock_source);
-                active.status = Set("pending".to_string());
-                active.admin_id = Set(String::new());
-                let _ = active.update(&self.db).await?;
-            }
-        }
-        Ok(())
+        self.upsert_pending_knock(topic_id, user_id, form.message, form.source)
+            .await?;
+        Ok(TopicInviteJoinResult {
+            topic_id: topic.id,
+            status: "pending".to_string(),
+        })
     }
 
     pub async fn list_pending_knocks(&self, topic_id: &str) -> DomainResult<Vec<TopicKnock>> {
@@ -650,9 +838,12 @@ impl TopicService {
         admin_id: &str,
         user_id: &str,
         _form: TopicKnockAcceptedForm,
-    ) -> DomainResult<()> {
+    ) -> DomainResult<bool> {
         let topic = self.get_by_id(topic_id).await?;
-        if !topic.owner_id.eq(admin_id) && !topic.admins.iter().any(|v| v == admin_id) {
+        if !self
+            .has_permission(&topic, admin_id, TopicPermission::Invite)
+            .await?
+        {
             return Err(DomainError::Forbidden);
         }
         let Some(row) =
@@ -660,10 +851,10 @@ impl TopicService {
                 .one(&self.db)
                 .await?
         else {
-            return Ok(());
+            return Ok(false);
         };
         if row.status != "pending" {
-            return Ok(());
+            return Ok(false);
         }
 
         let mut active = row.into_active_model();
@@ -673,7 +864,7 @@ impl TopicService {
         let updated = active.update(&self.db).await?;
         self.join_members(topic_id, vec![user_id.to_string()], updated.source)
             .await?;
-        Ok(())
+        Ok(true)
     }
 
     pub async fn reject_knock(
@@ -682,9 +873,12 @@ impl TopicService {
         admin_id: &str,
         user_id: &str,
         _form: TopicKnockRejectedForm,
-    ) -> DomainResult<()> {
+    ) -> DomainResult<bool> {
         let topic = self.get_by_id(topic_id).await?;
-        if !topic.owner_id.eq(admin_id) && !topic.admins.iter().any(|v| v == admin_id) {
+        if !self
+            .has_permission(&topic, admin_id, TopicPermission::Invite)
+            .await?
+        {
             return Err(DomainError::Forbidden);
         }
         let Some(row) =
@@ -692,97 +886,835 @@ impl TopicService {
                 .one(&self.db)
                 .await?
         else {
-            return Ok(());
+            return Ok(false);
         };
         if row.status != "pending" {
-            return Ok(());
+            return Ok(false);
         }
         let mut active = row.into_active_model();
         active.status = Set("rejected".to_string());
         active.admin_id = Set(admin_id.to_string());
         active.updated_at = Set(now());
         let _ = active.update(&self.db).await?;
-        Ok(())
+        Ok(true)
     }
 
-    pub async fn update_notice(
+    pub async fn expire_knocks(&self, before: &str) -> DomainResult<Vec<TopicKnock>> {
+        let rows = topic_knock::Entity::find()
+            .filter(topic_knock::Column::Status.eq("pending"))
+            .filter(topic_knock::Column::UpdatedAt.lt(before.to_string()))
+            .all(&self.db)
+            .await?;
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            // a knock refreshed or handled meanwhile must not be expired
+            let rows_affected = topic_knock::Entity::update_many()
+                .col_expr(topic_knock::Column::Status, Expr::value("expired"))
+                .col_expr(topic_knock::Column::UpdatedAt, Expr::value(now()))
+                .filter(topic_knock::Column::TopicId.eq(row.topic_id.clone()))
+                .filter(topic_knock::Column::UserId.eq(row.user_id.clone()))
+                .filter(topic_knock::Column::Status.eq("pending"))
+                .filter(topic_knock::Column::UpdatedAt.eq(row.updated_at.clone()))
+                .exec(&self.db)
+                .await?
+                .rows_affected;
+            if rows_affected == 0 {
+                continue;
+            }
+            out.push(TopicKnock {
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+                topic_id: row.topic_id,
+                user_id: row.user_id,
+                message: row.message,
+                source: row.source,
+                status: "expired".to_string(),
+                admin_id: String::new(),
+            });
+        }
+        Ok(out)
+    }
+
+    pub async fn get_knock_rule(&self, topic_id: &str) -> DomainResult<TopicKnockRule> {
+        let _ = self.get_by_id(topic_id).await?;
+        let rule = topic_knock_rule::Entity::find_by_id(topic_id.to_string())
+            .one(&self.db)
+            .await?
+            .map(Into::into)
+            .unwrap_or_else(|| TopicKnockRule {
+                topic_id: topic_id.to_string(),
+                ..Default::default()
+            });
+        Ok(rule)
+    }
+
+    pub async fn update_knock_rule(
         &self,
         topic_id: &str,
-        admin_id: &str,
-        form: UpdateNoticeForm,
-    ) -> DomainResult<()> {
+        form: TopicKnockRuleForm,
+    ) -> DomainResult<TopicKnockRule> {
         let topic = self.get_by_id(topic_id).await?;
-        if !topic.owner_id.eq(admin_id) && !topic.admins.iter().any(|v| v == admin_id) {
-            return Err(DomainError::Forbidden);
+        if !topic.multiple {
+            return Err(DomainError::Validation(
+                "not multiple user topic".to_string(),
+            ));
         }
-        let _ = self
-            .update_topic(
-                topic_id,
-                OpenApiUpdateTopicForm {
-                    notice: Some(crate::TopicNoticeInput {
-                        text: form.text,
-                        publisher: admin_id.to_string(),
-                        updated_at: now(),
-                    }),
-                    ..OpenApiUpdateTopicForm::default()
-                },
-            )
+        let mut sources: Vec<String> = Vec::new();
+        for source in form.sources {
+            let source = source.trim().to_string();
+            if !source.is_empty() && !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+        let attributes: std::collections::HashMap<String, String> = form
+            .attributes
+            .into_iter()
+            .map(|(k, v)| (k.trim().to_string(), v))
+            .filter(|(k, _)| !k.is_empty())
+            .collect();
+
+        let now_ts = now();
+        let existing = topic_knock_rule::Entity::find_by_id(topic_id.to_string())
+            .one(&self.db)
             .await?;
-        Ok(())
+        let updated = match existing {
+            Some(row) => {
+                let mut active = row.into_active_model();
+                active.sources_json = Set(encode_json(&sources));
+                active.attributes_json = Set(encode_json(&attributes));
+                active.owner_contacts = Set(form.owner_contacts);
+                active.updated_at = Set(now_ts);
+                active.update(&self.db).await?
+            }
+            None => {
+                topic_knock_rule::ActiveModel {
+                    topic_id: Set(topic_id.to_string()),
+                    sources_json: Set(encode_json(&sources)),
+                    attributes_json: Set(encode_json(&attributes)),
+                    owner_contacts: Set(form.owner_contacts),
+                    created_at: Set(now_ts.clone()),
+                    updated_at: Set(now_ts),
+                }
+                .insert(&self.db)
+                .await?
+            }
+        };
+        Ok(updated.into())
     }
 
-    async fn modify_admin_list<F>(&self, topic_id: &str, f: F) -> DomainResult<()>
-    where
-        F: FnOnce(&mut Vec<String>),
-    {
-        let existing = topic::Entity::find_by_id(topic_id.to_string())
+    pub async fn create_invite(
+        &self,
+        topic_id: &str,
+        form: TopicInviteForm,
+    ) -> DomainResult<TopicInvite> {
+        let topic = self.get_by_id(topic_id).await?;
+        if !topic.multiple {
+            return Err(DomainError::Validation(
+                "not multiple user topic".to_string(),
+            ));
+        }
+        let expires_at = if form.duration.trim().is_empty() {
+            String::new()
+        } else {
+            parse_duration_to_time(&form.duration)
+                .ok_or_else(|| DomainError::Validation("invalid duration".to_string()))?
+        };
+        let now_ts = now();
+        let row = topic_invite::ActiveModel {
+            token: Set(format!("inv-{}", uuid::Uuid::new_v4().simple())),
+            topic_id: Set(topic_id.to_string()),
+            created_by: Set(form.created_by),
+            max_uses: Set(form.max_uses.min(i32::MAX as u32) as i32),
+            used_count: Set(0),
+            expires_at: Set(expires_at),
+            auto_approve: Set(form.auto_approve),
+            revoked: Set(false),
+            created_at: Set(now_ts.clone()),
+            updated_at: Set(now_ts),
+        }
+        .insert(&self.db)
+        .await?;
+        Ok(row.into())
+    }
+
+    pub async fn list_invites(&self, topic_id: &str) -> DomainResult<Vec<TopicInvite>> {
+        let now_ts = now();
+        let rows = topic_invite::Entity::find()
+            .filter(topic_invite::Column::TopicId.eq(topic_id.to_string()))
+            .filter(topic_invite::Column::Revoked.eq(false))
+            .order_by_desc(topic_invite::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .filter(|row| invite_is_active(row, &now_ts))
+            .map(Into::into)
+            .collect())
+    }
+
+    pub async fn revoke_invite(&self, topic_id: &str, token: &str) -> DomainResult<()> {
+        let row = topic_invite::Entity::find_by_id(token.to_string())
             .one(&self.db)
             .await?
+            .filter(|row| row.topic_id == topic_id)
             .ok_or(DomainError::NotFound)?;
-        let mut list: Vec<String> = serde_json::from_str(&existing.admins_json).unwrap_or_default();
-        f(&mut list);
-        let mut active = existing.into_active_model();
-        active.admins_json = Set(serde_json::to_string(&list).unwrap_or_else(|_| "[]".to_string()));
+        let mut active = row.into_active_model();
+        active.revoked = Set(true);
         active.updated_at = Set(now());
         let _ = active.update(&self.db).await?;
         Ok(())
     }
 
-    async fn modify_silent_whitelist<F>(&self, topic_id: &str, f: F) -> DomainResult<Vec<String>>
-    where
-        F: FnOnce(&mut Vec<String>),
-    {
-        let existing = topic::Entity::find_by_id(topic_id.to_string())
-            .one(&self.db)
-            .await?
-            .ok_or(DomainError::NotFound)?;
-        let mut list: Vec<String> =
-            serde_json::from_str(&existing.silent_white_list_json).unwrap_or_default();
-        f(&mut list);
-        let mut active = existing.into_active_model();
-        active.silent_white_list_json =
-            Set(serde_json::to_string(&list).unwrap_or_else(|_| "[]".to_string()));
-        active.updated_at = Set(now());
-        let _ = active.update(&self.db).await?;
-        Ok(list)
+    pub async fn preview_invite(&self, token: &str) -> DomainResult<TopicInvitePreview> {
+        let row = self.get_active_invite(token).await?;
+        let topic = self.get_by_id(&row.topic_id).await?;
+        Ok(TopicInvitePreview {
+            token: row.token,
+            topic_id: topic.id,
+            name: topic.name,
+            icon: topic.icon,
+            remark: topic.remark,
+            members: topic.members,
+            expires_at: row.expires_at,
+            need_verify: topic.knock_need_verify && !row.auto_approve,
+        })
     }
 
-    async fn refresh_members_count(&self, topic_id: &str) -> DomainResult<()> {
-        let count = topic_member::Entity::find()
-            .filter(topic_member::Column::TopicId.eq(topic_id.to_string()))
-            .count(&self.db)
+    pub async fn join_by_invite(
+        &self,
+        token: &str,
+        user_id: &str,
+        form: TopicKnockForm,
+    ) -> DomainResult<TopicInviteJoinResult> {
+        let row = self.get_active_invite(token).await?;
+        let topic = self.get_by_id(&row.topic_id).await?;
+        if self.is_banned(&topic.id, user_id).await? {
+            return Err(DomainError::Forbidden);
+        }
+        let exists = topic_member::Entity::find_by_id((topic.id.clone(), user_id.to_string()))
+            .one(&self.db)
             .await?;
+        if exists.is_some() {
+            return Ok(TopicInviteJoinResult {
+                topic_id: topic.id,
+                status: "member".to_string(),
+            });
+        }
 
-        if let Some(existing) = topic::Entity::find_by_id(topic_id.to_string())
-            .one(&self.db)
+        let consumed = topic_invite::Entity::update_many()
+            .col_expr(
+                topic_invite::Column::UsedCount,
+                Expr::col(topic_invite::Column::UsedCount).add(1),
+            )
+            .col_expr(topic_invite::Column::UpdatedAt, Expr::value(now()))
+            .filter(topic_invite::Column::Token.eq(row.token.clone()))
+            .filter(
+                Condition::any()
+                    .add(topic_invite::Column::MaxUses.lte(0))
+                    .add(
+                        Expr::col(topic_invite::Column::UsedCount)
+                            .lt(Expr::col(topic_invite::Column::MaxUses)),
+                    ),
+            )
+            .exec(&self.db)
             .await?
+            .rows_affected;
+        if consumed == 0 {
+            return Err(DomainError::Validation(
+                "invite link is no longer valid".to_string(),
+            ));
+        }
+
+        let source = if form.source.is_empty() {
+            "invite".to_string()
+        } else {
+            form.source
+        };
+        if row.auto_approve
+            || !topic.knock_need_verify
+            || self.knock_auto_approved(&topic, user_id).await?
         {
-            let mut active = existing.into_active_model();
-            active.members = Set(count as i32);
-            active.updated_at = Set(now());
-            let _ = active.update(&self.db).await?;
+            self.join_members(&topic.id, vec![user_id.to_string()], source)
+                .await?;
+            return Ok(TopicInviteJoinResult {
+                topic_id: topic.id,
+                status: "joined".to_string(),
+            });
         }
-        Ok(())
+        self.upsert_pending_knock(&topic.id, user_id, form.message, source)
+            .await?;
+        Ok(TopicInviteJoinResult {
+            topic_id: topic.id,
+            status: "pending".to_string(),
+        })
+    }
+
+    pub async fn search_directory(
+        &self,
+        user_id: &str,
+        form: TopicDirectoryForm,
+    ) -> DomainResult<TopicDirectoryResult> {
+        let limit = if form.limit == 0 {
+            20
+        } else {
+            form.limit.clamp(1, 100)
+        };
+        let mut query = topic::Entity::find()
+            .filter(topic::Column::Multiple.eq(true))
+            .filter(topic::Column::Private.eq(false))
+            .filter(topic::Column::Enabled.eq(true));
+        if let Some(keyword) = Some(form.keyword.trim()).filter(|v| !v.is_empty()) {
+            query = query.filter(
+                topic::Column::Name
+                    .contains(keyword)
+                    .or(topic::Column::ExtraJson.contains(keyword)),
+            );
+        }
+        query = match form.sort.as_str() {
+            "active" => query
+                .order_by_desc(topic::Column::LastSeq)
+                .order_by_desc(topic::Column::UpdatedAt),
+            "members" | "" => query.order_by_desc(topic::Column::Members),
+            _ => {
+                return Err(DomainError::Validation(format!(
+                    "unsupported sort: {}",
+                    form.sort
+                )))
+            }
+        };
+        query = query.order_by_asc(topic::Column::Id);
+
+        let total = query.clone().count(&self.db).await?;
+        let rows: Vec<topic::Model> = query.offset(form.offset).limit(limit).all(&self.db).await?;
+        let joined: Vec<String> = if rows.is_empty() {
+            vec![]
+        } else {
+            topic_member::Entity::find()
+                .filter(topic_member::Column::UserId.eq(user_id.to_string()))
+                .filter(topic_member::Column::TopicId.is_in(rows.iter().map(|v| v.id.clone())))
+                .all(&self.db)
+                .await?
+                .into_iter()
+                .map(|v| v.topic_id)
+                .collect()
+        };
+        let items: Vec<TopicDirectoryItem> = rows
+            .into_iter()
+            .map(|row| {
+                let is_member = joined.contains(&row.id);
+                directory_item(row.into(), is_member)
+            })
+            .collect();
+        Ok(TopicDirectoryResult {
+            total,
+            offset: form.offset,
+            has_more: form.offset + (items.len() as u64) < total,
+            items,
+        })
+    }
+
+    pub async fn preview_directory(
+        &self,
+        topic_id: &str,
+        user_id: &str,
+    ) -> DomainResult<TopicDirectoryItem> {
+        let topic = self.get_directory_topic(topic_id).await?;
+        let is_member = topic_member::Entity::find_by_id((topic.id.clone(), user_id.to_string()))
+            .one(&self.db)
+            .await?
+            .is_some();
+        Ok(directory_item(topic, is_member))
+    }
+
+    pub async fn join_from_directory(
+        &self,
+        topic_id: &str,
+        user_id: &str,
+        form: TopicKnockForm,
+    ) -> DomainResult<TopicInviteJoinResult> {
+        let topic = self.get_directory_topic(topic_id).await?;
+        if self.is_banned(&topic.id, user_id).await? {
+            return Err(DomainError::Forbidden);
+        }
+        let exists = topic_member::Entity::find_by_id((topic.id.clone(), user_id.to_string()))
+            .one(&self.db)
+            .await?;
+        if exists.is_some() {
+            return Ok(TopicInviteJoinResult {
+                topic_id: topic.id,
+                status: "member".to_string(),
+            });
+        }
+
+        let source = if form.source.is_empty() {
+            "directory".to_string()
+        } else {
+            form.source
+        };
+        if !topic.knock_need_verify || self.knock_auto_approved(&topic, user_id).await? {
+            self.join_members(&topic.id, vec![user_id.to_string()], source)
+                .await?;
+            return Ok(TopicInviteJoinResult {
+                topic_id: topic.id,
+                status: "joined".to_string(),
+            });
+        }
+        self.upsert_pending_knock(&topic.id, user_id, form.message, source)
+            .await?;
+        Ok(TopicInviteJoinResult {
+            topic_id: topic.id,
+            status: "pending".to_string(),
+        })
+    }
+
+    async fn get_directory_topic(&self, topic_id: &str) -> DomainResult<Topic> {
+        let row = topic::Entity::find_by_id(topic_id.to_string())
+            .one(&self.db)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+        // private or disabled topics are not listed, so don't reveal they exist
+        if !row.multiple || row.private || !row.enabled {
+            return Err(DomainError::NotFound);
+        }
+        Ok(row.into())
+    }
+
+    async fn get_active_invite(&self, token: &str) -> DomainResult<topic_invite::Model> {
+        let row = topic_invite::Entity::find_by_id(token.to_string())
+            .one(&self.db)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+        if !invite_is_active(&row, &now()) {
+            return Err(DomainError::Validation(
+                "invite link is no longer valid".to_string(),
+            ));
+        }
+        Ok(row)
+    }
+
+    pub async fn update_notice(
+        &self,
+        topic_id: &str,
+        admin_id: &str,
+        form: UpdateNoticeForm,
+    ) -> DomainResult<()> {
+        let topic = self.get_by_id(topic_id).await?;
+        if !self
+            .has_permission(&topic, admin_id, TopicPermission::EditNotice)
+            .await?
+        {
+            return Err(DomainError::Forbidden);
+        }
+        let _ = self
+            .update_topic(
+                topic_id,
+                OpenApiUpdateTopicForm {
+                    notice: Some(crate::TopicNoticeInput {
+                        text: form.text,
+                        publisher: admin_id.to_string(),
+                        updated_at: now(),
+                    }),
+                    ..OpenApiUpdateTopicForm::default()
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn ban_member(
+        &self,
+        topic_id: &str,
+        user_id: &str,
+        form: TopicBanForm,
+    ) -> DomainResult<(TopicBan, bool)> {
+        let topic = self.get_by_id(topic_id).await?;
+        if topic.owner_id == user_id {
+            return Err(DomainError::Validation(
+                "topic owner can't be banned".to_string(),
+            ));
+        }
+        let expires_at = if form.duration.trim().is_empty() {
+            String::new()
+        } else {
+            parse_duration_to_time(&form.duration)
+                .ok_or_else(|| DomainError::Validation("invalid duration".to_string()))?
+        };
+        let now_ts = now();
+        let existing = topic_ban::Entity::find_by_id((topic_id.to_string(), user_id.to_string()))
+            .one(&self.db)
+            .await?;
+        let row = match existing {
+            Some(row) => {
+                let mut active = row.into_active_model();
+                active.reason = Set(form.reason);
+                active.admin_id = Set(form.admin_id);
+                active.expires_at = Set(expires_at);
+                active.updated_at = Set(now_ts);
+                active.update(&self.db).await?
+            }
+            None => {
+                topic_ban::ActiveModel {
+                    topic_id: Set(topic_id.to_string()),
+                    user_id: Set(user_id.to_string()),
+                    reason: Set(form.reason),
+                    admin_id: Set(form.admin_id),
+                    expires_at: Set(expires_at),
+                    created_at: Set(now_ts.clone()),
+                    updated_at: Set(now_ts),
+                }
+                .insert(&self.db)
+                .await?
+            }
+        };
+        if topic.admins.iter().any(|v| v == user_id) {
+            self.remove_admin(topic_id, user_id).await?;
+        }
+        let removed = self
+            .quit_members(topic_id, vec![user_id.to_string()])
+            .await?;
+        Ok((row.into(), !removed.is_empty()))
+    }
+
+    pub async fn unban_member(&self, topic_id: &str, user_id: &str) -> DomainResult<()> {
+        let rows = topic_ban::Entity::delete_by_id((topic_id.to_string(), user_id.to_string()))
+            .exec(&self.db)
+            .await?
+            .rows_affected;
+        if rows == 0 {
+            return Err(DomainError::NotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn list_bans(&self, topic_id: &str) -> DomainResult<Vec<TopicBan>> {
+        let now_ts = now();
+        let rows = topic_ban::Entity::find()
+            .filter(topic_ban::Column::TopicId.eq(topic_id.to_string()))
+            .order_by_desc(topic_ban::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .filter(|row| row.expires_at.is_empty() || row.expires_at > now_ts)
+            .map(Into::into)
+            .collect())
+    }
+
+    pub async fn is_banned(&self, topic_id: &str, user_id: &str) -> DomainResult<bool> {
+        let Some(row) = topic_ban::Entity::find_by_id((topic_id.to_string(), user_id.to_string()))
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(false);
+        };
+        Ok(row.expires_at.is_empty() || row.expires_at > now())
+    }
+
+    pub async fn has_permission(
+        &self,
+        topic: &Topic,
+        user_id: &str,
+        permission: TopicPermission,
+    ) -> DomainResult<bool> {
+        if !topic.owner_id.is_empty() && topic.owner_id == user_id {
+            return Ok(true);
+        }
+        let role = if topic.admins.iter().any(|v| v == user_id) {
+            "admin".to_string()
+        } else {
+            match topic_member::Entity::find_by_id((topic.id.clone(), user_id.to_string()))
+                .one(&self.db)
+                .await?
+            {
+                Some(member) if !member.role.is_empty() => member.role,
+                Some(_) => "member".to_string(),
+                None => return Ok(false),
+            }
+        };
+        let permissions = self.role_permissions(&topic.id, &role).await?;
+        Ok(permissions.contains(&permission))
+    }
+
+    pub async fn list_roles(&self, topic_id: &str) -> DomainResult<Vec<TopicRole>> {
+        let rows = topic_role::Entity::find()
+            .filter(topic_role::Column::TopicId.eq(topic_id.to_string()))
+            .order_by_asc(topic_role::Column::Name)
+            .all(&self.db)
+            .await?;
+        let mut roles: Vec<TopicRole> = BUILTIN_ROLES
+            .iter()
+            .filter(|name| **name == "owner" || !rows.iter().any(|row| row.name == **name))
+            .map(|name| TopicRole {
+                topic_id: topic_id.to_string(),
+                name: name.to_string(),
+                permissions: builtin_role_permissions(name).unwrap_or_default(),
+                builtin: true,
+                ..TopicRole::default()
+            })
+            .collect();
+        roles.extend(rows.into_iter().map(Into::into));
+        Ok(roles)
+    }
+
+    pub async fn update_role(
+        &self,
+        topic_id: &str,
+        name: &str,
+        form: TopicRoleForm,
+    ) -> DomainResult<TopicRole> {
+        let name = name.trim();
+        if name.is_empty() || name.len() > 64 {
+            return Err(DomainError::Validation("invalid role name".to_string()));
+        }
+        if name == "owner" {
+            return Err(DomainError::Validation(
+                "owner role can't be changed".to_string(),
+            ));
+        }
+        let _ = self.get_by_id(topic_id).await?;
+        let mut permissions: Vec<TopicPermission> = Vec::with_capacity(form.permissions.len());
+        for permission in form.permissions {
+            if !permissions.contains(&permission) {
+                permissions.push(permission);
+            }
+        }
+        let now_ts = now();
+        let existing = topic_role::Entity::find_by_id((topic_id.to_string(), name.to_string()))
+            .one(&self.db)
+            .await?;
+        let row = match existing {
+            Some(row) => {
+                let mut active = row.into_active_model();
+                active.permissions_json = Set(encode_json(&permissions));
+                active.updated_at = Set(now_ts);
+                active.update(&self.db).await?
+            }
+            None => {
+                topic_role::ActiveModel {
+                    topic_id: Set(topic_id.to_string()),
+                    name: Set(name.to_string()),
+                    permissions_json: Set(encode_json(&permissions)),
+                    created_at: Set(now_ts.clone()),
+                    updated_at: Set(now_ts),
+                }
+                .insert(&self.db)
+                .await?
+            }
+        };
+        Ok(row.into())
+    }
+
+    pub async fn remove_role(&self, topic_id: &str, name: &str) -> DomainResult<()> {
+        let rows = topic_role::Entity::delete_by_id((topic_id.to_string(), name.to_string()))
+            .exec(&self.db)
+            .await?
+            .rows_affected;
+        if rows == 0 {
+            return Err(DomainError::NotFound);
+        }
+        if builtin_role_permissions(name).is_none() {
+            topic_member::Entity::update_many()
+                .col_expr(topic_member::Column::Role, Expr::value("member"))
+                .col_expr(topic_member::Column::UpdatedAt, Expr::value(now()))
+                .filter(topic_member::Column::TopicId.eq(topic_id.to_string()))
+                .filter(topic_member::Column::Role.eq(name.to_string()))
+                .exec(&self.db)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn assign_role(
+        &self,
+        topic_id: &str,
+        user_id: &str,
+        role: &str,
+    ) -> DomainResult<TopicMember> {
+        if role == "owner" || role == "admin" {
+            return Err(DomainError::Validation(
+                "use transfer or admin endpoints for owner and admin".to_string(),
+            ));
+        }
+        if role != "member"
+            && topic_role::Entity::find_by_id((topic_id.to_string(), role.to_string()))
+                .one(&self.db)
+                .await?
+                .is_none()
+        {
+            return Err(DomainError::Validation("role not found".to_string()));
+        }
+        let existing =
+            topic_member::Entity::find_by_id((topic_id.to_string(), user_id.to_string()))
+                .one(&self.db)
+                .await?
+                .ok_or(DomainError::NotFound)?;
+        let mut active = existing.into_active_model();
+        active.role = Set(role.to_string());
+        active.updated_at = Set(now());
+        let updated = active.update(&self.db).await?;
+        Ok(updated.into())
+    }
+
+    async fn role_permissions(
+        &self,
+        topic_id: &str,
+        role: &str,
+    ) -> DomainResult<Vec<TopicPermission>> {
+        let rows = topic_role::Entity::find()
+            .filter(topic_role::Column::TopicId.eq(topic_id.to_string()))
+            .filter(topic_role::Column::Name.is_in([role.to_string(), "member".to_string()]))
+            .all(&self.db)
+            .await?;
+        if let Some(row) = rows.iter().find(|row| row.name == role) {
+            return Ok(decode_json(&row.permissions_json));
+        }
+        if let Some(permissions) = builtin_role_permissions(role) {
+            return Ok(permissions);
+        }
+        // roles removed after assignment fall back to the member role
+        match rows.into_iter().find(|row| row.name == "member") {
+            Some(row) => Ok(decode_json(&row.permissions_json)),
+            None => Ok(builtin_role_permissions("member").unwrap_or_default()),
+        }
+    }
+
+    async fn knock_auto_approved(&self, topic: &Topic, user_id: &str) -> DomainResult<bool> {
+        let Some(rule) = topic_knock_rule::Entity::find_by_id(topic.id.clone())
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let rule: TopicKnockRule = rule.into();
+        if !rule.sources.is_empty() || !rule.attributes.is_empty() {
+            if let Some(user) = user::Entity::find_by_id(user_id.to_string())
+                .one(&self.db)
+                .await?
+            {
+                if !user.source.is_empty() && rule.sources.contains(&user.source) {
+                    return Ok(true);
+                }
+                if !rule.attributes.is_empty()
+                    && rule.attributes.iter().all(|(key, value)| {
+                        let field = match key.as_str() {
+                            "source" => &user.source,
+                            "locale" => &user.locale,
+                            "city" => &user.city,
+                            "country" => &user.country,
+                            "gender" => &user.gender,
+                            _ => return false,
+                        };
+                        field == value
+                    })
+                {
+                    return Ok(true);
+                }
+            }
+        }
+        if rule.owner_contacts && !topic.owner_id.is_empty() {
+            let is_contact =
+                relation::Entity::find_by_id((topic.owner_id.clone(), user_id.to_string()))
+                    .one(&self.db)
+                    .await?
+                    .map(|row| row.is_contact && !row.is_blocked)
+                    .unwrap_or(false);
+            if is_contact {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    async fn upsert_pending_knock(
+        &self,
+        topic_id: &str,
+        user_id: &str,
+        message: String,
+        source: String,
+    ) -> DomainResult<()> {
+        let now_ts = now();
+        let knock = topic_knock::ActiveModel {
+            topic_id: Set(topic_id.to_string()),
+            user_id: Set(user_id.to_string()),
+            created_at: Set(now_ts.clone()),
+            updated_at: Set(now_ts),
+            message: Set(message.clone()),
+            source: Set(source.clone()),
+            status: Set("pending".to_string()),
+            admin_id: Set(String::new()),
+        };
+        match knock.insert(&self.db).await {
+            Ok(_) => {}
+            Err(_) => {
+                let existing =
+                    topic_knock::Entity::find_by_id((topic_id.to_string(), user_id.to_string()))
+                        .one(&self.db)
+                        .await?
+                        .ok_or(DomainError::NotFound)?;
+                let mut active = existing.into_active_model();
+                active.updated_at = Set(now());
+                active.message = Set(message);
+                active.source = Set(source);
+                active.status = Set("pending".to_string());
+                active.admin_id = Set(String::new());
+                let _ = active.update(&self.db).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn modify_admin_list<F>(&self, topic_id: &str, f: F) -> DomainResult<()>
+    where
+        F: FnOnce(&mut Vec<String>),
+    {
+        let existing = topic::Entity::find_by_id(topic_id.to_string())
+            .one(&self.db)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+        let mut list: Vec<String> = serde_json::from_str(&existing.admins_json).unwrap_or_default();
+        f(&mut list);
+        let mut active = existing.into_active_model();
+        active.admins_json = Set(serde_json::to_string(&list).unwrap_or_else(|_| "[]".to_string()));
+        active.updated_at = Set(now());
+        let _ = active.update(&self.db).await?;
+        Ok(())
+    }
+
+    async fn modify_silent_whitelist<F>(&self, topic_id: &str, f: F) -> DomainResult<Vec<String>>
+    where
+        F: FnOnce(&mut Vec<String>),
+    {
+        let existing = topic::Entity::find_by_id(topic_id.to_string())
+            .one(&self.db)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+        let mut list: Vec<String> =
+            serde_json::from_str(&existing.silent_white_list_json).unwrap_or_default();
+        f(&mut list);
+        let mut active = existing.into_active_model();
+        active.silent_white_list_json =
+            Set(serde_json::to_string(&list).unwrap_or_else(|_| "[]".to_string()));
+        active.updated_at = Set(now());
+        let _ = active.update(&self.db).await?;
+        Ok(list)
+    }
+
+    async fn refresh_members_count(&self, topic_id: &str) -> DomainResult<()> {
+        let count = topic_member::Entity::find()
+            .filter(topic_member::Column::TopicId.eq(topic_id.to_string()))
+            .count(&self.db)
+            .await?;
+
+        if let Some(existing) = topic::Entity::find_by_id(topic_id.to_string())
+            .one(&self.db)
+            .await?
+        {
+            let mut active = existing.into_active_model();
+            active.members = Set(count as i32);
+            active.updated_at = Set(now());
+            let _ = active.update(&self.db).await?;
+        }
+        Ok(())
     }
 }
 
@@ -790,7 +1722,43 @@ fn now() -> String {
     Utc::now().to_rfc3339()
 }
 
-fn parse_duration_to_time(duration: &str) -> Option<String> {
+const BUILTIN_ROLES: [&str; 3] = ["owner", "admin", "member"];
+
+fn builtin_role_permissions(role: &str) -> Option<Vec<TopicPermission>> {
+    match role {
+        "owner" | "admin" => Some(TopicPermission::all()),
+        "member" => Some(vec![
+            TopicPermission::Send,
+            TopicPermission::SendAttachment,
+            TopicPermission::MentionAll,
+        ]),
+        _ => None,
+    }
+}
+
+fn directory_item(topic: Topic, is_member: bool) -> TopicDirectoryItem {
+    TopicDirectoryItem {
+        topic_id: topic.id,
+        name: topic.name,
+        icon: topic.icon,
+        notice: topic.notice.map(|v| v.text).unwrap_or_default(),
+        members: topic.members,
+        need_verify: topic.knock_need_verify,
+        is_member,
+    }
+}
+
+fn invite_is_active(row: &topic_invite::Model, now_ts: &str) -> bool {
+    if row.revoked {
+        return false;
+    }
+    if row.max_uses > 0 && row.used_count >= row.max_uses {
+        return false;
+    }
+    row.expires_at.is_empty() || row.expires_at.as_str() > now_ts
+}
+
+pub(crate) fn parse_duration_to_time(duration: &str) -> Option<String> {
     let input = duration.trim().to_ascii_lowercase();
     if input.is_empty() {
         return None;
@@ -976,7 +1944,7 @@ mod tests {
             .unwrap();
 
         let result = service
-            .list_members_detailed("alice:bob", None, None)
+            .list_members_detailed("alice:bob", "", TopicMemberListForm::default())
             .await
             .unwrap();
         assert_eq!(result.items.len(), 2);
@@ -1024,7 +1992,7 @@ mod tests {
         }
 
         let result = service
-            .list_members_detailed("group-1", None, None)
+            .list_members_detailed("group-1", "", TopicMemberListForm::default())
             .await
             .unwrap();
         assert_eq!(result.items.len(), 2);
@@ -1032,4 +2000,299 @@ mod tests {
         assert!(user_ids.contains(&"alice"));
         assert!(user_ids.contains(&"bob"));
     }
+
+    #[tokio::test]
+    async fn test_list_members_detailed_paginates_and_filters() {
+        let db = setup_db().await;
+        let service = TopicService::new(db.clone());
+
+        let ts = now();
+        let topic = crate::Topic {
+            id: "group-page".to_string(),
+            owner_id: "alice".to_string(),
+            admins: vec!["bob".to_string()],
+            multiple: true,
+            enabled: true,
+            created_at: ts.clone(),
+            updated_at: ts.clone(),
+            ..crate::Topic::default()
+        };
+        topic::ActiveModel::from((topic, ts.as_str()))
+            .insert(&db)
+            .await
+            .unwrap();
+        for (idx, user_id) in ["alice", "bob", "carol", "dave", "erin"].iter().enumerate() {
+            create_user(&db, user_id).await;
+            let joined_at = format!("2026-01-0{}T00:00:00Z", idx + 1);
+            let member = TopicMember {
+                topic_id: "group-page".to_string(),
+                user_id: user_id.to_string(),
+                joined_at: joined_at.clone(),
+                role: if *user_id == "erin" {
+                    "moderator".to_string()
+                } else {
+                    String::new()
+                },
+                ..TopicMember::default()
+            };
+            topic_member::ActiveModel::from((member, joined_at.as_str()))
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+
+        let mut cursor = String::new();
+        let mut seen = Vec::new();
+        loop {
+            let page = service
+                .list_members_detailed(
+                    "group-page",
+                    "",
+                    TopicMemberListForm {
+                        cursor: cursor.clone(),
+                        limit: 2,
+                        ..TopicMemberListForm::default()
+                    },
+                )
+                .await
+                .unwrap();
+            seen.extend(page.items.into_iter().map(|u| u.user_id));
+            if !page.has_more {
+                break;
+            }
+            cursor = page.cursor;
+        }
+        assert_eq!(seen, vec!["alice", "bob", "carol", "dave", "erin"]);
+
+        let members = service
+            .list_members_detailed(
+                "group-page",
+                "",
+                TopicMemberListForm {
+                    role: "member".to_string(),
+                    ..TopicMemberListForm::default()
+                },
+            )
+            .await
+            .unwrap();
+        let user_ids: Vec<&str> = members.items.iter().map(|u| u.user_id.as_str()).collect();
+        assert_eq!(user_ids, vec!["carol", "dave"]);
+
+        let moderators = service
+            .list_members_detailed(
+                "group-page",
+                "",
+                TopicMemberListForm {
+                    role: "moderator".to_string(),
+                    ..TopicMemberListForm::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(moderators.items.len(), 1);
+        assert_eq!(moderators.items[0].user_id, "erin");
+
+        let search = service
+            .list_members_detailed(
+                "group-page",
+                "",
+                TopicMemberListForm {
+                    keyword: "aro".to_string(),
+                    ..TopicMemberListForm::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(search.items.len(), 1);
+        assert_eq!(search.items[0].user_id, "carol");
+
+        let changed = service
+            .list_members_detailed(
+                "group-page",
+                "",
+                TopicMemberListForm {
+                    updated_at: "2026-01-04T00:00:00Z".to_string(),
+                    ..TopicMemberListForm::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(changed.items.len(), 1);
+        assert_eq!(changed.items[0].user_id, "erin");
+    }
+
+    #[tokio::test]
+    async fn test_has_permission_resolves_roles() {
+        let db = setup_db().await;
+        let service = TopicService::new(db.clone());
+
+        let ts = now();
+        let topic = crate::Topic {
+            id: "group-roles".to_string(),
+            owner_id: "alice".to_string(),
+            admins: vec!["bob".to_string()],
+            multiple: true,
+            enabled: true,
+            created_at: ts.clone(),
+            updated_at: ts.clone(),
+            ..crate::Topic::default()
+        };
+        topic::ActiveModel::from((topic, ts.as_str()))
+            .insert(&db)
+            .await
+            .unwrap();
+        service
+            .join_members(
+                "group-roles",
+                vec!["bob".to_string(), "carol".to_string()],
+                "test".to_string(),
+            )
+            .await
+            .unwrap();
+        let topic = service.get_by_id("group-roles").await.unwrap();
+
+        assert!(service
+            .has_permission(&topic, "alice", TopicPermission::ManageRoles)
+            .await
+            .unwrap());
+        assert!(service
+            .has_permission(&topic, "bob", TopicPermission::Kick)
+            .await
+            .unwrap());
+        assert!(service
+            .has_permission(&topic, "carol", TopicPermission::Send)
+            .await
+            .unwrap());
+        assert!(!service
+            .has_permission(&topic, "carol", TopicPermission::Kick)
+            .await
+            .unwrap());
+        assert!(!service
+            .has_permission(&topic, "dave", TopicPermission::Send)
+            .await
+            .unwrap());
+
+        service
+            .update_role(
+                "group-roles",
+                "moderator",
+                TopicRoleForm {
+                    permissions: vec![TopicPermission::Send, TopicPermission::Kick],
+                },
+            )
+            .await
+            .unwrap();
+        service
+            .assign_role("group-roles", "carol", "moderator")
+            .await
+            .unwrap();
+        assert!(service
+            .has_permission(&topic, "carol", TopicPermission::Kick)
+            .await
+            .unwrap());
+        assert!(!service
+            .has_permission(&topic, "carol", TopicPermission::MentionAll)
+            .await
+            .unwrap());
+
+        service
+            .remove_role("group-roles", "moderator")
+            .await
+            .unwrap();
+        let member = service.get_member("group-roles", "carol").await.unwrap();
+        assert_eq!(member.role, "member");
+        assert!(!service
+            .has_permission(&topic, "carol", TopicPermission::Kick)
+            .await
+            .unwrap());
+
+        assert!(service
+            .update_role("group-roles", "owner", TopicRoleForm::default())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_knock_rules_and_expiry() {
+        let db = setup_db().await;
+        let service = TopicService::new(db.clone());
+
+        let ts = now();
+        let topic = crate::Topic {
+            id: "group-knock".to_string(),
+            owner_id: "alice".to_string(),
+            multiple: true,
+            enabled: true,
+            knock_need_verify: true,
+            created_at: ts.clone(),
+            updated_at: ts.clone(),
+            ..crate::Topic::default()
+        };
+        topic::ActiveModel::from((topic, ts.as_str()))
+            .insert(&db)
+            .await
+            .unwrap();
+        for (user_id, source, city) in [
+            ("bob", "partner", ""),
+            ("carol", "", "paris"),
+            ("dave", "", ""),
+        ] {
+            let u = crate::User {
+                user_id: user_id.to_string(),
+                source: source.to_string(),
+                city: city.to_string(),
+                enabled: true,
+                created_at: ts.clone(),
+                ..crate::User::default()
+            };
+            user::ActiveModel::from((u, ts.as_str()))
+                .insert(&db)
+                .await
+                .unwrap();
+        }
+
+        let rule = service.get_knock_rule("group-knock").await.unwrap();
+        assert!(rule.sources.is_empty());
+        let rule = service
+            .update_knock_rule(
+                "group-knock",
+                TopicKnockRuleForm {
+                    sources: vec![" partner ".to_string(), "partner".to_string()],
+                    attributes: [("city".to_string(), "paris".to_string())].into(),
+                    owner_contacts: false,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(rule.sources, vec!["partner".to_string()]);
+
+        for (user_id, status) in [("bob", "joined"), ("carol", "joined"), ("dave", "pending")] {
+            let r = service
+                .add_knock("group-knock", user_id, TopicKnockForm::default())
+                .await
+                .unwrap();
+            assert_eq!(r.status, status, "{user_id}");
+        }
+
+        let future = (Utc::now() + Duration::seconds(60)).to_rfc3339();
+        let expired = service.expire_knocks(&future).await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].user_id, "dave");
+        assert!(service.expire_knocks(&future).await.unwrap().is_empty());
+        assert!(service
+            .list_pending_knocks("group-knock")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(!service
+            .accept_knock("group-knock", "alice", "dave", TopicKnockForm::default())
+            .await
+            .unwrap());
+        assert!(matches!(
+            service
+                .reject_knock("group-knock", "mallory", "dave", TopicKnockForm::default())
+                .await,
+            Err(DomainError::Forbidden)
+        ));
+    }
 }
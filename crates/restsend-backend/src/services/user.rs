@@ -1,12 +1,19 @@
 use chrono::Utc;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
-    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Select, Set,
 };
 
-use crate::entity::user;
+use crate::entity::{encode_json, oidc_identity, relation, user, user_presence};
 use crate::services::{DomainError, DomainResult};
-use crate::{OpenApiUserForm, User};
+use crate::{
+    OpenApiUserForm, OpenApiUserSearchForm, PresenceForm, User, UserDirectoryForm,
+    UserDirectoryResult, UserPresence,
+};
+
+pub const PRESENCE_ONLINE: &str = "online";
+pub const PRESENCE_INVISIBLE: &str = "invisible";
+const PRESENCE_STATUSES: [&str; 4] = [PRESENCE_ONLINE, "away", "busy", PRESENCE_INVISIBLE];
 
 #[derive(Clone)]
 pub struct UserService {
@@ -51,6 +58,45 @@ impl UserService {
         }
     }
 
+    // logins resolve through the provider+subject link, and only accounts the provider
+    // created get linked, so a subject equal to a local user id never reaches that account
+    pub async fn get_or_create_for_oidc(
+        &self,
+        provider: &str,
+        subject: &str,
+        create_when_not_exist: bool,
+    ) -> DomainResult<User> {
+        if let Some(link) =
+            oidc_identity::Entity::find_by_id((provider.to_string(), subject.to_string()))
+                .one(&self.db)
+                .await?
+        {
+            return self.get_by_user_id(&link.user_id).await;
+        }
+        if !create_when_not_exist {
+            return Err(DomainError::NotFound);
+        }
+        let user_id = format!("oidc:{provider}:{subject}");
+        let user = self
+            .register(
+                &user_id,
+                OpenApiUserForm {
+                    source: format!("oidc:{provider}"),
+                    ..OpenApiUserForm::default()
+                },
+            )
+            .await?;
+        oidc_identity::ActiveModel {
+            provider: Set(provider.to_string()),
+            subject: Set(subject.to_string()),
+            user_id: Set(user_id),
+            created_at: Set(now()),
+        }
+        .insert(&self.db)
+        .await?;
+        Ok(user)
+    }
+
     pub async fn register(&self, user_id: &str, form: OpenApiUserForm) -> DomainResult<User> {
         if user_id.trim().is_empty() {
             return Err(DomainError::Validation("user id is required".to_string()));
@@ -75,6 +121,8 @@ impl UserService {
             country: form.country,
             gender: form.gender,
             public_key: form.public_key,
+            extra: form.extra,
+            search_opt_out: form.search_opt_out.unwrap_or_default(),
             enabled: true,
             created_at: now.clone(),
             ..User::default()
@@ -117,7 +165,13 @@ impl UserService {
             active.public_key = Set(form.public_key);
         }
         if !form.password.is_empty() {
-            active.password = Set(crate::api::auth::hash_password(&form.password));
+            active.password = Set(crate::api::auth::spawn_hash_password(&form.password).await);
+        }
+        if let Some(extra) = form.extra {
+            active.extra_json = Set(encode_json(&Some(extra)));
+        }
+        if let Some(search_opt_out) = form.search_opt_out {
+            active.search_opt_out = Set(search_opt_out);
         }
         active.updated_at = Set(now());
 
@@ -153,6 +207,14 @@ impl UserService {
         Ok(updated.into())
     }
 
+    // false for unknown users
+    pub async fn is_staff(&self, user_id: &str) -> DomainResult<bool> {
+        Ok(user::Entity::find_by_id(user_id.to_string())
+            .one(&self.db)
+            .await?
+            .is_some_and(|model| model.is_staff))
+    }
+
     pub async fn set_staff(&self, user_id: &str, is_staff: bool) -> DomainResult<User> {
         let existing = user::Entity::find_by_id(user_id.to_string())
             .one(&self.db)
@@ -184,6 +246,229 @@ impl UserService {
         let rows: Vec<user::Model> = query.offset(offset).limit(limit).all(&self.db).await?;
         Ok((rows.into_iter().map(Into::into).collect(), total))
     }
+
+    pub async fn search_directory(
+        &self,
+        user_id: &str,
+        form: UserDirectoryForm,
+    ) -> DomainResult<UserDirectoryResult> {
+        let keyword = form.keyword.trim();
+        if keyword.is_empty() {
+            return Err(DomainError::Validation("keyword is required".to_string()));
+        }
+        // hide users blocked in either direction
+        let mut excluded: Vec<String> = relation::Entity::find()
+            .filter(relation::Column::IsBlocked.eq(true))
+            .filter(
+                Condition::any()
+                    .add(relation::Column::OwnerId.eq(user_id.to_string()))
+                    .add(relation::Column::TargetId.eq(user_id.to_string())),
+            )
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|v| {
+                if v.owner_id == user_id {
+                    v.target_id
+                } else {
+                    v.owner_id
+                }
+            })
+            .collect();
+        excluded.push(user_id.to_string());
+
+        let query = user::Entity::find()
+            .filter(user::Column::Enabled.eq(true))
+            .filter(user::Column::SearchOptOut.eq(false))
+            .filter(user::Column::UserId.is_not_in(excluded))
+            .filter(keyword_condition(keyword));
+        self.page_directory(query, form.offset, form.limit).await
+    }
+
+    pub async fn search_users(
+        &self,
+        form: OpenApiUserSearchForm,
+    ) -> DomainResult<UserDirectoryResult> {
+        let mut query = user::Entity::find();
+        if let Some(keyword) = Some(form.keyword.trim()).filter(|v| !v.is_empty()) {
+            query = query.filter(keyword_condition(keyword));
+        }
+        if let Some(source) = form.source {
+            query = query.filter(user::Column::Source.eq(source));
+        }
+        if let Some(is_staff) = form.is_staff {
+            query = query.filter(user::Column::IsStaff.eq(is_staff));
+        }
+        if let Some(enabled) = form.enabled {
+            query = query.filter(user::Column::Enabled.eq(enabled));
+        }
+        self.page_directory(query, form.offset, form.limit).await
+    }
+
+    pub async fn get_presence(&self, user_id: &str) -> DomainResult<UserPresence> {
+        let presence = match user_presence::Entity::find_by_id(user_id.to_string())
+            .one(&self.db)
+            .await?
+        {
+            Some(model) => model.into(),
+            None => UserPresence {
+                user_id: user_id.to_string(),
+                status: PRESENCE_ONLINE.to_string(),
+                ..UserPresence::default()
+            },
+        };
+        Ok(clear_expired_status(presence, &now()))
+    }
+
+    // one query for a batch of users, in the order given
+    pub async fn get_presences(&self, user_ids: &[String]) -> DomainResult<Vec<UserPresence>> {
+        let mut rows: std::collections::HashMap<String, UserPresence> =
+            user_presence::Entity::find()
+                .filter(user_presence::Column::UserId.is_in(user_ids.to_vec()))
+                .all(&self.db)
+                .await?
+                .into_iter()
+                .map(|model| (model.user_id.clone(), model.into()))
+                .collect();
+        let now = now();
+        Ok(user_ids
+            .iter()
+            .map(|user_id| {
+                let presence = rows.remove(user_id).unwrap_or_else(|| UserPresence {
+                    user_id: user_id.clone(),
+                    status: PRESENCE_ONLINE.to_string(),
+                    ..UserPresence::default()
+                });
+                clear_expired_status(presence, &now)
+            })
+            .collect())
+    }
+
+    pub async fn set_presence(
+        &self,
+        user_id: &str,
+        form: PresenceForm,
+    ) -> DomainResult<UserPresence> {
+        if !form.status.is_empty() && !PRESENCE_STATUSES.contains(&form.status.as_str()) {
+            return Err(DomainError::Validation(format!(
+                "unsupported status: {}",
+                form.status
+            )));
+        }
+        let now_ts = Utc::now();
+        let status_expires_at = if form.expire_secs > 0 {
+            (now_ts + chrono::Duration::seconds(form.expire_secs as i64)).to_rfc3339()
+        } else {
+            String::new()
+        };
+        let now_ts = now_ts.to_rfc3339();
+        let existing = user_presence::Entity::find_by_id(user_id.to_string())
+            .one(&self.db)
+            .await?;
+        let updated = match existing {
+            Some(model) => {
+                let status = if form.status.is_empty() {
+                    model.status.clone()
+                } else {
+                    form.status
+                };
+                let mut active = model.into_active_model();
+                active.status = Set(status);
+                active.status_text = Set(form.status_text);
+                active.status_expires_at = Set(status_expires_at);
+                active.updated_at = Set(now_ts.clone());
+                active.update(&self.db).await?
+            }
+            None => {
+                let status = if form.status.is_empty() {
+                    PRESENCE_ONLINE.to_string()
+                } else {
+                    form.status
+                };
+                user_presence::ActiveModel {
+                    user_id: Set(user_id.to_string()),
+                    status: Set(status),
+                    status_text: Set(form.status_text),
+                    status_expires_at: Set(status_expires_at),
+                    last_seen_at: Set(String::new()),
+                    updated_at: Set(now_ts.clone()),
+                }
+                .insert(&self.db)
+                .await?
+            }
+        };
+        Ok(clear_expired_status(updated.into(), &now_ts))
+    }
+
+    // invisible users keep their previous last-seen so going offline reveals nothing
+    pub async fn touch_last_seen(&self, user_id: &str) -> DomainResult<()> {
+        let now_ts = now();
+        match user_presence::Entity::find_by_id(user_id.to_string())
+            .one(&self.db)
+            .await?
+        {
+            Some(model) => {
+                let current = clear_expired_status(model.clone().into(), &now_ts);
+                if current.status == PRESENCE_INVISIBLE {
+                    return Ok(());
+                }
+                let mut active = model.into_active_model();
+                active.last_seen_at = Set(now_ts.clone());
+                active.updated_at = Set(now_ts);
+                active.update(&self.db).await?;
+            }
+            None => {
+                user_presence::ActiveModel {
+                    user_id: Set(user_id.to_string()),
+                    status: Set(PRESENCE_ONLINE.to_string()),
+                    status_text: Set(String::new()),
+                    status_expires_at: Set(String::new()),
+                    last_seen_at: Set(now_ts.clone()),
+                    updated_at: Set(now_ts),
+                }
+                .insert(&self.db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn page_directory(
+        &self,
+        query: Select<user::Entity>,
+        offset: u64,
+        limit: u64,
+    ) -> DomainResult<UserDirectoryResult> {
+        let limit = if limit == 0 { 20 } else { limit.clamp(1, 100) };
+        let query = query.order_by_asc(user::Column::UserId);
+        let total = query.clone().count(&self.db).await?;
+        let rows: Vec<user::Model> = query.offset(offset).limit(limit).all(&self.db).await?;
+        let items: Vec<User> = rows.into_iter().map(Into::into).collect();
+        Ok(UserDirectoryResult {
+            total,
+            offset,
+            has_more: offset + (items.len() as u64) < total,
+            items,
+        })
+    }
+}
+
+fn clear_expired_status(mut presence: UserPresence, now_ts: &str) -> UserPresence {
+    if !presence.status_expires_at.is_empty() && presence.status_expires_at.as_str() <= now_ts {
+        presence.status = PRESENCE_ONLINE.to_string();
+        presence.status_text.clear();
+        presence.status_expires_at.clear();
+    }
+    presence
+}
+
+fn keyword_condition(keyword: &str) -> Condition {
+    Condition::any()
+        .add(user::Column::UserId.contains(keyword))
+        .add(user::Column::DisplayName.contains(keyword))
+        .add(user::Column::Locale.contains(keyword))
+        .add(user::Column::City.contains(keyword))
+        .add(user::Column::ExtraJson.contains(keyword))
 }
 
 fn now() -> String {
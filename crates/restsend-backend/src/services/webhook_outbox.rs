@@ -0,0 +1,559 @@
+use std::sync::Arc;
+
+use chrono::DateTime;
+use sea_orm::sea_query::{Expr, OnConflict, Query};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use sha2::{Digest, Sha256};
+use tokio::sync::Notify;
+
+use super::{DomainError, DomainResult};
+use crate::entity::{decode_json, encode_json, webhook_dead_letter, webhook_event, webhook_outbox};
+use crate::{WebhookDelivery, WebhookEventView, WebhookSelectForm};
+
+const DEFAULT_LIST_LIMIT: u64 = 100;
+const MAX_SELECT_LIMIT: u64 = 1000;
+
+// an event as written to the outbox, targets are resolved when it is fanned out
+pub struct NewWebhookEvent {
+    pub id: String,
+    pub name: String,
+    pub topic_id: String,
+    pub data: serde_json::Value,
+    pub explicit_targets: Vec<String>,
+    pub use_topic_webhooks: bool,
+}
+
+// a resolved delivery target of an event
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WebhookTarget {
+    pub url: String,
+    // secret of whoever registered the target (topic, inbox or subscription)
+    pub owner_secret: Option<String>,
+    // set when the target came from a webhook subscription
+    pub subscription_id: String,
+}
+
+// chat events are written with the chat log and again when they are published,
+// a stable id turns the second write into a no-op
+pub fn chat_event_id(topic_id: &str, chat_id: &str) -> String {
+    let digest = Sha256::digest(format!("{topic_id}\n{chat_id}").as_bytes());
+    let hex: String = digest.iter().take(16).map(|b| format!("{b:02x}")).collect();
+    format!("chat-{hex}")
+}
+
+// true when the event was inserted, false when the id was already recorded
+pub async fn record_event<C: ConnectionTrait>(
+    conn: &C,
+    event: &NewWebhookEvent,
+    now: i64,
+) -> Result<bool, DbErr> {
+    let row = webhook_event::ActiveModel {
+        id: Set(event.id.clone()),
+        event_name: Set(event.name.clone()),
+        topic_id: Set(event.topic_id.clone()),
+        payload: Set(event.data.to_string()),
+        explicit_targets_json: Set(encode_json(&event.explicit_targets)),
+        use_topic_webhooks: Set(event.use_topic_webhooks),
+        dispatched: Set(false),
+        created_at: Set(now),
+    };
+    let inserted = webhook_event::Entity::insert(row)
+        .on_conflict(
+            OnConflict::column(webhook_event::Column::Id)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(conn)
+        .await?;
+    Ok(inserted > 0)
+}
+
+// the json body posted to every target of an event
+pub fn delivery_body(event: &webhook_event::Model) -> Vec<u8> {
+    let data = serde_json::from_str::<serde_json::Value>(&event.payload)
+        .unwrap_or_else(|_| serde_json::json!({}));
+    let topic_id = Some(event.topic_id.as_str()).filter(|v| !v.is_empty());
+    serde_json::to_vec(&serde_json::json!({
+        "id": event.id,
+        "name": event.event_name,
+        "topicId": topic_id,
+        "data": data,
+    }))
+    .unwrap_or_default()
+}
+
+#[derive(Clone)]
+pub struct WebhookOutboxService {
+    db: DatabaseConnection,
+    wakeup: Arc<Notify>,
+}
+
+impl WebhookOutboxService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self {
+            db,
+            wakeup: Arc::new(Notify::new()),
+        }
+    }
+
+    pub async fn record(&self, event: &NewWebhookEvent, now: i64) -> DomainResult<bool> {
+        let inserted = record_event(&self.db, event, now).await?;
+        self.wake();
+        Ok(inserted)
+    }
+
+    // nudges the dispatcher instead of waiting for its next poll
+    pub fn wake(&self) {
+        self.wakeup.notify_one();
+    }
+
+    pub async fn woken(&self) {
+        self.wakeup.notified().await;
+    }
+
+    pub async fn event(&self, id: &str) -> DomainResult<Option<webhook_event::Model>> {
+        Ok(webhook_event::Entity::find_by_id(id.to_string())
+            .one(&self.db)
+            .await?)
+    }
+
+    pub async fn pending_events(&self, limit: u64) -> DomainResult<Vec<webhook_event::Model>> {
+        Ok(webhook_event::Entity::find()
+            .filter(webhook_event::Column::Dispatched.eq(false))
+            .order_by_asc(webhook_event::Column::CreatedAt)
+            .limit(limit)
+            .all(&self.db)
+            .await?)
+    }
+
+    // claims the event and writes one outbox row per target in one transaction,
+    // false when another dispatcher already fanned it out
+    pub async fn fan_out(
+        &self,
+        event_id: &str,
+        targets: &[WebhookTarget],
+        now: i64,
+    ) -> DomainResult<bool> {
+        let txn = self.db.begin().await?;
+        let claimed = webhook_event::Entity::update_many()
+            .col_expr(webhook_event::Column::Dispatched, Expr::value(true))
+            .filter(webhook_event::Column::Id.eq(event_id))
+            .filter(webhook_event::Column::Dispatched.eq(false))
+            .exec(&txn)
+            .await?;
+        if claimed.rows_affected == 0 {
+            txn.rollback().await?;
+            return Ok(false);
+        }
+        if !targets.is_empty() {
+            let rows = targets.iter().map(|target| webhook_outbox::ActiveModel {
+                id: Set(uuid::Uuid::new_v4().simple().to_string()),
+                event_id: Set(event_id.to_string()),
+                target: Set(target.url.clone()),
+                owner_secret: Set(target.owner_secret.clone().unwrap_or_default()),
+                attempts: Set(0),
+                next_attempt_at: Set(now),
+                last_error: Set(String::new()),
+                created_at: Set(now),
+                subscription_id: Set(target.subscription_id.clone()),
+            });
+            webhook_outbox::Entity::insert_many(rows)
+                .exec_without_returning(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn due(&self, now: i64, limit: u64) -> DomainResult<Vec<webhook_outbox::Model>> {
+        Ok(webhook_outbox::Entity::find()
+            .filter(webhook_outbox::Column::NextAttemptAt.lte(now))
+            .order_by_asc(webhook_outbox::Column::NextAttemptAt)
+            .limit(limit)
+            .all(&self.db)
+            .await?)
+    }
+
+    // leases the row until `until` so other dispatchers skip it while it is in flight,
+    // a node that dies mid delivery leaves it to be picked up once the lease runs out
+    pub async fn claim(&self, row: &webhook_outbox::Model, until: i64) -> DomainResult<bool> {
+        let result = webhook_outbox::Entity::update_many()
+            .col_expr(webhook_outbox::Column::NextAttemptAt, Expr::value(until))
+            .filter(webhook_outbox::Column::Id.eq(row.id.clone()))
+            .filter(webhook_outbox::Column::NextAttemptAt.eq(row.next_attempt_at))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn complete(&self, id: &str) -> DomainResult<()> {
+        webhook_outbox::Entity::delete_by_id(id.to_string())
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn reschedule(
+        &self,
+        id: &str,
+        attempts: i32,
+        next_attempt_at: i64,
+        error: &str,
+    ) -> DomainResult<()> {
+        webhook_outbox::Entity::update_many()
+            .col_expr(webhook_outbox::Column::Attempts, Expr::value(attempts))
+            .col_expr(
+                webhook_outbox::Column::NextAttemptAt,
+                Expr::value(next_attempt_at),
+            )
+            .col_expr(webhook_outbox::Column::LastError, Expr::value(error))
+            .filter(webhook_outbox::Column::Id.eq(id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    // moves an exhausted delivery to the dead letters
+    pub async fn bury(
+        &self,
+        row: webhook_outbox::Model,
+        attempts: i32,
+        error: &str,
+        now: i64,
+    ) -> DomainResult<()> {
+        let txn = self.db.begin().await?;
+        webhook_dead_letter::Entity::insert(webhook_dead_letter::ActiveModel {
+            id: Set(row.id.clone()),
+            event_id: Set(row.event_id),
+            target: Set(row.target),
+            owner_secret: Set(row.owner_secret),
+            attempts: Set(attempts),
+            last_error: Set(error.to_string()),
+            created_at: Set(row.created_at),
+            failed_at: Set(now),
+            subscription_id: Set(row.subscription_id),
+        })
+        .exec_without_returning(&txn)
+        .await?;
+        webhook_outbox::Entity::delete_by_id(row.id)
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    pub async fn list_events(
+        &self,
+        form: &WebhookSelectForm,
+    ) -> DomainResult<Vec<WebhookEventView>> {
+        let cond = select_condition(
+            form,
+            webhook_event::Column::Id,
+            webhook_event::Column::CreatedAt,
+            None,
+        )?;
+        let rows = webhook_event::Entity::find()
+            .filter(cond)
+            .order_by_desc(webhook_event::Column::CreatedAt)
+            .limit(list_limit(form))
+            .all(&self.db)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| WebhookEventView {
+                data: serde_json::from_str(&row.payload).unwrap_or_default(),
+                id: row.id,
+                name: row.event_name,
+                topic_id: row.topic_id,
+                dispatched: row.dispatched,
+                created_at: crate::entity::unix_to_rfc3339(row.created_at),
+            })
+            .collect())
+    }
+
+    pub async fn list_outbox(
+        &self,
+        form: &WebhookSelectForm,
+    ) -> DomainResult<Vec<WebhookDelivery>> {
+        let cond = select_condition(
+            form,
+            webhook_outbox::Column::Id,
+            webhook_outbox::Column::CreatedAt,
+            Some(webhook_outbox::Column::Target),
+        )?;
+        let rows = webhook_outbox::Entity::find()
+            .filter(cond)
+            .order_by_asc(webhook_outbox::Column::NextAttemptAt)
+            .limit(list_limit(form))
+            .all(&self.db)
+            .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn list_dead_letters(
+        &self,
+        form: &WebhookSelectForm,
+    ) -> DomainResult<Vec<WebhookDelivery>> {
+        let cond = select_condition(
+            form,
+            webhook_dead_letter::Column::Id,
+            webhook_dead_letter::Column::FailedAt,
+            Some(webhook_dead_letter::Column::Target),
+        )?;
+        let rows = webhook_dead_letter::Entity::find()
+            .filter(cond)
+            .order_by_desc(webhook_dead_letter::Column::FailedAt)
+            .limit(list_limit(form))
+            .all(&self.db)
+            .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    // puts dead letters back into the outbox with a fresh attempt budget
+    pub async fn retry_dead_letters(
+        &self,
+        form: &WebhookSelectForm,
+        now: i64,
+    ) -> DomainResult<u64> {
+        ensure_selection(form)?;
+        let cond = select_condition(
+            form,
+            webhook_dead_letter::Column::Id,
+            webhook_dead_letter::Column::FailedAt,
+            Some(webhook_dead_letter::Column::Target),
+        )?;
+        let txn = self.db.begin().await?;
+        let rows = webhook_dead_letter::Entity::find()
+            .filter(cond)
+            .limit(MAX_SELECT_LIMIT)
+            .all(&txn)
+            .await?;
+        let count = rows.len() as u64;
+        for row in rows {
+            webhook_outbox::Entity::insert(webhook_outbox::ActiveModel {
+                id: Set(row.id.clone()),
+                event_id: Set(row.event_id),
+                target: Set(row.target),
+                owner_secret: Set(row.owner_secret),
+                attempts: Set(0),
+                next_attempt_at: Set(now),
+                last_error: Set(row.last_error),
+                created_at: Set(row.created_at),
+                subscription_id: Set(row.subscription_id),
+            })
+            .exec_without_returning(&txn)
+            .await?;
+            webhook_dead_letter::Entity::delete_by_id(row.id)
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+        if count > 0 {
+            self.wake();
+        }
+        Ok(count)
+    }
+
+    // marks recorded events undispatched so they are fanned out to their current targets again
+    pub async fn replay(&self, form: &WebhookSelectForm) -> DomainResult<u64> {
+        ensure_selection(form)?;
+        let cond = select_condition(
+            form,
+            webhook_event::Column::Id,
+            webhook_event::Column::CreatedAt,
+            None,
+        )?;
+        let result = webhook_event::Entity::update_many()
+            .col_expr(webhook_event::Column::Dispatched, Expr::value(false))
+            .filter(cond)
+            .exec(&self.db)
+            .await?;
+        if result.rows_affected > 0 {
+            self.wake();
+        }
+        Ok(result.rows_affected)
+    }
+
+    // drops delivered events past retention, events still referenced by a pending
+    // delivery or a dead letter are kept so they can still be sent
+    pub async fn cleanup(&self, before: i64) -> DomainResult<u64> {
+        let result = webhook_event::Entity::delete_many()
+            .filter(webhook_event::Column::Dispatched.eq(true))
+            .filter(webhook_event::Column::CreatedAt.lt(before))
+            .filter(
+                webhook_event::Column::Id.not_in_subquery(
+                    Query::select()
+                        .column(webhook_outbox::Column::EventId)
+                        .from(webhook_outbox::Entity)
+                        .to_owned(),
+                ),
+            )
+            .filter(
+                webhook_event::Column::Id.not_in_subquery(
+                    Query::select()
+                        .column(webhook_dead_letter::Column::EventId)
+                        .from(webhook_dead_letter::Entity)
+                        .to_owned(),
+                ),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+pub fn explicit_targets(event: &webhook_event::Model) -> Vec<String> {
+    decode_json(&event.explicit_targets_json)
+}
+
+fn list_limit(form: &WebhookSelectForm) -> u64 {
+    form.limit
+        .unwrap_or(DEFAULT_LIST_LIMIT)
+        .clamp(1, MAX_SELECT_LIMIT)
+}
+
+// retry and replay never act on everything at once
+fn ensure_selection(form: &WebhookSelectForm) -> DomainResult<()> {
+    if form.ids.is_empty() && form.since.trim().is_empty() && form.until.trim().is_empty() {
+        return Err(DomainError::Validation(
+            "ids or a time range is required".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn select_condition<C: ColumnTrait>(
+    form: &WebhookSelectForm,
+    id: C,
+    time: C,
+    target: Option<C>,
+) -> DomainResult<Condition> {
+    let mut cond = Condition::all();
+    if !form.ids.is_empty() {
+        cond = cond.add(id.is_in(form.ids.clone()));
+    } else {
+        if let Some(since) = parse_time(&form.since)? {
+            cond = cond.add(time.gte(since));
+        }
+        if let Some(until) = parse_time(&form.until)? {
+            cond = cond.add(time.lte(until));
+        }
+    }
+    if let Some(target) = target.filter(|_| !form.target.trim().is_empty()) {
+        cond = cond.add(target.eq(form.target.trim()));
+    }
+    Ok(cond)
+}
+
+fn parse_time(value: &str) -> DomainResult<Option<i64>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|v| Some(v.timestamp()))
+        .map_err(|_| DomainError::Validation(format!("invalid time {value}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::db::run_migrations;
+    use sea_orm::Database;
+
+    fn event(id: &str) -> NewWebhookEvent {
+        NewWebhookEvent {
+            id: id.to_string(),
+            name: "chat".to_string(),
+            topic_id: "topic-1".to_string(),
+            data: serde_json::json!({"chatId": "c1"}),
+            explicit_targets: vec![],
+            use_topic_webhooks: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_outbox_fan_out_bury_retry_and_replay() {
+        let db_url = format!(
+            "sqlite:file:test-{}?mode=memory&cache=shared",
+            uuid::Uuid::new_v4().simple()
+        );
+        let db = Database::connect(&db_url).await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let outbox = WebhookOutboxService::new(db);
+
+        assert!(outbox.record(&event("evt-1"), 100).await.unwrap());
+        // the same id from the publish path falls through
+        assert!(!outbox.record(&event("evt-1"), 101).await.unwrap());
+
+        let pending = outbox.pending_events(10).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        let targets = vec![
+            WebhookTarget {
+                url: "http://a/hook".to_string(),
+                ..Default::default()
+            },
+            WebhookTarget {
+                url: "http://b/hook".to_string(),
+                owner_secret: Some("topic-secret".to_string()),
+                subscription_id: "sub-1".to_string(),
+            },
+        ];
+        assert!(outbox.fan_out("evt-1", &targets, 100).await.unwrap());
+        assert!(!outbox.fan_out("evt-1", &targets, 100).await.unwrap());
+        assert!(outbox.pending_events(10).await.unwrap().is_empty());
+
+        let due = outbox.due(100, 10).await.unwrap();
+        assert_eq!(due.len(), 2);
+        assert!(outbox.claim(&due[0], 130).await.unwrap());
+        // a second dispatcher holding the stale row loses
+        assert!(!outbox.claim(&due[0], 130).await.unwrap());
+        assert_eq!(outbox.due(100, 10).await.unwrap().len(), 1);
+
+        outbox.bury(due[0].clone(), 3, "boom", 140).await.unwrap();
+        let dead = outbox
+            .list_dead_letters(&WebhookSelectForm::default())
+            .await
+            .unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].last_error, "boom");
+
+        assert!(outbox
+            .retry_dead_letters(&WebhookSelectForm::default(), 150)
+            .await
+            .is_err());
+        let by_id = WebhookSelectForm {
+            ids: vec![dead[0].id.clone()],
+            ..Default::default()
+        };
+        assert_eq!(outbox.retry_dead_letters(&by_id, 150).await.unwrap(), 1);
+        assert_eq!(outbox.due(150, 10).await.unwrap().len(), 2);
+
+        let body: serde_json::Value = serde_json::from_slice(&delivery_body(
+            &outbox.event("evt-1").await.unwrap().unwrap(),
+        ))
+        .unwrap();
+        assert_eq!(body["id"], "evt-1");
+        assert_eq!(body["topicId"], "topic-1");
+        assert_eq!(body["data"]["chatId"], "c1");
+
+        let range = WebhookSelectForm {
+            since: "1970-01-01T00:01:00+00:00".to_string(),
+            until: "1970-01-01T00:02:00+00:00".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(outbox.replay(&range).await.unwrap(), 1);
+        assert_eq!(outbox.pending_events(10).await.unwrap().len(), 1);
+
+        // referenced by pending deliveries, so retention keeps it
+        assert!(outbox.fan_out("evt-1", &[], 200).await.unwrap());
+        assert_eq!(outbox.cleanup(1000).await.unwrap(), 0);
+        for row in outbox.due(1000, 10).await.unwrap() {
+            outbox.complete(&row.id).await.unwrap();
+        }
+        assert_eq!(outbox.cleanup(1000).await.unwrap(), 1);
+    }
+}
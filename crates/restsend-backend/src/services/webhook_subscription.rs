@@ -0,0 +1,345 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder,
+};
+use tokio::sync::RwLock;
+
+use super::{DomainError, DomainResult, WebhookTarget};
+use crate::entity::webhook_subscription;
+use crate::util::split_list;
+use crate::{WebhookSubscription, WebhookSubscriptionForm};
+
+// changes made on this node drop the cache at once, other nodes pick them up on reload
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+// an enabled subscription with its filters split once at load time
+struct ActiveSubscription {
+    id: String,
+    url: String,
+    secret: String,
+    events: Vec<String>,
+    topic_ids: Vec<String>,
+    kinds: Vec<String>,
+}
+
+#[derive(Default)]
+struct SubscriptionCache {
+    // bumped on every change so a load that raced with it is not kept
+    generation: u64,
+    loaded: Option<(Instant, Arc<Vec<ActiveSubscription>>)>,
+}
+
+#[derive(Clone)]
+pub struct WebhookSubscriptionService {
+    db: DatabaseConnection,
+    cache: Arc<RwLock<SubscriptionCache>>,
+}
+
+impl WebhookSubscriptionService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self {
+            db,
+            cache: Arc::new(RwLock::new(SubscriptionCache::default())),
+        }
+    }
+
+    pub async fn list(&self) -> DomainResult<Vec<WebhookSubscription>> {
+        let rows = webhook_subscription::Entity::find()
+            .order_by_asc(webhook_subscription::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn create(
+        &self,
+        form: WebhookSubscriptionForm,
+        created_by: &str,
+    ) -> DomainResult<WebhookSubscription> {
+        let url = normalize_url(form.url.as_deref().unwrap_or_default())?;
+        let now = Utc::now().to_rfc3339();
+        let row = webhook_subscription::ActiveModel {
+            id: Set(uuid::Uuid::new_v4().simple().to_string()),
+            url: Set(url),
+            secret: Set(form.secret.unwrap_or_default().trim().to_string()),
+            events: Set(normalize_events(&form.events.unwrap_or_default())?.join(",")),
+            topic_ids: Set(normalize_list(&form.topic_ids.unwrap_or_default()).join(",")),
+            kinds: Set(normalize_list(&form.kinds.unwrap_or_default()).join(",")),
+            enabled: Set(form.enabled.unwrap_or(true)),
+            created_by: Set(created_by.to_string()),
+            created_at: Set(now.clone()),
+            updated_at: Set(now),
+        }
+        .insert(&self.db)
+        .await?;
+        self.invalidate().await;
+        Ok(row.into())
+    }
+
+    pub async fn update(
+        &self,
+        id: &str,
+        form: WebhookSubscriptionForm,
+    ) -> DomainResult<WebhookSubscription> {
+        let row = webhook_subscription::Entity::find_by_id(id.to_string())
+            .one(&self.db)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+        let mut active = row.into_active_model();
+        if let Some(url) = form.url {
+            active.url = Set(normalize_url(&url)?);
+        }
+        if let Some(secret) = form.secret {
+            active.secret = Set(secret.trim().to_string());
+        }
+        if let Some(events) = form.events {
+            active.events = Set(normalize_events(&events)?.join(","));
+        }
+        if let Some(topic_ids) = form.topic_ids {
+            active.topic_ids = Set(normalize_list(&topic_ids).join(","));
+        }
+        if let Some(kinds) = form.kinds {
+            active.kinds = Set(normalize_list(&kinds).join(","));
+        }
+        if let Some(enabled) = form.enabled {
+            active.enabled = Set(enabled);
+        }
+        active.updated_at = Set(Utc::now().to_rfc3339());
+        let row = active.update(&self.db).await?;
+        self.invalidate().await;
+        Ok(row.into())
+    }
+
+    pub async fn delete(&self, id: &str) -> DomainResult<()> {
+        let result = webhook_subscription::Entity::delete_by_id(id.to_string())
+            .exec(&self.db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(DomainError::NotFound);
+        }
+        self.invalidate().await;
+        Ok(())
+    }
+
+    async fn invalidate(&self) {
+        let mut cache = self.cache.write().await;
+        cache.generation += 1;
+        cache.loaded = None;
+    }
+
+    async fn enabled(&self) -> DomainResult<Arc<Vec<ActiveSubscription>>> {
+        let generation = {
+            let cache = self.cache.read().await;
+            if let Some((loaded_at, rows)) = &cache.loaded {
+                if loaded_at.elapsed() < CACHE_TTL {
+                    return Ok(rows.clone());
+                }
+            }
+            cache.generation
+        };
+        let rows = webhook_subscription::Entity::find()
+            .filter(webhook_subscription::Column::Enabled.eq(true))
+            .order_by_asc(webhook_subscription::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+        let rows = Arc::new(
+            rows.into_iter()
+                .map(|row| ActiveSubscription {
+                    events: split_list(&row.events),
+                    topic_ids: split_list(&row.topic_ids),
+                    kinds: split_list(&row.kinds),
+                    id: row.id,
+                    url: row.url,
+                    secret: row.secret,
+                })
+                .collect::<Vec<_>>(),
+        );
+        let mut cache = self.cache.write().await;
+        if cache.generation == generation {
+            cache.loaded = Some((Instant::now(), rows.clone()));
+        }
+        Ok(rows)
+    }
+
+    // enabled subscriptions that want this event, `topic_kind` is only
+    // looked up when a matching subscription filters on kinds
+    pub async fn targets_for<F, Fut>(
+        &self,
+        event_name: &str,
+        topic_id: Option<&str>,
+        topic_kind: F,
+    ) -> DomainResult<Vec<WebhookTarget>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Option<String>>,
+    {
+        let rows = self.enabled().await?;
+        let candidates = rows
+            .iter()
+            .filter(|row| {
+                row.events.is_empty() || row.events.iter().any(|p| event_matches(p, event_name))
+            })
+            .filter(|row| {
+                row.topic_ids.is_empty()
+                    || topic_id.is_some_and(|id| row.topic_ids.iter().any(|v| v == id))
+            })
+            .collect::<Vec<_>>();
+
+        let mut kind = None;
+        if candidates.iter().any(|row| !row.kinds.is_empty()) {
+            kind = topic_kind().await;
+        }
+        Ok(candidates
+            .into_iter()
+            .filter(|row| {
+                row.kinds.is_empty()
+                    || kind
+                        .as_deref()
+                        .is_some_and(|k| row.kinds.iter().any(|v| v == k))
+            })
+            .map(|row| WebhookTarget {
+                url: row.url.clone(),
+                owner_secret: Some(row.secret.clone()).filter(|v| !v.is_empty()),
+                subscription_id: row.id.clone(),
+            })
+            .collect())
+    }
+}
+
+// `chat` matches exactly, `topic.*` matches `topic.create` and `topic.knock.accept`, `*` matches all
+fn event_matches(pattern: &str, event_name: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match pattern.strip_suffix(".*") {
+        Some(prefix) => event_name
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('.')),
+        None => pattern == event_name,
+    }
+}
+
+fn normalize_url(url: &str) -> DomainResult<String> {
+    let url = url.trim();
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(DomainError::Validation(
+            "url must be http or https".to_string(),
+        ));
+    }
+    Ok(url.to_string())
+}
+
+fn normalize_events(events: &[String]) -> DomainResult<Vec<String>> {
+    let events = normalize_list(events);
+    for pattern in events.iter() {
+        let name = pattern.strip_suffix(".*").unwrap_or(pattern);
+        let valid = pattern == "*"
+            || (!name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.'));
+        if !valid {
+            return Err(DomainError::Validation(format!(
+                "invalid event pattern {pattern}"
+            )));
+        }
+    }
+    Ok(events)
+}
+
+fn normalize_list(values: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for value in values {
+        let value = value.trim();
+        if !value.is_empty() && !value.contains(',') && !out.iter().any(|v| v == value) {
+            out.push(value.to_string());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_patterns() {
+        assert!(event_matches("*", "chat"));
+        assert!(event_matches("chat", "chat"));
+        assert!(!event_matches("chat", "chat.recall"));
+        assert!(event_matches("topic.*", "topic.create"));
+        assert!(event_matches("topic.*", "topic.knock.accept"));
+        assert!(!event_matches("topic.*", "topic"));
+        assert!(!event_matches("topic.*", "topics.create"));
+        assert!(normalize_events(&["Topic.*".to_string()]).is_err());
+        assert!(normalize_events(&[".*".to_string()]).is_err());
+        assert_eq!(
+            normalize_events(&[" chat ".to_string(), "chat".to_string()]).unwrap(),
+            vec!["chat".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_targets_are_cached_until_changed() {
+        let db = crate::infra::db::connect_db(&format!(
+            "sqlite:file:webhook-subscriptions-{}?mode=memory&cache=shared",
+            uuid::Uuid::new_v4().simple()
+        ))
+        .await
+        .unwrap();
+        crate::infra::db::run_migrations(&db).await.unwrap();
+        let service = WebhookSubscriptionService::new(db.clone());
+        let targets = |service: &WebhookSubscriptionService| {
+            let service = service.clone();
+            async move {
+                service
+                    .targets_for("chat", None, || async { None })
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|v| v.url)
+                    .collect::<Vec<_>>()
+            }
+        };
+        let form = |url: &str| WebhookSubscriptionForm {
+            url: Some(url.to_string()),
+            ..WebhookSubscriptionForm::default()
+        };
+
+        let first = service.create(form("http://a/hook"), "test").await.unwrap();
+        assert_eq!(targets(&service).await, vec!["http://a/hook"]);
+
+        // rows written behind the service's back wait for the ttl
+        let now = Utc::now().to_rfc3339();
+        webhook_subscription::ActiveModel {
+            id: Set("direct".to_string()),
+            url: Set("http://direct/hook".to_string()),
+            secret: Set(String::new()),
+            events: Set(String::new()),
+            topic_ids: Set(String::new()),
+            kinds: Set(String::new()),
+            enabled: Set(true),
+            created_by: Set("test".to_string()),
+            created_at: Set(now.clone()),
+            updated_at: Set(now),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        assert_eq!(targets(&service).await, vec!["http://a/hook"]);
+
+        let disable = WebhookSubscriptionForm {
+            enabled: Some(false),
+            ..WebhookSubscriptionForm::default()
+        };
+        service.update(&first.id, disable).await.unwrap();
+        assert_eq!(targets(&service).await, vec!["http://direct/hook"]);
+
+        service.delete("direct").await.unwrap();
+        assert!(targets(&service).await.is_empty());
+    }
+}
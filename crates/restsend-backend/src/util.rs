@@ -0,0 +1,9 @@
+// comma separated columns such as api key scopes and webhook subscription filters
+pub fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
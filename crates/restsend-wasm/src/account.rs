@@ -77,6 +77,23 @@ pub async fn guestLogin(
         .map_err(|e| e.into())
 }
 
+/// Exchange a refresh token for a new token pair
+#[allow(non_snake_case)]
+#[wasm_bindgen]
+pub async fn refreshToken(
+    endpoint: String,
+    userId: String,
+    refreshToken: String,
+) -> Result<JsValue, JsValue> {
+    let endpoint = get_endpoint(endpoint);
+    let serializer = &serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+
+    restsend_sdk::services::auth::refresh_auth_token(endpoint, userId, refreshToken)
+        .await
+        .map(|v| v.serialize(serializer).unwrap_or(JsValue::UNDEFINED))
+        .map_err(|e| e.into())
+}
+
 /// Logout with token
 #[allow(non_snake_case)]
 #[wasm_bindgen]
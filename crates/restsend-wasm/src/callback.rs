@@ -1,7 +1,7 @@
 use crate::{js_util::get_function, CallbackFunction, Client};
 use restsend_sdk::{
     callback::ChatRequestStatus,
-    models::{Content, Conversation},
+    models::{AuthInfo, Content, Conversation, UserPresence},
     request::ChatRequest,
     services::response::Upload,
 };
@@ -179,6 +179,7 @@ pub(super) struct CallbackWasmWrap {
     pub(super) cb_on_connected: CallbackFunction,
     pub(super) cb_on_connecting: CallbackFunction,
     pub(super) cb_on_token_expired: CallbackFunction,
+    pub(super) cb_on_token_refreshed: CallbackFunction,
     pub(super) cb_on_net_broken: CallbackFunction,
     pub(super) cb_on_kickoff_by_other_client: CallbackFunction,
     pub(super) cb_on_ping_failed: CallbackFunction,
@@ -189,6 +190,7 @@ pub(super) struct CallbackWasmWrap {
     pub(super) cb_on_topic_read: CallbackFunction,
     pub(super) cb_on_conversations_updated: CallbackFunction,
     pub(super) cb_on_conversation_removed: CallbackFunction,
+    pub(super) cb_on_presence_changed: CallbackFunction,
 }
 unsafe impl Send for CallbackWasmWrap {}
 unsafe impl Sync for CallbackWasmWrap {}
@@ -241,6 +243,16 @@ impl restsend_sdk::callback::RsCallback for CallbackWasmWrap {
         }
     }
 
+    fn on_token_refreshed(&self, info: AuthInfo) {
+        if let Some(cb) = self.cb_on_token_refreshed.borrow().as_ref() {
+            let serializer = &serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+            let info = info.serialize(serializer).unwrap_or(JsValue::UNDEFINED);
+            cb.call1(&JsValue::NULL, &info)
+                .err()
+                .map(|e| web_sys::console::error_1(&e));
+        }
+    }
+
     fn on_system_request(&self, req: ChatRequest) -> Option<ChatRequest> {
         if let Some(cb) = self.cb_on_system_request.borrow().as_ref() {
             let serializer = &serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
@@ -344,6 +356,14 @@ impl restsend_sdk::callback::RsCallback for CallbackWasmWrap {
                 .map(|e| web_sys::console::error_1(&e));
         }
     }
+    fn on_presence_changed(&self, user_id: String, presence: UserPresence) {
+        if let Some(cb) = self.cb_on_presence_changed.borrow().as_ref() {
+            let presence = serde_wasm_bindgen::to_value(&presence).unwrap_or(JsValue::UNDEFINED);
+            cb.call2(&JsValue::NULL, &JsValue::from_str(&user_id), &presence)
+                .err()
+                .map(|e| web_sys::console::error_1(&e));
+        }
+    }
 }
 
 #[allow(non_snake_case)]
@@ -376,6 +396,17 @@ impl Client {
                 .replace(js_sys::Function::from(cb));
         }
     }
+    /// Set the callback when the token is refreshed automatically
+    /// # Arguments
+    /// * `info` AuthInfo - The new auth info, save it for the next login
+    #[wasm_bindgen(setter)]
+    pub fn set_ontokenrefreshed(&self, cb: JsValue) {
+        if cb.is_function() {
+            self.cb_on_token_refreshed
+                .borrow_mut()
+                .replace(js_sys::Function::from(cb));
+        }
+    }
     /// Set the callback when connection broken
     /// # Arguments
     /// * `reason` String - The reason of the connection broken
@@ -580,4 +611,25 @@ impl Client {
                 .replace(js_sys::Function::from(cb));
         }
     }
+    /// Set the callback when a subscribed user's presence changed
+    /// # Arguments
+    /// * `userId` - The user id
+    /// * `presence` - The presence of the user
+    /// # Example
+    /// ```javascript
+    /// const client = new Client(info);
+    /// await client.connect();
+    /// await client.subscribePresence(['bob']);
+    /// client.onpresencechanged = (userId, presence) => {
+    /// console.log(userId, presence.online);
+    /// }
+    /// ```
+    #[wasm_bindgen(setter)]
+    pub fn set_onpresencechanged(&self, cb: JsValue) {
+        if cb.is_function() {
+            self.cb_on_presence_changed
+                .borrow_mut()
+                .replace(js_sys::Function::from(cb));
+        }
+    }
 }
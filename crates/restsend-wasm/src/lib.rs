@@ -25,6 +25,7 @@ pub struct Client {
     cb_on_connected: CallbackFunction,
     cb_on_connecting: CallbackFunction,
     cb_on_token_expired: CallbackFunction,
+    cb_on_token_refreshed: CallbackFunction,
     cb_on_net_broken: CallbackFunction,
     cb_on_kickoff_by_other_client: CallbackFunction,
     cb_on_ping_failed: CallbackFunction,
@@ -35,6 +36,7 @@ pub struct Client {
     cb_on_topic_read: CallbackFunction,
     cb_on_conversations_updated: CallbackFunction,
     cb_on_conversation_removed: CallbackFunction,
+    cb_on_presence_changed: CallbackFunction,
     inner: restsend_sdk::client::Client,
 }
 
@@ -56,6 +58,8 @@ impl Client {
                     endpoint: get_string(&info, "endpoint").unwrap_or_default(),
                     user_id: get_string(&info, "userId").unwrap_or_default(),
                     token: get_string(&info, "token").unwrap_or_default(),
+                    refresh_token: get_string(&info, "refreshToken").unwrap_or_default(),
+                    expires_at: get_string(&info, "expiresAt").unwrap_or_default(),
                     is_cross_domain: get_bool(&info, "isCrossDomain"),
                     is_staff: get_bool(&info, "isStaff"),
                     avatar: get_string(&info, "avatar").unwrap_or_default(),
@@ -260,6 +264,7 @@ impl Client {
         let cb_on_connected = Rc::new(RefCell::new(None));
         let cb_on_connecting = Rc::new(RefCell::new(None));
         let cb_on_token_expired = Rc::new(RefCell::new(None));
+        let cb_on_token_refreshed = Rc::new(RefCell::new(None));
         let cb_on_net_broken = Rc::new(RefCell::new(None));
         let cb_on_kickoff_by_other_client = Rc::new(RefCell::new(None));
         let cb_on_ping_failed = Rc::new(RefCell::new(None));
@@ -270,11 +275,13 @@ impl Client {
         let cb_on_topic_read = Rc::new(RefCell::new(None));
         let cb_on_conversations_updated = Rc::new(RefCell::new(None));
         let cb_on_conversation_removed = Rc::new(RefCell::new(None));
+        let cb_on_presence_changed = Rc::new(RefCell::new(None));
 
         let cb = Box::new(CallbackWasmWrap {
             cb_on_connected: cb_on_connected.clone(),
             cb_on_connecting: cb_on_connecting.clone(),
             cb_on_token_expired: cb_on_token_expired.clone(),
+            cb_on_token_refreshed: cb_on_token_refreshed.clone(),
             cb_on_net_broken: cb_on_net_broken.clone(),
             cb_on_kickoff_by_other_client: cb_on_kickoff_by_other_client.clone(),
             cb_on_ping_failed: cb_on_ping_failed.clone(),
@@ -285,6 +292,7 @@ impl Client {
             cb_on_topic_read: cb_on_topic_read.clone(),
             cb_on_conversations_updated: cb_on_conversations_updated.clone(),
             cb_on_conversation_removed: cb_on_conversation_removed.clone(),
+            cb_on_presence_changed: cb_on_presence_changed.clone(),
         });
         inner.set_callback(Some(cb));
 
@@ -292,6 +300,7 @@ impl Client {
             cb_on_connected,
             cb_on_connecting,
             cb_on_token_expired,
+            cb_on_token_refreshed,
             cb_on_net_broken,
             cb_on_kickoff_by_other_client,
             cb_on_ping_failed,
@@ -302,6 +311,7 @@ impl Client {
             cb_on_topic_read,
             cb_on_conversations_updated,
             cb_on_conversation_removed,
+            cb_on_presence_changed,
             inner,
         }
     }
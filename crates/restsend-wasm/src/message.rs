@@ -74,6 +74,27 @@ impl Client {
     pub async fn doTyping(&self, topicId: String) -> Result<(), JsValue> {
         self.inner.do_typing(topicId).await.map_err(|e| e.into())
     }
+    /// Subscribe presence changes of users, replaces the previous subscription
+    /// # Arguments
+    /// * `userIds` - The user ids, subscribe all contacts when empty
+    /// * `topicId` - Subscribe all members of the topic (optional)
+    pub async fn subscribePresence(
+        &self,
+        userIds: Vec<String>,
+        topicId: Option<String>,
+    ) -> Result<(), JsValue> {
+        self.inner
+            .subscribe_presence(userIds, topicId)
+            .await
+            .map_err(|e| e.into())
+    }
+    /// Unsubscribe all presence changes
+    pub async fn unsubscribePresence(&self) -> Result<(), JsValue> {
+        self.inner
+            .unsubscribe_presence()
+            .await
+            .map_err(|e| e.into())
+    }
     /// Recall message
     /// # Arguments
     /// * `topicId` - The topic id
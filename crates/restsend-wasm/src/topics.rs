@@ -1,5 +1,5 @@
 use super::Client;
-use crate::js_util::get_string;
+use crate::js_util::{get_f64, get_string};
 use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
@@ -108,6 +108,72 @@ impl Client {
             .await?;
         r.serialize(serializer).map_err(|e| e.into())
     }
+    /// Search topic members page by page, results are cached locally
+    /// #Arguments
+    /// * `topicId` - topic id
+    /// * `option` - option
+    ///     * `updatedAt` - only members changed after updated_at
+    ///     * `cursor` - cursor from the previous page
+    ///     * `keyword` - match name or remark
+    ///     * `limit` - limit
+    /// #Return
+    /// * `ListUserResult` || `undefined`
+    pub async fn searchTopicMembers(
+        &self,
+        topicId: String,
+        option: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let serializer = &serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        let r = self
+            .inner
+            .search_topic_members(
+                topicId,
+                get_string(&option, "updatedAt").unwrap_or_default(),
+                get_string(&option, "cursor").unwrap_or_default(),
+                get_string(&option, "keyword").unwrap_or_default(),
+                get_f64(&option, "limit") as u32,
+            )
+            .await?;
+        r.serialize(serializer).map_err(|e| e.into())
+    }
+    /// Sync topic members into local storage
+    /// #Arguments
+    /// * `topicId` - topic id
+    /// * `fullSync` - drop cached members and fetch all again
+    /// * `limit` - page size, default 100
+    /// #Return
+    /// * `Number` - count of members fetched
+    pub async fn syncTopicMembers(
+        &self,
+        topicId: String,
+        fullSync: bool,
+        limit: Option<u32>,
+    ) -> Result<u32, JsValue> {
+        self.inner
+            .sync_topic_members(topicId, fullSync, limit)
+            .await
+            .map_err(|e| e.into())
+    }
+    /// Get topic members from local storage
+    /// #Arguments
+    /// * `topicId` - topic id
+    /// * `keyword` - match name or remark
+    /// * `limit` - limit
+    /// #Return
+    /// * `Vec<User>`
+    pub async fn getCachedTopicMembers(
+        &self,
+        topicId: String,
+        keyword: Option<String>,
+        limit: u32,
+    ) -> JsValue {
+        let serializer = &serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        self.inner
+            .get_cached_topic_members(topicId, keyword, limit)
+            .await
+            .serialize(serializer)
+            .unwrap_or(JsValue::UNDEFINED)
+    }
     /// Get topic knocks
     /// #Arguments
     /// * `topicId` - topic id
@@ -59,6 +59,93 @@ impl Client {
             .await
             .map_err(|e| e.into())
     }
+    /// Sync contacts into local storage
+    /// #Arguments
+    /// * `fullSync` - drop cached contacts and fetch all again
+    /// * `limit` - page size, default 100
+    /// #Return
+    /// * `Number` - count of contacts fetched or removed
+    pub async fn syncContacts(&self, fullSync: bool, limit: Option<u32>) -> Result<u32, JsValue> {
+        self.inner
+            .sync_contacts(fullSync, limit)
+            .await
+            .map_err(|e| e.into())
+    }
+    /// Get contacts from local storage
+    /// #Arguments
+    /// * `keyword` - match name, remark or user id
+    /// #Return
+    /// * `Vec<User>`
+    pub async fn getContacts(&self, keyword: Option<String>) -> JsValue {
+        let serializer = &serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        self.inner
+            .get_contacts(keyword)
+            .await
+            .serialize(serializer)
+            .unwrap_or(JsValue::UNDEFINED)
+    }
+    /// Set own presence status
+    /// #Arguments
+    /// * `status` - online, away, busy or invisible
+    /// * `statusText` - custom status text
+    /// * `expireSecs` - clear the status after seconds, 0 keeps it
+    /// #Return
+    /// * `UserPresence`
+    pub async fn setPresence(
+        &self,
+        status: String,
+        statusText: Option<String>,
+        expireSecs: Option<u32>,
+    ) -> Result<JsValue, JsValue> {
+        let serializer = &serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        let presence = self
+            .inner
+            .set_presence(status, statusText, expireSecs.map(|v| v as u64))
+            .await?;
+        presence.serialize(serializer).map_err(|e| e.into())
+    }
+    /// Get user presence
+    /// #Arguments
+    /// * `userId` - user id
+    /// #Return
+    /// * `UserPresence`
+    pub async fn getPresence(&self, userId: String) -> Result<JsValue, JsValue> {
+        let serializer = &serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        let presence = self.inner.get_presence(userId).await?;
+        presence.serialize(serializer).map_err(|e| e.into())
+    }
+    /// List login sessions of current user
+    /// #Return
+    /// * `DeviceSession[]`
+    pub async fn listSessions(&self) -> Result<JsValue, JsValue> {
+        let serializer = &serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        let sessions = self.inner.list_sessions().await?;
+        sessions.serialize(serializer).map_err(|e| e.into())
+    }
+    /// Revoke a login session, its connection will be kicked out
+    /// #Arguments
+    /// * `sessionId` - session id from listSessions
+    pub async fn revokeSession(&self, sessionId: String) -> Result<(), JsValue> {
+        self.inner
+            .revoke_session(sessionId)
+            .await
+            .map_err(|e| e.into())
+    }
+    /// Log out all other devices, keeping the current session
+    pub async fn logoutOtherDevices(&self) -> Result<(), JsValue> {
+        self.inner
+            .logout_other_devices()
+            .await
+            .map_err(|e| e.into())
+    }
+    /// Exchange the refresh token for a new token pair
+    /// #Return
+    /// * `AuthInfo`
+    pub async fn refreshAuth(&self) -> Result<JsValue, JsValue> {
+        let serializer = &serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        let info = self.inner.refresh_auth().await?;
+        info.serialize(serializer).map_err(|e| e.into())
+    }
 
     /// Set allow guest chat
     /// #Arguments
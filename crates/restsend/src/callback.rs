@@ -1,5 +1,5 @@
 use crate::{
-    models::{Content, Conversation, GetChatLogsResult},
+    models::{AuthInfo, Content, Conversation, GetChatLogsResult, UserPresence},
     request::ChatRequest,
     services::response::Upload,
     Error,
@@ -32,6 +32,7 @@ pub trait RsCallback: Send + Sync {
     fn on_connected(&self) {}
     fn on_connecting(&self) {}
     fn on_token_expired(&self, reason: String) {}
+    fn on_token_refreshed(&self, info: AuthInfo) {}
     fn on_net_broken(&self, reason: String) {}
     fn on_kickoff_by_other_client(&self, reason: String) {}
     fn on_ping_failed(&self, reason: String) {}
@@ -50,6 +51,7 @@ pub trait RsCallback: Send + Sync {
     fn on_topic_read(&self, topic_id: String, message: ChatRequest) {}
     fn on_conversations_updated(&self, conversations: Vec<Conversation>, total: Option<i64>) {}
     fn on_conversation_removed(&self, conversation_id: String) {}
+    fn on_presence_changed(&self, user_id: String, presence: UserPresence) {}
 }
 #[allow(unused_variables)]
 #[export_wasm_or_ffi(#[uniffi::export(callback_interface)])]
@@ -4,7 +4,10 @@ use super::{
 };
 use crate::{
     client::store::ClientOptionRef,
+    error::ClientError,
+    models::UserPresence,
     request::{ChatRequest, ChatRequestType},
+    services::auth::login_with_token,
     utils::{sleep, spawn_task},
     websocket::{WebSocket, WebSocketCallback, WebsocketOption},
 };
@@ -199,11 +202,9 @@ impl WebSocketCallback for ConnectionInner {
     }
 
     fn on_unauthorized(&self) {
-        self.callback_ref
-            .read()
-            .unwrap()
-            .as_ref()
-            .map(|cb| cb.on_token_expired("unauthorized".to_string()));
+        // serve_connection tries a refresh before reporting on_token_expired
+        self.connect_state_ref
+            .add_error("websocket unauthorized".to_string());
     }
 
     fn on_net_broken(&self, reason: String) {
@@ -286,11 +287,10 @@ impl Client {
         let state_ref = self.state.clone();
         let store_ref = self.store.clone();
         let endpoint = self.endpoint.clone();
-        let token = self.token.clone();
         let is_cross_domain = self.is_cross_domain;
 
         spawn_task(async move {
-            serve_connection(&endpoint, &token, is_cross_domain, store_ref, state_ref).await;
+            serve_connection(&endpoint, is_cross_domain, store_ref, state_ref).await;
             warn!("connection serve_connection done");
         });
     }
@@ -332,9 +332,32 @@ impl Client {
     }
 }
 
+// the websocket handshake only reports 401, so ask the http api why the token was rejected
+async fn token_expired_reason(
+    endpoint: &str,
+    store_ref: &ClientStoreRef,
+    refresh_err: ClientError,
+) -> String {
+    if let ClientError::TokenExpired(reason) = &refresh_err {
+        if !store_ref.refresh_token().is_empty() {
+            return reason.clone();
+        }
+    }
+    match login_with_token(
+        endpoint.to_string(),
+        store_ref.user_id().to_string(),
+        store_ref.token(),
+    )
+    .await
+    {
+        Err(ClientError::TokenExpired(reason)) => reason,
+        Err(ClientError::InvalidPassword(reason)) => reason,
+        _ => "unauthorized".to_string(),
+    }
+}
+
 async fn serve_connection(
     endpoint: &str,
-    token: &str,
     is_cross_domain: bool,
     store_ref: ClientStoreRef,
     state_ref: ConnectStateRef,
@@ -348,7 +371,8 @@ async fn serve_connection(
             state_ref.wait_for_next_connect().await;
 
             let url = WebsocketOption::url_from_endpoint(endpoint);
-            let opt = WebsocketOption::new(&url, token, is_cross_domain);
+            let token = store_ref.token();
+            let opt = WebsocketOption::new(&url, &token, is_cross_domain);
             info!("connect websocket url: {}", url);
 
             let (incoming_tx, mut incoming_rx) = unbounded_channel();
@@ -438,6 +462,13 @@ async fn serve_connection(
                             });
                             vec![]
                         }
+                        ChatRequestType::Presence => {
+                            let presence = UserPresence::from(&req);
+                            callback_ref.read().unwrap().as_ref().map(|cb| {
+                                cb.on_presence_changed(presence.user_id.clone(), presence)
+                            });
+                            vec![]
+                        }
                         ChatRequestType::Kickout => {
                             let reason = req.message.unwrap_or_default();
                             warn!("websocket kickout by other client: {}", reason);
@@ -464,9 +495,11 @@ async fn serve_connection(
                 }
             };
 
+            let mut unauthorized = false;
             select! {
-                _ = conn.serve(&opt, Box::new(conn_inner)) => {
+                r = conn.serve(&opt, Box::new(conn_inner)) => {
                     warn!("connection serve done");
+                    unauthorized = matches!(r, Err(ClientError::TokenExpired(_)));
                 },
                 _ = store_ref.handle_outgoing(outgoing_tx) => {
                     warn!("connection handle_outgoing done");
@@ -481,6 +514,22 @@ async fn serve_connection(
                     warn!("connection keepalive_loop done");
                 }
             }
+
+            if unauthorized {
+                match store_ref.refresh_auth().await {
+                    Ok(_) => {
+                        info!("websocket token refreshed, reconnecting");
+                        continue;
+                    }
+                    Err(e) => {
+                        let reason = token_expired_reason(endpoint, &store_ref, e).await;
+                        warn!("websocket token expired: {}", reason);
+                        if let Some(cb) = callback_ref.read().unwrap().as_ref() {
+                            cb.on_token_expired(reason);
+                        }
+                    }
+                }
+            }
             state_ref.did_broken();
         }
     };
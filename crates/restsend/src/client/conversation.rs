@@ -24,12 +24,12 @@ const QUICK_SYNC_FETCH_THROTTLE_MS: i64 = 250;
 #[export_wasm_or_ffi]
 impl Client {
     pub async fn create_chat(&self, user_id: String) -> Result<Conversation> {
-        let conversation = create_chat(&self.endpoint, &self.token, &user_id).await?;
+        let conversation = create_chat(&self.endpoint, &self.token(), &user_id).await?;
         self.store.update_conversation(conversation).await
     }
 
     pub async fn clean_messages(&self, topic_id: String) -> Result<()> {
-        clean_messages(&self.endpoint, &self.token, &topic_id).await
+        clean_messages(&self.endpoint, &self.token(), &topic_id).await
     }
 
     pub async fn remove_messages(
@@ -41,7 +41,7 @@ impl Client {
         self.store.remove_messages(&topic_id, &chat_ids).await;
 
         if sync_to_server {
-            remove_messages(&self.endpoint, &self.token, &topic_id, chat_ids).await
+            remove_messages(&self.endpoint, &self.token(), &topic_id, chat_ids).await
         } else {
             Ok(())
         }
@@ -243,7 +243,7 @@ impl Client {
             None => {}
         }
 
-        match get_chat_logs_desc(&self.endpoint, &self.token, &topic_id, last_seq, limit).await {
+        match get_chat_logs_desc(&self.endpoint, &self.token(), &topic_id, last_seq, limit).await {
             Ok(mut lr) => {
                 let now = now_millis();
                 for c in lr.items.iter_mut() {
@@ -383,7 +383,7 @@ impl Client {
         callback: Box<dyn SyncChatLogsCallback>,
     ) {
         let st_fetch = now_millis();
-        match get_chat_logs_desc(&self.endpoint, &self.token, topic_id, last_seq, limit).await {
+        match get_chat_logs_desc(&self.endpoint, &self.token(), topic_id, last_seq, limit).await {
             Ok(mut lr) => {
                 let now = now_millis();
                 for c in lr.items.iter_mut() {
@@ -510,7 +510,7 @@ impl Client {
             let st_0 = now_millis();
             match get_conversations(
                 &self.endpoint,
-                &self.token,
+                &self.token(),
                 &updated_at,
                 category.as_deref(),
                 last_updated_at_remote.clone(),
@@ -655,7 +655,7 @@ impl Client {
             })
             .collect();
 
-        let r = batch_get_chat_logs_desc(&self.endpoint, &self.token, form).await?;
+        let r = batch_get_chat_logs_desc(&self.endpoint, &self.token(), form).await?;
 
         let mut updated_conversations = vec![];
         let mut store_conversations = vec![];
@@ -771,7 +771,7 @@ impl Client {
                 self.store.emit_topic_read(topic_id.clone(), msg)
             });
         if heavy {
-            set_conversation_read(&self.endpoint, &self.token, &topic_id).await
+            set_conversation_read(&self.endpoint, &self.token(), &topic_id).await
         } else {
             self.do_read(topic_id).await
         }
@@ -781,7 +781,7 @@ impl Client {
     pub async fn set_all_conversations_read(&self) {
         self.store.set_all_conversations_read_local().await;
 
-        set_all_conversations_read(&self.endpoint, &self.token)
+        set_all_conversations_read(&self.endpoint, &self.token())
             .await
             .ok();
     }
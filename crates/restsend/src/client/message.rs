@@ -59,7 +59,7 @@ impl Client {
         topic_id: String,
         req: ChatRequest,
     ) -> Result<APISendResponse> {
-        send_request(&self.endpoint, &self.token, &topic_id, req).await
+        send_request(&self.endpoint, &self.token(), &topic_id, req).await
     }
 
     async fn send_chat_request_via_connection(
@@ -251,6 +251,24 @@ impl Client {
             .map(|_| ())
     }
 
+    pub async fn subscribe_presence(
+        &self,
+        user_ids: Vec<String>,
+        topic_id: Option<String>,
+    ) -> Result<()> {
+        let req = ChatRequest::new_subscribe_presence(user_ids, topic_id);
+        self.send_chat_request_via_connection(req, None)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn unsubscribe_presence(&self) -> Result<()> {
+        let req = ChatRequest::new_unsubscribe_presence();
+        self.send_chat_request_via_connection(req, None)
+            .await
+            .map(|_| ())
+    }
+
     pub async fn do_recall(
         &self,
         topic_id: String,
@@ -6,8 +6,9 @@ use crate::{
     callback::{CountableCallback, DownloadCallback, RsCallback},
     error::ClientError,
     media::{build_download_url, download_file},
-    models::{AuthInfo, User},
-    services::user::set_allow_guest_chat,
+    models::{AuthInfo, DeviceSession, User, UserPresence},
+    services::auth::{list_sessions, logout_other_devices, revoke_session},
+    services::user::{get_presence, set_allow_guest_chat, set_presence},
     DB_SUFFIX, TEMP_FILENAME_LEN,
 };
 use crate::{utils, Result};
@@ -25,7 +26,6 @@ mod topic;
 pub struct Client {
     pub root_path: String,
     pub user_id: String,
-    pub token: String,
     pub endpoint: String,
     pub is_cross_domain: bool,
 
@@ -69,17 +69,21 @@ impl Client {
             &info.token,
             &info.user_id,
         );
+        store.set_tokens(&info.token, &info.refresh_token);
 
         Self {
             root_path: root_path.to_string(),
             user_id: info.user_id.to_string(),
-            token: info.token.to_string(),
             endpoint: info.endpoint.to_string().trim_end_matches("/").to_string(),
             is_cross_domain: info.is_cross_domain,
             state: Arc::new(ConnectState::new(store.option.clone())),
             store: Arc::new(store),
         }
     }
+
+    pub fn token(&self) -> String {
+        self.store.token()
+    }
 }
 
 #[export_wasm_or_ffi]
@@ -104,6 +108,16 @@ impl Client {
         self.store.get_users(user_ids).await
     }
 
+    pub async fn sync_contacts(&self, full_sync: bool, limit: Option<u32>) -> Result<u32> {
+        self.store
+            .sync_contacts(full_sync, limit.unwrap_or(100))
+            .await
+    }
+
+    pub async fn get_contacts(&self, keyword: Option<String>) -> Vec<User> {
+        self.store.get_contacts(keyword).await
+    }
+
     pub async fn set_user_remark(&self, user_id: String, remark: String) -> Result<()> {
         self.store.set_user_remark(&user_id, &remark).await
     }
@@ -114,7 +128,43 @@ impl Client {
         self.store.set_user_block(&user_id, block).await
     }
     pub async fn set_allow_guest_chat(&self, allow: bool) -> Result<()> {
-        set_allow_guest_chat(&self.endpoint, &self.token, allow).await
+        set_allow_guest_chat(&self.endpoint, &self.token(), allow).await
+    }
+
+    pub async fn set_presence(
+        &self,
+        status: String,
+        status_text: Option<String>,
+        expire_secs: Option<u64>,
+    ) -> Result<UserPresence> {
+        set_presence(
+            &self.endpoint,
+            &self.token(),
+            &status,
+            &status_text.unwrap_or_default(),
+            expire_secs.unwrap_or_default(),
+        )
+        .await
+    }
+
+    pub async fn get_presence(&self, user_id: String) -> Result<UserPresence> {
+        get_presence(&self.endpoint, &self.token(), &user_id).await
+    }
+
+    pub async fn list_sessions(&self) -> Result<Vec<DeviceSession>> {
+        list_sessions(&self.endpoint, &self.token()).await
+    }
+
+    pub async fn revoke_session(&self, session_id: String) -> Result<()> {
+        revoke_session(&self.endpoint, &self.token(), &session_id).await
+    }
+
+    pub async fn logout_other_devices(&self) -> Result<()> {
+        logout_other_devices(&self.endpoint, &self.token()).await
+    }
+
+    pub async fn refresh_auth(&self) -> Result<AuthInfo> {
+        self.store.refresh_auth().await
     }
 
     pub async fn get_unread_count(&self) -> u32 {
@@ -158,7 +208,7 @@ impl Client {
 
         download_file(
             download_url,
-            Some(self.token.clone()),
+            Some(self.token()),
             save_file_name,
             callback,
             cancel_rx,
@@ -157,7 +157,7 @@ impl ClientStore {
         let task_callback = Box::new(UploadTaskCallback { task: task.clone() });
 
         let endpoint = self.endpoint.to_string();
-        let token = self.token.to_string();
+        let token = self.token();
 
         let uploader = build_upload_url(&endpoint, "");
         //TODO: retry
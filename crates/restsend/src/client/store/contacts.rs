@@ -0,0 +1,107 @@
+use super::users::update_user_with_storage;
+use super::ClientStore;
+use crate::models::User;
+use crate::services::user::get_contacts;
+use crate::Result;
+
+// contact ids are indexed in their own partition, profiles stay in the "" partition.
+// the row keyed "" keeps the last sync time in created_at
+const CONTACTS_PARTITION: &str = "contacts";
+const SYNC_MARKER_KEY: &str = "";
+
+impl ClientStore {
+    pub async fn sync_contacts(&self, full_sync: bool, limit: u32) -> Result<u32> {
+        let t = self.message_storage.table::<User>().await?;
+        let last_synced_at = match full_sync {
+            true => String::new(),
+            false => t
+                .get(CONTACTS_PARTITION, SYNC_MARKER_KEY)
+                .await
+                .map(|v| v.created_at)
+                .unwrap_or_default(),
+        };
+        if last_synced_at.is_empty() {
+            t.clear(CONTACTS_PARTITION).await?;
+        }
+
+        let mut cursor = String::new();
+        let mut synced_at = String::new();
+        let mut count = 0;
+        loop {
+            let lr = get_contacts(
+                &self.endpoint,
+                &self.token(),
+                &last_synced_at,
+                &cursor,
+                limit,
+            )
+            .await?;
+            if synced_at.is_empty() {
+                synced_at = lr.updated_at.clone();
+            }
+            count += (lr.items.len() + lr.removed.len()) as u32;
+
+            for user_id in lr.removed.iter() {
+                t.remove(CONTACTS_PARTITION, user_id).await?;
+                if let Some(mut u) = t.get("", user_id).await {
+                    u.is_contact = false;
+                    u.is_star = false;
+                    t.set("", user_id, Some(&u)).await?;
+                }
+            }
+            for user in lr.items {
+                let user = update_user_with_storage(&self.message_storage, user).await?;
+                t.set(CONTACTS_PARTITION, &user.user_id, Some(&user))
+                    .await?;
+            }
+            if !lr.has_more || lr.cursor.is_empty() {
+                break;
+            }
+            cursor = lr.cursor;
+        }
+
+        let marker = User {
+            created_at: synced_at,
+            ..Default::default()
+        };
+        t.set(CONTACTS_PARTITION, SYNC_MARKER_KEY, Some(&marker))
+            .await?;
+        Ok(count)
+    }
+
+    pub async fn get_contacts(&self, keyword: Option<String>) -> Vec<User> {
+        let t = match self.message_storage.readonly_table::<User>().await {
+            Ok(t) => t,
+            Err(_) => return vec![],
+        };
+        let rows = t
+            .filter(CONTACTS_PARTITION, Box::new(Some), None, None)
+            .await
+            .unwrap_or_default();
+        let keyword = keyword.unwrap_or_default().to_lowercase();
+        let mut items = vec![];
+        for row in rows {
+            if row.user_id.is_empty() {
+                continue;
+            }
+            let user = t.get("", &row.user_id).await.unwrap_or(row);
+            if !user.is_contact && !user.is_star {
+                continue;
+            }
+            if !keyword.is_empty()
+                && ![&user.name, &user.remark, &user.user_id]
+                    .iter()
+                    .any(|v| v.to_lowercase().contains(&keyword))
+            {
+                continue;
+            }
+            items.push(user);
+        }
+        items.sort_by(|a, b| {
+            b.is_star
+                .cmp(&a.is_star)
+                .then_with(|| a.user_id.cmp(&b.user_id))
+        });
+        items
+    }
+}
@@ -3,7 +3,7 @@ use crate::{
     callback::ChatRequestStatus,
     models::{
         conversation::{ConversationUpdateFields, Extra, Tags},
-        ChatLog, ChatLogStatus, ContentType, Conversation,
+        ChatLog, ChatLogStatus, Content, ContentType, Conversation,
     },
     request::ChatRequest,
     services::{conversation::*, topic::get_topic},
@@ -333,6 +333,13 @@ impl ClientStore {
                                 conversation.unread = 1;
                                 req_status.has_read = false;
                             }
+                            // read on another device of the same user
+                            if let Some(last_read_seq) = fields.last_read_seq {
+                                if last_read_seq >= conversation.last_read_seq {
+                                    conversation.last_read_seq = last_read_seq;
+                                    conversation.unread = fields.unread.unwrap_or(0);
+                                }
+                            }
                             conversation.sticky = fields.sticky.unwrap_or(conversation.sticky);
                             conversation.mute = fields.mute.unwrap_or(conversation.mute);
                         }
@@ -638,7 +645,7 @@ impl ClientStore {
             }
         }
 
-        let c = set_conversation_remark(&self.endpoint, &self.token, &topic_id, remark).await?;
+        let c = set_conversation_remark(&self.endpoint, &self.token(), topic_id, remark).await?;
         let mut c = merge_conversation(self.message_storage.clone(), c).await?;
         self.ensure_conversation_readable_last_message(&mut c).await;
         if self.ensure_topic_owner_id(&mut c).await {
@@ -660,7 +667,7 @@ impl ClientStore {
             }
         }
 
-        let c = set_conversation_sticky(&self.endpoint, &self.token, &topic_id, sticky).await?;
+        let c = set_conversation_sticky(&self.endpoint, &self.token(), topic_id, sticky).await?;
         let mut c = merge_conversation(self.message_storage.clone(), c).await?;
         self.ensure_conversation_readable_last_message(&mut c).await;
         if self.ensure_topic_owner_id(&mut c).await {
@@ -678,7 +685,7 @@ impl ClientStore {
             }
         }
 
-        let c = set_conversation_mute(&self.endpoint, &self.token, &topic_id, mute).await?;
+        let c = set_conversation_mute(&self.endpoint, &self.token(), topic_id, mute).await?;
         let mut c = merge_conversation(self.message_storage.clone(), c).await?;
         self.ensure_conversation_readable_last_message(&mut c).await;
         if self.ensure_topic_owner_id(&mut c).await {
@@ -761,7 +768,7 @@ impl ClientStore {
         let values = serde_json::json!({
             "tags": tags.unwrap_or_default(),
         });
-        let c = update_conversation(&self.endpoint, &self.token, &topic_id, &values).await?;
+        let c = update_conversation(&self.endpoint, &self.token(), topic_id, &values).await?;
         let mut c = merge_conversation(self.message_storage.clone(), c).await?;
         self.ensure_conversation_readable_last_message(&mut c).await;
         if self.ensure_topic_owner_id(&mut c).await {
@@ -781,7 +788,7 @@ impl ClientStore {
                 }
             }
         }
-        mark_conversation_unread(&self.endpoint, &self.token, &topic_id)
+        mark_conversation_unread(&self.endpoint, &self.token(), topic_id)
             .await
             .ok();
         Ok(())
@@ -804,7 +811,7 @@ impl ClientStore {
             "extra": extra.unwrap_or_default(),
         });
 
-        let c = update_conversation(&self.endpoint, &self.token, &topic_id, &values).await?;
+        let c = update_conversation(&self.endpoint, &self.token(), topic_id, &values).await?;
         let mut c = merge_conversation(self.message_storage.clone(), c).await?;
         self.ensure_conversation_readable_last_message(&mut c).await;
         if self.ensure_topic_owner_id(&mut c).await {
@@ -850,7 +857,7 @@ impl ClientStore {
             self.clear_conversation(topic_id).await.ok();
         }
 
-        match remove_conversation(&self.endpoint, &self.token, &topic_id).await {
+        match remove_conversation(&self.endpoint, &self.token(), topic_id).await {
             Ok(_) => {
                 if let Some(cb) = self.callback.read().unwrap().as_ref() {
                     cb.on_conversation_removed(topic_id.to_string());
@@ -930,7 +937,7 @@ impl ClientStore {
         };
 
         if ensure_last_version {
-            match get_conversation(&self.endpoint, &self.token, &conversation.topic_id).await {
+            match get_conversation(&self.endpoint, &self.token(), &conversation.topic_id).await {
                 Ok(mut new_conversation) => {
                     new_conversation.is_partial = false;
                     new_conversation.cached_at = now_millis();
@@ -1036,12 +1043,14 @@ impl ClientStore {
         chat_id: &str,
         status: ChatLogStatus,
         seq: Option<i64>,
+        content: Option<Content>,
     ) -> Result<()> {
         let t = self.message_storage.table::<ChatLog>().await?;
 
         if let Some(mut log) = t.get(topic_id, chat_id).await {
             log.status = status;
             seq.map(|v| log.seq = v);
+            content.map(|v| log.content = v);
             t.set(topic_id, chat_id, Some(&log)).await?;
             self.invalidate_recent_chat_logs(topic_id);
         }
@@ -1474,7 +1483,7 @@ impl ClientStore {
             return true;
         }
 
-        match get_topic(&self.endpoint, &self.token, &conversation.topic_id).await {
+        match get_topic(&self.endpoint, &self.token(), &conversation.topic_id).await {
             Ok(topic) => {
                 if !topic.owner_id.is_empty() {
                     self.cache_topic_owner(&conversation.topic_id, &topic.owner_id);
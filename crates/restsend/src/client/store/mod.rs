@@ -1,7 +1,9 @@
 use self::attachments::UploadTask;
 use crate::callback::{CountableCallback, RsCallback, SyncChatLogsCallback};
-use crate::models::Attachment;
+use crate::error::ClientError;
+use crate::models::{Attachment, AuthInfo};
 use crate::models::{ChatLog, GetChatLogsResult};
+use crate::services::auth::refresh_auth_token;
 use crate::storage::Storage;
 use crate::utils::{elapsed, now_millis};
 use crate::{
@@ -22,8 +24,10 @@ use std::{
 use tokio::sync::mpsc::UnboundedSender;
 
 mod attachments;
+mod contacts;
 mod conversations;
 mod requests;
+mod topic_members;
 mod users;
 
 const QUICK_SYNC_WAITERS_TTL_MS: i64 = 30_000;
@@ -182,7 +186,8 @@ pub(super) struct RecentChatLogsCacheEntry {
 pub struct ClientStore {
     user_id: String,
     endpoint: String,
-    token: String,
+    token: RwLock<String>,
+    refresh_token: RwLock<String>,
     tmps: RwLock<VecDeque<String>>,
     outgoings: PendingRequests,
     upload_tasks: RwLock<HashMap<String, Arc<UploadTask>>>,
@@ -213,7 +218,8 @@ impl ClientStore {
         Self {
             user_id: user_id.to_string(),
             endpoint: endpoint.to_string(),
-            token: token.to_string(),
+            token: RwLock::new(token.to_string()),
+            refresh_token: RwLock::new(String::new()),
             tmps: RwLock::new(VecDeque::new()),
             outgoings: Arc::new(RwLock::new(HashMap::new())),
             upload_tasks: RwLock::new(HashMap::new()),
@@ -234,6 +240,38 @@ impl ClientStore {
         }
     }
 
+    pub(crate) fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    pub fn token(&self) -> String {
+        self.token.read().unwrap().clone()
+    }
+
+    pub(crate) fn refresh_token(&self) -> String {
+        self.refresh_token.read().unwrap().clone()
+    }
+
+    // replaced after a refresh so later requests and reconnects use the new pair
+    pub(crate) fn set_tokens(&self, token: &str, refresh_token: &str) {
+        *self.token.write().unwrap() = token.to_string();
+        *self.refresh_token.write().unwrap() = refresh_token.to_string();
+    }
+
+    pub(crate) async fn refresh_auth(&self) -> crate::Result<AuthInfo> {
+        let refresh_token = self.refresh_token();
+        if refresh_token.is_empty() {
+            return Err(ClientError::TokenExpired("no refresh token".to_string()));
+        }
+        let info =
+            refresh_auth_token(self.endpoint.clone(), self.user_id.clone(), refresh_token).await?;
+        self.set_tokens(&info.token, &info.refresh_token);
+        if let Some(cb) = self.callback.read().unwrap().as_ref() {
+            cb.on_token_refreshed(info.clone());
+        }
+        Ok(info)
+    }
+
     pub(super) fn begin_quick_sync_singleflight(
         &self,
         key: String,
@@ -53,12 +53,19 @@ impl ClientStore {
                 } else {
                     ChatLogStatus::SendFailed
                 };
+                // set by the server when a presend hook rewrote the message
+                let ack_content = match content_type.as_str() {
+                    "ping" => None,
+                    _ => req.content.clone(),
+                };
 
                 if let Some(pending) = self.peek_pending_request(&req.chat_id).await {
                     match status {
                         ChatLogStatus::Sent => {
                             let mut req = req;
-                            req.content = pending.req.content.clone();
+                            if req.content.is_none() {
+                                req.content = pending.req.content.clone();
+                            }
                             pending.callback.map(|cb| cb.on_ack(req));
                         }
                         ChatLogStatus::SendFailed => {
@@ -98,9 +105,15 @@ impl ClientStore {
                     }
                 }
                 if content_type != "ping" {
-                    self.update_outoing_chat_log_state(&topic_id, &chat_id, status, Some(ack_seq))
-                        .await
-                        .ok();
+                    self.update_outoing_chat_log_state(
+                        &topic_id,
+                        &chat_id,
+                        status,
+                        Some(ack_seq),
+                        ack_content,
+                    )
+                    .await
+                    .ok();
                 }
                 vec![]
             }
@@ -245,7 +258,10 @@ impl ClientStore {
         callback: Option<Box<dyn MessageCallback>>,
     ) {
         match ChatRequestType::from(&req.req_type) {
-            ChatRequestType::Typing | ChatRequestType::Read => {
+            ChatRequestType::Typing
+            | ChatRequestType::Read
+            | ChatRequestType::Subscribe
+            | ChatRequestType::Unsubscribe => {
                 let outgoing_tx = self.msg_direct_tx.read().unwrap();
                 match outgoing_tx.as_ref() {
                     Some(tx) => {
@@ -0,0 +1,121 @@
+use super::users::update_user_with_storage;
+use super::ClientStore;
+use crate::models::{ListUserResult, TopicMember, User};
+use crate::services::topic::get_topic_members;
+use crate::storage::StoreModel;
+use crate::Result;
+
+// per-topic sync markers live in the "" partition, member rows in the topic_id partition
+const SYNC_MARKER_PARTITION: &str = "";
+
+impl ClientStore {
+    pub async fn sync_topic_members(
+        &self,
+        topic_id: &str,
+        full_sync: bool,
+        limit: u32,
+    ) -> Result<u32> {
+        let t = self.message_storage.table::<TopicMember>().await?;
+        let last_synced_at = match full_sync {
+            true => String::new(),
+            false => t
+                .get(SYNC_MARKER_PARTITION, topic_id)
+                .await
+                .map(|v| v.updated_at)
+                .unwrap_or_default(),
+        };
+        if last_synced_at.is_empty() {
+            t.clear(topic_id).await?;
+        }
+
+        let mut cursor = String::new();
+        let mut synced_at = String::new();
+        let mut count = 0;
+        loop {
+            let lr = get_topic_members(
+                &self.endpoint,
+                &self.token(),
+                topic_id,
+                &last_synced_at,
+                &cursor,
+                "",
+                limit,
+            )
+            .await?;
+            if synced_at.is_empty() {
+                synced_at = lr.updated_at.clone();
+            }
+            count += lr.items.len() as u32;
+            let has_more = lr.has_more && !lr.cursor.is_empty();
+            cursor = lr.cursor.clone();
+            self.save_topic_members(topic_id, lr).await?;
+            if !has_more {
+                break;
+            }
+        }
+
+        let mut marker = TopicMember::new(topic_id, "");
+        marker.updated_at = synced_at;
+        t.set(SYNC_MARKER_PARTITION, topic_id, Some(&marker))
+            .await?;
+        Ok(count)
+    }
+
+    pub async fn save_topic_members(&self, topic_id: &str, lr: ListUserResult) -> Result<()> {
+        let t = self.message_storage.table::<TopicMember>().await?;
+        for member in lr.members {
+            t.set(topic_id, &member.user_id.clone(), Some(&member))
+                .await?;
+        }
+        for user in lr.items {
+            if t.get(topic_id, &user.user_id).await.is_none() {
+                let member = TopicMember::new(topic_id, &user.user_id);
+                t.set(topic_id, &user.user_id, Some(&member)).await?;
+            }
+            update_user_with_storage(&self.message_storage, user).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_cached_topic_members(
+        &self,
+        topic_id: &str,
+        keyword: Option<String>,
+        limit: u32,
+    ) -> Vec<User> {
+        let member_t = match self.message_storage.readonly_table::<TopicMember>().await {
+            Ok(t) => t,
+            Err(_) => return vec![],
+        };
+        let mut members = member_t
+            .filter(topic_id, Box::new(Some), None, None)
+            .await
+            .unwrap_or_default();
+        members.sort_by_key(|m| (m.sort_key(), m.user_id.clone()));
+
+        let user_t = match self.message_storage.readonly_table::<User>().await {
+            Ok(t) => t,
+            Err(_) => return vec![],
+        };
+        let keyword = keyword.unwrap_or_default().to_lowercase();
+        let mut items = vec![];
+        for member in members {
+            let user = user_t
+                .get("", &member.user_id)
+                .await
+                .unwrap_or(User::new(&member.user_id));
+            if !keyword.is_empty()
+                && ![&member.name, &user.name, &user.remark, &user.user_id]
+                    .iter()
+                    .any(|v| v.to_lowercase().contains(&keyword))
+            {
+                continue;
+            }
+            items.push(user);
+            if items.len() >= limit as usize {
+                break;
+            }
+        }
+        items
+    }
+}
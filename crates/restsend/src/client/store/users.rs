@@ -19,7 +19,7 @@ impl ClientStore {
             }
         }
 
-        set_user_remark(&self.endpoint, &self.token, user_id, remark).await
+        set_user_remark(&self.endpoint, &self.token(), user_id, remark).await
     }
 
     pub async fn set_user_star(&self, user_id: &str, star: bool) -> Result<()> {
@@ -31,7 +31,7 @@ impl ClientStore {
             }
         }
 
-        set_user_star(&self.endpoint, &self.token, user_id, star).await
+        set_user_star(&self.endpoint, &self.token(), user_id, star).await
     }
 
     pub async fn set_user_block(&self, user_id: &str, block: bool) -> Result<()> {
@@ -42,7 +42,7 @@ impl ClientStore {
                 t.set("", user_id, Some(&u)).await.ok();
             }
         }
-        set_user_block(&self.endpoint, &self.token, user_id, block).await
+        set_user_block(&self.endpoint, &self.token(), user_id, block).await
     }
 
     pub async fn get_user(&self, user_id: &str, blocking: bool) -> Option<User> {
@@ -63,7 +63,7 @@ impl ClientStore {
         }
 
         let endpoint = self.endpoint.clone();
-        let token = self.token.clone();
+        let token = self.token();
         let user_id = user_id.to_string();
         let message_storage = self.message_storage.clone();
         let runner = async move {
@@ -114,7 +114,7 @@ impl ClientStore {
             }
         }
 
-        match get_users(&self.endpoint, &self.token, missing_ids).await {
+        match get_users(&self.endpoint, &self.token(), missing_ids).await {
             Ok(us) => {
                 for u in us {
                     pending_users.insert(u.user_id.clone(), u);
@@ -141,7 +141,7 @@ impl ClientStore {
                 self.option.user_cache_expire_secs.load(Ordering::Relaxed) as i64,
             )
         {
-            let user = get_user(&self.endpoint, &self.token, user_id).await?;
+            let user = get_user(&self.endpoint, &self.token(), user_id).await?;
             return self.update_user(user).await;
         }
         Ok(u)
@@ -163,7 +163,7 @@ impl ClientStore {
                 users.push(u);
             }
         }
-        match get_users(&self.endpoint, &self.token, missing_ids).await {
+        match get_users(&self.endpoint, &self.token(), missing_ids).await {
             Ok(mut us) => {
                 users.append(&mut us);
             }
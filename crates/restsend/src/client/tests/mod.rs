@@ -1,5 +1,6 @@
 pub(crate) mod test_server;
 mod test_client;
+mod test_contacts;
 mod test_conversation;
 mod test_demo_dm;
 mod test_local_e2e;
@@ -8,6 +9,7 @@ mod test_merge_conversation;
 mod test_message_conversation;
 mod test_reentrancy;
 mod test_sync_first_page;
+mod test_topic_members;
 mod test_upload;
 mod test_users;
 
@@ -0,0 +1,68 @@
+use crate::{
+    client::{
+        tests::{test_server::LocalTestServer, unique_test_user},
+        Client,
+    },
+    services::auth::{login_with_password, signup},
+    utils::init_log,
+};
+
+#[tokio::test]
+async fn test_sync_contacts() {
+    init_log("INFO".to_string(), true);
+    let server = LocalTestServer::start().await;
+
+    let mut user_ids = vec![];
+    for prefix in ["sdk-alice", "sdk-bob", "sdk-carol"] {
+        let user_id = unique_test_user(prefix);
+        signup(
+            server.endpoint.clone(),
+            user_id.clone(),
+            "pass-1".to_string(),
+        )
+        .await
+        .expect("signup user");
+        user_ids.push(user_id);
+    }
+
+    let info = login_with_password(
+        server.endpoint.clone(),
+        user_ids[0].clone(),
+        "pass-1".to_string(),
+    )
+    .await
+    .expect("login alice");
+    let c = Client::new("".to_string(), "".to_string(), &info);
+
+    c.set_user_star(user_ids[1].clone(), true)
+        .await
+        .expect("star bob");
+    c.set_user_star(user_ids[2].clone(), true)
+        .await
+        .expect("star carol");
+
+    let count = c.sync_contacts(true, Some(1)).await.expect("full sync");
+    assert_eq!(count, 2);
+
+    let contacts = c.get_contacts(None).await;
+    assert_eq!(contacts.len(), 2);
+    assert!(contacts.iter().all(|u| u.is_star));
+
+    let carol = c.get_contacts(Some("sdk-carol".to_string())).await;
+    assert_eq!(carol.len(), 1);
+    assert_eq!(carol[0].user_id, user_ids[2]);
+
+    c.set_user_star(user_ids[2].clone(), false)
+        .await
+        .expect("unstar carol");
+
+    let count = c
+        .sync_contacts(false, None)
+        .await
+        .expect("incremental sync");
+    assert_eq!(count, 1);
+
+    let contacts = c.get_contacts(None).await;
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(contacts[0].user_id, user_ids[1]);
+}
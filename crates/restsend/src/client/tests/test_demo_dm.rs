@@ -44,6 +44,18 @@ impl DemoServer {
             webhook_timeout_secs: 5,
             webhook_retries: 2,
             webhook_targets: vec![],
+            webhook_secret: None,
+            webhook_target_secrets: vec![],
+            webhook_outbox: true,
+            webhook_max_attempts: 10,
+            webhook_backoff_secs: 2,
+            webhook_backoff_max_secs: 3600,
+            webhook_circuit_threshold: 5,
+            webhook_circuit_cooldown_secs: 60,
+            webhook_event_retention_secs: 604800,
+            presend_hook_url: None,
+            presend_hook_timeout_ms: 1000,
+            presend_hook_fail_open: false,
             event_bus_size: 256,
             message_worker_count: 2,
             message_queue_size: 64,
@@ -56,10 +68,33 @@ impl DemoServer {
             presence_node_id: "demo-dm-node".to_string(),
             presence_ttl_secs: 90,
             presence_heartbeat_secs: 10,
+            presence_debounce_ms: 0,
             ws_per_user_limit: 0,
             ws_client_queue_size: 0,
             ws_typing_interval_ms: 1000,
             ws_drop_on_backpressure: true,
+            ws_ticket_ttl_secs: 30,
+            ws_require_ticket: false,
+            knock_expire_secs: 7 * 24 * 3600,
+            dm_contacts_only: false,
+            user_search_rate_limit: 30,
+            token_ttl_secs: 30 * 24 * 3600,
+            token_idle_secs: 0,
+            refresh_token_ttl_secs: 90 * 24 * 3600,
+            token_gc_interval_secs: 300,
+            token_touch_flush_secs: 30,
+            signed_token_alg: "HS256".to_string(),
+            signed_token_keys: vec![],
+            token_revocation_refresh_secs: 30,
+            password_min_length: 4,
+            login_max_failures: 5,
+            login_ip_max_failures: 20,
+            login_lockout_secs: 60,
+            login_lockout_max_secs: 3600,
+            login_failure_window_secs: 900,
+            trusted_proxies: vec![],
+            oidc_providers: vec![],
+            openapi_allow_anonymous: false,
         };
 
         let (app, state) = build_router(config).await.expect("build router");
@@ -228,17 +263,21 @@ async fn test_demo_dm_alice_bob_websocket_delivery() {
     log::info!("=== WebSocket delivery confirmed: alice → bob ===");
 
     // Verify Bob's unread updated (hasRead=false since bob isn't viewing alice's conv)
-    let bob_unreads = bob_conv_unreads.lock().unwrap().clone();
-    let bob_topic_unreads: Vec<i64> = bob_unreads
-        .iter()
-        .filter(|(t, _)| t == "alice:bob")
-        .map(|(_, u)| *u)
-        .collect();
-    assert!(
-        bob_topic_unreads.iter().any(|u| *u >= 1),
-        "Bob's conversation should have unread >= 1, got: {:?}",
-        bob_topic_unreads
-    );
+    // on_new_message fires before the conversation is merged, and bob has no local
+    // conversation yet so the merge fetches it first; wait for the merged update
+    let bob_max_unread = || {
+        bob_conv_unreads
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(t, _)| t == "alice:bob")
+            .map(|(_, u)| *u)
+            .max()
+            .unwrap_or(0)
+    };
+    check_until(WS_TIMEOUT, || bob_max_unread() >= 1)
+        .await
+        .expect("Bob's conversation should have unread >= 1");
 
     // === 3. Send second message to verify unread increments ===
     let req2 = ChatRequest::new_text(&conv.topic_id, "Second message");
@@ -264,20 +303,10 @@ async fn test_demo_dm_alice_bob_websocket_delivery() {
     );
 
     // Bob's unread should now be >= 2 (hasRead=false)
-    tokio::time::sleep(Duration::from_millis(500)).await;
-    let bob_unreads_2 = bob_conv_unreads.lock().unwrap().clone();
-    let bob_topic_unreads_2: Vec<i64> = bob_unreads_2
-        .iter()
-        .filter(|(t, _)| t == "alice:bob")
-        .map(|(_, u)| *u)
-        .collect();
-    let max_unread = bob_topic_unreads_2.iter().max().copied().unwrap_or(0);
-    assert!(
-        max_unread >= 2,
-        "Bob's conversation should have unread >= 2 after 2 messages, got max={} all={:?}",
-        max_unread,
-        bob_topic_unreads_2
-    );
+    check_until(WS_TIMEOUT, || bob_max_unread() >= 2)
+        .await
+        .expect("Bob's conversation should have unread >= 2 after 2 messages");
+    let max_unread = bob_max_unread();
     log::info!(
         "=== Unread count verified: bob's unread = {} ===",
         max_unread
@@ -639,3 +639,52 @@ async fn test_unreadable_message_heals_stale_last_message() {
         "last_message text should be from the healed local log"
     );
 }
+
+/// Test that a conversation.update pushed from another device of the same
+/// user syncs the read state and settings, and fires on_conversations_updated.
+#[tokio::test]
+async fn test_conversation_update_from_other_device_syncs_read_state() {
+    let store = ClientStore::new("", ":memory:", "http://test", "token", "receiver-user");
+    let conv_updated = Arc::new(AtomicU32::new(0));
+    let callback: Arc<RwLock<Option<Box<dyn callback::RsCallback>>>> =
+        Arc::new(RwLock::new(Some(Box::new(TestCallback {
+            conv_updated: conv_updated.clone(),
+        }))));
+
+    let mut conv = Conversation::new("topic_sync");
+    conv.owner_id = "receiver-user".to_string();
+    let t = store.message_storage.table::<Conversation>().await.unwrap();
+    t.set("", "topic_sync", Some(&conv)).await.unwrap();
+    drop(t);
+
+    for seq in 1..=3 {
+        let chat_id = format!("chat_{}", seq);
+        let req = make_incoming_chat("topic_sync", &chat_id, seq, "sender-user", "Hi");
+        store.process_incoming(req, callback.clone()).await;
+    }
+
+    let req = ChatRequest {
+        req_type: "chat".to_string(),
+        chat_id: "conv-updated-1".to_string(),
+        topic_id: "topic_sync".to_string(),
+        attendee: "receiver-user".to_string(),
+        created_at: "2026-05-11T10:00:00Z".to_string(),
+        content: Some(Content {
+            content_type: "conversation.update".to_string(),
+            text: r#"{"lastReadSeq":3,"unread":0,"sticky":true}"#.to_string(),
+            unreadable: true,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let before = conv_updated.load(Ordering::Relaxed);
+    store.process_incoming(req, callback.clone()).await;
+    assert_eq!(conv_updated.load(Ordering::Relaxed), before + 1);
+
+    let t = store.message_storage.table::<Conversation>().await.unwrap();
+    let updated = t.get("", "topic_sync").await.unwrap();
+    assert_eq!(updated.last_read_seq, 3);
+    assert_eq!(updated.unread, 0);
+    assert!(updated.sticky);
+    assert_eq!(updated.last_seq, 3);
+}
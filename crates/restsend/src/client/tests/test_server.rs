@@ -27,6 +27,18 @@ impl LocalTestServer {
             webhook_timeout_secs: 5,
             webhook_retries: 2,
             webhook_targets: vec![],
+            webhook_secret: None,
+            webhook_target_secrets: vec![],
+            webhook_outbox: true,
+            webhook_max_attempts: 10,
+            webhook_backoff_secs: 2,
+            webhook_backoff_max_secs: 3600,
+            webhook_circuit_threshold: 5,
+            webhook_circuit_cooldown_secs: 60,
+            webhook_event_retention_secs: 604800,
+            presend_hook_url: None,
+            presend_hook_timeout_ms: 1000,
+            presend_hook_fail_open: false,
             event_bus_size: 256,
             message_worker_count: 2,
             message_queue_size: 64,
@@ -39,10 +51,33 @@ impl LocalTestServer {
             presence_node_id: "sdk-e2e-node".to_string(),
             presence_ttl_secs: 90,
             presence_heartbeat_secs: 10,
+            presence_debounce_ms: 0,
             ws_per_user_limit: 0,
             ws_client_queue_size: 0,
             ws_typing_interval_ms: 1000,
             ws_drop_on_backpressure: true,
+            ws_ticket_ttl_secs: 30,
+            ws_require_ticket: false,
+            knock_expire_secs: 7 * 24 * 3600,
+            dm_contacts_only: false,
+            user_search_rate_limit: 30,
+            token_ttl_secs: 30 * 24 * 3600,
+            token_idle_secs: 0,
+            refresh_token_ttl_secs: 90 * 24 * 3600,
+            token_gc_interval_secs: 300,
+            token_touch_flush_secs: 30,
+            signed_token_alg: "HS256".to_string(),
+            signed_token_keys: vec![],
+            token_revocation_refresh_secs: 30,
+            password_min_length: 4,
+            login_max_failures: 5,
+            login_ip_max_failures: 20,
+            login_lockout_secs: 60,
+            login_lockout_max_secs: 3600,
+            login_failure_window_secs: 900,
+            trusted_proxies: vec![],
+            oidc_providers: vec![],
+            openapi_allow_anonymous: false,
         };
 
         let (app, state) = build_router(config).await.expect("build router");
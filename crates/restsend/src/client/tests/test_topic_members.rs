@@ -0,0 +1,82 @@
+use crate::{
+    client::{
+        tests::{test_server::LocalTestServer, unique_test_user},
+        Client,
+    },
+    services::auth::{login_with_password, signup},
+    utils::init_log,
+};
+
+#[tokio::test]
+async fn test_sync_topic_members() {
+    init_log("INFO".to_string(), true);
+    let server = LocalTestServer::start().await;
+
+    let mut user_ids = vec![];
+    for prefix in ["sdk-owner", "sdk-member", "sdk-guest"] {
+        let user_id = unique_test_user(prefix);
+        signup(
+            server.endpoint.clone(),
+            user_id.clone(),
+            "pass-1".to_string(),
+        )
+        .await
+        .expect("signup user");
+        user_ids.push(user_id);
+    }
+
+    let info = login_with_password(
+        server.endpoint.clone(),
+        user_ids[0].clone(),
+        "pass-1".to_string(),
+    )
+    .await
+    .expect("login owner");
+    let c = Client::new("".to_string(), "".to_string(), &info);
+
+    let conversation = c
+        .create_topic(
+            user_ids.clone(),
+            Some("sdk-members".to_string()),
+            None,
+            None,
+        )
+        .await
+        .expect("create topic");
+    let topic_id = conversation.topic_id;
+
+    let page = c
+        .search_topic_members(
+            topic_id.clone(),
+            String::new(),
+            String::new(),
+            String::new(),
+            2,
+        )
+        .await
+        .expect("first page");
+    assert_eq!(page.items.len(), 2);
+    assert!(page.has_more);
+    assert!(!page.cursor.is_empty());
+
+    let count = c
+        .sync_topic_members(topic_id.clone(), true, Some(1))
+        .await
+        .expect("sync members");
+    assert_eq!(count, 3);
+
+    let cached = c.get_cached_topic_members(topic_id.clone(), None, 10).await;
+    assert_eq!(cached.len(), 3);
+
+    let guests = c
+        .get_cached_topic_members(topic_id.clone(), Some("sdk-guest".to_string()), 10)
+        .await;
+    assert_eq!(guests.len(), 1);
+    assert_eq!(guests[0].user_id, user_ids[2]);
+
+    let count = c
+        .sync_topic_members(topic_id.clone(), false, None)
+        .await
+        .expect("incremental sync");
+    assert_eq!(count, 0);
+}
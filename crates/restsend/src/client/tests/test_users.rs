@@ -46,3 +46,39 @@ async fn test_get_user() {
         }
     }
 }
+
+#[tokio::test]
+async fn test_presence() {
+    init_log("INFO".to_string(), true);
+    let server = LocalTestServer::start().await;
+
+    let user_id = unique_test_user("sdk-presence");
+    signup(server.endpoint.clone(), user_id.clone(), "pass-1".to_string())
+        .await
+        .expect("signup user");
+
+    let info = login_with_password(
+        server.endpoint.clone(),
+        user_id.clone(),
+        "pass-1".to_string(),
+    )
+    .await;
+    let c = Client::new("".to_string(), "".to_string(), &info.unwrap());
+
+    let presence = c
+        .set_presence("busy".to_string(), Some("focus".to_string()), Some(60))
+        .await
+        .expect("set presence");
+    assert_eq!(presence.status, "busy");
+    assert_eq!(presence.status_text, "focus");
+    assert!(!presence.status_expires_at.is_empty());
+
+    let presence = c.get_presence(user_id.clone()).await.expect("get presence");
+    assert_eq!(presence.status, "busy");
+    assert_eq!(presence.status_text, "focus");
+
+    assert!(c
+        .set_presence("sleeping".to_string(), None, None)
+        .await
+        .is_err());
+}
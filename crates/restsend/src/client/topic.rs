@@ -15,7 +15,7 @@ impl Client {
         icon: Option<String>,
         kind: Option<String>,
     ) -> Result<Conversation> {
-        create_topic(&self.endpoint, &self.token, members, name, icon, kind)
+        create_topic(&self.endpoint, &self.token(), members, name, icon, kind)
             .await
             .map(|t| Conversation::from(&t))
     }
@@ -28,7 +28,7 @@ impl Client {
     ) -> Result<()> {
         join_topic(
             &self.endpoint,
-            &self.token,
+            &self.token(),
             &topic_id,
             &message.unwrap_or_default(),
             &source.unwrap_or_default(),
@@ -37,11 +37,11 @@ impl Client {
     }
 
     pub async fn add_topic_member(&self, topic_id: String, user_id: String) -> Result<TopicMember> {
-        add_topic_member(&self.endpoint, &self.token, &topic_id, &user_id).await
+        add_topic_member(&self.endpoint, &self.token(), &topic_id, &user_id).await
     }
 
     pub async fn get_topic(&self, topic_id: String) -> Result<Topic> {
-        get_topic(&self.endpoint, &self.token, &topic_id).await
+        get_topic(&self.endpoint, &self.token(), &topic_id).await
     }
 
     pub async fn get_topic_admins(&self, topic_id: String) -> Option<Vec<User>> {
@@ -64,11 +64,59 @@ impl Client {
         updated_at: String,
         limit: u32,
     ) -> Result<ListUserResult> {
-        get_topic_members(&self.endpoint, &self.token, &topic_id, &updated_at, limit).await
+        self.search_topic_members(topic_id, updated_at, String::new(), String::new(), limit)
+            .await
+    }
+
+    pub async fn search_topic_members(
+        &self,
+        topic_id: String,
+        updated_at: String,
+        cursor: String,
+        keyword: String,
+        limit: u32,
+    ) -> Result<ListUserResult> {
+        let lr = get_topic_members(
+            &self.endpoint,
+            &self.token(),
+            &topic_id,
+            &updated_at,
+            &cursor,
+            &keyword,
+            limit,
+        )
+        .await?;
+        self.store
+            .save_topic_members(&topic_id, lr.clone())
+            .await
+            .ok();
+        Ok(lr)
+    }
+
+    pub async fn sync_topic_members(
+        &self,
+        topic_id: String,
+        full_sync: bool,
+        limit: Option<u32>,
+    ) -> Result<u32> {
+        self.store
+            .sync_topic_members(&topic_id, full_sync, limit.unwrap_or(100))
+            .await
+    }
+
+    pub async fn get_cached_topic_members(
+        &self,
+        topic_id: String,
+        keyword: Option<String>,
+        limit: u32,
+    ) -> Vec<User> {
+        self.store
+            .get_cached_topic_members(&topic_id, keyword, limit)
+            .await
     }
 
     pub async fn get_topic_knocks(&self, topic_id: String) -> Option<Vec<TopicKnock>> {
-        get_topic_knocks(&self.endpoint, &self.token, &topic_id)
+        get_topic_knocks(&self.endpoint, &self.token(), &topic_id)
             .await
             .ok()
     }
@@ -80,15 +128,15 @@ impl Client {
         icon: Option<String>,
         kind: Option<String>,
     ) -> Result<()> {
-        update_topic(&self.endpoint, &self.token, &topic_id, name, icon, kind).await
+        update_topic(&self.endpoint, &self.token(), &topic_id, name, icon, kind).await
     }
 
     pub async fn update_topic_notice(&self, topic_id: String, text: String) -> Result<()> {
-        update_topic_notice(&self.endpoint, &self.token, &topic_id, &text).await
+        update_topic_notice(&self.endpoint, &self.token(), &topic_id, &text).await
     }
 
     pub async fn silent_topic(&self, topic_id: String, duration: Option<String>) -> Result<()> {
-        silent_topic(&self.endpoint, &self.token, &topic_id, duration).await
+        silent_topic(&self.endpoint, &self.token(), &topic_id, duration).await
     }
 
     pub async fn silent_topic_member(
@@ -97,27 +145,27 @@ impl Client {
         user_id: String,
         duration: Option<String>,
     ) -> Result<()> {
-        silent_topic_member(&self.endpoint, &self.token, &topic_id, &user_id, duration).await
+        silent_topic_member(&self.endpoint, &self.token(), &topic_id, &user_id, duration).await
     }
 
     pub async fn add_topic_admin(&self, topic_id: String, user_id: String) -> Result<()> {
-        add_topic_admin(&self.endpoint, &self.token, &topic_id, &user_id).await
+        add_topic_admin(&self.endpoint, &self.token(), &topic_id, &user_id).await
     }
 
     pub async fn remove_topic_admin(&self, topic_id: String, user_id: String) -> Result<()> {
-        remove_topic_admin(&self.endpoint, &self.token, &topic_id, &user_id).await
+        remove_topic_admin(&self.endpoint, &self.token(), &topic_id, &user_id).await
     }
 
     pub async fn transfer_topic(&self, topic_id: String, user_id: String) -> Result<()> {
-        transfer_topic(&self.endpoint, &self.token, &topic_id, &user_id).await
+        transfer_topic(&self.endpoint, &self.token(), &topic_id, &user_id).await
     }
 
     pub async fn quit_topic(&self, topic_id: String) -> Result<()> {
-        quit_topic(&self.endpoint, &self.token, &topic_id).await
+        quit_topic(&self.endpoint, &self.token(), &topic_id).await
     }
 
     pub async fn dismiss_topic(&self, topic_id: String) -> Result<()> {
-        dismiss_topic(&self.endpoint, &self.token, &topic_id).await
+        dismiss_topic(&self.endpoint, &self.token(), &topic_id).await
     }
 
     pub async fn accept_topic_join(
@@ -126,7 +174,7 @@ impl Client {
         user_id: String,
         memo: Option<String>,
     ) -> Result<()> {
-        accept_topic_join(&self.endpoint, &self.token, &topic_id, &user_id, memo).await
+        accept_topic_join(&self.endpoint, &self.token(), &topic_id, &user_id, memo).await
     }
 
     pub async fn decline_topic_join(
@@ -135,10 +183,10 @@ impl Client {
         user_id: String,
         message: Option<String>,
     ) -> Result<()> {
-        decline_topic_join(&self.endpoint, &self.token, &topic_id, &user_id, message).await
+        decline_topic_join(&self.endpoint, &self.token(), &topic_id, &user_id, message).await
     }
 
     pub async fn remove_topic_member(&self, topic_id: String, user_id: String) -> Result<()> {
-        remove_topic_member(&self.endpoint, &self.token, &topic_id, &user_id).await
+        remove_topic_member(&self.endpoint, &self.token(), &topic_id, &user_id).await
     }
 }
@@ -11,6 +11,7 @@ pub mod request;
 pub mod services;
 pub mod storage;
 pub mod utils;
+pub mod webhook;
 mod websocket;
 #[allow(unused)]
 const USER_AGENT: &str = concat!("restsend/", env!("CARGO_PKG_VERSION"));
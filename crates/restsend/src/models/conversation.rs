@@ -452,4 +452,12 @@ pub struct ConversationUpdateFields {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mark_unread: Option<bool>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_read_seq: Option<i64>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unread: Option<i64>,
 }
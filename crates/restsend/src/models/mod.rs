@@ -44,7 +44,13 @@ pub struct ListUserResult {
     pub has_more: bool,
     pub updated_at: String,
     #[serde(default)]
+    pub cursor: String,
+    #[serde(default)]
     pub items: Vec<User>,
+    #[serde(default)]
+    pub members: Vec<TopicMember>,
+    #[serde(default)]
+    pub removed: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -135,6 +141,6 @@ pub use conversation::Conversation;
 pub use topic::Topic;
 pub use topic::TopicNotice;
 pub use topic_member::TopicMember;
-pub use user::{AuthInfo, User, UserProfile};
+pub use user::{AuthInfo, DeviceSession, User, UserPresence, UserProfile};
 
 use crate::storage::QueryResult;
@@ -1,8 +1,11 @@
 use super::conversation::Extra;
+use crate::storage::StoreModel;
 use restsend_macros::export_wasm_or_ffi;
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 #[export_wasm_or_ffi(#[derive(uniffi::Record)])]
 pub struct TopicMember {
@@ -17,6 +20,10 @@ pub struct TopicMember {
     #[serde(skip_serializing_if = "String::is_empty")]
     pub source: String,
 
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub role: String,
+
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub silence_at: Option<String>,
@@ -43,3 +50,25 @@ impl TopicMember {
         }
     }
 }
+
+impl FromStr for TopicMember {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str::<TopicMember>(s)
+    }
+}
+
+impl Display for TopicMember {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&serde_json::to_string(self).unwrap_or_default())
+    }
+}
+
+impl StoreModel for TopicMember {
+    fn sort_key(&self) -> i64 {
+        chrono::DateTime::parse_from_rfc3339(&self.joined_at)
+            .map(|v| v.timestamp_millis())
+            .unwrap_or(0)
+    }
+}
@@ -15,6 +15,15 @@ pub struct AuthInfo {
     pub name: String,
     pub token: String,
 
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub refresh_token: String,
+
+    // rfc3339, empty when the token does not expire
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub expires_at: String,
+
     #[serde(default)]
     #[serde(skip_serializing_if = "omit_empty")]
     pub is_staff: bool,
@@ -34,6 +43,8 @@ impl AuthInfo {
             endpoint: endpoint.to_string(),
             user_id: user_id.to_string(),
             token: token.to_string(),
+            refresh_token: String::default(),
+            expires_at: String::default(),
             name: String::default(),
             avatar: String::default(),
             is_staff: false,
@@ -207,3 +218,47 @@ impl StoreModel for User {
         self.cached_at
     }
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+#[export_wasm_or_ffi(#[derive(uniffi::Record)])]
+pub struct UserPresence {
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub status_text: String,
+    #[serde(default)]
+    pub status_expires_at: String,
+    #[serde(default)]
+    pub last_seen_at: String,
+    #[serde(default)]
+    pub online: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+#[export_wasm_or_ffi(#[derive(uniffi::Record)])]
+pub struct DeviceSession {
+    #[serde(default)]
+    pub session_id: String,
+    #[serde(default)]
+    pub device: String,
+    #[serde(default)]
+    pub device_name: String,
+    #[serde(default)]
+    pub platform: String,
+    #[serde(default)]
+    pub app_version: String,
+    #[serde(default)]
+    pub ip: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub last_seen_at: String,
+    #[serde(default)]
+    pub online: bool,
+    #[serde(default)]
+    pub current: bool,
+}
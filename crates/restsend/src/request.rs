@@ -1,6 +1,6 @@
 use crate::models::conversation::Extra;
 use crate::models::Attachment;
-use crate::models::{omit_empty, Content, ContentType, User};
+use crate::models::{omit_empty, Content, ContentType, User, UserPresence};
 use crate::utils::random_text;
 use restsend_macros::export_wasm_or_ffi;
 use serde::{Deserialize, Serialize};
@@ -15,6 +15,9 @@ pub enum ChatRequestType {
     System,
     Nop,  // KeepAlive
     Ping, // Health check with error logs
+    Presence,
+    Subscribe,
+    Unsubscribe,
     Unknown(String),
 }
 
@@ -29,6 +32,9 @@ impl From<&String> for ChatRequestType {
             "system" => ChatRequestType::System,
             "nop" => ChatRequestType::Nop,
             "ping" => ChatRequestType::Ping,
+            "presence" => ChatRequestType::Presence,
+            "subscribe" => ChatRequestType::Subscribe,
+            "unsubscribe" => ChatRequestType::Unsubscribe,
             _ => ChatRequestType::Unknown(value.clone()),
         }
     }
@@ -45,6 +51,9 @@ impl From<ChatRequestType> for String {
             ChatRequestType::System => "system",
             ChatRequestType::Nop => "nop",
             ChatRequestType::Ping => "ping",
+            ChatRequestType::Presence => "presence",
+            ChatRequestType::Subscribe => "subscribe",
+            ChatRequestType::Unsubscribe => "unsubscribe",
             ChatRequestType::Unknown(v) => return v.clone(),
         }
         .to_string()
@@ -96,6 +105,10 @@ pub struct ChatRequest {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    pub users: Vec<String>,
 }
 
 impl ChatRequest {
@@ -127,6 +140,23 @@ impl ChatRequest {
         }
     }
 
+    pub fn new_subscribe_presence(user_ids: Vec<String>, topic_id: Option<String>) -> Self {
+        ChatRequest {
+            req_type: String::from(ChatRequestType::Subscribe),
+            chat_id: random_text(crate::CHAT_ID_LEN),
+            topic_id: topic_id.unwrap_or_default(),
+            users: user_ids,
+            ..Default::default()
+        }
+    }
+    pub fn new_unsubscribe_presence() -> Self {
+        ChatRequest {
+            req_type: String::from(ChatRequestType::Unsubscribe),
+            chat_id: random_text(crate::CHAT_ID_LEN),
+            ..Default::default()
+        }
+    }
+
     pub fn new_chat_with_content(topic_id: &str, content: Content) -> Self {
         ChatRequest {
             req_type: String::from(ChatRequestType::Chat),
@@ -354,6 +384,22 @@ impl From<&ChatRequest> for String {
     }
 }
 
+impl From<&ChatRequest> for UserPresence {
+    fn from(req: &ChatRequest) -> Self {
+        let content = req.content.clone().unwrap_or_default();
+        let extra = content.extra.unwrap_or_default();
+        let field = |key: &str| extra.get(key).cloned().unwrap_or_default();
+        UserPresence {
+            user_id: req.attendee.clone(),
+            status: content.text,
+            status_text: field("statusText"),
+            status_expires_at: field("statusExpiresAt"),
+            last_seen_at: field("lastSeenAt"),
+            online: field("online") == "true",
+        }
+    }
+}
+
 impl TryFrom<String> for ChatRequest {
     type Error = serde_json::Error;
     fn try_from(data: String) -> Result<Self, Self::Error> {
@@ -361,6 +407,21 @@ impl TryFrom<String> for ChatRequest {
     }
 }
 
+#[test]
+fn test_presence_request_decode() {
+    let data = r#"{"type":"presence","attendee":"bob","content":{"type":"presence","text":"busy","extra":{"online":"true","statusText":"meeting"}}}"#;
+    let req = serde_json::from_str::<ChatRequest>(data).unwrap();
+    assert_eq!(
+        ChatRequestType::from(&req.req_type),
+        ChatRequestType::Presence
+    );
+    let presence = UserPresence::from(&req);
+    assert_eq!(presence.user_id, "bob");
+    assert_eq!(presence.status, "busy");
+    assert_eq!(presence.status_text, "meeting");
+    assert!(presence.online);
+}
+
 #[test]
 fn test_chat_request_decode() {
     let data = r#"{"type":"resp","id":"wn8qzkq6nt","code":404}"#;
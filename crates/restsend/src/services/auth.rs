@@ -1,7 +1,7 @@
 use crate::error::ClientError::{self, Forbidden, HTTP};
 use crate::models::conversation::Extra;
-use crate::models::AuthInfo;
-use crate::services::{handle_response, make_get_request, make_post_request, response};
+use crate::models::{AuthInfo, DeviceSession};
+use crate::services::{api_call, handle_response, make_get_request, make_post_request, response};
 use crate::utils::{elapsed, now_millis};
 use crate::Result;
 use log::{info, warn};
@@ -40,6 +40,18 @@ pub async fn signup(endpoint: String, email: String, password: String) -> Result
     signin_or_signup(&endpoint, "/auth/register", &email, data.to_string()).await
 }
 
+#[export_wasm_or_ffi]
+pub async fn refresh_auth_token(
+    endpoint: String,
+    user_id: String,
+    refresh_token: String,
+) -> Result<AuthInfo> {
+    let data = serde_json::json!({
+        "refreshToken": refresh_token,
+    });
+    signin_or_signup(&endpoint, "/auth/refresh", &user_id, data.to_string()).await
+}
+
 #[export_wasm_or_ffi]
 pub async fn logout(endpoint: String, token: String) -> Result<()> {
     let st = now_millis();
@@ -74,6 +86,27 @@ pub async fn logout(endpoint: String, token: String) -> Result<()> {
     }
 }
 
+pub async fn list_sessions(endpoint: &str, token: &str) -> Result<Vec<DeviceSession>> {
+    api_call(endpoint, "/sessions", token, None).await
+}
+
+pub async fn revoke_session(endpoint: &str, token: &str, session_id: &str) -> Result<()> {
+    api_call(
+        endpoint,
+        &format!("/sessions/revoke/{}", session_id),
+        token,
+        None,
+    )
+    .await
+    .map(|_: bool| ())
+}
+
+pub async fn logout_other_devices(endpoint: &str, token: &str) -> Result<()> {
+    api_call(endpoint, "/sessions/revoke_others", token, None)
+        .await
+        .map(|_: bool| ())
+}
+
 async fn signin_or_signup(
     endpoint: &str,
     uri: &str,
@@ -107,6 +140,8 @@ async fn signin_or_signup(
         avatar: r.profile.avatar,
         name: r.display_name,
         token: r.token,
+        refresh_token: r.refresh_token,
+        expires_at: r.expires_at,
         is_staff: r.is_staff,
         is_cross_domain: false,
         private_extra: r.profile.private_extra,
@@ -225,3 +260,82 @@ async fn test_login_logout() {
         .await
         .expect("logout failed");
 }
+
+#[cfg(not(target_family = "wasm"))]
+#[tokio::test]
+async fn test_sessions_revoke() {
+    let server = crate::client::tests::test_server::LocalTestServer::start().await;
+    let user_id = format!("svc-sessions-{}", crate::utils::random_text(8));
+    let password = "pass-1".to_string();
+    let first = signup(server.endpoint.clone(), user_id.clone(), password.clone())
+        .await
+        .expect("signup sessions user");
+    let second = login_with_password(server.endpoint.clone(), user_id.clone(), password.clone())
+        .await
+        .expect("login second device");
+    let third = login_with_password(server.endpoint.clone(), user_id.clone(), password)
+        .await
+        .expect("login third device");
+
+    let sessions = list_sessions(&server.endpoint, &first.token)
+        .await
+        .expect("list sessions");
+    assert_eq!(sessions.len(), 3);
+    let current = sessions
+        .iter()
+        .find(|s| s.current)
+        .expect("current session");
+
+    let other = sessions.iter().find(|s| !s.current).expect("other session");
+    revoke_session(&server.endpoint, &first.token, &other.session_id)
+        .await
+        .expect("revoke session");
+    assert!(
+        revoke_session(&server.endpoint, &first.token, &other.session_id)
+            .await
+            .is_err()
+    );
+
+    logout_other_devices(&server.endpoint, &first.token)
+        .await
+        .expect("logout other devices");
+    assert!(list_sessions(&server.endpoint, &second.token)
+        .await
+        .is_err());
+    assert!(list_sessions(&server.endpoint, &third.token).await.is_err());
+
+    let sessions = list_sessions(&server.endpoint, &first.token)
+        .await
+        .expect("list remaining sessions");
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].session_id, current.session_id);
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[tokio::test]
+async fn test_refresh_token_rotation() {
+    let server = crate::client::tests::test_server::LocalTestServer::start().await;
+    let user_id = format!("svc-refresh-{}", crate::utils::random_text(8));
+    let info = signup(
+        server.endpoint.clone(),
+        user_id.clone(),
+        "pass-1".to_string(),
+    )
+    .await
+    .expect("signup refresh user");
+    assert!(!info.refresh_token.is_empty());
+    assert!(!info.expires_at.is_empty());
+
+    let client = crate::client::Client::new_sync("".to_string(), "".to_string(), &info);
+    let refreshed = client.refresh_auth().await.expect("refresh auth");
+    assert_ne!(refreshed.token, info.token);
+    assert_ne!(refreshed.refresh_token, info.refresh_token);
+    assert_eq!(client.token(), refreshed.token);
+
+    let reused = refresh_auth_token(server.endpoint.clone(), user_id, info.refresh_token).await;
+    assert!(matches!(reused, Err(ClientError::InvalidPassword(_))));
+    assert!(list_sessions(&server.endpoint, &info.token).await.is_err());
+    list_sessions(&server.endpoint, &client.token())
+        .await
+        .expect("list sessions with refreshed token");
+}
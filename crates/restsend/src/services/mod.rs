@@ -1,4 +1,4 @@
-use crate::error::ClientError::{Forbidden, InvalidPassword, HTTP};
+use crate::error::ClientError::{Forbidden, InvalidPassword, TokenExpired, HTTP};
 use crate::Result;
 #[cfg(not(target_family = "wasm"))]
 use crate::USER_AGENT;
@@ -52,7 +52,7 @@ pub(super) fn make_get_request(
 }
 
 #[allow(unused_variables)]
-pub(super) fn make_post_request(
+pub(crate) fn make_post_request(
     endpoint: &str,
     uri: &str,
     auth_token: Option<&str>,
@@ -131,6 +131,9 @@ where
 
             match status {
                 reqwest::StatusCode::FORBIDDEN => Err(Forbidden(msg.to_string())),
+                reqwest::StatusCode::UNAUTHORIZED if msg.starts_with("token expired") => {
+                    Err(TokenExpired(msg.to_string()))
+                }
                 reqwest::StatusCode::UNAUTHORIZED => Err(InvalidPassword(msg.to_string())),
                 reqwest::StatusCode::BAD_REQUEST => Err(HTTP(msg.to_string())),
                 _ => Err(HTTP(msg.to_string())),
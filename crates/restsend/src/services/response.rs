@@ -10,6 +10,10 @@ pub struct Login {
     pub display_name: String,
     pub token: String,
     #[serde(default)]
+    pub refresh_token: String,
+    #[serde(default)]
+    pub expires_at: String,
+    #[serde(default)]
     pub profile: UserProfile,
     #[serde(default)]
     pub is_staff: bool,
@@ -23,6 +23,8 @@ pub async fn get_topic_members(
     token: &str,
     topic_id: &str,
     updated_at: &str,
+    cursor: &str,
+    keyword: &str,
     limit: u32,
 ) -> Result<ListUserResult> {
     let mut data = serde_json::json!({
@@ -31,6 +33,12 @@ pub async fn get_topic_members(
     if !updated_at.is_empty() {
         data["updatedAt"] = serde_json::json!(updated_at);
     }
+    if !cursor.is_empty() {
+        data["cursor"] = serde_json::json!(cursor);
+    }
+    if !keyword.is_empty() {
+        data["keyword"] = serde_json::json!(keyword);
+    }
 
     api_call(
         endpoint,
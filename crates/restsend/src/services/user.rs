@@ -1,4 +1,5 @@
 use super::api_call;
+use crate::models::{ListUserResult, UserPresence};
 use crate::Result;
 use crate::{models::User, utils::now_millis};
 
@@ -33,6 +34,55 @@ pub async fn get_users(endpoint: &str, token: &str, user_ids: Vec<String>) -> Re
         })
 }
 
+pub async fn get_contacts(
+    endpoint: &str,
+    token: &str,
+    updated_at: &str,
+    cursor: &str,
+    limit: u32,
+) -> Result<ListUserResult> {
+    let mut data = serde_json::json!({
+        "limit": limit,
+    });
+    if !updated_at.is_empty() {
+        data["updatedAt"] = serde_json::json!(updated_at);
+    }
+    if !cursor.is_empty() {
+        data["cursor"] = serde_json::json!(cursor);
+    }
+    api_call(endpoint, "/contacts", token, Some(data.to_string()))
+        .await
+        .map(|mut lr: ListUserResult| {
+            lr.items.iter_mut().for_each(|user| {
+                user.cached_at = now_millis();
+                if !user.avatar.is_empty() && !user.avatar.starts_with("http") {
+                    user.avatar = format!("{}{}", endpoint.trim_end_matches('/'), user.avatar);
+                }
+            });
+            lr
+        })
+}
+
+pub async fn get_presence(endpoint: &str, token: &str, user_id: &str) -> Result<UserPresence> {
+    api_call(endpoint, &format!("/presence/{}", user_id), token, None).await
+}
+
+pub async fn set_presence(
+    endpoint: &str,
+    token: &str,
+    status: &str,
+    status_text: &str,
+    expire_secs: u64,
+) -> Result<UserPresence> {
+    let data = serde_json::json!({
+        "status": status,
+        "statusText": status_text,
+        "expireSecs": expire_secs,
+    })
+    .to_string();
+    api_call(endpoint, "/presence/update", token, Some(data)).await
+}
+
 pub async fn set_user_block(endpoint: &str, token: &str, user_id: &str, block: bool) -> Result<()> {
     let action = if block { "block" } else { "unblock" };
     api_call(endpoint, &format!("/{}/{}", action, user_id), token, None)
@@ -1,6 +1,10 @@
 use std::time::Duration;
 
-use crate::DEVICE;
+use crate::error::ClientError::{self, TokenExpired, HTTP};
+use crate::services::{handle_response, make_post_request};
+use crate::{Result, DEVICE};
+use log::warn;
+use serde::Deserialize;
 #[cfg(test)]
 mod tests;
 
@@ -44,6 +48,68 @@ impl WebsocketOption {
             is_cross_domain,
         }
     }
+
+    // exchanges the bearer token for a single use ticket, so the token never ends up in the ws url.
+    // returns None when there is no token or the server predates tickets
+    pub(crate) async fn fetch_ticket(&self) -> Result<Option<String>> {
+        if self.token.is_empty() {
+            return Ok(None);
+        }
+        let url = match self.url.strip_prefix("ws") {
+            Some(rest) => format!("http{}", rest),
+            None => self.url.clone(),
+        };
+        let ticket_url = match url.split_once('?') {
+            Some((path, query)) => format!("{}/ticket?{}", path.trim_end_matches('/'), query),
+            None => format!("{}/ticket", url.trim_end_matches('/')),
+        };
+        #[cfg(not(target_family = "wasm"))]
+        let timeout = Some(self.handshake_timeout);
+        #[cfg(target_family = "wasm")]
+        let timeout = None;
+
+        let resp = make_post_request(&ticket_url, "", Some(&self.token), None, None, timeout)
+            .send()
+            .await
+            .map_err(|e| HTTP(e.to_string()))?;
+        match resp.status() {
+            reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::METHOD_NOT_ALLOWED => {
+                warn!(
+                    "ws ticket not supported by {}, falling back to token",
+                    ticket_url
+                );
+                return Ok(None);
+            }
+            reqwest::StatusCode::UNAUTHORIZED => {
+                let reason = handle_response::<ConnectTicket>(resp)
+                    .await
+                    .err()
+                    .map(|e| e.to_string())
+                    .unwrap_or_default();
+                return Err(TokenExpired(format!("ws ticket unauthorized: {}", reason)));
+            }
+            _ => {}
+        }
+        let ticket = handle_response::<ConnectTicket>(resp).await?;
+        Ok(Some(ticket.ticket))
+    }
+
+    // the url to dial, carrying the ticket when one was issued
+    pub(crate) fn url_with_ticket(&self, ticket: Option<&str>) -> Result<String> {
+        let url = self.url.replacen("http", "ws", 1);
+        let Some(ticket) = ticket else {
+            return Ok(url);
+        };
+        let mut u = url::Url::parse(&url)
+            .map_err(|_| ClientError::HTTP(format!("url parse fail {}", url)))?;
+        u.query_pairs_mut().append_pair("ticket", ticket);
+        Ok(u.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct ConnectTicket {
+    ticket: String,
 }
 
 #[cfg(not(target_family = "wasm"))]
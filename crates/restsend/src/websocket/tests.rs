@@ -45,8 +45,50 @@ async fn test_websocket_handshake() {
     let opt = super::WebsocketOption::new(&url, "", false);
     let cb = Box::new(WebSocketCallbackImpl::default());
     let r = ws.serve(&opt, cb).await;
-    assert!(r
-        .unwrap_err()
-        .to_string()
-        .contains("expected HTTP 101 Switching Protocols"));
+    assert!(matches!(
+        r,
+        Err(crate::error::ClientError::TokenExpired(reason))
+            if reason.contains("expected HTTP 101 Switching Protocols")
+    ));
+}
+
+#[tokio::test]
+async fn test_websocket_connect_with_ticket() {
+    let server = LocalTestServer::start().await;
+    let user_id = crate::client::tests::unique_test_user("ws-ticket");
+    crate::services::auth::signup(
+        server.endpoint.clone(),
+        user_id.clone(),
+        "pass-1".to_string(),
+    )
+    .await
+    .expect("signup user");
+    let info = crate::services::auth::login_with_password(
+        server.endpoint.clone(),
+        user_id,
+        "pass-1".to_string(),
+    )
+    .await
+    .expect("login user");
+
+    let url = super::WebsocketOption::url_from_endpoint(&server.endpoint);
+    let opt = super::WebsocketOption::new(&url, &info.token, false);
+    let ticket = opt.fetch_ticket().await.unwrap().expect("ticket issued");
+    assert!(ticket.starts_with("wst-"));
+    let ws_url = opt.url_with_ticket(Some(&ticket)).unwrap();
+    assert!(ws_url.starts_with("ws"));
+    assert!(ws_url.contains(&format!("ticket={}", ticket)));
+    assert!(!ws_url.contains(&info.token));
+
+    let bad = super::WebsocketOption::new(&url, "bad-token", false);
+    assert!(matches!(
+        bad.fetch_ticket().await,
+        Err(crate::error::ClientError::TokenExpired(_))
+    ));
+
+    // serve fetches its own ticket and keeps running once connected
+    let ws = super::WebSocket::new();
+    let cb = Box::new(WebSocketCallbackImpl::default());
+    let r = tokio::time::timeout(Duration::from_secs(2), ws.serve(&opt, cb)).await;
+    assert!(r.is_err(), "serve returned early: {:?}", r);
 }
@@ -44,13 +44,31 @@ impl WebSocketImpl {
         opt: &WebsocketOption,
         callback: Box<dyn WebSocketCallback>,
     ) -> Result<()> {
-        let url = opt.url.replacen("http", "ws", 1);
+        let ticket = match opt.fetch_ticket().await {
+            Ok(v) => v,
+            Err(e @ TokenExpired(_)) => {
+                warn!("websocket ticket unauthorized: {}", e);
+                callback.on_unauthorized();
+                return Err(e);
+            }
+            Err(e) => {
+                warn!("websocket ticket failed: {}", e);
+                callback.on_net_broken(e.to_string());
+                return Err(e);
+            }
+        };
+        let url = opt.url_with_ticket(ticket.as_deref())?;
 
-        let req = ClientBuilder::new()
-            .add_header(
+        let req = ClientBuilder::new();
+        // a ticket already identifies the user, the header is only for servers without tickets
+        let req = match ticket {
+            Some(_) => req,
+            None => req.add_header(
                 AUTHORIZATION,
                 format!("Bearer {}", opt.token).parse().unwrap(),
-            )
+            ),
+        };
+        let req = req
             .add_header(USER_AGENT, crate::USER_AGENT.parse().unwrap())
             .add_header(ACCEPT, "application/json".parse().unwrap())
             .uri(&url)
@@ -70,6 +88,16 @@ impl WebSocketImpl {
 
         let (stream, resp) = match resp {
             Ok(v) => v,
+            // the handshake error swallows the response, so 401 never reaches the match below
+            Err(
+                e @ tokio_websockets::Error::Upgrade(
+                    tokio_websockets::upgrade::Error::DidNotSwitchProtocols(401),
+                ),
+            ) => {
+                warn!("websocket unauthorized failed: url: {} {}", url, e);
+                callback.on_unauthorized();
+                return Err(TokenExpired(format!("websocket unauthorized: {}", e)));
+            }
             Err(e) => {
                 warn!("websocket connect failed: url: {} {}", url, e);
                 let reason = format!("websocket connect failed: {}", e);
@@ -45,7 +45,18 @@ impl WebSocketImpl {
         opt: &WebsocketOption,
         callback: Box<dyn WebSocketCallback>,
     ) -> Result<()> {
-        let mut url = opt.url.replacen("http", "ws", 1);
+        let ticket = match opt.fetch_ticket().await {
+            Ok(v) => v,
+            Err(e @ ClientError::TokenExpired(_)) => {
+                callback.on_unauthorized();
+                return Err(e);
+            }
+            Err(e) => {
+                callback.on_net_broken(e.to_string());
+                return Err(e);
+            }
+        };
+        let mut url = opt.url_with_ticket(ticket.as_deref())?;
         let st: i64 = now_millis();
 
         let current_host = match web_sys::window() {
@@ -56,7 +67,7 @@ impl WebSocketImpl {
         let is_cross_domain =
             current_host.is_empty() || !url.contains(&current_host) || opt.is_cross_domain;
 
-        if is_cross_domain && !opt.token.is_empty() {
+        if ticket.is_none() && is_cross_domain && !opt.token.is_empty() {
             let mut u = url::Url::parse(&url)
                 .map_err(|_| ClientError::HTTP(format!("url parse fail {}", url)))?;
             u.query_pairs_mut().append_pair("token", &opt.token);